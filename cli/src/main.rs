@@ -39,6 +39,8 @@ mod errors;
 mod format;
 mod optimize_check;
 mod profile;
+mod pulse_report;
+mod quantize;
 mod run;
 mod rusage;
 mod stream_check;
@@ -95,10 +97,13 @@ fn main() {
             "Add a subsampling to output on axis 0")
 
         (@arg kaldi_left_context: --("kaldi-left-context") +takes_value
-            "Add lines of left context to input (dupping first time frame)")
+            "Add lines of left context to inputs (dupping first time frame), as N or name=N,...")
 
         (@arg kaldi_right_context: --("kaldi-right-context") +takes_value
-            "Add lines of right context to input (dupping last time frame)")
+            "Add lines of right context to inputs (dupping last time frame), as N or name=N,...")
+
+        (@arg kaldi_input_period: --("kaldi-input-period") +takes_value
+            "Hold inputs read by ReplaceIndex for N frames (like an ivector period), as N or name=N,...")
 
         (@arg input_node: --("input-node") +takes_value +multiple number_of_values(1)
             "Override input nodes names (auto-detects otherwise).")
@@ -125,6 +130,16 @@ fn main() {
 
         (@arg optimize: -O --optimize "Optimize before running")
         (@arg pulse: --pulse +takes_value "Translate to pulse network")
+        (@arg running_time_pooling: --("running-time-pooling")
+            "Before pulsing, emit running values of the reductions over the streaming axis on each frame")
+        (@arg bidi_chunk: --("bidi-chunk") +takes_value
+            "Before pulsing, run the backward pass of bidirectional layers over chunks of this size")
+        (@arg bidi_right_context: --("bidi-right-context") +takes_value requires[bidi_chunk]
+            "Right context of the chunked backward pass of bidirectional layers (default 0)")
+        (@arg quantize_dynamic: --("quantize-dynamic")
+            "Before optimizing, quantize matrix products to i8, quantizing their input on each run")
+        (@arg f16_weights: --("f16-weights")
+            "Before optimizing, store the weights of matrix products and convolutions as f16")
 
         (@arg verbosity: -v ... "Sets the level of verbosity.")
 
@@ -259,6 +274,29 @@ fn main() {
         .long_about("Compare output of optimized and un-optimized graph");
     app = app.subcommand(output_options(optimize_check));
 
+    let quantize = clap::SubCommand::with_name("quantize")
+        .long_about("Quantize convolutions and matrix products to i8, calibrating on input bundles")
+        .arg(
+            Arg::with_name("calibration")
+                .takes_value(true)
+                .required(true)
+                .multiple(true)
+                .number_of_values(1)
+                .long("calibration")
+                .help("Input bundle (.npz) to calibrate on, can be repeated"),
+        );
+    app = app.subcommand(output_options(quantize));
+
+    let pulse_report = clap::SubCommand::with_name("pulse-report")
+        .long_about("Report the delay of a pulsed model outputs, and its state memory")
+        .arg(
+            Arg::with_name("frame-rate")
+                .takes_value(true)
+                .long("frame-rate")
+                .help("Frames per second on the streaming axis, to report delays in milliseconds"),
+        );
+    app = app.subcommand(pulse_report);
+
     let stream_check = clap::SubCommand::with_name("stream-check")
         .long_about("Compare output of streamed and regular exec");
     app = app.subcommand(output_options(stream_check));
@@ -290,6 +328,22 @@ fn main() {
     info_usage("done");
 }
 
+/// Per-input Kaldi options are either a number of frames for all inputs, or a
+/// comma-separated list of name=frames (None for missing inputs).
+fn kaldi_frames_for_input(spec: &str, input: &str) -> CliResult<Option<usize>> {
+    if !spec.contains('=') {
+        return Ok(Some(spec.parse()?));
+    }
+    for item in spec.split(',') {
+        let mut tokens = item.splitn(2, '=');
+        if tokens.next().map(|n| n.trim()) == Some(input) {
+            let frames = tokens.next().ok_or_else(|| format!("Invalid Kaldi option {}", spec))?;
+            return Ok(Some(frames.trim().parse()?));
+        }
+    }
+    Ok(None)
+}
+
 fn output_options<'a, 'b>(command: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
     use clap::*;
     command
@@ -386,6 +440,13 @@ impl Parameters {
                 if let Some(i) = matches.value_of("kaldi_adjust_final_offset") {
                     graph.adjust_final_offset = i.parse()?;
                 }
+                if let Some(spec) = matches.value_of("kaldi_input_period") {
+                    for (input, _) in &graph.config_lines.input_nodes {
+                        if let Some(period) = kaldi_frames_for_input(spec, input)? {
+                            graph.input_periods.insert(input.clone(), period);
+                        }
+                    }
+                }
                 let parsed = kaldi.model_for_proto_model(&graph)?;
                 (SomeGraphDef::Kaldi(graph), parsed)
             }
@@ -495,20 +556,22 @@ impl Parameters {
         if matches.value_of("kaldi_left_context").is_some()
             || matches.value_of("kaldi_right_context").is_some()
         {
-            let left = matches.value_of("kaldi_left_context").unwrap_or("0").parse()?;
-            let right = matches.value_of("kaldi_right_context").unwrap_or("0").parse()?;
-            let op = tract_core::ops::array::Pad::new(
-                vec![(left, right), (0, 0)],
-                tract_core::ops::array::PadMode::Edge,
-            );
+            let left = matches.value_of("kaldi_left_context").unwrap_or("0");
+            let right = matches.value_of("kaldi_right_context").unwrap_or("0");
             let mut patch = InferenceModelPatch::default();
             for input in raw_model.input_outlets()? {
+                let name = &*raw_model.node(input.node).name;
+                let left = kaldi_frames_for_input(left, name)?.unwrap_or(0);
+                let right = kaldi_frames_for_input(right, name)?.unwrap_or(0);
+                if left == 0 && right == 0 {
+                    continue;
+                }
+                let op = tract_core::ops::array::Pad::new(
+                    vec![(left, right), (0, 0)],
+                    tract_core::ops::array::PadMode::Edge,
+                );
                 let tap = patch.tap_model(&raw_model, *input)?;
-                let pad = patch.wire_node(
-                    format!("{}-pad", raw_model.node(input.node).name),
-                    op.clone(),
-                    &[tap],
-                )?[0];
+                let pad = patch.wire_node(format!("{}-pad", name), op, &[tap])?[0];
                 patch.shunt_outside(*input, pad)?;
             }
             patch.apply(&mut raw_model)?;
@@ -588,11 +651,16 @@ impl Parameters {
 
         let pulse: Option<usize> = matches.value_of("pulse").map(|s| s.parse()).transpose()?;
         let mut typed_model = None;
-        let normalized_model: Option<NormalizedModel> = None;
+        let mut normalized_model: Option<NormalizedModel> = None;
 
         let tract_model: Box<dyn Model> = {
             let stop_at = matches.value_of("pass").unwrap_or(if matches.is_present("optimize") {
                 "optimize"
+            } else if pulse.is_some()
+                && (matches.subcommand_name() == Some("pulse-report")
+                    || matches.subcommand_name() == Some("stream-check"))
+            {
+                "pulse"
             } else if pulse.is_some() {
                 "pulse-declutter"
             } else {
@@ -633,14 +701,33 @@ impl Parameters {
                 }
                 info_usage("after declutter");
                 if let Some(pulse) = pulse {
+                    if matches.is_present("running_time_pooling") {
+                        info!("Running 'running-time-pooling'");
+                        model = ::tract_core::pulse::causal::running_time_pooling(&model)?;
+                    }
+                    if let Some(chunk) = matches.value_of("bidi_chunk") {
+                        let chunk = chunk.parse::<usize>()?;
+                        let right_context: usize = matches
+                            .value_of("bidi_right_context")
+                            .map(|s| s.parse())
+                            .transpose()?
+                            .unwrap_or(0);
+                        info!("Running 'bidi-latency-control' ({}, {})", chunk, right_context);
+                        model = ::tract_core::pulse::bidi::latency_controlled(
+                            &model,
+                            chunk,
+                            right_context,
+                        )?;
+                    }
                     info!("Running 'pulse-normalize'");
-                    let normalized_model = model.clone().into_normalized()?;
+                    let normalized = model.clone().into_normalized()?;
+                    normalized_model = Some(normalized.clone());
                     if stop_at == "pulse-normalize" {
-                        return Ok(Box::new(normalized_model) as _);
+                        return Ok(Box::new(normalized) as _);
                     }
                     info_usage("after pulse-normalize");
                     info!("Running 'pulse' ({})", pulse);
-                    let pulsed = ::tract_core::pulse::PulsedModel::new(&normalized_model, pulse)?;
+                    let pulsed = ::tract_core::pulse::PulsedModel::new(&normalized, pulse)?;
                     if stop_at == "pulse" {
                         return Ok(Box::new(pulsed) as _);
                     }
@@ -658,6 +745,14 @@ impl Parameters {
                     }
                     info_usage("after pulse-declutter");
                 }
+                if matches.is_present("quantize_dynamic") {
+                    info!("Running 'quantize-dynamic'");
+                    model = model.quantize_dynamic()?;
+                }
+                if matches.is_present("f16_weights") {
+                    info!("Running 'f16-weights'");
+                    model = model.f16_weights()?;
+                }
                 info!("Running 'optimize'");
                 model = model.clone().codegen()?;
                 Ok(Box::new(model) as _)
@@ -827,10 +922,22 @@ fn handle(matches: clap::ArgMatches) -> CliResult<()> {
             optimize_check::handle(params, display_options_from_clap(&matches, m)?)
         }
 
+        ("quantize", Some(m)) => quantize::handle(
+            params,
+            display_options_from_clap(&matches, m)?,
+            m.values_of("calibration").unwrap().map(|s| s.to_string()).collect(),
+            matches.is_present("optimize"),
+        ),
+
         ("stream-check", Some(m)) => {
             stream_check::handle(params, display_options_from_clap(&matches, m)?)
         }
 
+        ("pulse-report", Some(m)) => {
+            let frame_rate = m.value_of("frame-rate").map(f32::from_str).transpose()?;
+            pulse_report::handle(params, frame_rate)
+        }
+
         ("cost", Some(m)) => {
             crate::cost::handle(params, display_options_from_clap(&matches, m)?, m)
         }
@@ -861,3 +968,23 @@ fn handle(matches: clap::ArgMatches) -> CliResult<()> {
         (s, _) => bail!("Unknown subcommand {}.", s),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kaldi_frames_for_all_inputs() {
+        assert_eq!(kaldi_frames_for_input("3", "input").unwrap(), Some(3));
+        assert_eq!(kaldi_frames_for_input("3", "ivector").unwrap(), Some(3));
+    }
+
+    #[test]
+    fn kaldi_frames_by_input_name() {
+        let spec = "input=3, ivector = 10";
+        assert_eq!(kaldi_frames_for_input(spec, "input").unwrap(), Some(3));
+        assert_eq!(kaldi_frames_for_input(spec, "ivector").unwrap(), Some(10));
+        assert_eq!(kaldi_frames_for_input(spec, "other").unwrap(), None);
+        assert!(kaldi_frames_for_input("input=three", "input").is_err());
+    }
+}
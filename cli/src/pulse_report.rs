@@ -0,0 +1,16 @@
+use tract_core::pulse::PulsedModel;
+
+use crate::{CliResult, Parameters};
+
+pub fn handle(params: Parameters, frame_rate: Option<f32>) -> CliResult<()> {
+    let pulsed = params
+        .tract_model
+        .downcast_ref::<PulsedModel>()
+        .ok_or("pulse-report needs a pulsed model (use --pulse, and --pass pulse)")?;
+    let mut report = pulsed.latency_report()?;
+    if let Some(frame_rate) = frame_rate {
+        report = report.with_frame_rate(frame_rate);
+    }
+    println!("{}", report);
+    Ok(())
+}
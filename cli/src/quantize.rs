@@ -0,0 +1,61 @@
+use tract_core::internal::*;
+use tract_core::model::quantize::Calibration;
+
+use crate::display_graph::DisplayOptions;
+use crate::errors::*;
+use crate::Parameters;
+
+pub fn handle(
+    params: Parameters,
+    options: DisplayOptions,
+    files: Vec<String>,
+    optimize: bool,
+) -> CliResult<()> {
+    let model = params.typed_model.as_ref().ok_or("Quantization requires a typed model")?;
+
+    let mut inputs_set = vec![];
+    for file in &files {
+        let mut npz = ndarray_npy::NpzReader::new(std::fs::File::open(file)?)?;
+        let inputs = model
+            .input_outlets()?
+            .iter()
+            .map(|input| {
+                let name = format!("{}.npy", model.node(input.node).name);
+                Ok(crate::tensor::for_npz(&mut npz, &name)
+                    .map_err(|e| format!("Reading {} from {}: {}", name, file, e))?)
+            })
+            .collect::<CliResult<TVec<Tensor>>>()?;
+        inputs_set.push(inputs);
+    }
+
+    let mut calibration = Calibration::default();
+    for inputs in &inputs_set {
+        calibration.observe(model, inputs.clone())?;
+    }
+    info!("Calibrated {} tensors on {} input sets", calibration.ranges.len(), inputs_set.len());
+
+    let mut quantized = model.quantize(&calibration)?.declutter()?;
+    if optimize {
+        quantized = quantized.codegen()?;
+    }
+
+    let reference = SimplePlan::new(model)?;
+    let plan = SimplePlan::new(&quantized)?;
+    for (ix, inputs) in inputs_set.iter().enumerate() {
+        let expected = reference.run(inputs.clone())?;
+        let found = plan.run(inputs.clone())?;
+        for (output, (e, f)) in expected.iter().zip(found.iter()).enumerate() {
+            if e.datum_type() != f32::datum_type() || f.datum_type() != f32::datum_type() {
+                continue;
+            }
+            let max_error = e
+                .as_slice::<f32>()?
+                .iter()
+                .zip(f.as_slice::<f32>()?.iter())
+                .fold(0f32, |acc, (e, f)| acc.max((e - f).abs()));
+            println!("{}: output #{} max absolute error: {}", files[ix], output, max_error);
+        }
+    }
+
+    crate::dump::handle_model(&quantized, &params, options)
+}
@@ -1,7 +1,7 @@
 use crate::errors::*;
 use crate::{Model, Parameters};
 use tract_core::internal::*;
-use tract_core::ndarray;
+use tract_core::pulse::session::PulsedSession;
 
 pub fn handle(params: Parameters, dump: bool) -> CliResult<()> {
     let outputs = if let Some(pulse) = params.tract_model.downcast_ref::<PulsedModel>() {
@@ -46,52 +46,27 @@ fn run_regular(tract: &dyn Model, params: &Parameters) -> CliResult<TVec<Arc<Ten
 }
 
 fn run_pulse_t(model: &PulsedModel, params: &Parameters) -> CliResult<TVec<Arc<Tensor>>> {
-    let input_fact = model.input_fact(0)?;
-    let output_fact = model.output_fact(0)?;
-
-    let output_pulse = output_fact.pulse();
-    //    println!("output_fact: {:?}", output_fact);
-    let axis = input_fact.axis;
-    let input: &Tensor = &params.input_values[0].as_ref().unwrap();
-    //    println!("input_shape: {:?}", input.shape());
-    let input_dim = input.shape()[axis];
-    //    println!("output_fact: {:?}", output_fact);
-    let output_dim = output_fact.dim.eval(input_dim as i32).unwrap() as i32;
-    let mut output_shape = output_fact.shape.to_vec();
-    output_shape[output_fact.axis] =
-        output_dim as usize + output_fact.delay + 4 * output_fact.pulse();
-    let plan = SimplePlan::new(model)?;
-    let mut state = ::tract_core::plan::SimpleState::new(&plan)?;
-    //    println!("output_shape: {:?}", output_shape);
-    let pulse = input_fact.pulse();
-    let mut result = ndarray::ArrayD::<f32>::default(output_shape);
-    let input = input.to_array_view::<f32>()?;
-    for ix in 0..input_dim.div_ceil(pulse) {
-        let chunk = input.slice_axis(ndarray::Axis(axis), (ix * pulse..(ix + 1) * pulse).into());
-        let input = if chunk.shape()[input_fact.axis] < pulse {
-            let mut chunk_shape = chunk.shape().to_vec();
-            chunk_shape[input_fact.axis] = pulse;
-            let mut padded_chunk = ndarray::ArrayD::<f32>::default(chunk_shape);
-            padded_chunk
-                .slice_axis_mut(
-                    ndarray::Axis(input_fact.axis),
-                    (..chunk.shape()[input_fact.axis]).into(),
-                )
-                .assign(&chunk);
-            padded_chunk
-        } else {
-            chunk.to_owned()
-        };
-        let outputs = state.run(tvec!(input.into()))?;
-        let result_chunk = outputs[0].to_array_view::<f32>()?;
-        result
-            .slice_axis_mut(
-                ndarray::Axis(output_fact.axis),
-                ((output_pulse * ix)..(output_pulse * (ix + 1))).into(),
-            )
-            .assign(&result_chunk);
+    let mut inputs: TVec<Tensor> = tvec!();
+    for ix in 0..model.input_outlets()?.len() {
+        let input = params
+            .input_values
+            .get(ix)
+            .and_then(|x| x.as_ref())
+            .ok_or_else(|| format!("Missing value for input #{}", ix))?;
+        inputs.push(input.as_ref().clone());
     }
-    result.slice_axis_inplace(ndarray::Axis(output_fact.axis), (output_fact.delay..).into());
-    result.slice_axis_inplace(ndarray::Axis(output_fact.axis), (..output_dim as usize).into());
-    Ok(tvec!(result.into_arc_tensor()))
+    let mut session = PulsedSession::new(model.clone())?;
+    let pushed = session.push(inputs)?;
+    let tail = session.finish()?;
+    let mut outputs = tvec!();
+    for (ix, (pushed, tail)) in pushed.iter().zip(tail.iter()).enumerate() {
+        let axis = model.output_fact(ix)?.axis;
+        outputs.push(dispatch_datum!(stack_t(pushed.datum_type())(axis, pushed, tail))?);
+    }
+    Ok(outputs)
+}
+
+fn stack_t<T: Datum>(axis: usize, a: &Tensor, b: &Tensor) -> TractResult<Arc<Tensor>> {
+    let views = [a.to_array_view::<T>()?, b.to_array_view::<T>()?];
+    Ok(T::stack_views(axis, &views)?.into_arc_tensor())
 }
@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
 use itertools::Itertools;
-use tract_core::ndarray::{ArrayD, Axis};
+use tract_core::ndarray::Axis;
 
 use tract_core::model::{Fact, OutletId};
-use tract_core::plan::{SimplePlan, SimpleState};
+use tract_core::plan::SimplePlan;
+use tract_core::pulse::session::PulsedSession;
 use tract_core::pulse::PulsedModel;
 
 use crate::display_graph;
@@ -41,7 +42,6 @@ pub fn handle(params: Parameters, options: display_graph::DisplayOptions) -> Cli
             pulsed.set_output_outlets(&[pulsed_outlet])?;
 
             let pulsed_output_fact = pulsed.output_fact(0)?;
-            let output_pulse = pulsed_output_fact.pulse();
             let output_axis = pulsed_output_fact.axis;
             let delay = pulsed_output_fact.delay;
 
@@ -58,54 +58,31 @@ pub fn handle(params: Parameters, options: display_graph::DisplayOptions) -> Cli
             let fixed_result = fixed_result.to_array_view::<f32>()?;
             let fixed_output_len = fixed_result.shape()[output_axis];
 
-            let plan = SimplePlan::new(&pulsed)?;
-            let mut state = SimpleState::new(&plan)?;
-
-            for i in 0.. {
-                let mut pulsed_input = ArrayD::from_elem(&*pulsed_input_fact.shape, std::f32::NAN);
-                let offset = i * input_pulse;
-                if offset < stream_dim {
-                    let count = input_pulse.min(stream_dim - offset);
-                    pulsed_input
-                        .slice_axis_mut(Axis(pulsed_input_fact.axis), (0..count).into())
-                        .assign(&fixed_input.to_array_view::<f32>()?.slice_axis(
-                            Axis(pulsed_input_fact.axis),
-                            (offset..offset + count).into(),
-                        ));
-                };
-                if offset + input_pulse > stream_dim {
-                    debug!("Set known_stream_len: {}", stream_dim);
-                    state.session_state.known_stream_len = Some(stream_dim)
-                };
-
-                let output = state.run(tvec!(pulsed_input.into()))?.remove(0);
+            // the session compensates the delay, and feeds the trailing
+            // pulses once the stream is finished
+            let mut session = PulsedSession::new(pulsed)?;
+            let input = fixed_input.to_array_view::<f32>()?;
+            let mut outputs = vec![];
+            for offset in (0..stream_dim).step_by(input_pulse) {
+                let end = (offset + input_pulse).min(stream_dim);
+                let chunk = input.slice_axis(Axis(pulsed_input_fact.axis), (offset..end).into());
+                let output = session.push(tvec!(chunk.to_owned().into()))?.remove(0);
+                outputs.push((format!("push {}..{}", offset, end), output));
+            }
+            debug!("Finish stream of {} frames", stream_dim);
+            outputs.push(("finish".to_string(), session.finish()?.remove(0)));
 
+            let mut checked = 0;
+            for (step, output) in outputs {
                 let output = output.to_array_view::<f32>()?;
-                let output_offset = i * output_pulse;
-                let (f_o, p_o, count) = if output_offset + output_pulse <= delay {
-                    // entire pulse before signal, wait
-                    continue;
-                } else if output_offset >= delay + fixed_output_len {
-                    // entire pulse after signal, we stop
-                    break;
-                } else if output_offset < delay {
-                    // beginning of signal
-                    let count = output_pulse + output_offset - delay;
-                    (0, output_pulse - count, count)
-                } else if output_offset + output_pulse > delay + fixed_output_len {
-                    // end of signal
-                    let count = fixed_output_len + delay - output_offset;
-                    (output_offset - delay, 0, count)
-                } else {
-                    (output_offset - delay, 0, output_pulse)
-                };
-                let valid_pulse_result =
-                    output.slice_axis(Axis(output_axis), (p_o..p_o + count).into());
-                let valid_fixed_result =
-                    fixed_result.slice_axis(Axis(output_axis), (f_o..f_o + count).into());
-                if valid_pulse_result != valid_fixed_result {
+                let count = output.shape()[output_axis];
+                let valid_fixed_result = fixed_result.slice_axis(
+                    Axis(output_axis),
+                    (checked.min(fixed_output_len)..(checked + count).min(fixed_output_len)).into(),
+                );
+                if output != valid_fixed_result {
                     display_graph.render_node(pulsed_node)?;
-                    println!("pulse: {} ({}..{})", i, i * output_pulse, (i + 1) * output_pulse);
+                    println!("{} (output {}..{})", step, checked, checked + count);
                     println!(
                         "expected: {}",
                         valid_fixed_result
@@ -115,13 +92,17 @@ pub fn handle(params: Parameters, options: display_graph::DisplayOptions) -> Cli
                     );
                     println!(
                         "got: {}",
-                        valid_pulse_result
+                        output
                             .axis_iter(Axis(output_axis))
                             .map(|s| *s.iter().next().unwrap())
                             .join(" ")
                     );
                     bail!("Pulse check failed")
                 }
+                checked += count;
+            }
+            if checked != fixed_output_len {
+                bail!("Pulse check failed: got {} frames, expected {}", checked, fixed_output_len)
             }
         }
     }
@@ -106,6 +106,12 @@ impl TDim {
         Self::s()
     }
 
+    /// The special value P, for the number of pulses in a run of a pulsed
+    /// model with a variable pulse.
+    pub fn p() -> TDim {
+        TDim(Stack::sym('P'))
+    }
+
     /// Try to convert the value to an integer, if it does not contains S.
     pub fn as_const(&self) -> Option<i32> {
         self.to_integer().ok()
@@ -90,6 +90,7 @@ pub mod model;
 mod optim;
 pub mod plan;
 pub mod pulse;
+pub mod snapshot;
 pub mod tensor;
 
 pub use crate::errors::*;
@@ -104,6 +105,7 @@ pub mod prelude {
     pub use crate::framework::Framework;
     pub use crate::model::*;
     pub use crate::plan::{SimplePlan, SimpleState};
+    pub use crate::snapshot::StateSnapshot;
     pub use crate::tensor::litteral::*;
     pub use crate::tensor::{IntoArcTensor, IntoTensor, Tensor};
     pub use crate::tvec;
@@ -127,6 +129,7 @@ pub mod internal {
     pub use crate::plan::SessionState;
     pub use crate::prelude::*;
     pub use crate::pulse::{PulsedFact, PulsedModel, PulsedNode};
+    pub use crate::snapshot::OpStateSnapshot;
     pub use crate::{args_1, args_2, args_3, args_4};
     pub use std::borrow::Cow;
     pub use std::collections::HashMap;
@@ -11,6 +11,17 @@ use std::fmt;
 pub trait Fact: std::fmt::Debug + Downcast + dyn_clone::DynClone + Send + Sync + 'static {
     /// Convert to InferenceFact, the most accomoding variant of Fact.
     fn to_tensor_fact(&self) -> InferenceFact;
+
+    /// Check that a tensor is compatible with the fact.
+    ///
+    /// Tensors are not checked against facts with a streaming dimension.
+    fn check_tensor(&self, tensor: &Tensor) -> TractResult<()> {
+        let fact = self.to_tensor_fact();
+        if fact.shape.is_concrete() && fact.stream_info()?.is_some() {
+            return Ok(());
+        }
+        fact.unify(&InferenceFact::from(tensor)).map(|_| ())
+    }
 }
 
 impl_downcast!(Fact);
@@ -50,6 +50,7 @@ mod model;
 mod node;
 pub mod order;
 mod patch;
+pub mod quantize;
 pub(crate) mod translator;
 
 pub use self::dsl::*;
@@ -328,6 +329,64 @@ impl TypedModel {
         Ok(model)
     }
 
+    /// Store the f32 weights of matrix products and convolutions as f16.
+    ///
+    /// Products still accumulate in f32. The weights stay f16 once packed at
+    /// codegen and are converted one panel at a time as the kernels run, so
+    /// this halves their size and memory bandwidth, at the cost of precision.
+    pub fn f16_weights(mut self) -> TractResult<TypedModel> {
+        use crate::ops::cnn::ConvUnary;
+        use crate::ops::matmul::MatMulUnary;
+        for node in self.nodes_mut() {
+            if let Some(op) = node.op_as_mut::<MatMulUnary>() {
+                op.f16_weights()?;
+            } else if let Some(op) = node.op_as_mut::<ConvUnary>() {
+                op.f16_weights()?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Quantize convolutions and matrix products to i8, using the tensor
+    /// ranges observed during calibration.
+    ///
+    /// The quantized model keeps the f32 inputs and outputs of the original
+    /// one.
+    pub fn quantize(&self, calibration: &quantize::Calibration) -> TractResult<TypedModel> {
+        let model = quantize::QuantizeTranslator::new(calibration).translate_model(self)?;
+        compact::compact(&model)
+    }
+
+    /// Replace f32 matrix products with constant weights by dynamically
+    /// quantized ones: weights are stored as i8, and the input is quantized
+    /// with parameters computed at each evaluation.
+    ///
+    /// Products of inputs already quantized by DynamicQuantizeLinear (as
+    /// imported from ONNX MatMulInteger) get their runtime zero point given to
+    /// the quantized kernel instead of being corrected for afterwards.
+    ///
+    /// Expects a decluttered model.
+    pub fn quantize_dynamic(mut self) -> TractResult<TypedModel> {
+        use crate::ops::matmul::dyn_quant::fuse_runtime_zero_point;
+        use crate::ops::matmul::MatMulUnary;
+        for node in self.nodes_mut() {
+            let quantized = if let Some(op) = node.op_as::<MatMulUnary>() {
+                op.dynamic_quantize()?
+            } else {
+                None
+            };
+            if let Some(op) = quantized {
+                node.op = Box::new(op);
+            }
+        }
+        for node in self.eval_order()? {
+            if let Some(patch) = fuse_runtime_zero_point(&self, self.node(node))? {
+                patch.apply(&mut self)?;
+            }
+        }
+        compact::compact(&self)
+    }
+
     pub fn invariants(&self) -> TractResult<invariants::Invariants> {
         invariants::for_model(self)
     }
@@ -368,4 +427,41 @@ mod test {
         is_sync::<TypedModel>();
         is_sync::<NormalizedModel>();
     }
+
+    #[test]
+    fn f16_weights() {
+        use crate::internal::*;
+        use crate::ops::cnn::{Conv, ConvUnary};
+        use crate::ops::matmul::MatMul;
+        let mut model = InferenceModel::default();
+        let source = model
+            .add_source("source", InferenceFact::dt_shape(f32::datum_type(), shapefact!(1, 2, 5)))
+            .unwrap();
+        let kernel = (0..18).map(|x| (x % 5) as f32 - 2.0).collect::<Vec<_>>();
+        let kernel = ndarray::Array3::from_shape_vec((3, 2, 3), kernel).unwrap();
+        let kernel = model.add_const("kernel", kernel).unwrap();
+        let conv = model.wire_node("conv", Conv::default(), &[source, kernel]).unwrap();
+        let a = model.add_const("a", tensor2(&[[1f32, 0.5, -1.0], [2.0, 0.0, 0.25]])).unwrap();
+        let mm = model.wire_node("mm", MatMul::default(), &[a, conv[0]]).unwrap();
+        model.set_output_outlets(&mm).unwrap();
+        let model = model.into_typed().unwrap().declutter().unwrap();
+
+        let input = tensor3(&[[[0f32, 1.0, 2.0, 3.0, 4.0], [4.0, -3.0, 2.0, -1.0, 0.5]]]);
+        let expected = SimplePlan::new(&model).unwrap().run(tvec!(input.clone())).unwrap();
+
+        let half = model.f16_weights().unwrap();
+        let mut convs = half.nodes().iter().filter_map(|n| n.op_as::<ConvUnary>());
+        assert!(convs.all(|op| op.kernel.datum_type() == f16::datum_type()));
+        let found = SimplePlan::new(&half).unwrap().run(tvec!(input.clone())).unwrap();
+        found[0].close_enough(&expected[0], false).unwrap();
+
+        let optimized = half.into_optimized().unwrap();
+        use crate::ops::matmul::phy::MatMatMulUnaryFinite;
+        assert!(optimized
+            .nodes()
+            .iter()
+            .any(|n| n.op_is::<MatMatMulUnaryFinite<f16, f32, f32, f32>>()));
+        let found = SimplePlan::new(&optimized).unwrap().run(tvec!(input)).unwrap();
+        found[0].close_enough(&expected[0], false).unwrap();
+    }
 }
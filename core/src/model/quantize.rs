@@ -0,0 +1,322 @@
+//! Post-training static quantization.
+//!
+//! A `Calibration` records the range of values taken by every f32 tensor of a
+//! TypedModel on a set of representative inputs. `TypedModel::quantize` then
+//! uses these ranges to replace convolutions and matrix products by their i8
+//! counterparts, keeping the element-wise operations that follow them in the
+//! quantized domain when they support it. QuantizeLinear and DequantizeLinear
+//! operators are inserted at the boundaries with the f32 parts of the graph.
+use std::cell::RefCell;
+
+use crate::internal::*;
+use crate::model::translator::Translate;
+use crate::ops::cnn::ConvUnary;
+use crate::ops::matmul::MatMulUnary;
+use crate::ops::quant::{quantization_params_for_range, quantize_linear_i8, DequantizeLinearF32};
+
+/// Observed ranges of the f32 tensors of a model.
+#[derive(Clone, Debug, Default)]
+pub struct Calibration {
+    pub ranges: HashMap<OutletId, (f32, f32)>,
+}
+
+impl Calibration {
+    /// Run the model on one set of inputs and widen the observed ranges.
+    pub fn observe(&mut self, model: &TypedModel, inputs: TVec<Tensor>) -> TractResult<()> {
+        let mut outlets = vec![];
+        for node in model.nodes() {
+            for (slot, output) in node.outputs.iter().enumerate() {
+                if output.fact.datum_type == f32::datum_type() && output.fact.konst.is_none() {
+                    outlets.push(OutletId::new(node.id, slot));
+                }
+            }
+        }
+        let values = SimplePlan::new_for_outputs(model, &outlets)?.run(inputs)?;
+        for (outlet, value) in outlets.into_iter().zip(values.iter()) {
+            let range = self.ranges.entry(outlet).or_insert((std::f32::MAX, std::f32::MIN));
+            for &x in value.as_slice::<f32>()? {
+                range.0 = range.0.min(x);
+                range.1 = range.1.max(x);
+            }
+        }
+        Ok(())
+    }
+
+    /// Quantization step and zero point covering the observed range of an
+    /// outlet with i8 values. The range is widened to include zero, so that it
+    /// is represented exactly.
+    pub fn i8_params(&self, outlet: OutletId) -> Option<(f32, i8)> {
+        let &(min, max) = self.ranges.get(&outlet)?;
+        let (step, zero_point) = quantization_params_for_range(min, max, -128, 127);
+        Some((step, zero_point as i8))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct QuantizedWire {
+    wire: OutletId,
+    step: f32,
+    zero_point: i8,
+}
+
+#[derive(Debug)]
+pub(crate) struct QuantizeTranslator<'a> {
+    calibration: &'a Calibration,
+    quantized: RefCell<HashMap<OutletId, QuantizedWire>>,
+}
+
+impl<'a> QuantizeTranslator<'a> {
+    pub fn new(calibration: &'a Calibration) -> QuantizeTranslator<'a> {
+        QuantizeTranslator { calibration, quantized: RefCell::new(HashMap::new()) }
+    }
+
+    /// i8 version of a source outlet, quantizing its f32 value if the
+    /// calibration allows it.
+    fn quantized_input(
+        &self,
+        source: &TypedModel,
+        outlet: OutletId,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<Option<QuantizedWire>> {
+        if let Some(q) = self.quantized.borrow().get(&outlet) {
+            return Ok(Some(*q));
+        }
+        if source.outlet_fact(outlet)?.datum_type != f32::datum_type() {
+            return Ok(None);
+        }
+        if let Some((step, zero_point)) = self.calibration.i8_params(outlet) {
+            let wire = target.wire_node(
+                format!("{}-quantize", source.node(outlet.node).name),
+                quantize_linear_i8(step.recip(), zero_point),
+                &[mapping[&outlet]],
+            )?[0];
+            let q = QuantizedWire { wire, step, zero_point };
+            self.quantized.borrow_mut().insert(outlet, q);
+            Ok(Some(q))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Wire a quantized operator, and its dequantized f32 output.
+    fn wire_quantized(
+        &self,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        op: Box<dyn TypedOp>,
+        input: QuantizedWire,
+        step: f32,
+        zero_point: i8,
+    ) -> TractResult<TVec<OutletId>> {
+        let wire = target.wire_node(&*node.name, op, &[input.wire])?[0];
+        self.quantized
+            .borrow_mut()
+            .insert(OutletId::new(node.id, 0), QuantizedWire { wire, step, zero_point });
+        target.wire_node(
+            format!("{}-dequantize", node.name),
+            DequantizeLinearF32::new(step, zero_point as i32),
+            &[wire],
+        )
+    }
+
+    fn translate_quantized(
+        &self,
+        source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<Option<TVec<OutletId>>> {
+        if node.inputs.len() != 1 || node.outputs.len() != 1 {
+            return Ok(None);
+        }
+        let output = OutletId::new(node.id, 0);
+        let quantized_input = self.quantized.borrow().get(&node.inputs[0]).cloned();
+        if node.op_is::<ConvUnary>() || node.op_is::<MatMulUnary>() {
+            let (step, zero_point) = if let Some(p) = self.calibration.i8_params(output) {
+                p
+            } else {
+                return Ok(None);
+            };
+            let input = if let Some(input) =
+                self.quantized_input(source, node.inputs[0], target, mapping)?
+            {
+                input
+            } else {
+                return Ok(None);
+            };
+            let params = ((input.step, input.zero_point), (step, zero_point));
+            let op: Option<Box<dyn TypedOp>> = if let Some(conv) = node.op_as::<ConvUnary>() {
+                conv.quantize_i8(params.0, params.1)?.map(|op| Box::new(op) as _)
+            } else if let Some(mm) = node.op_as::<MatMulUnary>() {
+                mm.quantize_i8(params.0, params.1)?.map(|op| Box::new(op) as _)
+            } else {
+                None
+            };
+            if let Some(op) = op {
+                return Ok(Some(self.wire_quantized(node, target, op, input, step, zero_point)?));
+            }
+        } else if let Some(input) = quantized_input {
+            // operators following a quantized one stay in its quantization
+            if let Some(op) = node.op.quantize(
+                source,
+                node,
+                i8::datum_type(),
+                input.step.recip(),
+                input.zero_point as i32,
+            )? {
+                return Ok(Some(self.wire_quantized(
+                    node,
+                    target,
+                    op,
+                    input,
+                    input.step,
+                    input.zero_point,
+                )?));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a> Translate<TypedFact, Box<dyn TypedOp>, TypedFact, Box<dyn TypedOp>>
+    for QuantizeTranslator<'a>
+{
+    fn translate_node(
+        &self,
+        source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if let Some(outlets) = self.translate_quantized(source, node, target, mapping)? {
+            return Ok(outlets);
+        }
+        let facts = node.outputs.iter().map(|of| of.fact.clone()).collect();
+        let id = target.add_node(&*node.name, node.op.clone(), facts)?;
+        for (ix, o) in node.inputs.iter().enumerate() {
+            target.add_edge(mapping[o], InletId::new(id, ix))?
+        }
+        Ok((0..node.outputs.len()).map(|ix| OutletId::new(id, ix)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::Conv;
+    use crate::ops::matmul::MatMul;
+
+    fn model() -> TypedModel {
+        let mut model = InferenceModel::default();
+        let source = model
+            .add_source("source", InferenceFact::dt_shape(f32::datum_type(), shapefact!(1, 2, 6)))
+            .unwrap();
+        let kernel = (0..18).map(|x| (x % 7) as f32 / 3.0 - 1.0).collect::<Vec<_>>();
+        let kernel = ndarray::Array3::from_shape_vec((3, 2, 3), kernel).unwrap();
+        let kernel = model.add_const("kernel", kernel).unwrap();
+        let bias = model.add_const("bias", tensor1(&[0.5f32, -0.25, 0.0])).unwrap();
+        let conv = model.wire_node("conv", Conv::default(), &[source, kernel, bias]).unwrap();
+        let relu =
+            model.wire_node("relu", crate::ops::math::scalar_max(tensor0(0f32)), &conv).unwrap();
+        let a = model.add_const("a", tensor2(&[[1f32, 0.5, -1.0], [2.0, 0.0, 0.25]])).unwrap();
+        let mm = model.wire_node("mm", MatMul::default(), &[a, relu[0]]).unwrap();
+        model.set_output_outlets(&mm).unwrap();
+        model.into_typed().unwrap().declutter().unwrap()
+    }
+
+    fn input(seed: usize) -> Tensor {
+        let values = (0..12).map(|x| ((x * 7 + seed * 3) % 11) as f32 / 5.0 - 1.0).collect();
+        ndarray::Array3::from_shape_vec((1, 2, 6), values).unwrap().into_tensor()
+    }
+
+    #[test]
+    fn i8_params() {
+        let mut calibration = Calibration::default();
+        calibration.ranges.insert(OutletId::new(0, 0), (-1.0, 3.0));
+        let (step, zp) = calibration.i8_params(OutletId::new(0, 0)).unwrap();
+        assert_eq!(crate::ops::quant::quantize_linear_f32_i8(-1.0, step.recip(), zp as i32), -128);
+        assert_eq!(crate::ops::quant::quantize_linear_f32_i8(0.0, step.recip(), zp as i32), zp);
+        assert_eq!(crate::ops::quant::quantize_linear_f32_i8(3.0, step.recip(), zp as i32), 127);
+    }
+
+    #[test]
+    fn conv_relu_matmul() {
+        let model = model();
+        let mut calibration = Calibration::default();
+        for seed in 0..4 {
+            calibration.observe(&model, tvec!(input(seed))).unwrap();
+        }
+        let quantized = model.quantize(&calibration).unwrap();
+        assert!(quantized
+            .nodes()
+            .iter()
+            .filter_map(|n| n.op_as::<ConvUnary>())
+            .all(|conv| conv.kernel.datum_type() == i8::datum_type()));
+        assert!(quantized.nodes().iter().any(|n| n.op_is::<DequantizeLinearF32>()));
+
+        let (min, max) = calibration.ranges[&model.output_outlets().unwrap()[0]];
+        let tolerance = (max - min) / 255.0 * 4.0;
+        let declutterred = quantized.declutter().unwrap();
+        let optimized = declutterred.clone().into_optimized().unwrap();
+        for seed in 0..4 {
+            let expected = SimplePlan::new(&model).unwrap().run(tvec!(input(seed))).unwrap();
+            for m in &[&declutterred, &optimized] {
+                let found = SimplePlan::new(*m).unwrap().run(tvec!(input(seed))).unwrap();
+                let expected = expected[0].to_array_view::<f32>().unwrap();
+                let found = found[0].to_array_view::<f32>().unwrap();
+                assert_eq!(found.shape(), expected.shape());
+                for (f, e) in found.iter().zip(expected.iter()) {
+                    assert!((f - e).abs() <= tolerance, "{:?} {:?}", found, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn conv_3x3x256_per_channel() {
+        // k = 3 * 3 * 256, output channels with widely spread kernel magnitudes
+        let mut model = InferenceModel::default();
+        let source = model
+            .add_source(
+                "source",
+                InferenceFact::dt_shape(f32::datum_type(), shapefact!(1, 256, 5, 5)),
+            )
+            .unwrap();
+        let amplitudes = [1.0f32, 0.4, 0.05, 0.01];
+        let kernel = ndarray::Array4::from_shape_fn((4, 256, 3, 3), |(o, i, y, x)| {
+            amplitudes[o] * (((o * 5 + i * 7 + y * 3 + x) % 13) as f32 / 6.0 - 1.0)
+        });
+        let kernel = model.add_const("kernel", kernel).unwrap();
+        let conv = model.wire_node("conv", Conv::default(), &[source, kernel]).unwrap();
+        model.set_output_outlets(&conv).unwrap();
+        let model = model.into_typed().unwrap().declutter().unwrap();
+        let input = |seed: usize| {
+            ndarray::Array4::from_shape_fn((1, 256, 5, 5), |(_, c, y, x)| {
+                ((c * 11 + y * 5 + x * 3 + seed * 7) % 17) as f32 / 8.0 - 1.0
+            })
+            .into_tensor()
+        };
+
+        let mut calibration = Calibration::default();
+        for seed in 0..4 {
+            calibration.observe(&model, tvec!(input(seed))).unwrap();
+        }
+        let quantized = model.quantize(&calibration).unwrap();
+        let (min, max) = calibration.ranges[&model.output_outlets().unwrap()[0]];
+        let tolerance = (max - min) / 255.0 * 2.0;
+        let optimized = quantized.clone().into_optimized().unwrap();
+        for seed in 0..4 {
+            let expected = SimplePlan::new(&model).unwrap().run(tvec!(input(seed))).unwrap();
+            let expected = expected[0].to_array_view::<f32>().unwrap();
+            for m in &[&quantized, &optimized] {
+                let found = SimplePlan::new(*m).unwrap().run(tvec!(input(seed))).unwrap();
+                let found = found[0].to_array_view::<f32>().unwrap();
+                assert_eq!(found.shape(), expected.shape());
+                for (f, e) in found.iter().zip(expected.iter()) {
+                    assert!((f - e).abs() <= tolerance, "found {} expected {}", f, e);
+                }
+            }
+        }
+    }
+}
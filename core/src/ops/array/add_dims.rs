@@ -156,10 +156,14 @@ impl PulsedOp for AddDim {
         let mut fact = inputs[0].clone();
         fact.shape = inputs[0].shape.clone();
         fact.shape.insert(self.axis, 1);
-        fact.axis += (self.axis >= fact.axis) as usize;
+        fact.axis += (self.axis <= fact.axis) as usize;
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
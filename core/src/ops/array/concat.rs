@@ -221,7 +221,7 @@ impl Op for NormConcat {
     }
 
     op_as_typed_op!();
-    not_a_pulsed_op!();
+    op_as_pulsed_op!();
 }
 
 impl TypedOp for NormConcat {
@@ -304,10 +304,6 @@ impl TypedOp for NormConcat {
         mapping: &HashMap<OutletId, OutletId>,
         _pulse: usize,
     ) -> TractResult<TVec<OutletId>> {
-        if node.inputs.len() > 1 {
-            bail!("Pulsification not implemented for more than one input to Concat")
-        }
-
         let input = mapping[&node.inputs[0]];
         let fact = target.outlet_fact(input)?;
 
@@ -316,7 +312,7 @@ impl TypedOp for NormConcat {
                 self, source, node, target, mapping
             ))
         } else {
-            bail!("Pulsify for Concat on a separate axis is not implemented (but possible)");
+            self.pulsify_along_other_axis(node, target, mapping)
         }
     }
 
@@ -350,16 +346,35 @@ impl TypedOp for NormConcat {
     }
 }
 
+impl PulsedOp for NormConcat {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape[self.axis] = inputs.iter().map(|f| f.shape[self.axis]).sum();
+        Ok(tvec!(fact))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
 impl StatelessOp for NormConcat {
     /// Evaluates the operation given the input tensors.
     fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
-        let dts = inputs.iter().map(|x| x.datum_type()).chain(self.slices.iter().filter_map(|s| {
-            if let NormConcatSlice::Const(s) = s {
-                Some(s.datum_type())
-            } else {
-                None
-            }
-        })).collect::<TVec<_>>();
+        let dts = inputs
+            .iter()
+            .map(|x| x.datum_type())
+            .chain(self.slices.iter().filter_map(|s| {
+                if let NormConcatSlice::Const(s) = s {
+                    Some(s.datum_type())
+                } else {
+                    None
+                }
+            }))
+            .collect::<TVec<_>>();
         let super_type: DatumType = DatumType::super_type_for(&dts)
             .chain_err(|| format!("No supertype found for {:?}", dts))?;
         let shapes = inputs.iter().map(|t| t.shape()).collect::<TVec<_>>();
@@ -370,6 +385,39 @@ impl StatelessOp for NormConcat {
 }
 
 impl NormConcat {
+    fn pulsify_along_other_axis(
+        &self,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if self.slices.iter().any(|s| !s.is_var()) {
+            bail!("Pulsify for Concat on a separate axis with constant slices is not implemented")
+        }
+        let facts = node
+            .inputs
+            .iter()
+            .map(|i| target.outlet_fact(mapping[i]).map(|f| f.clone()))
+            .collect::<TractResult<TVec<_>>>()?;
+        if facts.iter().any(|f| f.axis != facts[0].axis) {
+            bail!("Can not pulsify Concat of inputs streaming along different axes")
+        }
+        let delay = facts.iter().map(|f| f.delay).max().unwrap();
+        let mut inputs = tvec!();
+        for (ix, fact) in facts.iter().enumerate() {
+            let mut input = mapping[&node.inputs[ix]];
+            if fact.delay < delay {
+                input = target.wire_node(
+                    format!("{}/Delay-{}", &*node.name, ix),
+                    Delay::new(fact, delay - fact.delay, 0),
+                    &[input],
+                )?[0];
+            }
+            inputs.push(input);
+        }
+        target.wire_node(&*node.name, self.clone(), &*inputs)
+    }
+
     fn pulsify_along_concat_axis_t<T: Datum>(
         &self,
         _source: &NormalizedModel,
@@ -407,13 +455,8 @@ impl NormConcat {
                 &[input],
             )?[0];
         }
-        let main_op = PulsedSameAxisConcat::new(
-            self.axis,
-            pre,
-            post,
-            fact.delay.saturating_sub(before),
-            fact.dim,
-        );
+        let main_op =
+            PulsedSameAxisConcat::new(self.axis, pre, post, fact.delay.max(before), fact.dim);
         target.wire_node(&*node.name, main_op, &[input])
     }
 }
@@ -539,6 +582,10 @@ impl<T: Datum> PulsedOp for PulsedSameAxisConcat<T> {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -586,6 +633,15 @@ impl<T: Datum> OpState for PulsedSameAxisConcatState<T> {
 
         return Ok(tvec!(data.into_arc_tensor()));
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        Ok(OpStateSnapshot::new(tvec!(tensor0(self.current_pos as i64))))
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        self.current_pos = snapshot.usize(0)?;
+        Ok(())
+    }
 }
 
 ////////////////////////////////////////////////
@@ -71,7 +71,7 @@ impl Gather {
             {
                 let mut to_update = output.index_axis_mut(Axis(axis), pattern[0]);
                 for idx in 1..pattern.ndim() {
-                    to_update = to_update.index_axis_move(Axis(0), pattern[idx]);
+                    to_update = to_update.index_axis_move(Axis(axis), pattern[idx]);
                 }
 
                 to_update.assign(&data_view.index_axis(Axis(axis), *index as usize));
@@ -91,6 +91,25 @@ impl TypedOp for Gather {
                 .compute_output_shape(&*inputs[0].shape.to_tvec(), &*inputs[1].shape.to_tvec())?
         )?))
     }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let data_fact = model.outlet_fact(node.inputs[0])?;
+        if let Some(indices) = model.outlet_fact(node.inputs[1])?.konst.clone() {
+            let axis = self.resolved_axis(data_fact.shape.rank())?;
+            let patch = TypedModelPatch::replace_single_op(
+                model,
+                node,
+                &node.inputs[0..1],
+                GatherUnary::new(axis, indices),
+            )?;
+            return Ok(Some(patch));
+        }
+        Ok(None)
+    }
 }
 
 impl StatelessOp for Gather {
@@ -125,6 +144,116 @@ impl InferenceRulesOp for Gather {
     to_typed!();
 }
 
+/// Gather with constant indices.
+#[derive(Debug, Clone, new)]
+pub struct GatherUnary {
+    pub axis: usize,
+    pub indices: Arc<Tensor>,
+}
+
+impl GatherUnary {
+    fn output_axis(&self, input_axis: usize) -> Option<usize> {
+        if input_axis < self.axis {
+            Some(input_axis)
+        } else if input_axis > self.axis {
+            Some(input_axis + self.indices.rank() - 1)
+        } else {
+            None
+        }
+    }
+}
+
+impl Op for GatherUnary {
+    fn name(&self) -> Cow<str> {
+        "GatherUnary".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} indices: {:?}", self.axis, self.indices)])
+    }
+
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatelessOp for GatherUnary {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let data = args_1!(inputs);
+        Gather::new(self.axis as i64).eval(tvec!(data, self.indices.clone()))
+    }
+}
+
+impl TypedOp for GatherUnary {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let indices_shape: TVec<TDim> = self.indices.shape().iter().map(|d| d.to_dim()).collect();
+        Ok(tvec!(TypedFact::dt_shape(
+            inputs[0].datum_type,
+            &*Gather::new(self.axis as i64)
+                .compute_output_shape(&*inputs[0].shape.to_tvec(), &*indices_shape)?
+        )?))
+    }
+
+    fn invariants(&self, model: &TypedModel, node: &TypedNode) -> TractResult<Invariants> {
+        let rank = model.outlet_fact(node.inputs[0])?.shape.rank();
+        Ok((0..rank)
+            .filter_map(|axis| {
+                self.output_axis(axis).map(|output| AxisInfo {
+                    outputs: tvec!(Some(output)),
+                    ..AxisInfo::simple(axis)
+                })
+            })
+            .collect())
+    }
+
+    fn dispose_dummy_axis(
+        &self,
+        _model: &TypedModel,
+        _node: &TypedNode,
+        axes: &[Option<usize>],
+    ) -> TractResult<Option<Box<dyn TypedOp>>> {
+        let axis = self.axis - (axes[0].unwrap() < self.axis) as usize;
+        Ok(Some(Box::new(GatherUnary::new(axis, self.indices.clone()))))
+    }
+
+    fn pulsify(
+        &self,
+        source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let axis = target.outlet_fact(input)?.axis;
+        let invariants = crate::pulse::normalized_invariants(source, node)?;
+        if crate::pulse::track_stream_axis(&invariants, axis).is_none() {
+            bail!("Can not pulsify {:?} along its streaming axis {}", self, axis)
+        }
+        target.wire_node(&*node.name, self.clone(), &[input])
+    }
+}
+
+impl PulsedOp for GatherUnary {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape = Gather::new(self.axis as i64)
+            .compute_output_shape(&*inputs[0].shape, self.indices.shape())?;
+        fact.axis = self
+            .output_axis(inputs[0].axis)
+            .ok_or_else(|| format!("Can not gather along the streaming axis {}", self.axis))?;
+        Ok(tvec!(fact))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,13 +63,13 @@ mod tile;
 
 pub use self::add_dims::{AddDim, AddDims};
 pub use self::broadcast::{MultiBroadcastTo, TypedMultiBroadcastTo};
-pub use self::concat::{Concat, NormConcat};
+pub use self::concat::{Concat, NormConcat, NormConcatSlice};
 pub use self::constant_like::ConstantLike;
 pub use self::constant_like::EyeLike;
 pub use self::constant_of_shape::ConstantOfShape;
 pub use self::crop::Crop;
 pub use self::flatten::Flatten;
-pub use self::gather::Gather;
+pub use self::gather::{Gather, GatherUnary};
 pub use self::pad::{Pad, PadMode};
 pub use self::permute_axes::PermuteAxes;
 pub use self::reshape::{FiniteReshape, Reshape, TypedReshape};
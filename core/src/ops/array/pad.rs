@@ -179,7 +179,16 @@ impl TypedOp for Pad {
                 }
             },
             PadMode::Edge => bail!("Edge padding mode needs pulse strictly bigger than left padding (pulse={} padding={})", pulse, before),
-            PadMode::Reflect => bail!("Reflect padding mode pulsing is not supported")
+            PadMode::Reflect if 2 * before < pulse => {
+                // left padding and the frames it reflects must fit in a pulse
+                let start_offset = (fact.delay + extra_delay) % pulse;
+                if before > start_offset {
+                    extra_delay += before - start_offset;
+                } else if start_offset + before >= pulse {
+                    extra_delay += pulse - start_offset + before;
+                }
+            },
+            PadMode::Reflect => bail!("Reflect padding mode needs pulse strictly bigger than twice the left padding (pulse={} padding={})", pulse, before),
         };
         if extra_delay > 0 {
             input = target.wire_node(
@@ -190,7 +199,6 @@ impl TypedOp for Pad {
         }
         let op = PulsePad::<f32>::new(
             fact.axis,
-            pulse,
             before,
             after,
             fact.delay + extra_delay,
@@ -204,7 +212,9 @@ impl TypedOp for Pad {
 #[derive(Debug, Clone, Default, new)]
 struct PulsePadOpState<T: Datum + Copy> {
     current_pos: usize,
-    last_valid_frame: Option<Tensor>,
+    /// Last valid input frames, enough to fill the right padding in Edge and
+    /// Reflect modes once the end of the stream is known.
+    last_valid_frames: Option<Tensor>,
     _slimer: PhantomData<T>,
 }
 
@@ -216,22 +226,27 @@ impl<T: Datum + Copy> OpState for PulsePadOpState<T> {
         mut inputs: TVec<Arc<Tensor>>,
     ) -> TractResult<TVec<Arc<Tensor>>> {
         let op = op.downcast_ref::<PulsePad<T>>().ok_or("Wrong Op type")?;
+        let pulse = inputs[0].shape()[op.axis];
         let pulse_begin = self.current_pos;
-        let pulse_end = self.current_pos + op.pulse;
-        self.current_pos += op.pulse;
+        let pulse_end = self.current_pos + pulse;
+        self.current_pos += pulse;
         let end_input = session
             .known_stream_len
             .map(|s| op.end_input.eval(s as i32).unwrap() as usize)
             .unwrap_or(std::usize::MAX);
 
-        if let PadMode::Edge = op.mode {
-            if op.after > 0 && pulse_begin < end_input {
-                let latest_valid_frame = (end_input - pulse_begin).min(op.pulse) - 1;
-                let data = inputs[0].to_array_view::<T>()?;
-                self.last_valid_frame = Some(
-                    data.index_axis(Axis(op.axis), latest_valid_frame).to_owned().into_tensor(),
-                );
-            }
+        let keep = op.kept_frames();
+        if keep > 0 && pulse_begin < end_input {
+            let valid = (end_input - pulse_begin).min(pulse);
+            let data = inputs[0].to_array_view::<T>()?;
+            let data = data.slice_axis(Axis(op.axis), (..valid).into());
+            let frames = match self.last_valid_frames.take() {
+                Some(frames) => stack(Axis(op.axis), &[frames.to_array_view::<T>()?, data])?,
+                None => data.to_owned(),
+            };
+            let len = frames.shape()[op.axis];
+            let frames = frames.slice_axis(Axis(op.axis), (len.saturating_sub(keep)..).into());
+            self.last_valid_frames = Some(frames.to_owned().into_tensor());
         }
 
         // pulse is entirely in valid input, just forward
@@ -248,7 +263,7 @@ impl<T: Datum + Copy> OpState for PulsePadOpState<T> {
         let mut data = input.into_tensor().into_array::<T>()?;
 
         if pulse_begin < op.begin_input {
-            let fill_up_to = (op.begin_input - pulse_begin).min(op.pulse);
+            let fill_up_to = (op.begin_input - pulse_begin).min(pulse);
             match &op.mode {
                 PadMode::Constant(c) => {
                     let c = c.to_scalar::<T>()?;
@@ -262,36 +277,61 @@ impl<T: Datum + Copy> OpState for PulsePadOpState<T> {
                         padding.index_axis_mut(Axis(op.axis), i).assign(&first_frame);
                     }
                 }
-                _ => unimplemented!(),
+                PadMode::Reflect => {
+                    // pulsify made sure the reflected frames are in this pulse
+                    let fill_from = (op.begin_input - op.before).saturating_sub(pulse_begin);
+                    for i in fill_from..fill_up_to {
+                        let source = 2 * (op.begin_input - pulse_begin) - i;
+                        let frame = data.index_axis(Axis(op.axis), source).to_owned();
+                        data.index_axis_mut(Axis(op.axis), i).assign(&frame);
+                    }
+                }
             }
         }
         if pulse_end > end_input && op.after > 0 {
-            let fill_from = op.pulse - (pulse_end - end_input).min(op.pulse);
+            let fill_from = pulse - (pulse_end - end_input).min(pulse);
             match &op.mode {
                 PadMode::Constant(c) => {
                     let c = c.to_scalar::<T>()?;
-                    data.slice_axis_mut(Axis(op.axis), (fill_from..op.pulse).into()).fill(*c);
+                    data.slice_axis_mut(Axis(op.axis), (fill_from..pulse).into()).fill(*c);
                 }
-                PadMode::Edge => {
-                    let mut data = data.view_mut();
-                    let last_frame =
-                        self.last_valid_frame.as_ref().unwrap().to_array_view::<T>()?;
-                    for i in fill_from..op.pulse {
-                        data.index_axis_mut(Axis(op.axis), i).assign(&last_frame);
+                PadMode::Edge | PadMode::Reflect => {
+                    // last_valid_frames ends on the last frame of the stream
+                    let frames = self.last_valid_frames.as_ref().unwrap().to_array_view::<T>()?;
+                    let kept = frames.shape()[op.axis];
+                    let fill_to = (end_input + op.after).saturating_sub(pulse_begin).min(pulse);
+                    for i in fill_from..fill_to {
+                        let source = if op.mode == PadMode::Edge {
+                            end_input - 1
+                        } else {
+                            2 * (end_input - 1) - (pulse_begin + i)
+                        };
+                        let frame = frames.index_axis(Axis(op.axis), kept - (end_input - source));
+                        data.index_axis_mut(Axis(op.axis), i).assign(&frame);
                     }
                 }
-                _ => unimplemented!(),
             }
         }
 
         Ok(tvec!(data.into_arc_tensor()))
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        let mut tensors = tvec!(tensor0(self.current_pos as i64));
+        tensors.extend(self.last_valid_frames.iter().cloned());
+        Ok(OpStateSnapshot::new(tensors))
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        self.current_pos = snapshot.usize(0)?;
+        self.last_valid_frames = snapshot.tensors.get(1).cloned();
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Default, new)]
 struct PulsePad<T: Datum + Copy> {
     axis: usize,
-    pulse: usize,
     before: usize,
     after: usize,
     begin_input: usize,
@@ -300,6 +340,20 @@ struct PulsePad<T: Datum + Copy> {
     _slimer: PhantomData<T>,
 }
 
+impl<T: Datum + Copy> PulsePad<T> {
+    /// Number of trailing valid frames the state keeps to fill the right padding.
+    fn kept_frames(&self) -> usize {
+        if self.after == 0 {
+            return 0;
+        }
+        match self.mode {
+            PadMode::Edge => 1,
+            PadMode::Reflect => self.after + 1,
+            PadMode::Constant(_) => 0,
+        }
+    }
+}
+
 impl<T: Datum + Copy> Op for PulsePad<T> {
     fn name(&self) -> Cow<str> {
         "PulsePad".into()
@@ -335,6 +389,18 @@ impl<T: Datum + Copy> TypedOp for PulsePad<T> {
         Ok(tvec!(inputs[0].clone()))
     }
 
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let keep = self.kept_frames();
+        if keep == 0 {
+            return Ok(tvec!());
+        }
+        let frame: TDim = (0..inputs[0].shape.rank())
+            .filter(|&ax| ax != self.axis)
+            .map(|ax| inputs[0].shape.dim(ax))
+            .product();
+        Ok(tvec!((Cost::Buffer(T::datum_type()), frame * keep)))
+    }
+
     typed_op_as_op!();
 }
 
@@ -346,6 +412,10 @@ impl<T: Datum + Copy> PulsedOp for PulsePad<T> {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -1,13 +1,20 @@
 use crate::internal::*;
 
-// FIXME: pulsification is very fragile
-
 #[derive(Debug, Clone, new)]
 pub struct PermuteAxes {
     pub axes: Option<Vec<usize>>,
 }
 
 impl PermuteAxes {
+    /// For each output axis, the input axis it comes from.
+    fn permutation(&self, rank: usize) -> Vec<usize> {
+        if let Some(axes) = self.axes.clone() {
+            axes
+        } else {
+            (0..rank).rev().collect()
+        }
+    }
+
     fn compute_shape<D: DimLike>(&self, input: &[D]) -> TVec<D> {
         if let Some(ref axes) = self.axes {
             let mut new_shape = tvec![D::zero(); input.len()];
@@ -86,16 +93,12 @@ impl TypedOp for PermuteAxes {
     }
 
     fn invariants(&self, model: &TypedModel, node: &TypedNode) -> TractResult<Invariants> {
-        let permutation = if let Some(axes) = self.axes.clone() {
-            axes
-        } else {
-            (0..model.outlet_fact(node.inputs[0])?.shape.rank()).rev().collect()
-        };
+        let permutation = self.permutation(model.outlet_fact(node.inputs[0])?.shape.rank());
         let mut infos = tvec!();
-        for (from, to) in permutation.iter().enumerate() {
+        for (to, from) in permutation.iter().enumerate() {
             infos.push(AxisInfo {
-                inputs: tvec!(Some(from)),
-                outputs: tvec!(Some(*to)),
+                inputs: tvec!(Some(*from)),
+                outputs: tvec!(Some(to)),
                 period: 1,
                 disposable: true,
             })
@@ -110,16 +113,11 @@ impl TypedOp for PermuteAxes {
         axes: &[Option<usize>],
     ) -> TractResult<Option<Box<dyn TypedOp>>> {
         let axis = axes[0].unwrap();
-        let permutation = if let Some(axes) = self.axes.clone() {
-            axes
-        } else {
-            (0..model.outlet_fact(node.inputs[0])?.shape.rank()).rev().collect()
-        };
-        let output_axis = permutation[axis];
+        let permutation = self.permutation(model.outlet_fact(node.inputs[0])?.shape.rank());
         let new_permutation = permutation
             .into_iter()
             .filter(|&src| axis != src)
-            .map(|dst| dst - (dst >= output_axis) as usize)
+            .map(|src| src - (src > axis) as usize)
             .collect();
         Ok(Some(Box::new(PermuteAxes::new(Some(new_permutation)))))
     }
@@ -133,13 +131,6 @@ impl TypedOp for PermuteAxes {
         _pulse: usize,
     ) -> TractResult<TVec<OutletId>> {
         let input = mapping[&node.inputs[0]];
-        let mut fact = target.outlet_fact(input)?.clone();
-        if let Some(axes) = &self.axes {
-            fact.axis = axes.iter().position(|x| x == &fact.axis).ok_or_else(|| {
-                format!("Could not find streaming axis {} if permute axes {:?}", fact.axis, axes)
-            })?;
-            fact.shape = axes.iter().map(|idx| fact.shape[*idx]).collect();
-        }
         target.wire_node(&*node.name, self.clone(), &[input])
     }
 
@@ -149,15 +140,18 @@ impl TypedOp for PermuteAxes {
 impl PulsedOp for PermuteAxes {
     fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
         let mut fact = inputs[0].clone();
-        fact.axis = if let Some(axes) = &self.axes {
-            axes[fact.axis]
-        } else {
-            fact.shape.len() - 1 - fact.axis
-        };
+        let permutation = self.permutation(fact.shape.len());
+        fact.axis = permutation.iter().position(|x| *x == inputs[0].axis).ok_or_else(|| {
+            format!("Could not find streaming axis {} in permutation {:?}", fact.axis, permutation)
+        })?;
         fact.shape = self.compute_shape(&*inputs[0].shape);
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
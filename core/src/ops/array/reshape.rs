@@ -96,6 +96,29 @@ pub struct TypedReshape {
     shape: TVec<TDim>,
 }
 
+impl TypedReshape {
+    /// Axes carried over unchanged from the input to the output: the leading
+    /// and trailing axes where both shapes agree.
+    ///
+    /// These are not exposed as the op invariants, as the target shape also
+    /// encodes the length of these axes.
+    fn axis_mapping(&self, input: &[TDim]) -> Invariants {
+        let prefix = input.iter().zip(self.shape.iter()).take_while(|(i, o)| i == o).count();
+        let suffix = input[prefix..]
+            .iter()
+            .rev()
+            .zip(self.shape[prefix..].iter().rev())
+            .take_while(|(i, o)| i == o)
+            .count();
+        let prefix = (0..prefix).map(|axis| (axis, axis));
+        let suffix = (1..=suffix).map(|k| (input.len() - k, self.shape.len() - k));
+        prefix
+            .chain(suffix)
+            .map(|(i, o)| AxisInfo { outputs: tvec!(Some(o)), ..AxisInfo::simple(i) })
+            .collect()
+    }
+}
+
 impl Op for TypedReshape {
     fn name(&self) -> Cow<str> {
         "TypedReshape".into()
@@ -106,7 +129,7 @@ impl Op for TypedReshape {
     }
 
     op_as_typed_op!();
-    not_a_pulsed_op!();
+    op_as_pulsed_op!();
 }
 
 impl StatelessOp for TypedReshape {
@@ -145,6 +168,43 @@ impl TypedOp for TypedReshape {
         }
         Ok(None)
     }
+
+    fn pulsify(
+        &self,
+        source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let fact = target.outlet_fact(input)?;
+        let (axis, pulse) = (fact.axis, fact.pulse());
+        let invariants = self.axis_mapping(&*source.outlet_fact(node.inputs[0])?.shape.to_tvec());
+        let output_axis = crate::pulse::track_stream_axis(&invariants, axis).ok_or_else(|| {
+            format!("Can not pulsify {:?} along its streaming axis {}", self, axis)
+        })?[0];
+        let mut op = self.clone();
+        op.shape[output_axis] = pulse.to_dim();
+        target.wire_node(&*node.name, op, &[input])
+    }
+}
+
+impl PulsedOp for TypedReshape {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        let input_shape: TVec<TDim> = fact.shape.iter().map(|d| d.to_dim()).collect();
+        fact.axis = crate::pulse::track_stream_axis(&self.axis_mapping(&input_shape), fact.axis)
+            .ok_or_else(|| {
+                format!("Streaming axis {} is not preserved by {:?}", fact.axis, self)
+            })?[0];
+        fact.shape =
+            self.shape.iter().map(|d| Ok(d.to_integer()? as usize)).collect::<TractResult<_>>()?;
+        Ok(tvec!(fact))
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
 }
 
 #[derive(Debug, Clone, new, Default)]
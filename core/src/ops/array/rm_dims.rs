@@ -246,6 +246,10 @@ impl PulsedOp for RmDim {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -157,12 +157,16 @@ impl<D: DimLike + ToDim> TypedOp for Slice<D> {
 
 impl<D: DimLike + ToDim> PulsedOp for Slice<D> {
     fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        // slices along the streaming axis are pulsified as PulsedAxisSlice
         let mut fact = inputs[0].clone();
-        fact.delay += self.start.to_integer()? as usize;
-        fact.dim = (self.end.clone() - &self.start).to_dim();
+        fact.shape[self.axis] = (self.end.clone() - &self.start).to_integer()? as usize;
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -205,5 +209,9 @@ impl PulsedOp for PulsedAxisSlice {
         Box::new(crate::ops::identity::Identity::default())
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
 }
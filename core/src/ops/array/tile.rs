@@ -110,7 +110,7 @@ impl Op for TypedTile {
     }
 
     op_as_typed_op!();
-    not_a_pulsed_op!();
+    op_as_pulsed_op!();
 }
 
 impl StatelessOp for TypedTile {
@@ -132,4 +132,59 @@ impl TypedOp for TypedTile {
             .collect::<TVec<_>>();
         Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*shape)?))
     }
+
+    fn invariants(&self, _model: &TypedModel, _node: &TypedNode) -> TractResult<Invariants> {
+        Ok(self
+            .multipliers
+            .iter()
+            .enumerate()
+            .filter(|(_, &m)| m == 1)
+            .map(|(axis, _)| AxisInfo::simple(axis))
+            .collect())
+    }
+
+    fn dispose_dummy_axis(
+        &self,
+        _model: &TypedModel,
+        _node: &TypedNode,
+        axes: &[Option<usize>],
+    ) -> TractResult<Option<Box<dyn TypedOp>>> {
+        let mut multipliers = self.multipliers.clone();
+        multipliers.remove(axes[0].unwrap());
+        Ok(Some(Box::new(TypedTile::new(multipliers))))
+    }
+
+    fn pulsify(
+        &self,
+        source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let axis = target.outlet_fact(input)?.axis;
+        let invariants = crate::pulse::normalized_invariants(source, node)?;
+        if crate::pulse::track_stream_axis(&invariants, axis).is_none() {
+            bail!("Can not pulsify {:?} along its streaming axis {}", self, axis)
+        }
+        target.wire_node(&*node.name, self.clone(), &[input])
+    }
+}
+
+impl PulsedOp for TypedTile {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        for (d, m) in fact.shape.iter_mut().zip(self.multipliers.iter()) {
+            *d *= m;
+        }
+        Ok(tvec!(fact))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
 }
@@ -112,6 +112,10 @@ impl PulsedOp for AvgPool {
         self.pool_spec.pulsed_output_facts(inputs)
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -125,9 +125,14 @@ impl Conv {
                     bail!("Input scale must be const")
                 }
             }
+            let mut per_channel_scale = None;
             if let Some(slot) = self.k_scale_input {
                 if let Some(ref value) = inputs[slot].borrow().konst {
-                    scale *= value.to_scalar::<f32>()?;
+                    if value.len() == 1 {
+                        scale *= value.as_slice::<f32>()?[0];
+                    } else {
+                        per_channel_scale = Some(value.as_slice::<f32>()?.to_vec());
+                    }
                 } else {
                     bail!("Filter scale must be const")
                 }
@@ -139,7 +144,10 @@ impl Conv {
                     bail!("Output scale must be const")
                 }
             }
-            if scale != 1.0 {
+            if let Some(per_channel) = per_channel_scale {
+                let scales: Vec<f32> = per_channel.iter().map(|s| s * scale).collect();
+                qp.get_or_insert(QParams::new(dt)).set_scale_factor_per_row(&rctensor1(&scales))?;
+            } else if scale != 1.0 {
                 qp.get_or_insert(QParams::new(dt)).set_scale_factor(scale);
             }
             if let Some(slot) = self.x_zero_point_input {
@@ -459,4 +467,59 @@ mod test {
             .unwrap();
         assert_eq!(result, tvec!(rctensor3(&[[[2.0f32]]])));
     }
+
+    fn q_linear_conv_per_channel(group: usize) {
+        setup_test_logger();
+        let ci = 2;
+        let co = 2;
+        let x = Array4::from_shape_fn((1, ci, 3, 3), |(_, c, y, x)| (c * 9 + y * 3 + x) as i8 - 5);
+        let k = Array4::from_shape_fn((co, ci / group, 2, 2), |(o, i, y, x)| {
+            (o * 7 + i * 4 + y * 2 + x) as i8 - 6
+        });
+        let (x_scale, x_zp, y_scale, y_zp) = (0.5f32, 3i8, 0.7f32, -2i8);
+        let k_scales = [0.1f32, 0.03];
+        let mut op = Conv::default().group(group);
+        op.x_scale_input = Some(2);
+        op.x_zero_point_input = Some(3);
+        op.k_scale_input = Some(4);
+        op.y_scale_input = Some(5);
+        op.y_zero_point_input = Some(6);
+        let result = op
+            .eval(tvec!(
+                x.clone().into_arc_tensor(),
+                k.clone().into_arc_tensor(),
+                rctensor0(x_scale),
+                rctensor0(x_zp),
+                rctensor1(&k_scales),
+                rctensor0(y_scale),
+                rctensor0(y_zp),
+            ))
+            .unwrap();
+        let expected = Array4::from_shape_fn((1, co, 2, 2), |(_, o, oy, ox)| {
+            let g = o / (co / group);
+            let mut acc = 0i32;
+            for i in 0..ci / group {
+                for ky in 0..2 {
+                    for kx in 0..2 {
+                        let input =
+                            x[(0, g * ci / group + i, oy + ky, ox + kx)] as i32 - x_zp as i32;
+                        acc += input * k[(o, i, ky, kx)] as i32;
+                    }
+                }
+            }
+            let scaled = (acc as f32 * x_scale * k_scales[o] / y_scale).round() as i32;
+            (scaled + y_zp as i32).max(-128).min(127) as i8
+        });
+        assert_eq!(result[0], expected.into_arc_tensor());
+    }
+
+    #[test]
+    fn test_eval_q_linear_per_channel() {
+        q_linear_conv_per_channel(1)
+    }
+
+    #[test]
+    fn test_eval_q_linear_per_channel_depthwise() {
+        q_linear_conv_per_channel(2)
+    }
 }
@@ -77,10 +77,15 @@ impl<T: Copy + Datum + Zero> Im2Col<T> {
     }
 
     pub(super) fn im2col<'i>(&'i self, input: &'i ArrayViewD<'i, T>) -> TractResult<Tensor> {
-        let mut packed = unsafe {
-            Tensor::uninitialized_aligned::<T>(&*self.output_shape.shape, self.b_pack.alignment())?
-        };
-        for i in 0..*self.input_shape.n_dim().unwrap_or(&1) {
+        // the batch may be of another size than the one the op was built for
+        let n = self.input_shape.n_axis().map(|axis| input.shape()[axis]).unwrap_or(1);
+        let mut shape = self.output_shape.shape.clone();
+        if let Some(axis) = self.output_shape.n_axis() {
+            shape[axis] = n;
+        }
+        let mut packed =
+            unsafe { Tensor::uninitialized_aligned::<T>(&shape, self.b_pack.alignment())? };
+        for i in 0..n {
             for g in 0..self.group {
                 let mut packed = packed.to_array_view_mut::<T>()?;
                 packed.slice_axis_inplace(Axis(0), (i..=i).into());
@@ -129,6 +134,18 @@ impl<T: Copy + Datum + Zero> TypedOp for Im2Col<T> {
     fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
         Ok(tvec!(TypedFact::dt_shape(T::datum_type(), &*self.output_shape.shape)?))
     }
+
+    fn invariants(&self, _model: &TypedModel, _node: &TypedNode) -> TractResult<Invariants> {
+        match (self.input_shape.n_axis(), self.output_shape.n_axis()) {
+            (Some(input), Some(output)) => Ok(Invariants::from(tvec!(AxisInfo {
+                inputs: tvec!(Some(input)),
+                outputs: tvec!(Some(output)),
+                period: 1,
+                disposable: true,
+            }))),
+            _ => Ok(Invariants::none()),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
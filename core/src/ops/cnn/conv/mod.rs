@@ -2,6 +2,7 @@ mod depth_wise;
 mod gen;
 mod im2col;
 mod unary;
+mod winograd;
 
 pub use self::gen::Conv;
 pub use self::im2col::Im2Col;
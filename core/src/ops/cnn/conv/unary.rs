@@ -3,13 +3,14 @@ use std::ops::{Add, Mul};
 
 use ndarray::*;
 
-use num_traits::Zero;
+use num_traits::{AsPrimitive, Zero};
 
 use crate::internal::*;
 use crate::model::*;
 
 use super::depth_wise::DepthWise;
 use super::im2col::Im2Col;
+use super::winograd::{self, Winograd};
 use super::Conv;
 use crate::ops::array::TypedReshape;
 use crate::ops::cnn::conv::KernelFormat;
@@ -19,7 +20,7 @@ use crate::ops::matmul::mmm_wrapper::MMMWrapper;
 use crate::ops::nn::DataFormat;
 use crate::ops::quant::QParams;
 
-use tract_linalg::frame::mmm::FusedSpec;
+use tract_linalg::frame::mmm::{per_row_scale_ops, FusedSpec};
 use tract_linalg::frame::PackA;
 
 use std::iter::Sum;
@@ -70,6 +71,67 @@ impl ConvUnary {
         Ok(unary)
     }
 
+    /// Store an f32 kernel as f16. Quantized convolutions are left untouched.
+    ///
+    /// Im2col keeps the kernel packed as f16. Depthwise convolutions convert it
+    /// back to f32 at codegen, and Winograd is not used for f16 kernels.
+    pub fn f16_weights(&mut self) -> TractResult<()> {
+        if self.kernel.datum_type() == f32::datum_type() && self.q_params.is_none() {
+            self.kernel = self.kernel.cast_to::<f16>()?.into_owned().into_arc_tensor();
+        }
+        Ok(())
+    }
+
+    /// Quantized i8 version of an f32 convolution, given the (step, zero point)
+    /// quantization of its input and output.
+    ///
+    /// The kernel is quantized symmetrically with one scale per output channel,
+    /// the bias is folded in the i32 accumulator. Returns None if the
+    /// convolution is not a plain f32 one.
+    pub fn quantize_i8(
+        &self,
+        input: (f32, i8),
+        output: (f32, i8),
+    ) -> TractResult<Option<ConvUnary>> {
+        use crate::ops::quant::quantize_symmetric_i8;
+        if self.kernel.datum_type() != f32::datum_type() || self.q_params.is_some() {
+            return Ok(None);
+        }
+        let co = self.output_channels();
+        let o_axis = match self.kernel_fmt {
+            KernelFormat::OIHW => Some(0),
+            KernelFormat::HWIO if self.group == 1 => Some(self.kernel.rank() - 1),
+            KernelFormat::HWIO => None,
+        };
+        let (kernel, k_scales) = quantize_symmetric_i8(&self.kernel, o_axis)?;
+        let k_scales: Vec<f32> =
+            (0..co).map(|o| if k_scales.len() == 1 { k_scales[0] } else { k_scales[o] }).collect();
+        let bias = if let Some(bias) = &self.bias {
+            let bias = bias.cast_to::<f32>()?;
+            let bias = bias.as_slice::<f32>()?;
+            let bias: Vec<i32> = (0..co)
+                .map(|o| {
+                    let b = if bias.len() == 1 { bias[0] } else { bias[o] };
+                    (b / (input.0 * k_scales[o])).round() as i32
+                })
+                .collect();
+            Some(rctensor1(&bias))
+        } else {
+            None
+        };
+        let scales: Vec<f32> = k_scales.iter().map(|k| input.0 * k / output.0).collect();
+        let q_params = QParams::new(i8::datum_type())
+            .with_zero_point_b(&rctensor0(input.1))
+            .with_zero_point_c(&rctensor0(output.1))
+            .with_scale_factor_per_row(&rctensor1(&scales))?;
+        Ok(Some(ConvUnary {
+            kernel: kernel.into_arc_tensor(),
+            bias,
+            q_params: Some(q_params),
+            ..self.clone()
+        }))
+    }
+
     fn input_channels(&self) -> usize {
         match self.kernel_fmt {
             KernelFormat::OIHW => self.kernel.shape()[1],
@@ -86,7 +148,8 @@ impl ConvUnary {
     }
 
     fn kernel_as_group_o_ihw<T: Datum>(&self) -> TractResult<Array3<T>> {
-        let kernel = self.kernel.to_array_view::<T>()?;
+        let kernel = self.kernel.cast_to::<T>()?;
+        let kernel = kernel.to_array_view::<T>()?;
         let final_shape = (
             self.group,
             self.output_channels() / self.group,
@@ -135,27 +198,29 @@ impl ConvUnary {
         Ok(packed_as.insert_axis(Axis(0)))
     }
 
-    fn bias_as_non_linear<T>(&self) -> TractResult<Option<ArrayD<Vec<FusedSpec<T>>>>>
+    /// Per group fused operations: bias, then per output channel requantization.
+    fn fused_ops_as_non_linear<T>(&self) -> TractResult<Option<ArrayD<Vec<FusedSpec<T>>>>>
     where
         T: Datum + Copy,
+        i32: AsPrimitive<T>,
     {
-        use crate::itertools::Itertools;
+        let co_per_group = self.output_channels() / self.group;
+        let mut ops: Vec<Vec<FusedSpec<T>>> = vec![vec![]; self.group];
         if let Some(bias) = &self.bias {
             let bias = bias.cast_to::<T>()?;
-            let bias = bias.as_slice::<T>()?;
-            Ok(Some(
-                Array2::from_shape_vec(
-                    (1, self.group),
-                    bias.iter()
-                        .chunks(self.output_channels() / self.group)
-                        .into_iter()
-                        .map(|c| vec![FusedSpec::PerRowAdd(c.into_iter().cloned().collect())])
-                        .collect(),
-                )?
-                .into_dyn(),
-            ))
-        } else {
+            for (g, b) in bias.as_slice::<T>()?.chunks(co_per_group).enumerate() {
+                ops[g].push(FusedSpec::PerRowAdd(b.to_vec()));
+            }
+        }
+        if let Some(scales) = self.q_params.as_ref().and_then(|q| q.scale_factor_per_row()) {
+            for (g, s) in scales.chunks(co_per_group).enumerate() {
+                ops[g].extend(per_row_scale_ops(s));
+            }
+        }
+        if ops.iter().all(|ops| ops.is_empty()) {
             Ok(None)
+        } else {
+            Ok(Some(Array2::from_shape_vec((1, self.group), ops)?.into_dyn()))
         }
     }
 
@@ -169,24 +234,28 @@ impl ConvUnary {
         let a = self.kernel.datum_type();
         let b = model.outlet_fact(wire)?.datum_type;
         if (a, b) == (f32::datum_type(), f32::datum_type()) {
-            return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
-                MMMWrapper::Plain((tract_linalg::ops().smmm)(m, k, n))
-            });
+            return self.wire_as_im2col_pair_t(model, name, wire, direct, MMMWrapper::f32);
+        } else if (a, b) == (f16::datum_type(), f32::datum_type()) {
+            return self.wire_as_im2col_pair_t(model, name, wire, direct, MMMWrapper::f16_f32);
         } else if (a, b) == (u8::datum_type(), u8::datum_type()) {
-            return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
-                MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_i32)(m, k, n))
-            });
+            if self.q_params.as_ref().map(|q| q.c_datum_type) == Some(u8::datum_type()) {
+                return self.wire_as_im2col_pair_t(model, name, wire, direct, MMMWrapper::u8_u8);
+            } else {
+                return self.wire_as_im2col_pair_t(model, name, wire, direct, MMMWrapper::u8_i32);
+            }
         } else if (a, b) == (i8::datum_type(), i8::datum_type()) {
             if let Some(q) = &self.q_params {
                 if q.c_datum_type == i8::datum_type() {
-                    return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
-                        MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n))
-                    });
+                    return self.wire_as_im2col_pair_t(
+                        model,
+                        name,
+                        wire,
+                        direct,
+                        MMMWrapper::i8_i8,
+                    );
                 }
             } else {
-                return self.wire_as_im2col_pair_t(model, name, wire, direct, &|m, k, n| {
-                    MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n))
-                });
+                return self.wire_as_im2col_pair_t(model, name, wire, direct, MMMWrapper::i8_i32);
             }
         }
         bail!("Unsupported combination for Conv (filters: {:?}, data:{:?})", a, b);
@@ -205,6 +274,7 @@ impl ConvUnary {
         TB: Datum + Copy + Zero,
         TC: Datum + Copy,
         TI: Datum + Copy + Add + Mul + Zero + fmt::Debug,
+        i32: AsPrimitive<TI>,
     {
         trace!("to_im2col_pair: {:?}", self);
         let (input_shape, geo, output_shape) =
@@ -225,7 +295,12 @@ impl ConvUnary {
         mmm.as_mmm_mut().c_from_data_and_strides(rsc, csc);
 
         if let Some(q) = self.q_params.as_ref() {
-            mmm.set_quant_params(q)?;
+            if q.scale_factor_per_row().is_some() {
+                // rows scales are applied per group, by the fused ops
+                mmm.set_quant_params(&QParams { scale_factor: None, ..q.clone() })?;
+            } else {
+                mmm.set_quant_params(q)?;
+            }
         }
 
         trace!(
@@ -272,7 +347,9 @@ impl ConvUnary {
             )?[0];
         }
 
-        let c_prefix_dim_and_stride = if *output_shape.n().unwrap_or(&1) != 1 || self.group != 1 {
+        // the batch axis is kept in the prefix even when it is 1, so that the
+        // product can be run on a batch of another size
+        let c_prefix_dim_and_stride = if output_shape.n().is_some() || self.group != 1 {
             let mut dims = tvec!(self.group as usize);
             let mut strides =
                 tvec!((output_shape.c() / self.group * output_shape.c_stride()) as isize);
@@ -293,7 +370,7 @@ impl ConvUnary {
                 c_fact: TypedFact::dt_shape(TC::datum_type(), &*output_shape.shape)?,
                 c_prefix_dim_and_stride,
                 packed_as: self.kernel_as_packed_as(&mmm.as_mmm().a_pack())?,
-                fused_ops: self.bias_as_non_linear()?,
+                fused_ops: self.fused_ops_as_non_linear()?,
                 mmm,
             },
             &[wire],
@@ -319,6 +396,55 @@ impl ConvUnary {
         Ok(Box::new(op))
     }
 
+    /// Winograd tile size to use for this convolution, if a Winograd
+    /// transformation applies and is expected to beat the direct computation.
+    pub fn winograd_tile(&self, dt: DatumType, input_full_shape: &[usize]) -> Option<usize> {
+        let input_shape = self.pool_spec.data_format.shape(input_full_shape);
+        if dt != f32::datum_type()
+            || self.kernel.datum_type() != f32::datum_type()
+            || self.q_params.is_some()
+            || self.group != 1
+            || input_shape.hw_rank() != 2
+            || self.kernel.shape()[self.kernel_fmt.h_axis()..][..2] != [3, 3]
+            || (0..2).any(|ax| self.pool_spec.stride(ax) != 1 || self.pool_spec.dilation(ax) != 1)
+        {
+            return None;
+        }
+        let (_, _, output_shape) = self.pool_spec.compute_geo(input_full_shape);
+        let ci = *input_shape.c();
+        let co = self.output_channels();
+        let direct = 9 * ci * co * output_shape.hw_dims().iter().product::<usize>();
+        [4, 2]
+            .iter()
+            .map(|&tile| (tile, winograd::cost(tile, ci, co, output_shape.hw_dims())))
+            .filter(|&(_, cost)| cost < direct)
+            .min_by_key(|&(_, cost)| cost)
+            .map(|(tile, _)| tile)
+    }
+
+    pub fn to_winograd(
+        &self,
+        input_full_shape: &[usize],
+        tile: usize,
+    ) -> TractResult<Box<dyn TypedOp>> {
+        let (input_shape, patch, output_shape) = self.pool_spec.compute_geo(input_full_shape);
+        let kernel = self.kernel_as_group_o_ihw::<f32>()?;
+        let bias = if let Some(b) = self.bias.as_ref() {
+            Some(b.as_slice::<f32>()?.to_vec())
+        } else {
+            None
+        };
+        let op = Winograd::new(
+            tile,
+            input_shape,
+            output_shape,
+            patch.pad_before.clone(),
+            kernel.index_axis(Axis(0), 0),
+            bias,
+        )?;
+        Ok(Box::new(op))
+    }
+
     fn declutter_stride_slice_to_downsample(
         &self,
         model: &TypedModel,
@@ -385,6 +511,12 @@ impl ConvUnary {
                 self.q_params.as_ref().map(|qp| qp.c_datum_type).unwrap_or(input_fact.datum_type);
             let must_split_quant =
                 self.bias.is_some() && self.bias.as_ref().unwrap().datum_type() != output_type;
+            if must_split_quant
+                && self.q_params.as_ref().and_then(|qp| qp.scale_factor_per_row()).is_some()
+            {
+                // the split requantization only handles a scalar scale
+                return Ok(None);
+            }
             let q_params = if must_split_quant {
                 Some(QParams {
                     c_datum_type: self.bias.as_ref().unwrap().datum_type(),
@@ -409,7 +541,12 @@ impl ConvUnary {
             if must_split_quant {
                 use crate::ops::quant::*;
                 let qp = self.q_params.as_ref().unwrap();
-                let scale = qp.scale_factor.unwrap_or(1.0);
+                let scale = qp
+                    .scale_factor
+                    .as_ref()
+                    .map(|s| s.to_scalar::<f32>().map(|s| *s))
+                    .transpose()?
+                    .unwrap_or(1.0);
                 let op = match output_type {
                     DatumType::I8 => quantize_linear_i8(
                         scale,
@@ -621,6 +758,8 @@ impl TypedOp for ConvUnary {
                     && (0..spatial_rank)
                         .all(|i| self.pool_spec.stride(i) == 1 && self.pool_spec.dilation(i) == 1)
                     && self.group == 1
+                    // a quantized bias belongs in the accumulator, before requantization
+                    && (self.q_params.is_none() || self.bias.is_none())
                 {
                     use crate::ops::matmul::MatMulUnary;
                     let mut patch = TypedModelPatch::default();
@@ -675,6 +814,12 @@ impl TypedOp for ConvUnary {
                     )?[0];
                     patch.shunt_outside(OutletId::new(node.id, 0), wire)?;
                     return Ok(Some(patch));
+                } else if let Some(tile) = self.winograd_tile(dt, &shape) {
+                    return Ok(Some(TypedModelPatch::single_unary_op(
+                        model,
+                        node,
+                        self.to_winograd(&shape, tile)?,
+                    )?));
                 } else if (0..spatial_rank).all(|ax| self.pool_spec.padding.valid_dim(ax))
                     && self.group == 1
                 {
@@ -683,7 +828,10 @@ impl TypedOp for ConvUnary {
                     let wire = self.wire_as_im2col_pair(&mut patch, &*node.name, wire, true)?;
                     patch.shunt_outside(OutletId::new(node.id, 0), wire)?;
                     return Ok(Some(patch));
-                } else if self.group != 1 && self.group == self.output_channels() {
+                } else if self.group != 1
+                    && self.group == self.output_channels()
+                    && !dt.is_integer()
+                {
                     return Ok(Some(TypedModelPatch::single_unary_op(
                         model,
                         node,
@@ -709,6 +857,10 @@ impl PulsedOp for ConvUnary {
         self.pool_spec.pulsed_output_facts(inputs)
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
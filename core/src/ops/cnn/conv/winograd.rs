@@ -0,0 +1,418 @@
+use crate::internal::*;
+use crate::ops::matmul::MMMWrapper;
+use crate::ops::nn::DataShape;
+use ndarray::*;
+
+// Winograd minimal filtering for 3x3 kernels, stride 1: F(m x m, 3 x 3), with
+// output tiles of m x m and input tiles of (m + 2) x (m + 2).
+//
+// Y = At [ (G g Gt) . (Bt d B) ] A
+//
+// The element-wise products are batched over channels and tiles: for each of
+// the (m + 2)^2 tile positions, U (output channels x input channels) times
+// V (input channels x tiles) is a plain matrix product.
+
+#[rustfmt::skip]
+const BT_2: [f32; 16] = [
+    1.0,  0.0, -1.0,  0.0,
+    0.0,  1.0,  1.0,  0.0,
+    0.0, -1.0,  1.0,  0.0,
+    0.0,  1.0,  0.0, -1.0,
+];
+
+#[rustfmt::skip]
+const G_2: [f32; 12] = [
+    1.0,  0.0, 0.0,
+    0.5,  0.5, 0.5,
+    0.5, -0.5, 0.5,
+    0.0,  0.0, 1.0,
+];
+
+#[rustfmt::skip]
+const AT_2: [f32; 8] = [
+    1.0, 1.0,  1.0,  0.0,
+    0.0, 1.0, -1.0, -1.0,
+];
+
+#[rustfmt::skip]
+const BT_4: [f32; 36] = [
+    4.0,  0.0, -5.0,  0.0, 1.0, 0.0,
+    0.0, -4.0, -4.0,  1.0, 1.0, 0.0,
+    0.0,  4.0, -4.0, -1.0, 1.0, 0.0,
+    0.0, -2.0, -1.0,  2.0, 1.0, 0.0,
+    0.0,  2.0, -1.0, -2.0, 1.0, 0.0,
+    0.0,  4.0,  0.0, -5.0, 0.0, 1.0,
+];
+
+#[rustfmt::skip]
+const G_4: [f32; 18] = [
+     1.0 / 4.0,   0.0,         0.0,
+    -1.0 / 6.0,  -1.0 / 6.0,  -1.0 / 6.0,
+    -1.0 / 6.0,   1.0 / 6.0,  -1.0 / 6.0,
+     1.0 / 24.0,  1.0 / 12.0,  1.0 / 6.0,
+     1.0 / 24.0, -1.0 / 12.0,  1.0 / 6.0,
+     0.0,         0.0,         1.0,
+];
+
+#[rustfmt::skip]
+const AT_4: [f32; 24] = [
+    1.0, 1.0,  1.0, 1.0,  1.0, 0.0,
+    0.0, 1.0, -1.0, 2.0, -2.0, 0.0,
+    0.0, 1.0,  1.0, 4.0,  4.0, 0.0,
+    0.0, 1.0, -1.0, 8.0, -8.0, 1.0,
+];
+
+fn matrices(tile: usize) -> (&'static [f32], &'static [f32], &'static [f32]) {
+    match tile {
+        2 => (&BT_2, &G_2, &AT_2),
+        4 => (&BT_4, &G_4, &AT_4),
+        _ => panic!("Winograd tile must be 2 or 4, got {}", tile),
+    }
+}
+
+/// out (r x r) = l (r x c) . x (c x c) . lt (c x r)
+fn sandwich(l: &[f32], r: usize, c: usize, x: &[f32], tmp: &mut [f32], out: &mut [f32]) {
+    for i in 0..r {
+        for j in 0..c {
+            tmp[i * c + j] = (0..c).map(|k| l[i * c + k] * x[k * c + j]).sum();
+        }
+    }
+    for i in 0..r {
+        for j in 0..r {
+            out[i * r + j] = (0..c).map(|k| tmp[i * c + k] * l[j * c + k]).sum();
+        }
+    }
+}
+
+/// Estimated multiplications count for a Winograd F(tile, 3) convolution, to be
+/// compared with the 9 * ci * co * points of the straightforward convolution.
+pub fn cost(tile: usize, ci: usize, co: usize, output_hw: &[usize]) -> usize {
+    let alpha = tile + 2;
+    let tiles = output_hw.iter().map(|d| (d + tile - 1) / tile).product::<usize>();
+    alpha * alpha * ci * co * tiles + 2 * alpha * alpha * alpha * (ci + co) * tiles
+}
+
+#[derive(Debug, Clone)]
+pub struct Winograd {
+    tile: usize,
+    input_shape: DataShape,
+    output_shape: DataShape,
+    pad_before: TVec<usize>,
+    packed_u: Vec<Arc<Tensor>>,
+    bias: Option<Vec<f32>>,
+    mmm: MMMWrapper<f32, f32, f32, f32>,
+}
+
+impl Winograd {
+    /// `kernel` is (output channels, input channels * 3 * 3).
+    pub fn new(
+        tile: usize,
+        input_shape: DataShape,
+        output_shape: DataShape,
+        pad_before: TVec<usize>,
+        kernel: ArrayView2<f32>,
+        bias: Option<Vec<f32>>,
+    ) -> TractResult<Winograd> {
+        let (_, g, _) = matrices(tile);
+        let alpha = tile + 2;
+        let co = *output_shape.c();
+        let ci = *input_shape.c();
+        let tiles = output_shape.hw_dims().iter().map(|d| (d + tile - 1) / tile).product();
+        let mmm = MMMWrapper::f32(co, ci, tiles);
+        let mut u = Array3::<f32>::zeros((alpha * alpha, co, ci));
+        let mut tmp = vec![0.0; alpha * 3];
+        let mut ut = vec![0.0; alpha * alpha];
+        for o in 0..co {
+            for i in 0..ci {
+                let mut g_oi = [0.0f32; 9];
+                for (ix, k) in g_oi.iter_mut().enumerate() {
+                    *k = kernel[(o, i * 9 + ix)];
+                }
+                sandwich(g, alpha, 3, &g_oi, &mut tmp, &mut ut);
+                for (xi_nu, v) in ut.iter().enumerate() {
+                    u[(xi_nu, o, i)] = *v;
+                }
+            }
+        }
+        let a_pack = mmm.as_mmm().a_pack();
+        let packed_u = u
+            .outer_iter()
+            .map(|u| unsafe {
+                let mut packed =
+                    Tensor::uninitialized_aligned::<f32>(&[a_pack.len()], a_pack.alignment())?;
+                a_pack.pack(packed.as_ptr_mut()?, u.as_ptr(), ci as isize, 1);
+                Ok(packed.into_arc_tensor())
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        Ok(Winograd { tile, input_shape, output_shape, pad_before, packed_u, bias, mmm })
+    }
+}
+
+impl Op for Winograd {
+    fn name(&self) -> Cow<str> {
+        "Conv::Winograd".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("F({}x{}, 3x3)", self.tile, self.tile), format!("{}", self.mmm)])
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for Winograd {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.as_slice::<f32>()?;
+        let (bt, _, at) = matrices(self.tile);
+        let m = self.tile;
+        let alpha = m + 2;
+        let ci = *self.input_shape.c();
+        let co = *self.output_shape.c();
+        let (h, w) = (self.input_shape.hw_dims()[0], self.input_shape.hw_dims()[1]);
+        let (oh, ow) = (self.output_shape.hw_dims()[0], self.output_shape.hw_dims()[1]);
+        let (tiles_h, tiles_w) = ((oh + m - 1) / m, (ow + m - 1) / m);
+        let tiles = tiles_h * tiles_w;
+        let (is_c, is_h, is_w) = (
+            *self.input_shape.c_stride(),
+            *self.input_shape.h_stride(),
+            *self.input_shape.w_stride(),
+        );
+        let (os_c, os_h, os_w) = (
+            *self.output_shape.c_stride(),
+            *self.output_shape.h_stride(),
+            *self.output_shape.w_stride(),
+        );
+
+        let mut output = unsafe { Tensor::uninitialized::<f32>(&*self.output_shape.shape)? };
+        let output_slice = output.as_slice_mut::<f32>()?;
+        let mut v = vec![0.0f32; alpha * alpha * ci * tiles];
+        let mut products = vec![0.0f32; alpha * alpha * co * tiles];
+        let b_pack = self.mmm.as_mmm().b_pack();
+        let mut packed_v =
+            unsafe { Tensor::uninitialized_aligned::<f32>(&[b_pack.len()], b_pack.alignment())? };
+
+        let mut d = vec![0.0f32; alpha * alpha];
+        let mut tmp = vec![0.0f32; alpha * alpha];
+        let mut t = vec![0.0f32; alpha * alpha];
+        for n in 0..*self.input_shape.n().unwrap_or(&1) {
+            let input = &input[n * self.input_shape.n_stride().unwrap_or(&0)..];
+            // input transform: V[xi, nu][c][tile] = (Bt d B)[xi, nu]
+            for c in 0..ci {
+                for ty in 0..tiles_h {
+                    for tx in 0..tiles_w {
+                        for y in 0..alpha {
+                            let iy = (ty * m + y) as isize - self.pad_before[0] as isize;
+                            for x in 0..alpha {
+                                let ix = (tx * m + x) as isize - self.pad_before[1] as isize;
+                                d[y * alpha + x] =
+                                    if iy >= 0 && (iy as usize) < h && ix >= 0 && (ix as usize) < w
+                                    {
+                                        input[c * is_c + iy as usize * is_h + ix as usize * is_w]
+                                    } else {
+                                        0.0
+                                    };
+                            }
+                        }
+                        sandwich(bt, alpha, alpha, &d, &mut tmp, &mut t);
+                        let tile = ty * tiles_w + tx;
+                        for (xi_nu, t) in t.iter().enumerate() {
+                            v[(xi_nu * ci + c) * tiles + tile] = *t;
+                        }
+                    }
+                }
+            }
+            // batched products: M[xi, nu] = U[xi, nu] . V[xi, nu]
+            for xi_nu in 0..alpha * alpha {
+                unsafe {
+                    b_pack.pack(
+                        packed_v.as_ptr_mut()?,
+                        v[xi_nu * ci * tiles..].as_ptr(),
+                        tiles as isize,
+                        1,
+                    );
+                    self.mmm.run(
+                        self.packed_u[xi_nu].as_ptr()?,
+                        packed_v.as_ptr()?,
+                        products[xi_nu * co * tiles..].as_mut_ptr(),
+                        &[],
+                    );
+                }
+            }
+            // output transform: Y = At M A
+            let output = &mut output_slice[n * self.output_shape.n_stride().unwrap_or(&0)..];
+            for c in 0..co {
+                let bias = self.bias.as_ref().map(|b| b[c]).unwrap_or(0.0);
+                for ty in 0..tiles_h {
+                    for tx in 0..tiles_w {
+                        let tile = ty * tiles_w + tx;
+                        for xi_nu in 0..alpha * alpha {
+                            d[xi_nu] = products[(xi_nu * co + c) * tiles + tile];
+                        }
+                        sandwich(at, m, alpha, &d, &mut tmp, &mut t);
+                        for y in 0..m.min(oh - ty * m) {
+                            for x in 0..m.min(ow - tx * m) {
+                                output[c * os_c + (ty * m + y) * os_h + (tx * m + x) * os_w] =
+                                    t[y * m + x] + bias;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Winograd {
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(f32::datum_type(), &*self.output_shape.shape)?))
+    }
+
+    fn cost(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        Ok(tvec!((
+            Cost::FMA(f32::datum_type()),
+            (self.input_shape.n().unwrap_or(&1)
+                * cost(
+                    self.tile,
+                    *self.input_shape.c(),
+                    *self.output_shape.c(),
+                    self.output_shape.hw_dims()
+                ))
+            .to_dim()
+        )))
+    }
+
+    typed_op_as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::conv::{ConvUnary, KernelFormat};
+    use crate::ops::cnn::{PaddingSpec, PoolSpec};
+    use crate::ops::nn::DataFormat;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[derive(Debug)]
+    struct WinogradProblem {
+        tile: usize,
+        data_format: DataFormat,
+        padding: PaddingSpec,
+        input: Tensor,
+        kernel: Tensor,
+        bias: Option<Tensor>,
+    }
+
+    impl WinogradProblem {
+        fn conv(&self) -> ConvUnary {
+            ConvUnary {
+                pool_spec: PoolSpec {
+                    data_format: self.data_format,
+                    kernel_shape: tvec!(3, 3),
+                    padding: self.padding.clone(),
+                    dilations: None,
+                    strides: None,
+                    output_channel_override: Some(self.kernel.shape()[0]),
+                },
+                kernel_fmt: KernelFormat::OIHW,
+                kernel: self.kernel.clone().into_arc_tensor(),
+                group: 1,
+                bias: self.bias.clone().map(|b| b.into_arc_tensor()),
+                q_params: None,
+            }
+        }
+
+        fn check(&self) -> Result<(), TestCaseError> {
+            let conv = self.conv();
+            let expected = conv.eval(tvec!(self.input.clone().into_arc_tensor())).unwrap();
+            let winograd = conv.to_winograd(self.input.shape(), self.tile).unwrap();
+            let found = winograd
+                .as_stateless()
+                .unwrap()
+                .eval(tvec!(self.input.clone().into_arc_tensor()))
+                .unwrap();
+            // F(4x4, 3x3) transforms have larger coefficients, hence a looser bound
+            let tolerance = if self.tile == 2 { 1e-5 } else { 1e-4 };
+            let found = found[0].as_slice::<f32>().unwrap();
+            let expected = expected[0].as_slice::<f32>().unwrap();
+            prop_assert_eq!(found.len(), expected.len());
+            for (f, e) in found.iter().zip(expected.iter()) {
+                prop_assert!(
+                    (f - e).abs() <= tolerance * (1.0 + e.abs()),
+                    "found: {:?} expected: {:?}",
+                    found,
+                    expected
+                );
+            }
+            Ok(())
+        }
+    }
+
+    impl Arbitrary for WinogradProblem {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<WinogradProblem>;
+        fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+            (
+                prop_oneof![Just(2usize), Just(4usize)],
+                prop_oneof![Just(DataFormat::NCHW), Just(DataFormat::HWC)],
+                prop_oneof![Just(PaddingSpec::Valid), Just(PaddingSpec::SameUpper)],
+                1usize..4,
+                1usize..4,
+                3usize..10,
+                3usize..10,
+                any::<bool>(),
+            )
+                .prop_flat_map(|(tile, data_format, padding, ci, co, h, w, bias)| {
+                    let input_shape = data_format.from_n_c_hw(1, ci, &[h, w]).shape;
+                    let input_len = input_shape.iter().product::<usize>();
+                    (
+                        Just((tile, data_format, padding, input_shape, ci, co, bias)),
+                        vec(-1.0f32..1.0, input_len),
+                        vec(-1.0f32..1.0, ci * co * 9),
+                        vec(-1.0f32..1.0, co),
+                    )
+                })
+                .prop_map(|((tile, data_format, padding, input_shape, ci, co, bias), i, k, b)| {
+                    WinogradProblem {
+                        tile,
+                        data_format,
+                        padding,
+                        input: Array::from_shape_vec(&*input_shape, i).unwrap().into(),
+                        kernel: Array::from_shape_vec((co, ci, 3, 3), k).unwrap().into(),
+                        bias: if bias { Some(Array1::from(b).into()) } else { None },
+                    }
+                })
+                .boxed()
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop(pb in any::<WinogradProblem>()) {
+            pb.check()?
+        }
+    }
+
+    #[test]
+    fn f4_same_5x5() {
+        let input: Tensor =
+            Array::from_shape_fn((1, 2, 5, 5), |(_, c, y, x)| (c * 25 + y * 5 + x) as f32 / 50.0)
+                .into();
+        let kernel: Tensor =
+            Array::from_shape_fn((3, 2, 3, 3), |(o, i, y, x)| ((o + i + y * x) % 3) as f32 - 1.0)
+                .into();
+        WinogradProblem {
+            tile: 4,
+            data_format: DataFormat::NCHW,
+            padding: PaddingSpec::SameUpper,
+            input,
+            kernel,
+            bias: Some(tensor1(&[0.5f32, -1.0, 2.0])),
+        }
+        .check()
+        .unwrap()
+    }
+}
@@ -117,6 +117,10 @@ impl PulsedOp for MaxPool {
         Ok(facts)
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -173,12 +173,17 @@ impl PoolSpec {
         );
         let mut fact = inputs[0].clone();
         let input_shape = self.data_format.shape(&*fact.shape);
+        if Some(fact.axis) == input_shape.n_axis() {
+            fact.shape = oshape.shape;
+            return Ok(tvec!(fact));
+        }
         let geo_axis = fact.axis - input_shape.h_axis();
         let dilation = self.dilations.as_ref().map(|d| d[geo_axis]).unwrap_or(1);
         let kernel_len = (self.kernel_shape[geo_axis] - 1) * dilation;
         let stride = self.strides.as_ref().and_then(|v| v.get(geo_axis).cloned()).unwrap_or(1);
         fact.delay /= stride;
         fact.dim = (fact.dim.clone() - kernel_len.to_dim()).div_ceil(stride.to_dim());
+        fact.variable_pulse = fact.variable_pulse.map(|step| step / stride);
         fact.shape = oshape.shape;
         Ok(tvec!(fact))
     }
@@ -125,8 +125,8 @@ impl TypedOp for Downsample {
         _pulse: usize,
     ) -> TractResult<TVec<OutletId>> {
         let input = mapping[&node.inputs[0]];
-        let pulse = target.outlet_fact(input)?.pulse();
-        if pulse % self.stride != 0 {
+        let fact = target.outlet_fact(input)?;
+        if self.axis == fact.axis && fact.pulse() % self.stride != 0 {
             bail!("Pulsificaton requires pulse to be a stride multiple")
         }
         target.wire_node(&*node.name, self.clone(), &[input])
@@ -138,11 +138,21 @@ impl TypedOp for Downsample {
 impl PulsedOp for Downsample {
     fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
         let mut fact = inputs[0].clone();
-        fact.shape[self.axis] /= self.stride;
-        fact.dim = fact.dim.div_ceil(self.stride.to_dim());
+        if self.axis == fact.axis {
+            fact.shape[self.axis] /= self.stride;
+            fact.dim = fact.dim.div_ceil(self.stride.to_dim());
+            fact.variable_pulse = fact.variable_pulse.map(|step| step / self.stride);
+        } else {
+            fact.shape[self.axis] =
+                self.transform_dim(&fact.shape[self.axis].to_dim()).to_integer()? as usize;
+        }
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
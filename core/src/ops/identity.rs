@@ -62,6 +62,10 @@ impl PulsedOp for Identity {
         Ok(tvec!(inputs[0].clone()))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
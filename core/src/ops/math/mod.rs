@@ -121,11 +121,10 @@ element_wise!(abs, Abs, [f16, f32, i32] => |_, xs| {
     Ok(())
 });
 
-element_wise!(exp, Exp, [f16, f32, f64] => |_, xs| {
-    xs.iter_mut().for_each(|x| *x = x.exp());
-    Ok(())
-};
-    validation: Validation::Rounding
+element_wise!(exp, Exp,
+   [f32] => |_, xs| { (tract_linalg::ops().sexp)().run(xs); Ok(()) },
+   [f16, f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.exp()); Ok(()) };
+   validation: Validation::Rounding
 );
 
 element_wise!(ln, Ln, [f16, f32, f64] => |_, xs| {
@@ -142,6 +141,13 @@ element_wise!(sqrt, Sqrt, [f16, f32, f64] => |_, xs| {
     validation: Validation::Rounding
 );
 
+element_wise!(square, Square, [f16, f32, f64, i8, i16, i32, i64] => |_, xs| {
+    xs.iter_mut().for_each(|x| *x = x.clone() * x.clone());
+    Ok(())
+};
+    cost: |dt| {tvec!((Cost::FMA(dt), 1))}
+);
+
 element_wise!(recip, Recip, [f16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.recip());
     Ok(())
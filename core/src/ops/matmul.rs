@@ -1,6 +1,9 @@
-pub mod mmm_wrapper;
+pub mod dyn_quant;
 pub mod logic;
+pub mod mmm_wrapper;
 pub mod phy;
+pub mod sparse;
 
-pub use mmm_wrapper::MMMWrapper;
 pub use self::logic::{infer_shapes, MatMul, MatMulUnary};
+pub use dyn_quant::{DynQuantMatMulUnary, DynZeroPointMatMulUnary};
+pub use mmm_wrapper::MMMWrapper;
@@ -0,0 +1,319 @@
+use num_traits::Zero;
+
+use crate::internal::*;
+use crate::ops::matmul::logic::Geo;
+use crate::ops::matmul::*;
+use crate::ops::quant::{quantization_params_for_range, quantize_linear_f32_i8};
+use ndarray::*;
+use tract_linalg::mmm::{QMatMatMulParams, QuantizedParam};
+
+/// Product of constant i8 weights by an f32 input quantized on the fly.
+///
+/// The weights are quantized symmetrically ahead of time, with one scale per
+/// row. At each evaluation, the input is quantized to i8 with a step and zero
+/// point computed from its actual range. These are given to the quantized
+/// kernel as runtime parameters, and the i32 accumulators are scaled back to
+/// f32.
+#[derive(Debug, Clone, new)]
+pub struct DynQuantMatMulUnary {
+    a: Arc<Tensor>,
+    a_scales: Arc<Tensor>,
+    a_trans: bool,
+    b_trans: bool,
+    c_trans: bool,
+}
+
+impl DynQuantMatMulUnary {
+    fn c_shapes(&self, b_shape: TVec<TDim>) -> TractResult<(TVec<TDim>, TVec<TDim>)> {
+        let (_, _, bc_c_shape, c_shape) = infer_shapes(
+            self.a.shape().iter().map(|d| d.to_dim()).collect(),
+            b_shape,
+            self.a_trans,
+            self.b_trans,
+            self.c_trans,
+        )?;
+        Ok((bc_c_shape, c_shape))
+    }
+}
+
+impl Op for DynQuantMatMulUnary {
+    fn name(&self) -> Cow<str> {
+        "DynQuantMatMulUnary".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!(
+                "a_trans:{:?} b_trans:{:?} c_trans:{:?}",
+                self.a_trans, self.b_trans, self.c_trans
+            ),
+            format!("A: {:?}", self.a),
+            format!("A scales: {:?}", self.a_scales),
+        ])
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for DynQuantMatMulUnary {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let b = args_1!(inputs);
+        let b = b.to_array_view::<f32>()?;
+        let (min, max) = b.iter().fold((0f32, 0f32), |(min, max), &x| (min.min(x), max.max(x)));
+        let (b_step, b_zero_point) = quantization_params_for_range(min, max, -128, 127);
+        let b = b.mapv(|x| quantize_linear_f32_i8(x, b_step.recip(), b_zero_point));
+
+        let a = self.a.to_array_view::<i8>()?;
+        let mut geo = Geo::new(
+            a.shape(),
+            b.shape(),
+            self.a_trans,
+            self.b_trans,
+            self.c_trans,
+            MMMWrapper::i8_i32,
+        )?;
+        let params = QMatMatMulParams {
+            zero_point_b: Some(QuantizedParam::Scalar(b_zero_point as i8)),
+            ..QMatMatMulParams::default()
+        };
+        let c = geo.compute(a, b.view(), Some(&params))?;
+
+        let a_scales = self.a_scales.as_slice::<f32>()?;
+        let row_axis = c.ndim() - 2 + self.c_trans as usize;
+        let c = Array::from_shape_fn(c.raw_dim(), |ix: IxDyn| {
+            c[&ix] as f32 * b_step * a_scales[ix[row_axis]]
+        });
+        Ok(tvec!(c.into_shape(&*geo.final_c_shape)?.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for DynQuantMatMulUnary {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(
+            f32::datum_type(),
+            &*self.c_shapes(inputs[0].shape.to_tvec())?.1
+        )?))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let (bc_c_shape, _) = self.c_shapes(inputs[0].shape.to_tvec())?;
+        let k = self.a.shape()[1 - self.a_trans as usize];
+        Ok(tvec!((Cost::FMA(i8::datum_type()), bc_c_shape.into_iter().product::<TDim>() * k)))
+    }
+
+    typed_op_as_op!();
+}
+
+/// Quantized product of constant weights by an input whose zero point is
+/// only known at evaluation, and given as a second input.
+///
+/// This is what DynamicQuantizeLinear followed by MatMulInteger amounts to.
+/// The zero point is given to the quantized kernel as a runtime parameter,
+/// instead of being corrected for after the product.
+#[derive(Debug, Clone, new)]
+pub struct DynZeroPointMatMulUnary {
+    a: Arc<Tensor>,
+    a_zero_point: Option<Arc<Tensor>>,
+    a_trans: bool,
+    b_trans: bool,
+    c_trans: bool,
+}
+
+impl DynZeroPointMatMulUnary {
+    fn c_shapes(&self, b_shape: TVec<TDim>) -> TractResult<(TVec<TDim>, TVec<TDim>)> {
+        let (_, _, bc_c_shape, c_shape) = infer_shapes(
+            self.a.shape().iter().map(|d| d.to_dim()).collect(),
+            b_shape,
+            self.a_trans,
+            self.b_trans,
+            self.c_trans,
+        )?;
+        Ok((bc_c_shape, c_shape))
+    }
+
+    fn eval_t<T: Datum + Copy + Zero>(
+        &self,
+        b: &Tensor,
+        b_zero_point: &Tensor,
+        mmm: impl Fn(usize, usize, usize) -> MMMWrapper<T, T, i32, i32>,
+    ) -> TractResult<Tensor> {
+        let a = self.a.to_array_view::<T>()?;
+        let b = b.to_array_view::<T>()?;
+        let mut geo =
+            Geo::new(a.shape(), b.shape(), self.a_trans, self.b_trans, self.c_trans, mmm)?;
+        let zero_point_a = if let Some(a0) = &self.a_zero_point {
+            if a0.rank() == 0 {
+                Some(QuantizedParam::Scalar(*a0.to_scalar::<T>()?))
+            } else {
+                Some(QuantizedParam::Vector(a0.as_slice::<T>()?.to_vec()))
+            }
+        } else {
+            None
+        };
+        let params = QMatMatMulParams {
+            zero_point_a,
+            zero_point_b: Some(QuantizedParam::Scalar(*b_zero_point.to_scalar::<T>()?)),
+            ..QMatMatMulParams::default()
+        };
+        let c = geo.compute(a, b, Some(&params))?;
+        unsafe { c.into_tensor().into_shape(&geo.final_c_shape) }
+    }
+}
+
+impl Op for DynZeroPointMatMulUnary {
+    fn name(&self) -> Cow<str> {
+        "DynZeroPointMatMulUnary".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!(
+                "a_trans:{:?} b_trans:{:?} c_trans:{:?}",
+                self.a_trans, self.b_trans, self.c_trans
+            ),
+            format!("A: {:?}", self.a),
+            format!("A zero point: {:?}", self.a_zero_point),
+        ])
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for DynZeroPointMatMulUnary {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (b, b_zero_point) = args_2!(inputs);
+        let c = if self.a.datum_type() == u8::datum_type() {
+            self.eval_t(&b, &b_zero_point, MMMWrapper::u8_i32)?
+        } else {
+            self.eval_t(&b, &b_zero_point, MMMWrapper::i8_i32)?
+        };
+        Ok(tvec!(c.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for DynZeroPointMatMulUnary {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(
+            i32::datum_type(),
+            &*self.c_shapes(inputs[0].shape.to_tvec())?.1
+        )?))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let (bc_c_shape, _) = self.c_shapes(inputs[0].shape.to_tvec())?;
+        let k = self.a.shape()[1 - self.a_trans as usize];
+        Ok(tvec!((Cost::FMA(self.a.datum_type()), bc_c_shape.into_iter().product::<TDim>() * k)))
+    }
+
+    typed_op_as_op!();
+}
+
+/// Replace the product of a dynamically quantized input, as ONNX
+/// DynamicQuantizeLinear and MatMulInteger import it, by a
+/// `DynZeroPointMatMulUnary`.
+///
+/// The imported product ignores the runtime zero point, and subtracts it
+/// afterwards times the sums of the weights:
+/// `sub(matmul(q), mul(cast(zero_point)))`.
+pub fn fuse_runtime_zero_point(
+    model: &TypedModel,
+    node: &TypedNode,
+) -> TractResult<Option<TypedModelPatch>> {
+    use crate::ops::binary::{TypedBinOp, UnaryOp};
+    use crate::ops::cast::Cast;
+    use crate::ops::quant::DynamicQuantizeLinearU8;
+    match node.op_as::<TypedBinOp>() {
+        Some(op) if op.0.is::<crate::ops::math::Sub>() => (),
+        _ => return Ok(None),
+    }
+    let product = model.node(node.inputs[0].node);
+    let correction = model.node(node.inputs[1].node);
+    let mm = if let Some(mm) = product.op_as::<MatMulUnary>() { mm } else { return Ok(None) };
+    let quant = product.inputs[0];
+    if quant.slot != 0 || !model.node(quant.node).op_is::<DynamicQuantizeLinearU8>() {
+        return Ok(None);
+    }
+    let sums = match correction.op_as::<UnaryOp>() {
+        Some(op) if op.mini_op.is::<crate::ops::math::Mul>() => &op.a,
+        _ => return Ok(None),
+    };
+    let cast = model.node(correction.inputs[0].node);
+    let zero_point = OutletId::new(quant.node, 2);
+    if !cast.op_is::<Cast>() || cast.inputs[0] != zero_point {
+        return Ok(None);
+    }
+    let op = if let Some(op) = mm.with_runtime_zero_point_b(sums)? { op } else { return Ok(None) };
+    let mut patch = TypedModelPatch::default();
+    let quant = patch.tap_model(model, quant)?;
+    let zero_point = patch.tap_model(model, zero_point)?;
+    let wire = patch.wire_node(&*node.name, op, &[quant, zero_point])?[0];
+    patch.shunt_outside(OutletId::new(node.id, 0), wire)?;
+    Ok(Some(patch))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dynamic_quantization_close_to_f32() {
+        let a = tensor2(&[[1f32, 0.5, -1.0], [2.0, 0.0, 0.25]]);
+        let b = tensor3(&[[[0f32, 1.0], [-0.5, 2.0], [0.25, -3.0]]]);
+        let mm = MatMulUnary::new(a.into_arc_tensor(), false, false, false, None);
+        let expected = mm.eval(tvec!(b.clone().into_arc_tensor())).unwrap();
+        let dq = mm.dynamic_quantize().unwrap().unwrap();
+        let found = dq.eval(tvec!(b.into_arc_tensor())).unwrap();
+        assert_eq!(found[0].shape(), expected[0].shape());
+        let expected = expected[0].as_slice::<f32>().unwrap();
+        for (f, e) in found[0].as_slice::<f32>().unwrap().iter().zip(expected.iter()) {
+            assert!((f - e).abs() < 0.05, "{:?} {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn dynamic_quantization_transposed() {
+        let a = tensor2(&[[1f32, 2.0], [0.5, 0.0], [-1.0, 0.25]]);
+        let b = tensor2(&[[0f32, -0.5, 0.25], [1.0, 2.0, -3.0]]);
+        let mm = MatMulUnary::new(a.into_arc_tensor(), true, true, true, None);
+        let expected = mm.eval(tvec!(b.clone().into_arc_tensor())).unwrap();
+        let dq = mm.dynamic_quantize().unwrap().unwrap();
+        let found = dq.eval(tvec!(b.into_arc_tensor())).unwrap();
+        assert_eq!(found[0].shape(), expected[0].shape());
+        let expected = expected[0].as_slice::<f32>().unwrap();
+        for (f, e) in found[0].as_slice::<f32>().unwrap().iter().zip(expected.iter()) {
+            assert!((f - e).abs() < 0.05, "{:?} {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn runtime_zero_point_replaces_correction() -> TractResult<()> {
+        use crate::ops::binary::TypedBinOp;
+        use crate::ops::cast::Cast;
+        use crate::ops::math::{mul, Sub};
+        use crate::ops::quant::{DynamicQuantizeLinearU8, QParams};
+        // DynamicQuantizeLinear and MatMulInteger, as imported and decluttered
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", TypedFact::dt_shape(f32::datum_type(), [2, 3].as_ref())?)?;
+        let q = model.wire_node("q", DynamicQuantizeLinearU8, &[x])?;
+        let w = tensor2(&[[0u8, 10, 20, 30], [40, 50, 60, 70], [80, 90, 100, 110]]);
+        let qp = QParams::new(i32::datum_type()).with_zero_point_a(&rctensor0(60u8));
+        let mm = MatMulUnary::new(w.into_arc_tensor(), true, true, true, Some(qp));
+        let product = model.wire_node("product", mm, &[q[0]])?[0];
+        let zero_point = model.wire_node("zero_point", Cast::new(i32::datum_type()), &[q[2]])?[0];
+        let sums = rctensor2(&[[-60i32, -30, 0, 30]]);
+        let correction = model.wire_node("correction", mul::unary(sums), &[zero_point])?[0];
+        let y = model.wire_node("y", TypedBinOp(Box::new(Sub)), &[product, correction])?[0];
+        model.set_output_outlets(&[y])?;
+
+        let input = tensor2(&[[0.5f32, -1.0, 2.0], [0.25, 1.5, -0.75]]);
+        let expected = SimplePlan::new(&model)?.run(tvec!(input.clone()))?;
+        let quantized = model.quantize_dynamic()?;
+        assert_eq!(quantized.nodes().len(), 3);
+        assert!(quantized.node(2).op_is::<DynZeroPointMatMulUnary>());
+        let found = SimplePlan::new(&quantized)?.run(tvec!(input))?;
+        assert_eq!(found, expected);
+        Ok(())
+    }
+}
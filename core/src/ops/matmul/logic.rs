@@ -6,6 +6,7 @@ use crate::internal::*;
 use crate::ops::matmul::*;
 use crate::ops::quant::QParams;
 use ndarray::*;
+use tract_linalg::mmm::QMatMatMulParams;
 
 fn eval(
     a: &Tensor,
@@ -17,34 +18,30 @@ fn eval(
 ) -> TractResult<Tensor> {
     if let Some(q) = q_params {
         if (a.datum_type(), b.datum_type()) == (i8::datum_type(), i8::datum_type()) {
-            return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
-                MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n))
-            });
-        } else if (a.datum_type(), b.datum_type()) == (i8::datum_type(), i8::datum_type()) {
             if q.c_datum_type == i32::datum_type() {
-                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
-                    MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n))
-                });
+                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, MMMWrapper::i8_i32);
             } else if q.c_datum_type == i8::datum_type() {
-                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
-                    MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n))
-                });
+                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, MMMWrapper::i8_i8);
             }
         } else if (a.datum_type(), b.datum_type()) == (u8::datum_type(), u8::datum_type()) {
             if q.c_datum_type == i32::datum_type() {
-                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
-                    MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_i32)(m, k, n))
-                });
+                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, MMMWrapper::u8_i32);
             } else if q.c_datum_type == u8::datum_type() {
-                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
-                    MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_u8)(m, k, n))
-                });
+                return eval_t(a, b, a_trans, b_trans, c_trans, q_params, MMMWrapper::u8_u8);
             }
         }
     } else if (a.datum_type(), b.datum_type()) == (f32::datum_type(), f32::datum_type()) {
-        return eval_t(a, b, a_trans, b_trans, c_trans, q_params, &|m, k, n| {
-            MMMWrapper::Plain((tract_linalg::ops().smmm)(m, k, n))
-        });
+        return eval_t(a, b, a_trans, b_trans, c_trans, q_params, MMMWrapper::f32);
+    } else if (a.datum_type(), b.datum_type()) == (f16::datum_type(), f32::datum_type()) {
+        return eval_t(
+            &*a.cast_to::<f32>()?,
+            b,
+            a_trans,
+            b_trans,
+            c_trans,
+            q_params,
+            &MMMWrapper::f32,
+        );
     }
     bail!(
         "Unsupported combination for MatMul eval (a: {:?}, b:{:?} q:{:?})",
@@ -72,57 +69,10 @@ where
     let a = a.to_array_view::<TA>()?;
     let b = b.to_array_view::<TB>()?;
     let mut geo = Geo::<TA, TB, TC, TI>::new(a.shape(), b.shape(), a_trans, b_trans, c_trans, mmm)?;
-    unsafe {
-        geo.mm.as_mmm_mut().c_from_data_and_strides(
-            if c_trans { 1 } else { *geo.bc_c_shape.last().unwrap() as isize },
-            if !c_trans { 1 } else { *geo.bc_c_shape.last().unwrap() as isize },
-        );
-        if let Some(q) = q_params {
-            geo.mm.set_quant_params(q)?;
-        }
-    }
-    let a = a.into_shape(&*geo.bc_a_shape)?;
-    let b = b.into_shape(&*geo.bc_b_shape)?;
-    let mut c = unsafe { Array::<TC, IxDyn>::uninitialized(&*geo.bc_c_shape) };
-
-    let b_pack = geo.mm.as_mmm().b_pack();
-
-    let mut pa = unsafe {
-        Tensor::uninitialized_aligned::<TA>(
-            &[geo.mm.as_mmm().a_pack().len()],
-            geo.mm.as_mmm().a_pack().alignment(),
-        )?
-    };
-    let mut pb =
-        unsafe { Tensor::uninitialized_aligned::<TB>(&[b_pack.len()], b_pack.alignment())? };
-
-    for prefix in indices(&*geo.c_shape_prefix).into_iter() {
-        let mut a = a.view();
-        let mut b = b.view();
-        let mut c = c.view_mut();
-        for (axis, &dim) in prefix.slice().iter().enumerate() {
-            let d = dim.min(a.shape()[axis] - 1);
-            a.slice_axis_inplace(Axis(axis), (d..=d).into());
-            let d = dim.min(b.shape()[axis] - 1);
-            b.slice_axis_inplace(Axis(axis), (d..=d).into());
-            c.slice_axis_inplace(Axis(axis), (dim..=dim).into());
-        }
-        geo.mm.as_mmm().a_pack().pack(
-            pa.as_ptr_mut()?,
-            a.as_ptr(),
-            a.strides()[prefix.ndim() + a_trans as usize],
-            a.strides()[prefix.ndim() + !a_trans as usize],
-        );
-        b_pack.pack(
-            pb.as_ptr_mut()?,
-            b.as_ptr(),
-            b.strides()[prefix.ndim() + b_trans as usize],
-            b.strides()[prefix.ndim() + !b_trans as usize],
-        );
-        unsafe {
-            geo.mm.run(pa.as_ptr()?, pb.as_ptr()?, c.as_mut_ptr(), &[]);
-        }
+    if let Some(q) = q_params {
+        geo.mm.set_quant_params(q)?;
     }
+    let c = geo.compute(a, b, None)?;
     unsafe { Ok(c.into_tensor().into_shape(&geo.final_c_shape)?) }
 }
 
@@ -199,7 +149,7 @@ pub fn infer_shapes<D: DimLike>(
 }
 
 #[derive(Debug, Clone)]
-struct Geo<TA, TB, TC, TI>
+pub(super) struct Geo<TA, TB, TC, TI>
 where
     TA: Datum + Copy + Zero,
     TB: Datum + Copy + Zero,
@@ -217,7 +167,7 @@ where
     bc_a_shape: TVec<usize>,
     bc_b_shape: TVec<usize>,
     bc_c_shape: TVec<usize>,
-    final_c_shape: TVec<usize>,
+    pub(super) final_c_shape: TVec<usize>,
     c_trans: bool,
     c_shape_prefix: TVec<usize>,
     a_stride_prefix: TVec<usize>,
@@ -232,7 +182,7 @@ where
     TC: Datum + Copy,
     TI: Datum + Copy + Add + Mul + Zero + fmt::Debug,
 {
-    pub fn new(
+    pub(super) fn new(
         a_shape: &[usize],
         b_shape: &[usize],
         a_trans: bool,
@@ -296,6 +246,73 @@ where
             c_trans,
         })
     }
+
+    /// Compute the product of a and b, in the broadcast c shape.
+    ///
+    /// If `q_params` is given, it overrides the quantization parameters set
+    /// on the operator.
+    pub(super) fn compute(
+        &mut self,
+        a: ArrayViewD<TA>,
+        b: ArrayViewD<TB>,
+        q_params: Option<&QMatMatMulParams<TA, TB, TC, TI>>,
+    ) -> TractResult<ArrayD<TC>> {
+        unsafe {
+            self.mm.as_mmm_mut().c_from_data_and_strides(
+                if self.c_trans { 1 } else { *self.bc_c_shape.last().unwrap() as isize },
+                if !self.c_trans { 1 } else { *self.bc_c_shape.last().unwrap() as isize },
+            );
+        }
+        let quant = if let Some(params) = q_params {
+            Some((self.mm.as_quant().ok_or("Quantization parameters on a float product")?, params))
+        } else {
+            None
+        };
+        let a = a.into_shape(&*self.bc_a_shape)?;
+        let b = b.into_shape(&*self.bc_b_shape)?;
+        let mut c = unsafe { Array::<TC, IxDyn>::uninitialized(&*self.bc_c_shape) };
+
+        let a_pack = self.mm.as_mmm().a_pack();
+        let b_pack = self.mm.as_mmm().b_pack();
+
+        let mut pa =
+            unsafe { Tensor::uninitialized_aligned::<TA>(&[a_pack.len()], a_pack.alignment())? };
+        let mut pb =
+            unsafe { Tensor::uninitialized_aligned::<TB>(&[b_pack.len()], b_pack.alignment())? };
+
+        for prefix in indices(&*self.c_shape_prefix).into_iter() {
+            let mut a = a.view();
+            let mut b = b.view();
+            let mut c = c.view_mut();
+            for (axis, &dim) in prefix.slice().iter().enumerate() {
+                let d = dim.min(a.shape()[axis] - 1);
+                a.slice_axis_inplace(Axis(axis), (d..=d).into());
+                let d = dim.min(b.shape()[axis] - 1);
+                b.slice_axis_inplace(Axis(axis), (d..=d).into());
+                c.slice_axis_inplace(Axis(axis), (dim..=dim).into());
+            }
+            a_pack.pack(
+                pa.as_ptr_mut()?,
+                a.as_ptr(),
+                a.strides()[prefix.ndim() + self.a_trans as usize],
+                a.strides()[prefix.ndim() + !self.a_trans as usize],
+            );
+            b_pack.pack(
+                pb.as_ptr_mut()?,
+                b.as_ptr(),
+                b.strides()[prefix.ndim() + self.b_trans as usize],
+                b.strides()[prefix.ndim() + !self.b_trans as usize],
+            );
+            unsafe {
+                if let Some((mm, params)) = quant {
+                    mm.run_with_q_params(pa.as_ptr()?, pb.as_ptr()?, c.as_mut_ptr(), &[], params);
+                } else {
+                    self.mm.run(pa.as_ptr()?, pb.as_ptr()?, c.as_mut_ptr(), &[]);
+                }
+            }
+        }
+        Ok(c)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -407,14 +424,22 @@ impl TypedOp for MatMul {
 
         let var_ix = 1 - konst_ix;
         let flip = konst_ix == 1;
+        if flip && self.q_params.as_ref().and_then(|qp| qp.scale_factor_per_row()).is_some() {
+            // flipping would turn the per row scale into a per column one
+            return Ok(None);
+        }
         let t_konst = [self.a_trans, self.b_trans][konst_ix] ^ flip;
         let t_var = [self.b_trans, self.a_trans][konst_ix] ^ flip;
         let konst = model.outlet_fact(node.inputs[konst_ix])?.konst.clone().unwrap();
+        let mut q_params = self.q_params.clone();
+        if let Some(qp) = q_params.as_mut().filter(|_| flip) {
+            std::mem::swap(&mut qp.zero_point_a, &mut qp.zero_point_b);
+        }
         let patch = TypedModelPatch::replace_single_op(
             model,
             node,
             &node.inputs[var_ix..][..1],
-            MatMulUnary::new(konst, t_konst, t_var, self.c_trans ^ flip, self.q_params.clone()),
+            MatMulUnary::new(konst, t_konst, t_var, self.c_trans ^ flip, q_params),
         )?;
         return Ok(Some(patch));
     }
@@ -441,6 +466,161 @@ pub struct MatMulUnary {
     q_params: Option<QParams>,
 }
 
+impl MatMulUnary {
+    /// Store f32 weights as f16. Quantized products are left untouched.
+    ///
+    /// The optimized product keeps the weights packed as f16 and accumulates in
+    /// f32. f16 weights are never turned into a sparse product.
+    pub fn f16_weights(&mut self) -> TractResult<()> {
+        if self.a.datum_type() == f32::datum_type() && self.q_params.is_none() {
+            self.a = self.a.cast_to::<f16>()?.into_owned().into_arc_tensor();
+        }
+        Ok(())
+    }
+
+    /// Quantized i8 version of an f32 product, given the (step, zero point)
+    /// quantization of its input and output.
+    ///
+    /// The weights are quantized symmetrically with one scale per row. Returns
+    /// None if the product is not a plain f32 one with a matrix as weights.
+    pub fn quantize_i8(&self, b: (f32, i8), c: (f32, i8)) -> TractResult<Option<MatMulUnary>> {
+        use crate::ops::quant::quantize_symmetric_i8;
+        if self.a.datum_type() != f32::datum_type() || self.q_params.is_some() || self.a.rank() != 2
+        {
+            return Ok(None);
+        }
+        let (a, a_scales) = quantize_symmetric_i8(&self.a, Some(self.a_trans as usize))?;
+        let scales: Vec<f32> = a_scales.iter().map(|a| b.0 * a / c.0).collect();
+        let q_params = QParams::new(i8::datum_type())
+            .with_zero_point_b(&rctensor0(b.1))
+            .with_zero_point_c(&rctensor0(c.1))
+            .with_scale_factor_per_row(&rctensor1(&scales))?;
+        Ok(Some(MatMulUnary { a: a.into_arc_tensor(), q_params: Some(q_params), ..self.clone() }))
+    }
+
+    /// Dynamically quantized version of an f32 product: the weights are
+    /// quantized symmetrically to i8 with one scale per row, the input will be
+    /// quantized at each evaluation.
+    ///
+    /// Returns None if the product is not a plain f32 one with a matrix as
+    /// weights.
+    pub fn dynamic_quantize(&self) -> TractResult<Option<DynQuantMatMulUnary>> {
+        use crate::ops::quant::quantize_symmetric_i8;
+        if self.a.datum_type() != f32::datum_type() || self.q_params.is_some() || self.a.rank() != 2
+        {
+            return Ok(None);
+        }
+        let (a, a_scales) = quantize_symmetric_i8(&self.a, Some(self.a_trans as usize))?;
+        Ok(Some(DynQuantMatMulUnary::new(
+            a.into_arc_tensor(),
+            rctensor1(&a_scales),
+            self.a_trans,
+            self.b_trans,
+            self.c_trans,
+        )))
+    }
+
+    /// Version of a quantized i32 product taking the zero point of its input
+    /// as a second input, in place of a `correction` subtracted from its
+    /// output: the zero point times the sums of the weights over k.
+    ///
+    /// Returns None if `correction` does not hold these sums, or if the product
+    /// already has a zero point for its input or rescales its output.
+    pub fn with_runtime_zero_point_b(
+        &self,
+        correction: &Tensor,
+    ) -> TractResult<Option<DynZeroPointMatMulUnary>> {
+        let qp = if let Some(qp) = &self.q_params { qp } else { return Ok(None) };
+        if qp.c_datum_type != i32::datum_type()
+            || qp.zero_point_b.is_some()
+            || qp.zero_point_c.is_some()
+            || qp.scale_factor.is_some()
+            || self.a.rank() != 2
+            || (self.a.datum_type() != u8::datum_type() && self.a.datum_type() != i8::datum_type())
+        {
+            return Ok(None);
+        }
+        let a = self.a.cast_to::<i32>()?;
+        let a = a.to_array_view::<i32>()?;
+        let k = a.shape()[!self.a_trans as usize];
+        let mut sums = a.sum_axis(Axis(!self.a_trans as usize));
+        if let Some(a0) = &qp.zero_point_a {
+            let a0 = a0.cast_to::<i32>()?;
+            let a0 = a0.to_array_view::<i32>()?;
+            sums.zip_mut_with(&a0, |s, a0| *s -= a0 * k as i32);
+        }
+        let correction = correction.cast_to::<i32>()?;
+        if correction.len() != sums.len()
+            || correction.as_slice::<i32>()? != sums.as_slice().unwrap()
+        {
+            return Ok(None);
+        }
+        Ok(Some(DynZeroPointMatMulUnary::new(
+            self.a.clone(),
+            qp.zero_point_a.clone(),
+            self.a_trans,
+            self.b_trans,
+            self.c_trans,
+        )))
+    }
+
+    /// Product by the B matrices of independent streams, stacked along `axis`
+    /// of B, for the batched pulsed executor.
+    ///
+    /// Only f32 products by a plain matrix A are supported, B having no other
+    /// stacking axis than `axis`.
+    pub(crate) fn stacked(
+        &self,
+        b: &TypedFact,
+        axis: usize,
+    ) -> TractResult<Option<phy::MatMatMulUnaryStacked>> {
+        let b_shape = if let Some(shape) = b.shape.as_finite() { shape } else { return Ok(None) };
+        let rank = b_shape.len();
+        if self.a.rank() != 2
+            || self.q_params.is_some()
+            || self.a.datum_type() != f32::datum_type()
+            || b.datum_type != f32::datum_type()
+            || rank < 3
+            || axis >= rank - 2
+            || b_shape[..rank - 2].iter().any(|&d| d != 1)
+        {
+            return Ok(None);
+        }
+        let (_, _, _, c_shape) = infer_shapes(
+            self.a.shape().into(),
+            b_shape.into(),
+            self.a_trans,
+            self.b_trans,
+            self.c_trans,
+        )?;
+        let m = self.a.shape()[self.a_trans as usize];
+        let k = self.a.shape()[!self.a_trans as usize];
+        // the f32 kernel does not depend on n, so A is packed once for all stacks
+        let a_pack = (tract_linalg::ops().smmm)(m, k, 1).a_pack();
+        let a = self.a.to_array_view::<f32>()?;
+        let packed_a = unsafe {
+            let mut packed_a =
+                Tensor::uninitialized_aligned::<f32>(&[a_pack.len()], a_pack.alignment())?;
+            a_pack.pack(
+                packed_a.as_ptr_mut()?,
+                a.as_ptr(),
+                a.strides()[self.a_trans as usize],
+                a.strides()[!self.a_trans as usize],
+            );
+            packed_a
+        };
+        Ok(Some(phy::MatMatMulUnaryStacked {
+            m,
+            k,
+            b_trans: self.b_trans,
+            c_trans: self.c_trans,
+            axis,
+            packed_a: packed_a.into_arc_tensor(),
+            c_fact: TypedFact::dt_shape(f32::datum_type(), &*c_shape)?,
+        }))
+    }
+}
+
 impl Op for MatMulUnary {
     fn name(&self) -> Cow<str> {
         "MatMulUnary".into()
@@ -542,6 +722,10 @@ impl TypedOp for MatMulUnary {
         use crate::ops::array::concat::NormConcatSlice;
         use crate::ops::array::NormConcat;
         let input_fact = model.outlet_fact(node.inputs[0])?;
+        if self.q_params.is_some() {
+            // partial quantized products can not be summed
+            return Ok(None);
+        }
         if let Some(concat) = model.node_op(node.inputs[0].node).downcast_ref::<NormConcat>() {
             let mut patch = TypedModelPatch::default();
             let k_axis = self.a.rank() - 1 - self.a_trans as usize;
@@ -603,7 +787,9 @@ impl TypedOp for MatMulUnary {
     ) -> TractResult<Option<OutletId>> {
         let b_fact = model.outlet_fact(node.inputs[0])?;
         let c_fact = &self.output_facts(&[b_fact])?[0];
-        if axis + self.c_trans as usize == c_fact.shape.rank() {
+        if axis + self.c_trans as usize == c_fact.shape.rank()
+            && self.q_params.as_ref().and_then(|qp| qp.scale_factor_per_row()).is_none()
+        {
             let a_split_axis = self.a.rank() - 1 - !self.a_trans as usize;
             let a = self.a.slice(a_split_axis, start, end)?.into_arc_tensor();
             let wire = patch.tap_model(model, node.inputs[0])?;
@@ -651,61 +837,122 @@ impl TypedOp for MatMulUnary {
     ) -> TractResult<Option<TypedModelPatch>> {
         let b = args_1!(model.node_input_facts(node.id)?);
         if let Some(b_shape) = b.shape.as_finite() {
-            let patch =
-                if (self.a.datum_type(), b.datum_type) == (f32::datum_type(), f32::datum_type()) {
-                    new_mat_mul_unary_finite(
-                        model,
-                        node,
-                        self.a.clone(),
-                        b_shape,
-                        self.a_trans,
-                        self.b_trans,
-                        self.c_trans,
-                        self.q_params.as_ref(),
-                        &|m, k, n| MMMWrapper::Plain((tract_linalg::ops().smmm)(m, k, n)),
-                    )?
-                } else if (
-                    self.a.datum_type(),
-                    b.datum_type,
-                    self.q_params.as_ref().map(|q| q.c_datum_type),
-                ) == (i8::datum_type(), i8::datum_type(), Some(i8::datum_type()))
-                {
-                    new_mat_mul_unary_finite(
-                        model,
-                        node,
-                        self.a.clone(),
-                        b_shape,
-                        self.a_trans,
-                        self.b_trans,
-                        self.c_trans,
-                        self.q_params.as_ref(),
-                        &|m, k, n| MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n)),
-                    )?
-                } else if (
+            if self.q_params.is_none() && b.datum_type == f32::datum_type() {
+                if let Some(patch) = sparse::new_sparse_mat_mul_unary_finite(
+                    model,
+                    node,
+                    &self.a,
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                )? {
+                    return Ok(Some(patch));
+                }
+            }
+            let patch = if (self.a.datum_type(), b.datum_type)
+                == (f32::datum_type(), f32::datum_type())
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &MMMWrapper::f32,
+                )?
+            } else if (self.a.datum_type(), b.datum_type) == (f16::datum_type(), f32::datum_type())
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &MMMWrapper::f16_f32,
+                )?
+            } else if (
+                self.a.datum_type(),
+                b.datum_type,
+                self.q_params.as_ref().map(|q| q.c_datum_type),
+            ) == (i8::datum_type(), i8::datum_type(), Some(i8::datum_type()))
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &MMMWrapper::i8_i8,
+                )?
+            } else if (
+                self.a.datum_type(),
+                b.datum_type,
+                self.q_params.as_ref().map(|q| q.c_datum_type),
+            ) == (i8::datum_type(), i8::datum_type(), Some(i32::datum_type()))
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &MMMWrapper::i8_i32,
+                )?
+            } else if (
+                self.a.datum_type(),
+                b.datum_type,
+                self.q_params.as_ref().map(|q| q.c_datum_type),
+            ) == (u8::datum_type(), u8::datum_type(), Some(u8::datum_type()))
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &MMMWrapper::u8_u8,
+                )?
+            } else if (
+                self.a.datum_type(),
+                b.datum_type,
+                self.q_params.as_ref().map(|q| q.c_datum_type),
+            ) == (u8::datum_type(), u8::datum_type(), Some(i32::datum_type()))
+            {
+                new_mat_mul_unary_finite(
+                    model,
+                    node,
+                    self.a.clone(),
+                    b_shape,
+                    self.a_trans,
+                    self.b_trans,
+                    self.c_trans,
+                    self.q_params.as_ref(),
+                    &MMMWrapper::u8_i32,
+                )?
+            } else {
+                bail!(
+                    "Unsupported combination for MatMul codegen (a: {:?}, b:{:?}, q: {:?})",
                     self.a.datum_type(),
                     b.datum_type,
-                    self.q_params.as_ref().map(|q| q.c_datum_type),
-                ) == (i8::datum_type(), i8::datum_type(), Some(i32::datum_type()))
-                {
-                    new_mat_mul_unary_finite(
-                        model,
-                        node,
-                        self.a.clone(),
-                        b_shape,
-                        self.a_trans,
-                        self.b_trans,
-                        self.c_trans,
-                        self.q_params.as_ref(),
-                        &|m, k, n| MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n)),
-                    )?
-                } else {
-                    bail!(
-                        "Unsupported combination for MatMul codegen (a: {:?}, b:{:?}, q: {:?})",
-                        self.a.datum_type(),
-                        b.datum_type,
-                        self.q_params
-                    );
-                };
+                    self.q_params
+                );
+            };
             return Ok(Some(patch));
         }
         Ok(None)
@@ -731,6 +978,10 @@ impl PulsedOp for MatMulUnary {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -893,4 +1144,51 @@ mod test {
         let c_found = op.eval(tvec!(b, a)).unwrap().pop().unwrap();
         c.close_enough(&c_found, true).unwrap();
     }
+
+    #[test]
+    fn stacked_product_runs_any_stack() {
+        let a = tensor2(&[[1f32, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        for &(b_shape, b_trans, c_trans) in &[
+            ([1, 3, 2], false, false),
+            ([1, 3, 2], false, true),
+            ([1, 2, 3], true, false),
+            ([1, 2, 3], true, true),
+        ] {
+            let op = MatMulUnary::new(a.clone().into_arc_tensor(), false, b_trans, c_trans, None);
+            let fact = TypedFact::dt_shape(f32::datum_type(), &b_shape[..]).unwrap();
+            let stacked = op.stacked(&fact, 0).unwrap().unwrap();
+            for &stack in &[1, 4] {
+                let shape = [stack, b_shape[1], b_shape[2]];
+                let len = shape.iter().product::<usize>();
+                let b = Tensor::from(
+                    ArrayD::from_shape_vec(&shape[..], (0..len).map(|x| x as f32).collect())
+                        .unwrap(),
+                )
+                .into_arc_tensor();
+                let found = stacked.eval(tvec!(b.clone())).unwrap();
+                let expected = op.eval(tvec!(b)).unwrap();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn declutter_keeps_zero_points() {
+        let mut model = TypedModel::default();
+        let a = model
+            .add_source("a", TypedFact::dt_shape(u8::datum_type(), [2, 3].as_ref()).unwrap())
+            .unwrap();
+        let b = model.add_const("b", tensor2(&[[3u8, 200], [7, 100], [128, 1]])).unwrap();
+        let qp = QParams::new(i32::datum_type())
+            .with_zero_point_a(&rctensor0(153u8))
+            .with_zero_point_b(&rctensor0(5u8));
+        let c = model.wire_node("c", MatMul::default().with_q_params(qp), &[a, b]).unwrap();
+        model.set_output_outlets(&c).unwrap();
+        let input = tensor2(&[[153u8, 255, 0], [26, 221, 179]]);
+        let expected = tensor2(&[[-18615i32, 10302], [3588, -18409]]);
+        let decluttered = model.declutter().unwrap();
+        assert!(decluttered.nodes().iter().any(|n| n.op_is::<MatMulUnary>()));
+        let found = SimplePlan::new(&decluttered).unwrap().run(tvec!(input)).unwrap();
+        assert_eq!(*found[0], expected);
+    }
 }
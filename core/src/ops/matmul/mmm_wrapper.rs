@@ -5,7 +5,7 @@ use std::ops::{Add, Deref, Mul};
 use crate::internal::*;
 use crate::ops::quant::QParams;
 
-use tract_linalg::mmm::{FusedSpec, MatMatMul, QMatMatMul};
+use tract_linalg::mmm::{FusedSpec, MatMatMul, MatVecMul, QMatMatMul};
 
 #[derive(Clone, Debug)]
 pub enum MMMWrapper<TA, TB, TC, TI>
@@ -16,6 +16,7 @@ where
     TI: Datum + Copy + Add + Mul + Zero + fmt::Debug,
 {
     Plain(Box<dyn MatMatMul<TA, TB, TC, TI>>),
+    Vec(Box<dyn MatVecMul<TA, TB, TC, TI>>),
     Quant(Box<dyn QMatMatMul<TA, TB, TC, TI>>),
 }
 
@@ -29,6 +30,7 @@ where
     pub fn as_mmm(&self) -> &dyn MatMatMul<TA, TB, TC, TI> {
         match self {
             MMMWrapper::Plain(a) => a.as_ref(),
+            MMMWrapper::Vec(a) => a.as_mmm(),
             MMMWrapper::Quant(a) => a.as_mmm(),
         }
     }
@@ -36,20 +38,21 @@ where
     pub fn as_mmm_mut(&mut self) -> &mut dyn MatMatMul<TA, TB, TC, TI> {
         match self {
             MMMWrapper::Plain(a) => a.as_mut(),
+            MMMWrapper::Vec(a) => a.as_mmm_mut(),
             MMMWrapper::Quant(a) => a.as_mmm_mut(),
         }
     }
 
     pub fn as_quant(&self) -> Option<&dyn QMatMatMul<TA, TB, TC, TI>> {
         match self {
-            MMMWrapper::Plain(_) => None,
+            MMMWrapper::Plain(_) | MMMWrapper::Vec(_) => None,
             MMMWrapper::Quant(a) => Some(a.deref()),
         }
     }
 
     pub fn as_quant_mut(&mut self) -> Option<&mut dyn QMatMatMul<TA, TB, TC, TI>> {
         match self {
-            MMMWrapper::Plain(_) => None,
+            MMMWrapper::Plain(_) | MMMWrapper::Vec(_) => None,
             MMMWrapper::Quant(ref mut a) => Some(a.as_mut()),
         }
     }
@@ -63,6 +66,7 @@ where
     ) {
         match self {
             MMMWrapper::Plain(p) => p.run(a, b, c, non_linear),
+            MMMWrapper::Vec(v) => v.run(a, b, c, non_linear),
             MMMWrapper::Quant(q) => q.run(a, b, c, non_linear),
         }
     }
@@ -87,14 +91,77 @@ where
             if let Some(t) = params.zero_point_c.as_ref() {
                 q.set_zero_point_c_scalar(t.cast_to_scalar()?)
             }
-            if let Some(factor) = params.scale_factor {
-                q.set_scale_factor(factor);
+            if let Some(t) = params.scale_factor.as_ref() {
+                if t.rank() == 0 {
+                    q.set_scale_factor(*t.to_scalar()?)
+                } else {
+                    q.set_scale_factor_per_row(t.as_slice()?)
+                }
             }
         }
         Ok(())
     }
 }
 
+// Constructors for the supported type combinations. Products with a single
+// column (n == 1) get the dedicated matrix-vector kernels.
+
+impl MMMWrapper<f32, f32, f32, f32> {
+    pub fn f32(m: usize, k: usize, n: usize) -> Self {
+        if n == 1 {
+            MMMWrapper::Vec((tract_linalg::ops().smmv)(m, k))
+        } else {
+            MMMWrapper::Plain((tract_linalg::ops().smmm)(m, k, n))
+        }
+    }
+}
+
+impl MMMWrapper<f16, f32, f32, f32> {
+    pub fn f16_f32(m: usize, k: usize, n: usize) -> Self {
+        MMMWrapper::Plain((tract_linalg::ops().smmm_f16)(m, k, n))
+    }
+}
+
+impl MMMWrapper<i8, i8, i32, i32> {
+    pub fn i8_i32(m: usize, k: usize, n: usize) -> Self {
+        if n == 1 {
+            MMMWrapper::Quant((tract_linalg::ops().qmmv_i8_i32)(m, k))
+        } else {
+            MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i32)(m, k, n))
+        }
+    }
+}
+
+impl MMMWrapper<i8, i8, i8, i32> {
+    pub fn i8_i8(m: usize, k: usize, n: usize) -> Self {
+        if n == 1 {
+            MMMWrapper::Quant((tract_linalg::ops().qmmv_i8_i8)(m, k))
+        } else {
+            MMMWrapper::Quant((tract_linalg::ops().qmmm_i8_i8)(m, k, n))
+        }
+    }
+}
+
+impl MMMWrapper<u8, u8, i32, i32> {
+    pub fn u8_i32(m: usize, k: usize, n: usize) -> Self {
+        if n == 1 {
+            MMMWrapper::Quant((tract_linalg::ops().qmmv_u8_i32)(m, k))
+        } else {
+            MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_i32)(m, k, n))
+        }
+    }
+}
+
+impl MMMWrapper<u8, u8, u8, i32> {
+    pub fn u8_u8(m: usize, k: usize, n: usize) -> Self {
+        if n == 1 {
+            MMMWrapper::Quant((tract_linalg::ops().qmmv_u8_u8)(m, k))
+        } else {
+            MMMWrapper::Quant((tract_linalg::ops().qmmm_u8_u8)(m, k, n))
+        }
+    }
+}
+
 impl<TA, TB, TC, TI> fmt::Display for MMMWrapper<TA, TB, TC, TI>
 where
     TA: Datum + Copy + Zero,
@@ -105,6 +172,7 @@ where
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             MMMWrapper::Plain(a) => write!(fmt, "{}", a),
+            MMMWrapper::Vec(a) => write!(fmt, "{}", a),
             MMMWrapper::Quant(a) => write!(fmt, "{}", a),
         }
     }
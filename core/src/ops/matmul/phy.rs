@@ -94,6 +94,35 @@ where
     pub(crate) mmm: MMMWrapper<TA, TB, TC, TI>,
 }
 
+impl<TA, TB, TC, TI> MatMatMulUnaryFinite<TA, TB, TC, TI>
+where
+    TA: Datum + Copy + Zero,
+    TB: Datum + Copy + Zero,
+    TC: Datum + Copy,
+    TI: Datum + Copy + Add + Mul + Zero + fmt::Debug,
+{
+    /// Whether the outer axis of B and C stacks products sharing the same A
+    /// and fused operations. The op then accepts a B with any number of them.
+    fn outer_axis_is_stack(&self) -> bool {
+        let c_shape = self.c_fact.shape.as_finite().unwrap();
+        match &self.c_prefix_dim_and_stride {
+            Some((dims, strides)) => {
+                c_shape == &*self.bc_c_shape
+                    && dims[0] == c_shape[0]
+                    && strides[0] == c_shape[1..].iter().product::<usize>() as isize
+                    && self.packed_as.ndim() > 0
+                    && self.packed_as.shape()[0] == 1
+                    && self
+                        .fused_ops
+                        .as_ref()
+                        .map(|f| f.ndim() == 0 || f.shape()[0] == 1)
+                        .unwrap_or(true)
+            }
+            None => false,
+        }
+    }
+}
+
 impl<TA, TB, TC, TI> Op for MatMatMulUnaryFinite<TA, TB, TC, TI>
 where
     TA: Datum + Copy + Zero,
@@ -189,8 +218,14 @@ where
     fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
         unsafe {
             let b = args_1!(inputs);
-            let mut c = Tensor::uninitialized::<TC>(&*self.c_fact.shape.as_finite().unwrap())?;
-            if let Some((prefix_dim, prefix_strides)) = &self.c_prefix_dim_and_stride {
+            let mut c_shape: TVec<usize> = self.c_fact.shape.as_finite().unwrap().into();
+            let mut c_prefix_dim_and_stride = Cow::Borrowed(&self.c_prefix_dim_and_stride);
+            if self.outer_axis_is_stack() && b.shape()[0] != c_shape[0] {
+                c_shape[0] = b.shape()[0];
+                c_prefix_dim_and_stride.to_mut().as_mut().unwrap().0[0] = b.shape()[0];
+            }
+            let mut c = Tensor::uninitialized::<TC>(&c_shape)?;
+            if let Some((prefix_dim, prefix_strides)) = &*c_prefix_dim_and_stride {
                 let b = b.to_array_view::<TB>()?;
                 let mut c = c.to_array_view_mut::<TC>()?;
                 for prefix in indices(&**prefix_dim).into_iter() {
@@ -207,7 +242,7 @@ where
                     let pa: &Tensor = a.iter().next().unwrap();
                     if let Some(fused) = &self.fused_ops {
                         let mut fused = fused.view();
-                        for &dim in prefix.slice() {
+                        for &dim in prefix.slice().iter().take(fused.ndim()) {
                             let d = dim.min(fused.shape()[0] - 1);
                             fused.index_axis_inplace(Axis(0), d);
                         }
@@ -249,5 +284,106 @@ where
         Ok(tvec!(self.c_fact.clone()))
     }
 
+    fn invariants(&self, model: &TypedModel, node: &TypedNode) -> TractResult<Invariants> {
+        let b = model.outlet_fact(node.inputs[0])?;
+        if self.outer_axis_is_stack() && b.shape.dim(0) == self.bc_c_shape[0].to_dim() {
+            Ok(Invariants::from(tvec!(AxisInfo::simple(0))))
+        } else {
+            Ok(Invariants::none())
+        }
+    }
+
+    typed_op_as_op!();
+}
+
+/// Product of a constant f32 A by the B matrices of a varying number of
+/// independent streams, stacked along an outer axis of B.
+///
+/// A is packed once, when the op is built. At each run, the columns of all
+/// the streams are packed side by side in a single set of B panels, so the
+/// whole stack is computed by one product.
+#[derive(Debug, Clone)]
+pub(crate) struct MatMatMulUnaryStacked {
+    pub(crate) m: usize,
+    pub(crate) k: usize,
+    pub(crate) b_trans: bool,
+    pub(crate) c_trans: bool,
+    pub(crate) axis: usize,
+    pub(crate) packed_a: Arc<Tensor>,
+    pub(crate) c_fact: TypedFact,
+}
+
+impl Op for MatMatMulUnaryStacked {
+    fn name(&self) -> Cow<str> {
+        "MatMatMulUnaryStacked".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "m:{} k:{} b_trans:{:?} c_trans:{:?} stacked on axis {}",
+            self.m, self.k, self.b_trans, self.c_trans, self.axis
+        )])
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for MatMatMulUnaryStacked {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let b = args_1!(inputs);
+        let rank = b.rank();
+        let stack = b.shape()[self.axis];
+        let n = b.shape()[rank - 1 - self.b_trans as usize];
+        let cols = stack * n;
+        let mut mmm = (tract_linalg::ops().smmm)(self.m, self.k, cols);
+        let b = b.to_array_view::<f32>()?;
+        let b = if self.b_trans {
+            // [stack, n, k] is already a [stack * n, k] matrix
+            CowArray::from(b.into_shape((cols, self.k))?)
+        } else {
+            // lay the [k, n] matrices side by side
+            let b = b.into_shape((stack, self.k, n))?.permuted_axes((1, 0, 2));
+            CowArray::from(b.as_standard_layout().into_owned().into_shape((self.k, cols))?)
+        };
+        let b_pack = mmm.b_pack();
+        let mut c_shape: TVec<usize> = self.c_fact.shape.as_finite().unwrap().into();
+        c_shape[self.axis] = stack;
+        unsafe {
+            let mut packed_b =
+                Tensor::uninitialized_aligned::<f32>(&[b_pack.len()], b_pack.alignment())?;
+            b_pack.pack(
+                packed_b.as_ptr_mut()?,
+                b.as_ptr(),
+                b.strides()[self.b_trans as usize],
+                b.strides()[!self.b_trans as usize],
+            );
+            if self.c_trans {
+                // [stack, n, m] is a [stack * n, m] matrix, so C is written in place
+                let mut c = Tensor::uninitialized::<f32>(&c_shape)?;
+                mmm.c_from_data_and_strides(1, self.m as isize);
+                mmm.run(self.packed_a.as_ptr()?, packed_b.as_ptr()?, c.as_ptr_mut()?, &[]);
+                Ok(tvec!(c.into_arc_tensor()))
+            } else {
+                let mut c = Array2::<f32>::uninitialized((self.m, cols));
+                mmm.c_from_data_and_strides(cols as isize, 1);
+                mmm.run(self.packed_a.as_ptr()?, packed_b.as_ptr()?, c.as_mut_ptr(), &[]);
+                let c = c.into_shape((self.m, stack, n))?.permuted_axes((1, 0, 2));
+                let c = c.as_standard_layout().into_owned().into_shape(&*c_shape)?;
+                Ok(tvec!(c.into_arc_tensor()))
+            }
+        }
+    }
+}
+
+impl TypedOp for MatMatMulUnaryStacked {
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(self.c_fact.clone()))
+    }
+
+    fn invariants(&self, _model: &TypedModel, _node: &TypedNode) -> TractResult<Invariants> {
+        Ok(Invariants::from(tvec!(AxisInfo::simple(self.axis))))
+    }
+
     typed_op_as_op!();
 }
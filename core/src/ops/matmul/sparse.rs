@@ -0,0 +1,193 @@
+use crate::internal::*;
+use ndarray::*;
+
+use tract_linalg::sparse::SparseMatMul;
+
+use super::infer_shapes;
+
+/// The block sparse kernels are scalar loops: each of their multiply-adds
+/// is counted as this many multiply-adds of the vectorized dense kernels.
+pub const SPARSE_FMA_COST: usize = 8;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SparseMatMulUnaryFinite {
+    pub(crate) b_trans: bool,
+    pub(crate) c_trans: bool,
+    pub(crate) bc_b_shape: TVec<usize>,
+    pub(crate) bc_c_shape: TVec<usize>,
+    pub(crate) c_fact: TypedFact,
+    pub(crate) mm: Box<dyn SparseMatMul<f32>>,
+}
+
+impl Op for SparseMatMulUnaryFinite {
+    fn name(&self) -> Cow<str> {
+        "SparseMatMulUnaryFinite".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!("b_trans:{:?} c_trans:{:?}", self.b_trans, self.c_trans),
+            format!("{} (density: {:.2})", self.mm, self.mm.a().density()),
+        ])
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for SparseMatMulUnaryFinite {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let b = args_1!(inputs);
+        let b = b.to_array_view::<f32>()?.into_shape(&*self.bc_b_shape)?;
+        let mut c = unsafe { Array::<f32, IxDyn>::uninitialized(&*self.bc_c_shape) };
+        let rank = self.bc_c_shape.len();
+        let n = self.bc_b_shape[rank - 1 - self.b_trans as usize];
+        for prefix in indices(&self.bc_c_shape[..rank - 2]).into_iter() {
+            let mut b = b.view();
+            let mut c = c.view_mut();
+            for &dim in prefix.slice() {
+                let d = dim.min(b.shape()[0] - 1);
+                b.index_axis_inplace(Axis(0), d);
+                c.index_axis_inplace(Axis(0), dim);
+            }
+            let (rsb, csb) = if self.b_trans {
+                (b.strides()[1], b.strides()[0])
+            } else {
+                (b.strides()[0], b.strides()[1])
+            };
+            let (rsc, csc) = if self.c_trans {
+                (c.strides()[1], c.strides()[0])
+            } else {
+                (c.strides()[0], c.strides()[1])
+            };
+            unsafe { self.mm.run(b.as_ptr(), rsb, csb, c.as_mut_ptr(), rsc, csc, n) };
+        }
+        let c = c.into_shape(&*self.c_fact.shape.as_finite().unwrap())?;
+        Ok(tvec!(c.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SparseMatMulUnaryFinite {
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(self.c_fact.clone()))
+    }
+
+    fn cost(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let rank = self.bc_c_shape.len();
+        let prefix: usize = self.bc_c_shape[..rank - 2].iter().product();
+        let n = self.bc_b_shape[rank - 1 - self.b_trans as usize];
+        let a = self.mm.a();
+        let fma = prefix * a.nnz_blocks() * a.block_rows * n * SPARSE_FMA_COST;
+        Ok(tvec!((Cost::FMA(f32::datum_type()), fma.to_dim())))
+    }
+
+    typed_op_as_op!();
+}
+
+/// Block sparse version of a f32 product by a constant matrix, if its non
+/// zero blocks are sparse enough for the scalar kernels to beat the dense
+/// product.
+pub(super) fn new_sparse_mat_mul_unary_finite(
+    model: &TypedModel,
+    node: &TypedNode,
+    a: &Tensor,
+    b_shape: &[usize],
+    a_trans: bool,
+    b_trans: bool,
+    c_trans: bool,
+) -> TractResult<Option<TypedModelPatch>> {
+    if a.rank() != 2 || a.datum_type() != f32::datum_type() || a.len() == 0 {
+        return Ok(None);
+    }
+    let a = a.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+    let a = if a_trans { a.reversed_axes() } else { a };
+    let (m, k) = a.dim();
+    let mm = (tract_linalg::ops().ssparse)(m, k, &|r, c| a[(r, c)]);
+    if mm.a().density() * SPARSE_FMA_COST as f32 >= 1.0 {
+        return Ok(None);
+    }
+    let (_, bc_b_shape, bc_c_shape, c_shape) =
+        infer_shapes(tvec!(m, k), b_shape.into(), false, b_trans, c_trans)?;
+    let mut patch = TypedModelPatch::default();
+    let wire = patch.tap_model(model, node.inputs[0])?;
+    let wire = patch.wire_node(
+        format!("{}-sparse", &*node.name),
+        SparseMatMulUnaryFinite {
+            b_trans,
+            c_trans,
+            bc_b_shape,
+            bc_c_shape,
+            c_fact: TypedFact::dt_shape(f32::datum_type(), &*c_shape)?,
+            mm,
+        },
+        &[wire],
+    )?[0];
+    patch.shunt_outside(OutletId::new(node.id, 0), wire)?;
+    Ok(Some(patch))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::matmul::MatMulUnary;
+
+    #[test]
+    fn sparse_weights_codegen() {
+        let mut a = ndarray::Array2::<f32>::zeros((6, 40));
+        a[(0, 3)] = 1.0;
+        a[(2, 7)] = -2.0;
+        a[(5, 0)] = 0.5;
+        a[(5, 39)] = 3.0;
+        for a_trans in &[false, true] {
+            let a = if *a_trans { a.t().to_owned() } else { a.clone() };
+            let mut model = TypedModel::default();
+            let b = model
+                .add_source(
+                    "b",
+                    TypedFact::dt_shape(f32::datum_type(), [2, 40, 3].as_ref()).unwrap(),
+                )
+                .unwrap();
+            let op = MatMulUnary::new(a.into_arc_tensor(), *a_trans, false, false, None);
+            let c = model.wire_node("c", op, &[b]).unwrap();
+            model.set_output_outlets(&c).unwrap();
+
+            let input = ndarray::Array3::from_shape_fn((2, 40, 3), |(i, j, k)| {
+                (i * 120 + j * 3 + k) as f32
+            })
+            .into_tensor();
+            let expected = SimplePlan::new(&model).unwrap().run(tvec!(input.clone())).unwrap();
+            let optimized = model.into_optimized().unwrap();
+            let sparse = optimized
+                .nodes()
+                .iter()
+                .find_map(|n| n.op_as::<SparseMatMulUnaryFinite>())
+                .expect("sparse product");
+            let cost = sparse.cost(&[]).unwrap();
+            let fma = 2 * 4 * sparse.mm.a().block_rows * 3 * SPARSE_FMA_COST;
+            assert_eq!(cost[0].1, fma.to_dim());
+            let found = SimplePlan::new(&optimized).unwrap().run(tvec!(input)).unwrap();
+            found[0].close_enough(&expected[0], false).unwrap();
+        }
+    }
+    #[test]
+    fn block_dense_weights_stay_dense() {
+        // one non zero value every eight rows: 7/8 of zeros, but every block
+        // has a non zero value
+        let a = ndarray::Array2::<f32>::from_shape_fn((16, 10), |(r, c)| {
+            if r % 8 == 0 {
+                c as f32 + 1.0
+            } else {
+                0.0
+            }
+        });
+        let mut model = TypedModel::default();
+        let b = model
+            .add_source("b", TypedFact::dt_shape(f32::datum_type(), [10, 3].as_ref()).unwrap())
+            .unwrap();
+        let op = MatMulUnary::new(a.into_arc_tensor(), false, false, false, None);
+        let c = model.wire_node("c", op, &[b]).unwrap();
+        model.set_output_outlets(&c).unwrap();
+        let optimized = model.into_optimized().unwrap();
+        assert!(optimized.nodes().iter().all(|n| n.op_as::<SparseMatMulUnaryFinite>().is_none()));
+    }
+}
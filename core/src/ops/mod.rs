@@ -26,6 +26,7 @@ pub mod math;
 pub mod matmul;
 pub mod nn;
 pub mod quant;
+pub mod rec;
 pub mod scan;
 pub mod source;
 pub mod unimpl;
@@ -76,6 +77,18 @@ pub trait OpState: fmt::Debug + Send + dyn_clone::DynClone {
         op: &dyn Op,
         inputs: TVec<Arc<Tensor>>,
     ) -> TractResult<TVec<Arc<Tensor>>>;
+
+    /// Export the internal state, so that it can be restored later with
+    /// `restore`.
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        bail!("{:?} does not support snapshots", self)
+    }
+
+    /// Restore a state exported by `snapshot`.
+    #[allow(unused_variables)]
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        bail!("{:?} does not support snapshots", self)
+    }
 }
 
 pub trait StatelessOp: Op {
@@ -294,6 +307,15 @@ pub trait PulsedOp:
 
     /// Deduce output facts from input facts.
     fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>>;
+
+    /// Whether the op can be run on tensors spanning any number of pulses
+    /// along the streaming axis, instead of exactly one.
+    ///
+    /// Ops must opt in, once checked they do not depend on the pulse they
+    /// were built for.
+    fn supports_variable_pulse(&self) -> bool {
+        false
+    }
 }
 
 /// An operation with tensor type inference
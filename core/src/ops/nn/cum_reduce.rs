@@ -0,0 +1,409 @@
+use crate::internal::*;
+use ndarray::*;
+use num_traits::{AsPrimitive, Bounded, One, Zero};
+
+use super::Reducer;
+
+/// Cumulative reduction along an axis: each output frame is the reduction of
+/// the input frames up to it (or after it, in reverse mode).
+///
+/// Supports Sum, Prod, Max, Min and Mean reducers. In exclusive mode, the
+/// current frame is not included.
+#[derive(Debug, Clone, new)]
+pub struct CumReduce {
+    pub axis: usize,
+    pub reducer: Reducer,
+    pub exclusive: bool,
+    pub reverse: bool,
+}
+
+impl CumReduce {
+    fn check(&self) -> TractResult<()> {
+        match self.reducer {
+            Reducer::Sum | Reducer::Prod | Reducer::Max | Reducer::Min => Ok(()),
+            Reducer::Mean if !self.exclusive => Ok(()),
+            _ => bail!("{:?} is not supported as a cumulative reduction", self),
+        }
+    }
+
+    fn eval_t<T: CumDatum>(&self, input: &Tensor) -> TractResult<Tensor>
+    where
+        usize: AsPrimitive<T>,
+    {
+        let mut input = input.to_array_view::<T>()?;
+        if self.reverse {
+            input.invert_axis(Axis(self.axis));
+        }
+        let mut acc = identity::<T>(self.reducer, &input, self.axis);
+        let mut output = accumulate_t(self.reducer, self.exclusive, self.axis, input, &mut acc, 0);
+        if self.reverse {
+            output.invert_axis(Axis(self.axis));
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+/// Element types supported by cumulative reductions.
+trait CumDatum:
+    Datum + Copy + Zero + One + Bounded + PartialOrd + std::ops::Div<Output = Self>
+{
+}
+impl<T: Datum + Copy + Zero + One + Bounded + PartialOrd + std::ops::Div<Output = T>> CumDatum
+    for T
+{
+}
+
+macro_rules! dispatch_cum {
+    ($($path:ident)::* ($dt:expr) ($($args:expr),*)) => {
+        match $dt {
+            DatumType::U8   => $($path)::*::<u8>($($args),*),
+            DatumType::U16  => $($path)::*::<u16>($($args),*),
+            DatumType::I8   => $($path)::*::<i8>($($args),*),
+            DatumType::I16  => $($path)::*::<i16>($($args),*),
+            DatumType::I32  => $($path)::*::<i32>($($args),*),
+            DatumType::I64  => $($path)::*::<i64>($($args),*),
+            DatumType::F32  => $($path)::*::<f32>($($args),*),
+            DatumType::F64  => $($path)::*::<f64>($($args),*),
+            _ => bail!("{:?} is not a number", $dt)
+        }
+    }
+}
+
+/// Initial accumulator: one frame of the input, filled with the neutral
+/// element of the reducer.
+fn identity<T: CumDatum>(reducer: Reducer, input: &ArrayViewD<T>, axis: usize) -> ArrayD<T> {
+    let value = match reducer {
+        Reducer::Prod => T::one(),
+        Reducer::Max => T::min_value(),
+        Reducer::Min => T::max_value(),
+        _ => T::zero(),
+    };
+    let mut shape = input.shape().to_vec();
+    shape.remove(axis);
+    ArrayD::from_elem(shape, value)
+}
+
+/// Accumulate the frames of input along axis.
+///
+/// `acc` is the running reduction, and `count` the number of frames it
+/// accounts for (used by Mean).
+fn accumulate_t<T: CumDatum>(
+    reducer: Reducer,
+    exclusive: bool,
+    axis: usize,
+    input: ArrayViewD<T>,
+    acc: &mut ArrayD<T>,
+    mut count: usize,
+) -> ArrayD<T>
+where
+    usize: AsPrimitive<T>,
+{
+    let mut output = input.to_owned();
+    for mut frame in output.axis_iter_mut(Axis(axis)) {
+        if exclusive {
+            let current = frame.to_owned();
+            frame.assign(&*acc);
+            update(reducer, acc, &current.view());
+        } else {
+            update(reducer, acc, &frame.view());
+            count += 1;
+            if let Reducer::Mean = reducer {
+                let count: T = count.as_();
+                Zip::from(&mut frame).and(&*acc).apply(|f, &a| *f = a / count);
+            } else {
+                frame.assign(&*acc);
+            }
+        }
+    }
+    output
+}
+
+fn update<T: CumDatum>(reducer: Reducer, acc: &mut ArrayD<T>, frame: &ArrayViewD<T>) {
+    let zip = Zip::from(acc).and(frame);
+    match reducer {
+        Reducer::Prod => zip.apply(|a, &x| *a = *a * x),
+        Reducer::Max => zip.apply(|a, &x| {
+            if x > *a {
+                *a = x
+            }
+        }),
+        Reducer::Min => zip.apply(|a, &x| {
+            if x < *a {
+                *a = x
+            }
+        }),
+        _ => zip.apply(|a, &x| *a = *a + x),
+    }
+}
+
+impl Op for CumReduce {
+    fn name(&self) -> Cow<str> {
+        format!("CumReduce<{:?}>", self.reducer).into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "axis: {} exclusive: {} reverse: {}",
+            self.axis, self.exclusive, self.reverse
+        )])
+    }
+
+    canonic!();
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatelessOp for CumReduce {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        self.check()?;
+        let input = args_1!(inputs);
+        Ok(tvec!(dispatch_cum!(Self::eval_t(input.datum_type())(self, &input))?.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for CumReduce {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+    to_typed!();
+}
+
+impl TypedOp for CumReduce {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        self.check()?;
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, inputs[0].shape.clone())?))
+    }
+
+    fn invariants(&self, model: &TypedModel, node: &TypedNode) -> TractResult<Invariants> {
+        let input = model.outlet_fact(node.inputs[0])?;
+        Ok((0..input.rank()).filter(|&axis| axis != self.axis).map(AxisInfo::simple).collect())
+    }
+
+    fn dispose_dummy_axis(
+        &self,
+        _model: &TypedModel,
+        _node: &TypedNode,
+        axes: &[Option<usize>],
+    ) -> TractResult<Option<Box<dyn TypedOp>>> {
+        let axis = axes[0].unwrap();
+        Ok(Some(Box::new(Self { axis: self.axis - (axis < self.axis) as usize, ..self.clone() })))
+    }
+
+    fn pulsify(
+        &self,
+        _source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let fact = target.outlet_fact(input)?.clone();
+        if fact.axis != self.axis {
+            return target.wire_node(&*node.name, self.clone(), &[input]);
+        }
+        if self.reverse {
+            bail!("Can not pulsify a reverse cumulative reduction along the streaming axis")
+        }
+        let op = PulsedCumReduce {
+            axis: self.axis,
+            reducer: self.reducer,
+            exclusive: self.exclusive,
+            skip: fact.delay,
+        };
+        target.wire_node(&*node.name, op, &[input])
+    }
+}
+
+impl PulsedOp for CumReduce {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+/// Pulsed form of CumReduce along the streaming axis.
+///
+/// The running reduction is carried from one pulse to the next in the op
+/// state, ignoring the `skip` first frames (the input delay).
+#[derive(Debug, Clone)]
+pub struct PulsedCumReduce {
+    axis: usize,
+    reducer: Reducer,
+    exclusive: bool,
+    skip: usize,
+}
+
+impl Op for PulsedCumReduce {
+    fn name(&self) -> Cow<str> {
+        format!("PulsedCumReduce<{:?}>", self.reducer).into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} exclusive: {} skip: {}", self.axis, self.exclusive, self.skip)])
+    }
+
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatefullOp for PulsedCumReduce {
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(CumReduceState::default())))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct CumReduceState {
+    position: usize,
+    count: usize,
+    acc: Option<Tensor>,
+}
+
+impl CumReduceState {
+    fn eval_t<T: CumDatum>(&mut self, op: &PulsedCumReduce, input: &Tensor) -> TractResult<Tensor>
+    where
+        usize: AsPrimitive<T>,
+    {
+        let input = input.to_array_view::<T>()?;
+        let skip = op.skip;
+        let pulse = input.shape()[op.axis];
+        let skipped = skip.saturating_sub(self.position).min(pulse);
+        self.position += pulse;
+        let mut acc = match self.acc.take() {
+            Some(acc) => acc.into_array::<T>()?,
+            None => identity::<T>(op.reducer, &input, op.axis),
+        };
+        let valid = input.slice_axis(Axis(op.axis), (skipped..).into());
+        let accumulated =
+            accumulate_t(op.reducer, op.exclusive, op.axis, valid, &mut acc, self.count);
+        self.count += pulse - skipped;
+        self.acc = Some(acc.into_tensor());
+        let mut output = input.to_owned();
+        output.slice_axis_mut(Axis(op.axis), (skipped..).into()).assign(&accumulated);
+        Ok(output.into_tensor())
+    }
+}
+
+impl OpState for CumReduceState {
+    fn eval(
+        &mut self,
+        _session: &mut SessionState,
+        op: &dyn Op,
+        mut inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = op.downcast_ref::<PulsedCumReduce>().ok_or("Wrong Op type")?;
+        let input = args_1!(inputs);
+        let output = dispatch_cum!(Self::eval_t(input.datum_type())(self, op, &input))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        let mut tensors = tvec!(tensor0(self.position as i64), tensor0(self.count as i64));
+        tensors.extend(self.acc.iter().cloned());
+        Ok(OpStateSnapshot::new(tensors))
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        self.position = snapshot.usize(0)?;
+        self.count = snapshot.usize(1)?;
+        self.acc = snapshot.tensors.get(2).cloned();
+        Ok(())
+    }
+}
+
+impl TypedOp for PulsedCumReduce {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, inputs[0].shape.clone())?))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let acc = (0..inputs[0].shape.rank())
+            .filter(|&ax| ax != self.axis)
+            .fold(1.to_dim(), |acc, ax| acc * inputs[0].shape.dim(ax));
+        Ok(tvec!((Cost::Buffer(inputs[0].datum_type), acc)))
+    }
+}
+
+impl PulsedOp for PulsedCumReduce {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cum(reducer: Reducer, exclusive: bool, reverse: bool, input: Tensor) -> Tensor {
+        let op = CumReduce::new(0, reducer, exclusive, reverse);
+        op.eval(tvec!(input.into_arc_tensor())).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn cum_sum() {
+        let input = tensor1(&[1i32, 2, 3, 4]);
+        assert_eq!(cum(Reducer::Sum, false, false, input.clone()), tensor1(&[1i32, 3, 6, 10]));
+        assert_eq!(cum(Reducer::Sum, true, false, input.clone()), tensor1(&[0i32, 1, 3, 6]));
+        assert_eq!(cum(Reducer::Sum, false, true, input.clone()), tensor1(&[10i32, 9, 7, 4]));
+        assert_eq!(cum(Reducer::Sum, true, true, input), tensor1(&[9i32, 7, 4, 0]));
+    }
+
+    #[test]
+    fn cum_max_and_mean() {
+        let input = tensor2(&[[1f32, 4.0], [3.0, 2.0], [2.0, 6.0]]);
+        assert_eq!(
+            cum(Reducer::Max, false, false, input.clone()),
+            tensor2(&[[1f32, 4.0], [3.0, 4.0], [3.0, 6.0]])
+        );
+        assert_eq!(
+            cum(Reducer::Mean, false, false, input),
+            tensor2(&[[1f32, 4.0], [2.0, 3.0], [2.0, 4.0]])
+        );
+    }
+
+    #[test]
+    fn pulsed_state_skips_delay() {
+        let op = PulsedCumReduce { axis: 0, reducer: Reducer::Sum, exclusive: false, skip: 3 };
+        let mut state = CumReduceState::default();
+        let mut session = SessionState::default();
+        let mut run = |input: Tensor| {
+            state.eval(&mut session, &op, tvec!(input.into_arc_tensor())).unwrap().remove(0)
+        };
+        assert_eq!(*run(tensor1(&[9i32, 9])), tensor1(&[9i32, 9]));
+        assert_eq!(*run(tensor1(&[9i32, 1])), tensor1(&[9i32, 1]));
+        assert_eq!(*run(tensor1(&[2i32, 3])), tensor1(&[3i32, 6]));
+    }
+}
@@ -1,4 +1,20 @@
 use crate::internal::*;
+use ndarray::*;
+
+/// Exponentiate a softmax layer in place, using the vectorized linalg kernel
+/// when the layer is contiguous f32.
+fn exp_inplace<D: Datum + ::num_traits::Float>(layer: &mut ArrayViewMut1<D>) {
+    if D::datum_type() == f32::datum_type() {
+        if let Some(slice) = layer.as_slice_mut() {
+            let slice = unsafe {
+                std::slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut f32, slice.len())
+            };
+            (tract_linalg::ops().sexp)().run(slice);
+            return;
+        }
+    }
+    layer.mapv_inplace(|x| x.exp());
+}
 
 #[derive(Debug, Clone, new, Default)]
 pub struct LayerHardmax {
@@ -91,6 +107,10 @@ impl PulsedOp for LayerHardmax {
         Ok(tvec!(inputs[0].clone()))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -118,7 +138,8 @@ impl LayerLogSoftmax {
                 .iter()
                 .max_by(|a, b| a.partial_cmp(&b).unwrap_or(::std::cmp::Ordering::Equal))
                 .cloned();
-            layer.mapv_inplace(|x| (x - max.unwrap()).exp());
+            layer.mapv_inplace(|x| x - max.unwrap());
+            exp_inplace(&mut layer);
             let divisor = layer.iter().cloned().sum();
             layer.mapv_inplace(|x| (x / divisor).ln());
         });
@@ -186,6 +207,10 @@ impl PulsedOp for LayerLogSoftmax {
         Ok(tvec!(inputs[0].clone()))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -213,7 +238,8 @@ impl LayerSoftmax {
                 .iter()
                 .max_by(|a, b| a.partial_cmp(&b).unwrap_or(::std::cmp::Ordering::Equal))
                 .cloned();
-            layer.mapv_inplace(|x| (x - max.unwrap()).exp());
+            layer.mapv_inplace(|x| x - max.unwrap());
+            exp_inplace(&mut layer);
             let divisor = layer.iter().cloned().sum();
             layer.mapv_inplace(|x| x / divisor);
         });
@@ -281,6 +307,10 @@ impl PulsedOp for LayerSoftmax {
         Ok(tvec!(inputs[0].clone()))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
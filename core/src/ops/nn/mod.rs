@@ -1,4 +1,5 @@
 mod arg_max_min;
+mod cum_reduce;
 mod data_formats;
 mod global_pools;
 mod layer_max;
@@ -6,11 +7,12 @@ mod lrn;
 mod reduce;
 
 pub use self::arg_max_min::ArgMaxMin;
+pub use self::cum_reduce::{CumReduce, PulsedCumReduce};
 pub use self::data_formats::{BaseDataShape, DataFormat, DataShape};
 pub use self::global_pools::{GlobalAvgPool, GlobalLpPool, GlobalMaxPool};
 pub use self::layer_max::{LayerHardmax, LayerLogSoftmax, LayerSoftmax};
 pub use self::lrn::Lrn;
-pub use self::reduce::{Reduce, Reducer};
+pub use self::reduce::{Reduce, Reducer, TypedReduce};
 
 use num_traits::{AsPrimitive, Float};
 
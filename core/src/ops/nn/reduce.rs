@@ -324,8 +324,8 @@ impl InferenceRulesOp for Reduce {
 
 #[derive(Clone, Debug, new)]
 pub struct TypedReduce {
-    axes: TVec<usize>,
-    reducer: Reducer,
+    pub axes: TVec<usize>,
+    pub reducer: Reducer,
 }
 
 impl Op for TypedReduce {
@@ -406,6 +406,10 @@ impl PulsedOp for TypedReduce {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -10,7 +10,9 @@ pub struct QParams {
     pub zero_point_a: Option<Arc<Tensor>>,
     pub zero_point_b: Option<Arc<Tensor>>,
     pub zero_point_c: Option<Arc<Tensor>>,
-    pub scale_factor: Option<f32>,
+    /// Requantization factor: a f32 scalar, or a vector with one factor per row
+    /// of the product (per output channel for convolutions).
+    pub scale_factor: Option<Arc<Tensor>>,
 }
 
 fn cleanup_zeropoint(zp: &Arc<Tensor>) -> Option<Arc<Tensor>> {
@@ -25,7 +27,7 @@ fn cleanup_zeropoint_t<T: Datum + Zero + Copy>(zp: &Arc<Tensor>) -> Option<Arc<T
     let mut zp = zp.clone();
     if zp.rank() == 1 {
         let slice = zp.as_slice::<T>().unwrap();
-        if slice[1..].iter().all(|&x| x == slice[0]) {
+        if !slice.is_empty() && slice[1..].iter().all(|&x| x == slice[0]) {
             zp = rctensor0(slice[0]);
         }
     }
@@ -36,6 +38,15 @@ fn cleanup_zeropoint_t<T: Datum + Zero + Copy>(zp: &Arc<Tensor>) -> Option<Arc<T
     }
 }
 
+fn cleanup_scale_factor(factor: &Arc<Tensor>) -> TractResult<Arc<Tensor>> {
+    let slice = factor.as_slice::<f32>()?;
+    if factor.rank() == 1 && !slice.is_empty() && slice[1..].iter().all(|&x| x == slice[0]) {
+        Ok(rctensor0(slice[0]))
+    } else {
+        Ok(factor.clone())
+    }
+}
+
 impl QParams {
     pub fn new(dt: DatumType) -> QParams {
         QParams {
@@ -60,7 +71,11 @@ impl QParams {
     }
 
     pub fn with_scale_factor(self, scale_factor: f32) -> QParams {
-        QParams { scale_factor: Some(scale_factor), ..self }
+        QParams { scale_factor: Some(rctensor0(scale_factor)), ..self }
+    }
+
+    pub fn with_scale_factor_per_row(self, scale_factor: &Arc<Tensor>) -> TractResult<QParams> {
+        Ok(QParams { scale_factor: Some(cleanup_scale_factor(scale_factor)?), ..self })
     }
 
     pub fn set_zero_point_a(&mut self, zero_point: &Arc<Tensor>) {
@@ -76,10 +91,74 @@ impl QParams {
     }
 
     pub fn set_scale_factor(&mut self, scale_factor: f32) {
-        self.scale_factor = Some(scale_factor)
+        self.scale_factor = Some(rctensor0(scale_factor))
+    }
+
+    pub fn set_scale_factor_per_row(&mut self, scale_factor: &Arc<Tensor>) -> TractResult<()> {
+        self.scale_factor = Some(cleanup_scale_factor(scale_factor)?);
+        Ok(())
+    }
+
+    /// Per row scale factors, if the requantization is not uniform.
+    pub fn scale_factor_per_row(&self) -> Option<&[f32]> {
+        self.scale_factor.as_ref().filter(|t| t.rank() == 1).and_then(|t| t.as_slice().ok())
     }
 }
 
+/// Symmetric i8 quantization of f32 weights.
+///
+/// If `axis` is given, each slice along it gets its own scale. Returns the
+/// quantized weights and the scales, so that `w ~= q * scale`.
+pub fn quantize_symmetric_i8(
+    weights: &Tensor,
+    axis: Option<usize>,
+) -> TractResult<(Tensor, Vec<f32>)> {
+    use ndarray::Axis;
+    let weights = weights.to_array_view::<f32>()?;
+    let mut quantized = weights.mapv(|_| 0i8);
+    let scale_of = |slice: ndarray::ArrayViewD<f32>| {
+        let max = slice.iter().fold(0f32, |acc, x| acc.max(x.abs()));
+        if max > 0.0 {
+            max / 127.0
+        } else {
+            1.0
+        }
+    };
+    let scales = if let Some(axis) = axis {
+        weights.axis_iter(Axis(axis)).map(scale_of).collect()
+    } else {
+        vec![scale_of(weights.view())]
+    };
+    for ((ix, q), w) in quantized.indexed_iter_mut().zip(weights.iter()) {
+        let scale = scales[axis.map(|axis| ix[axis]).unwrap_or(0)];
+        *q = (w / scale).round().max(-127.0).min(127.0) as i8;
+    }
+    Ok((quantized.into_tensor(), scales))
+}
+
+/// Round to the nearest integer, halfway cases to even, as specified by ONNX
+/// for quantization.
+pub fn round_ties_to_even(x: f32) -> f32 {
+    let r = x.round();
+    if (r - x).abs() == 0.5 {
+        2.0 * (x / 2.0).round()
+    } else {
+        r
+    }
+}
+
+/// Quantization step and zero point mapping the [min, max] range, widened to
+/// include zero, on the [qmin, qmax] integer range.
+///
+/// This is the computation done by ONNX DynamicQuantizeLinear.
+pub fn quantization_params_for_range(min: f32, max: f32, qmin: i32, qmax: i32) -> (f32, i32) {
+    let (min, max) = (min.min(0.0), max.max(0.0));
+    let step = if max > min { (max - min) / (qmax - qmin) as f32 } else { 1.0 };
+    let zero_point =
+        round_ties_to_even(qmin as f32 - min / step).max(qmin as f32).min(qmax as f32) as i32;
+    (step, zero_point)
+}
+
 pub fn quantize_linear_f32_u8(x: f32, scale: f32, zero_point: i32) -> u8 {
     (((x * scale).round() as i32) + zero_point as i32)
         .max(u8::min_value() as i32)
@@ -302,10 +381,81 @@ impl PulsedOp for DequantizeLinearF32 {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
 
+/// Quantization of f32 values to u8 with a step and zero point computed at
+/// each evaluation from the range of the values.
+///
+/// Outputs the quantized tensor, the step and the zero point.
+#[derive(Clone, Debug, Default)]
+pub struct DynamicQuantizeLinearU8;
+
+impl Op for DynamicQuantizeLinearU8 {
+    fn name(&self) -> Cow<str> {
+        "DynamicQuantizeLinearU8".into()
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for DynamicQuantizeLinearU8 {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.to_array_view::<f32>()?;
+        let (min, max) = input.iter().fold((0f32, 0f32), |(min, max), &x| (min.min(x), max.max(x)));
+        let (step, zero_point) = quantization_params_for_range(min, max, 0, 255);
+        let output = input
+            .mapv(|x| (round_ties_to_even(x / step) as i32 + zero_point).max(0).min(255) as u8);
+        Ok(tvec!(output.into_arc_tensor(), rctensor0(step), rctensor0(zero_point as u8)))
+    }
+}
+
+impl InferenceRulesOp for DynamicQuantizeLinearU8 {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 3)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].datum_type, u8::datum_type())?;
+        s.equals(&outputs[1].datum_type, f32::datum_type())?;
+        s.equals(&outputs[2].datum_type, u8::datum_type())?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.equals(&outputs[1].rank, 0)?;
+        s.equals(&outputs[2].rank, 0)?;
+        Ok(())
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(3)
+    }
+
+    inference_op_as_op!();
+    to_typed!();
+}
+
+impl TypedOp for DynamicQuantizeLinearU8 {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(
+            TypedFact::dt_shape(u8::datum_type(), inputs[0].shape.clone())?,
+            TypedFact::dt_shape(f32::datum_type(), [0usize; 0].as_ref())?,
+            TypedFact::dt_shape(u8::datum_type(), [0usize; 0].as_ref())?
+        ))
+    }
+
+    typed_op_as_op!();
+}
+
 element_wise_oop!(lookup_table, LookupTable {table: Box<dyn Lut>},
     [i8] => i8 |op, xs, ys| {
         ys.copy_from_slice(xs);
@@ -321,3 +471,34 @@ element_wise_oop!(lookup_table, LookupTable {table: Box<dyn Lut>},
         Ok(())
     }
 );
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_ties_to_even() {
+        let values = [-2.5f32, -1.5, -0.5, 0.5, 1.5, 2.5, 2.6];
+        let rounded: Vec<f32> = values.iter().map(|&x| super::round_ties_to_even(x)).collect();
+        assert_eq!(rounded, vec![-2.0, -2.0, 0.0, 0.0, 2.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn empty_per_row_params() {
+        let qp = QParams::new(i8::datum_type())
+            .with_zero_point_a(&rctensor1::<i8>(&[]))
+            .with_scale_factor_per_row(&rctensor1::<f32>(&[]))
+            .unwrap();
+        assert_eq!(qp.scale_factor_per_row(), Some(&[][..]));
+    }
+
+    #[test]
+    fn dynamic_quantize_linear() {
+        // example from the ONNX operator documentation
+        let x = rctensor1(&[0f32, 2.0, -3.0, -2.5, 1.34, 0.5]);
+        let outputs = DynamicQuantizeLinearU8.eval(tvec!(x)).unwrap();
+        assert_eq!(*outputs[0], tensor1(&[153u8, 255, 0, 26, 221, 179]));
+        assert!((*outputs[1].to_scalar::<f32>().unwrap() - 0.019_607_844).abs() < 1e-6);
+        assert_eq!(*outputs[2], tensor0(153u8));
+    }
+}
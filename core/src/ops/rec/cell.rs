@@ -0,0 +1,480 @@
+use crate::internal::*;
+use crate::ops::element_wise::ElementWiseMiniOp;
+use ndarray::*;
+use tract_linalg::mmm::MatMatMul;
+
+/// Kind of recurrent cell, and its specific parameters.
+#[derive(Debug, Clone)]
+pub enum CellKind {
+    /// Gates are i, o, f, c. Peepholes for i, o and f are a [3, hidden_size]
+    /// tensor.
+    Lstm { peepholes: Option<Arc<Tensor>> },
+    /// Gates are z, r, h. With `linear_before_reset`, the recurrent bias of
+    /// the h gate is applied before the reset gate, so it can not be folded
+    /// with the input bias: `reset_bias` holds it.
+    Gru { linear_before_reset: bool, reset_bias: Option<Arc<Tensor>> },
+    /// Single gate.
+    Rnn,
+}
+
+impl CellKind {
+    pub fn gates(&self) -> usize {
+        match self {
+            CellKind::Lstm { .. } => 4,
+            CellKind::Gru { .. } => 3,
+            CellKind::Rnn => 1,
+        }
+    }
+
+    /// Number of state tensors carried from one step to the next.
+    pub fn states(&self) -> usize {
+        match self {
+            CellKind::Lstm { .. } => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// One step of a LSTM, GRU or RNN layer, with constant weights.
+///
+/// Inputs are x [batch_size, input_size] and the previous states h (and c
+/// for LSTM) [batch_size, hidden_size]. Outputs are the new states.
+///
+/// Input and recurrent weights of all gates are stacked as [W;R] and packed
+/// once into linalg panels, so the preactivations of all gates are a single
+/// product [x, h].[W;R] per step. Biases from the input and recurrent
+/// products are folded together when possible.
+#[derive(Debug, Clone)]
+pub struct RecurrentCell {
+    pub kind: CellKind,
+    pub input_size: usize,
+    pub hidden_size: usize,
+    /// Packed [W;R], [blocks * hidden_size, input_size + hidden_size]. A GRU
+    /// with `linear_before_reset` has a fourth block, for h.Rh alone.
+    pub packed: Arc<Tensor>,
+    /// Packed Rh for a GRU without `linear_before_reset`, as it applies to
+    /// (rt (.) h), which is only known after the main product.
+    pub packed_rh: Option<Arc<Tensor>>,
+    /// Folded biases, [gates, hidden_size].
+    pub bias: Arc<Tensor>,
+    /// Gate activations: f, g, h for LSTM, f, g for GRU, f for RNN.
+    pub activations: TVec<Box<dyn ElementWiseMiniOp>>,
+}
+
+/// Scratch buffers and products for a given batch size, reused from one step
+/// to the next.
+#[derive(Debug, Clone)]
+pub struct CellBuffers {
+    mmm: Box<dyn MatMatMul<f32, f32, f32, f32>>,
+    rh_mmm: Option<Box<dyn MatMatMul<f32, f32, f32, f32>>>,
+    /// [x, h], [batch_size, input_size + hidden_size].
+    xh: Tensor,
+    packed_b: Tensor,
+    /// Product output, [batch_size, blocks * hidden_size].
+    preactivations: Tensor,
+    gates: TVec<Tensor>,
+    tmp: Tensor,
+}
+
+fn view2_mut(t: &mut Tensor) -> TractResult<ArrayViewMut2<f32>> {
+    Ok(t.to_array_view_mut::<f32>()?.into_dimensionality()?)
+}
+
+fn view2(t: &Tensor) -> TractResult<ArrayView2<f32>> {
+    Ok(t.to_array_view::<f32>()?.into_dimensionality()?)
+}
+
+fn pack_a(a: ArrayView2<f32>) -> TractResult<Arc<Tensor>> {
+    let (m, k) = a.dim();
+    // the f32 kernel does not depend on n, so weights are packed once for any batch size
+    let a_pack = (tract_linalg::ops().smmm)(m, k, 1).a_pack();
+    unsafe {
+        let mut packed = Tensor::uninitialized_aligned::<f32>(&[a_pack.len()], a_pack.alignment())?;
+        a_pack.pack(packed.as_ptr_mut()?, a.as_ptr(), a.strides()[0], a.strides()[1]);
+        Ok(packed.into_arc_tensor())
+    }
+}
+
+impl CellKind {
+    /// Number of hidden_size blocks in the output of the main product.
+    fn blocks(&self) -> usize {
+        match self {
+            CellKind::Gru { linear_before_reset: true, .. } => 4,
+            _ => self.gates(),
+        }
+    }
+}
+
+impl RecurrentCell {
+    /// Build a cell from ONNX-style weights for a single direction: w is
+    /// [gates * hidden_size, input_size], r is [gates * hidden_size,
+    /// hidden_size], and bias [2 * gates * hidden_size] holds the input biases
+    /// followed by the recurrent biases.
+    pub fn new(
+        kind: CellKind,
+        w: &Tensor,
+        r: &Tensor,
+        bias: Option<&Tensor>,
+        activations: TVec<Box<dyn ElementWiseMiniOp>>,
+    ) -> TractResult<RecurrentCell> {
+        let gates = kind.gates();
+        let w = w.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let r = r.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let hidden_size = r.shape()[1];
+        let input_size = w.shape()[1];
+        if w.shape()[0] != gates * hidden_size || r.shape()[0] != gates * hidden_size {
+            bail!("Inconsistent weights shapes {:?} and {:?}", w.shape(), r.shape());
+        }
+        let hs = hidden_size;
+        let mut wr = Array2::<f32>::zeros((kind.blocks() * hs, input_size + hs));
+        wr.slice_mut(s![..gates * hs, ..input_size]).assign(&w);
+        let mut packed_rh = None;
+        match kind {
+            CellKind::Gru { linear_before_reset, .. } => {
+                wr.slice_mut(s![..2 * hs, input_size..]).assign(&r.slice(s![..2 * hs, ..]));
+                if linear_before_reset {
+                    wr.slice_mut(s![3 * hs.., input_size..]).assign(&r.slice(s![2 * hs.., ..]));
+                } else {
+                    packed_rh = Some(pack_a(r.slice(s![2 * hs.., ..]))?);
+                }
+            }
+            _ => wr.slice_mut(s![.., input_size..]).assign(&r),
+        }
+        let mut folded = Array2::<f32>::zeros((gates, hidden_size));
+        let mut kind = kind;
+        if let Some(bias) = bias {
+            let bias = bias.to_array_view::<f32>()?.into_shape((2, gates, hidden_size))?;
+            folded.assign(&bias.index_axis(Axis(0), 0));
+            if let CellKind::Gru { linear_before_reset: true, ref mut reset_bias } = kind {
+                let mut zr = folded.slice_mut(s![0..2, ..]);
+                zr += &bias.slice(s![1, 0..2, ..]);
+                *reset_bias = Some(bias.slice(s![1, 2, ..]).to_owned().into_arc_tensor());
+            } else {
+                folded += &bias.index_axis(Axis(0), 1);
+            }
+        }
+        Ok(RecurrentCell {
+            kind,
+            input_size,
+            hidden_size,
+            packed: pack_a(wr.view())?,
+            packed_rh,
+            bias: folded.into_arc_tensor(),
+            activations,
+        })
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden_size
+    }
+
+    pub fn buffers(&self, batch_size: usize) -> TractResult<CellBuffers> {
+        let hs = self.hidden_size;
+        let (m, k) = (self.kind.blocks() * hs, self.input_size + hs);
+        let zeros = |cols| Array2::<f32>::zeros((batch_size, cols)).into_tensor();
+        // B is [x, h] seen as [k, batch_size], C [batch_size, m] seen as [m, batch_size]
+        let mut mmm = (tract_linalg::ops().smmm)(m, k, batch_size);
+        let mut rh_mmm =
+            self.packed_rh.as_ref().map(|_| (tract_linalg::ops().smmm)(hs, hs, batch_size));
+        unsafe {
+            mmm.c_from_data_and_strides(1, m as isize);
+            if let Some(rh_mmm) = rh_mmm.as_mut() {
+                rh_mmm.c_from_data_and_strides(1, m as isize);
+            }
+        }
+        let b_pack = mmm.b_pack();
+        let packed_b =
+            unsafe { Tensor::uninitialized_aligned::<f32>(&[b_pack.len()], b_pack.alignment())? };
+        Ok(CellBuffers {
+            mmm,
+            rh_mmm,
+            xh: zeros(k),
+            packed_b,
+            preactivations: zeros(m),
+            gates: (0..self.kind.blocks()).map(|_| zeros(hs)).collect(),
+            tmp: zeros(hs),
+        })
+    }
+
+    /// Gate preactivations: [x, h].[W;R] + bias, for all gates in one product.
+    fn preactivations(
+        &self,
+        x: &ArrayView2<f32>,
+        h: &Tensor,
+        buffers: &mut CellBuffers,
+    ) -> TractResult<()> {
+        let CellBuffers { mmm, xh, packed_b, preactivations, gates, .. } = buffers;
+        let mut xh_view = view2_mut(xh)?;
+        xh_view.slice_mut(s![.., ..self.input_size]).assign(x);
+        xh_view.slice_mut(s![.., self.input_size..]).assign(&view2(h)?);
+        unsafe {
+            let k = self.input_size + self.hidden_size;
+            mmm.b_pack().pack(packed_b.as_ptr_mut()?, xh.as_ptr()?, 1, k as isize);
+            mmm.run(self.packed.as_ptr()?, packed_b.as_ptr()?, preactivations.as_ptr_mut()?, &[]);
+        }
+        let preactivations = view2(preactivations)?;
+        let bias = self.bias.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let hs = self.hidden_size;
+        for (ix, gate) in gates.iter_mut().enumerate() {
+            let block = preactivations.slice(s![.., ix * hs..(ix + 1) * hs]);
+            if ix < bias.shape()[0] {
+                Zip::from(&mut view2_mut(gate)?)
+                    .and(&block)
+                    .and_broadcast(bias.row(ix))
+                    .apply(|g, p, b| *g = p + b);
+            } else {
+                view2_mut(gate)?.assign(&block);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one step, updating the states in place.
+    pub fn step(
+        &self,
+        x: &ArrayView2<f32>,
+        states: &mut [Tensor],
+        buffers: &mut CellBuffers,
+    ) -> TractResult<()> {
+        self.preactivations(x, &states[0], buffers)?;
+        let CellBuffers { rh_mmm, packed_b, preactivations, gates, tmp, .. } = buffers;
+        match &self.kind {
+            CellKind::Lstm { peepholes } => {
+                let (h, c) = states.split_at_mut(1);
+                let (h, c) = (&mut h[0], &mut c[0]);
+                let peepholes = if let Some(p) = peepholes {
+                    Some(p.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?)
+                } else {
+                    None
+                };
+                // it = f(.. + Pi (.) Ct-1), ft = f(.. + Pf (.) Ct-1)
+                if let Some(p) = &peepholes {
+                    for &gate in &[0, 2] {
+                        Zip::from(&mut view2_mut(&mut gates[gate])?)
+                            .and(&view2(c)?)
+                            .and_broadcast(&p.row(gate))
+                            .apply(|g, c, p| *g += p * c);
+                    }
+                }
+                self.activations[0].eval_in_place(&mut gates[0])?;
+                self.activations[0].eval_in_place(&mut gates[2])?;
+                self.activations[1].eval_in_place(&mut gates[3])?;
+                // Ct = ft (.) Ct-1 + it (.) ct
+                Zip::from(&mut view2_mut(c)?)
+                    .and(&view2(&gates[0])?)
+                    .and(&view2(&gates[2])?)
+                    .and(&view2(&gates[3])?)
+                    .apply(|c, i, f, cc| *c = f * *c + i * cc);
+                // ot = f(.. + Po (.) Ct)
+                if let Some(p) = &peepholes {
+                    Zip::from(&mut view2_mut(&mut gates[1])?)
+                        .and(&view2(c)?)
+                        .and_broadcast(&p.row(1))
+                        .apply(|g, c, p| *g += p * c);
+                }
+                self.activations[0].eval_in_place(&mut gates[1])?;
+                // Ht = ot (.) h(Ct)
+                view2_mut(tmp)?.assign(&view2(c)?);
+                self.activations[2].eval_in_place(tmp)?;
+                Zip::from(&mut view2_mut(h)?)
+                    .and(&view2(&gates[1])?)
+                    .and(&view2(tmp)?)
+                    .apply(|h, o, hc| *h = o * hc);
+            }
+            CellKind::Gru { linear_before_reset, reset_bias } => {
+                let h = &mut states[0];
+                for gate in 0..2 {
+                    self.activations[0].eval_in_place(&mut gates[gate])?;
+                }
+                if *linear_before_reset {
+                    // ht = g(Xt*(Wh^T) + (rt (.) (Ht-1*(Rh^T) + Rbh)) + Wbh)
+                    let (g, hr) = gates.split_at_mut(3);
+                    let mut t = view2_mut(&mut hr[0])?;
+                    if let Some(rb) = reset_bias {
+                        t += &rb.to_array_view::<f32>()?;
+                    }
+                    t.zip_mut_with(&view2(&g[1])?, |t, r| *t *= r);
+                    let mut hh = view2_mut(&mut g[2])?;
+                    hh += &t;
+                } else {
+                    // ht = g(Xt*(Wh^T) + (rt (.) Ht-1)*(Rh^T) + Rbh + Wbh)
+                    Zip::from(&mut view2_mut(tmp)?)
+                        .and(&view2(&gates[1])?)
+                        .and(&view2(h)?)
+                        .apply(|t, r, h| *t = r * h);
+                    // the product overwrites the first block of the preactivations, which
+                    // are consumed already
+                    let rh_mmm = rh_mmm.as_ref().unwrap();
+                    let packed_rh = self.packed_rh.as_ref().unwrap();
+                    unsafe {
+                        let pb = packed_b.as_ptr_mut()?;
+                        rh_mmm.b_pack().pack(pb, tmp.as_ptr()?, 1, self.hidden_size as isize);
+                        rh_mmm.run(packed_rh.as_ptr()?, pb, preactivations.as_ptr_mut()?, &[]);
+                    }
+                    let rh = view2(preactivations)?;
+                    let mut hh = view2_mut(&mut gates[2])?;
+                    hh += &rh.slice(s![.., ..self.hidden_size]);
+                }
+                self.activations[1].eval_in_place(&mut gates[2])?;
+                // Ht = (1 - zt) (.) ht + zt (.) Ht-1
+                Zip::from(&mut view2_mut(h)?)
+                    .and(&view2(&gates[0])?)
+                    .and(&view2(&gates[2])?)
+                    .apply(|h, z, hh| *h = (1.0 - z) * hh + z * *h);
+            }
+            CellKind::Rnn => {
+                let h = &mut states[0];
+                self.activations[0].eval_in_place(&mut gates[0])?;
+                view2_mut(h)?.assign(&view2(&gates[0])?);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Op for RecurrentCell {
+    fn name(&self) -> Cow<str> {
+        "RecurrentCell".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!("{:?}", self.kind),
+            format!("input: {}, hidden: {}", self.input_size(), self.hidden_size()),
+            format!("activations: {:?}", self.activations),
+        ])
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for RecurrentCell {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let x = inputs[0].to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let mut states: TVec<Tensor> = inputs[1..].iter().map(|t| t.as_ref().clone()).collect();
+        let mut buffers = self.buffers(x.shape()[0])?;
+        self.step(&x, &mut states, &mut buffers)?;
+        Ok(states.into_iter().map(|t| t.into_arc_tensor()).collect())
+    }
+}
+
+impl TypedOp for RecurrentCell {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs.len() != 1 + self.kind.states() {
+            bail!("{:?} expects {} inputs", self.kind, 1 + self.kind.states());
+        }
+        if inputs.iter().any(|i| i.datum_type != f32::datum_type()) {
+            bail!("RecurrentCell only supports f32");
+        }
+        Ok(inputs[1..].iter().map(|&i| i.clone()).collect())
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let batch = inputs[0].shape.dim(0);
+        let per_batch =
+            self.kind.gates() * self.hidden_size() * (self.input_size() + self.hidden_size());
+        Ok(tvec!((Cost::FMA(f32::datum_type()), batch * per_batch)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math::tanh;
+    use crate::ops::nn::sigmoid;
+
+    fn step(cell: &RecurrentCell, x: f32, h: f32) -> f32 {
+        let x = arr2(&[[x]]);
+        let mut states = tvec!(arr2(&[[h]]).into_tensor());
+        cell.step(&x.view(), &mut states, &mut cell.buffers(1).unwrap()).unwrap();
+        states[0].to_array_view::<f32>().unwrap()[[0, 0]]
+    }
+
+    fn sig(x: f32) -> f32 {
+        1.0 / (1.0 + (-x).exp())
+    }
+
+    #[test]
+    fn rnn() {
+        let w = tensor2(&[[2.0f32]]);
+        let r = tensor2(&[[0.5f32]]);
+        let b = tensor1(&[0.25f32, -0.5]);
+        let cell = RecurrentCell::new(CellKind::Rnn, &w, &r, Some(&b), tvec!(tanh().0)).unwrap();
+        let expected = (2.0f32 * 0.3 + 0.5 * -1.0 + 0.25 - 0.5).tanh();
+        assert!((step(&cell, 0.3, -1.0) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gru() {
+        // gates z, r, h
+        let w = tensor2(&[[1.0f32], [-1.0], [0.5]]);
+        let r = tensor2(&[[0.5f32], [2.0], [-1.5]]);
+        let b = tensor1(&[0.1f32, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        let (x, h) = (0.7f32, -0.4f32);
+        for &lbr in &[false, true] {
+            let kind = CellKind::Gru { linear_before_reset: lbr, reset_bias: None };
+            let cell =
+                RecurrentCell::new(kind, &w, &r, Some(&b), tvec!(sigmoid().0, tanh().0)).unwrap();
+            let z = sig(x + 0.5 * h + 0.1 + 0.4);
+            let rt = sig(-x + 2.0 * h + 0.2 + 0.5);
+            let hh = if lbr {
+                (0.5 * x + 0.3 + rt * (-1.5 * h + 0.6)).tanh()
+            } else {
+                (0.5 * x + -1.5 * (rt * h) + 0.3 + 0.6).tanh()
+            };
+            let expected = (1.0 - z) * hh + z * h;
+            assert!((step(&cell, x, h) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn gru_batch() {
+        let (batch, input, hidden) = (3, 5, 4);
+        let values = |shape: (usize, usize), seed: usize| {
+            Array2::from_shape_fn(shape, |(i, j)| ((i * 7 + j * 3 + seed) % 11) as f32 / 10.0 - 0.5)
+        };
+        let (w, r) = (values((3 * hidden, input), 1), values((3 * hidden, hidden), 2));
+        let b = values((2, 3 * hidden), 3);
+        let (x, h) = (values((batch, input), 4), values((batch, hidden), 5));
+        let gate = |m: &Array2<f32>, g: usize| {
+            m.slice(s![g * hidden..(g + 1) * hidden, ..]).t().to_owned()
+        };
+        let bias = |i: usize, g: usize| b.slice(s![i, g * hidden..(g + 1) * hidden]).to_owned();
+        for &lbr in &[false, true] {
+            let kind = CellKind::Gru { linear_before_reset: lbr, reset_bias: None };
+            let cell = RecurrentCell::new(
+                kind,
+                &w.clone().into_tensor(),
+                &r.clone().into_tensor(),
+                Some(&b.clone().into_shape(6 * hidden).unwrap().into_tensor()),
+                tvec!(sigmoid().0, tanh().0),
+            )
+            .unwrap();
+            let mut states = tvec!(h.clone().into_tensor());
+            cell.step(&x.view(), &mut states, &mut cell.buffers(batch).unwrap()).unwrap();
+            let z = (x.dot(&gate(&w, 0)) + h.dot(&gate(&r, 0)) + bias(0, 0) + bias(1, 0)).mapv(sig);
+            let rt =
+                (x.dot(&gate(&w, 1)) + h.dot(&gate(&r, 1)) + bias(0, 1) + bias(1, 1)).mapv(sig);
+            let hh = if lbr {
+                x.dot(&gate(&w, 2)) + bias(0, 2) + &rt * &(h.dot(&gate(&r, 2)) + bias(1, 2))
+            } else {
+                x.dot(&gate(&w, 2)) + (&rt * &h).dot(&gate(&r, 2)) + bias(0, 2) + bias(1, 2)
+            }
+            .mapv(f32::tanh);
+            let expected = (1.0 - &z) * &hh + &z * &h;
+            let found = states[0].to_array_view::<f32>().unwrap();
+            assert!(found.iter().zip(expected.iter()).all(|(f, e)| (f - e).abs() < 1e-5));
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use crate::internal::*;
+use crate::ops::scan::{InputMapping, OutputMapping, StateInitializer};
+use ndarray::*;
+
+use super::RecurrentCell;
+
+/// A recurrent layer looping a `RecurrentCell` over its input.
+///
+/// This is what a Scan whose body is a single `RecurrentCell` turns into: the
+/// states are kept in the op state and updated in place, and the gates
+/// buffers are allocated once, instead of running the body model at every
+/// step.
+///
+/// Mappings are in the cell order: input 0 is the scanned input, then come
+/// the state initializers, and output i is state i.
+#[derive(Debug, Clone, new)]
+pub struct FusedRecurrent {
+    pub skip: usize,
+    pub cell: RecurrentCell,
+    pub input_mapping: Vec<InputMapping<usize>>,
+    pub output_mapping: Vec<OutputMapping<usize, TDim>>,
+}
+
+impl Op for FusedRecurrent {
+    fn name(&self) -> Cow<str> {
+        "FusedRecurrent".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut lines = self.cell.info()?;
+        for (ix, im) in self.input_mapping.iter().enumerate() {
+            lines.push(format!("Cell input  #{}: {:?}", ix, im));
+        }
+        for (ix, om) in self.output_mapping.iter().enumerate() {
+            lines.push(format!("Cell output #{}: {:?}", ix, om));
+        }
+        Ok(lines)
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatefullOp for FusedRecurrent {
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(FusedRecurrentState {
+            op: self.clone(),
+            position: 0,
+            states: tvec!(),
+            buffers: None,
+        })))
+    }
+}
+
+/// The state keeps its own copy of the op, so a Scan switching to the fused
+/// implementation does not have to rebuild it at every evaluation.
+#[derive(Debug, Clone)]
+struct FusedRecurrentState {
+    op: FusedRecurrent,
+    position: usize,
+    states: TVec<Tensor>,
+    buffers: Option<super::CellBuffers>,
+}
+
+impl OpState for FusedRecurrentState {
+    fn eval(
+        &mut self,
+        _session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = &self.op;
+        let (x_slot, _axis, _chunk) =
+            op.input_mapping[0].as_scan().ok_or("Expect cell input 0 to be scanned")?;
+        let x = inputs[x_slot].to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let (iters, batch_size) = (x.shape()[0], x.shape()[1]);
+        let hidden_size = op.cell.hidden_size();
+
+        // initialize state at first pass
+        if self.states.len() == 0 {
+            for input in &op.input_mapping[1..] {
+                let init = match input.as_state().ok_or("Expect cell inputs to be states")? {
+                    StateInitializer::FromInput(slot) => (*inputs[*slot]).clone(),
+                    StateInitializer::Value(v) => (**v).clone(),
+                };
+                self.states.push(unsafe { init.into_shape(&[batch_size, hidden_size])? });
+            }
+        }
+        if self.buffers.is_none() {
+            self.buffers = Some(op.cell.buffers(batch_size)?);
+        }
+        let buffers = self.buffers.as_mut().unwrap();
+
+        let mut outputs = tvec!();
+        for output in &op.output_mapping {
+            if let Some(slot) = output.full_slot {
+                let len = output
+                    .full_dim_hint
+                    .as_ref()
+                    .and_then(|d| d.to_integer().ok().map(|i| i as usize))
+                    .unwrap_or(iters);
+                outputs.push((slot, Array3::<f32>::zeros((len, batch_size, hidden_size))));
+            }
+        }
+
+        for i in 0..iters {
+            self.position += 1;
+            if self.position <= op.skip {
+                continue;
+            }
+            op.cell.step(&x.index_axis(Axis(0), i), &mut self.states, buffers)?;
+            for (state, mapping) in self.states.iter().zip(&op.output_mapping) {
+                if let Some(slot) = mapping.full_slot {
+                    let full = &mut outputs.iter_mut().find(|o| o.0 == slot).unwrap().1;
+                    if i < full.shape()[0] {
+                        full.index_axis_mut(Axis(0), i).assign(&state.to_array_view::<f32>()?);
+                    }
+                }
+            }
+        }
+
+        let mut outputs: TVec<(usize, Tensor)> =
+            outputs.into_iter().map(|(slot, t)| (slot, t.into_tensor())).collect();
+        for (state, mapping) in self.states.iter().zip(&op.output_mapping) {
+            if let Some(slot) = mapping.last_value_slot {
+                let last = unsafe { state.clone().into_shape(&[1, batch_size, hidden_size])? };
+                outputs.push((slot, last));
+            }
+        }
+        outputs.sort_by_key(|a| a.0);
+        Ok(outputs.into_iter().map(|(_slot, t)| t.into_arc_tensor()).collect())
+    }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        let mut tensors = tvec!(tensor0(self.position as i64));
+        tensors.extend(self.states.iter().cloned());
+        Ok(OpStateSnapshot { tensors, nested: tvec!() })
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        self.position = snapshot.usize(0)?;
+        self.states = snapshot.tensors[1..].into();
+        Ok(())
+    }
+}
+
+impl TypedOp for FusedRecurrent {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let (x_slot, _axis, _chunk) =
+            self.input_mapping[0].as_scan().ok_or("Expect cell input 0 to be scanned")?;
+        let x = &inputs[x_slot].shape;
+        let hidden = self.cell.hidden_size().to_dim();
+        let mut outputs = tvec!();
+        for output in &self.output_mapping {
+            if let Some(slot) = output.full_slot {
+                let len = output.full_dim_hint.clone().unwrap_or(x.dim(0));
+                let shape = [len, x.dim(1), hidden.clone()];
+                outputs.push((slot, TypedFact::dt_shape(f32::datum_type(), shape.as_ref())?));
+            }
+            if let Some(slot) = output.last_value_slot {
+                let shape = [1.to_dim(), x.dim(1), hidden.clone()];
+                outputs.push((slot, TypedFact::dt_shape(f32::datum_type(), shape.as_ref())?));
+            }
+        }
+        outputs.sort_by_key(|a| a.0);
+        Ok(outputs.into_iter().map(|(_slot, v)| v).collect())
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let (x_slot, _axis, _chunk) =
+            self.input_mapping[0].as_scan().ok_or("Expect cell input 0 to be scanned")?;
+        let x = &inputs[x_slot].shape;
+        let step = TypedFact::dt_shape(f32::datum_type(), [x.dim(1), x.dim(2)].as_ref())?;
+        let mut cost = self.cell.cost(&[&step])?;
+        for c in cost.iter_mut() {
+            c.1 = c.1.clone() * x.dim(0);
+        }
+        let state = self.cell.kind.states() * self.cell.hidden_size();
+        cost.push((Cost::Buffer(f32::datum_type()), x.dim(1) * state));
+        Ok(cost)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::array::{AddDim, RmDim};
+    use crate::ops::rec::CellKind;
+    use crate::ops::scan::TypedScan;
+
+    fn values(shape: &[usize], seed: usize) -> Tensor {
+        let len = shape.iter().product();
+        let v = (0..len).map(|i| ((i * 7 + seed) % 11) as f32 / 10.0 - 0.5).collect::<Vec<_>>();
+        ArrayD::from_shape_vec(shape, v).unwrap().into_tensor()
+    }
+
+    fn lstm_scan(input: usize, hidden: usize) -> TypedScan {
+        let peepholes = values(&[3, hidden], 1).into_arc_tensor();
+        let cell = RecurrentCell::new(
+            CellKind::Lstm { peepholes: Some(peepholes) },
+            &values(&[4 * hidden, input], 2),
+            &values(&[4 * hidden, hidden], 3),
+            Some(&values(&[8 * hidden], 4)),
+            tvec!(
+                crate::ops::nn::sigmoid().0,
+                crate::ops::math::tanh().0,
+                crate::ops::math::tanh().0
+            ),
+        )
+        .unwrap();
+        let mut body = TypedModel::default();
+        let x = body
+            .add_source(
+                "x",
+                TypedFact::dt_shape(f32::datum_type(), [1, 1, input].as_ref()).unwrap(),
+            )
+            .unwrap();
+        let state = TypedFact::dt_shape(f32::datum_type(), [1, 1, hidden].as_ref()).unwrap();
+        let h = body.add_source("h", state.clone()).unwrap();
+        let c = body.add_source("c", state).unwrap();
+        let mut inputs = tvec!();
+        for (ix, s) in [x, h, c].iter().enumerate() {
+            inputs.push(body.wire_node(format!("rm-{}", ix), RmDim::new(0), &[*s]).unwrap()[0]);
+        }
+        let states = body.wire_node("cell", cell, &inputs).unwrap();
+        let h = body.wire_node("add-h", AddDim::new(0), &[states[0]]).unwrap()[0];
+        let c = body.wire_node("add-c", AddDim::new(0), &[states[1]]).unwrap()[0];
+        body.set_output_outlets(&[h, c]).unwrap();
+        let initial = || InputMapping::State {
+            initializer: StateInitializer::Value(values(&[1, 1, hidden], 5).into_arc_tensor()),
+        };
+        let input_mapping =
+            vec![InputMapping::Scan { slot: 0, axis: 0, chunk: 1.to_dim() }, initial(), initial()];
+        let output_mapping = vec![
+            OutputMapping::new(Some(0), 0, 1.to_dim(), None, Some(1), true),
+            OutputMapping::new(None, 0, 1.to_dim(), None, Some(2), true),
+        ];
+        TypedScan::new(body, input_mapping, output_mapping, None).unwrap()
+    }
+
+    fn run(op: Box<dyn TypedOp>, input: &Tensor, halves: bool) -> TVec<Arc<Tensor>> {
+        let mut model = TypedModel::default();
+        let mut fact = TypedFact::dt_shape(f32::datum_type(), input.shape()).unwrap();
+        if halves {
+            fact.shape.set_dim(0, (input.shape()[0] / 2).to_dim()).unwrap();
+        }
+        let x = model.add_source("x", fact).unwrap();
+        let outputs = model.wire_node("scan", op, &[x]).unwrap();
+        model.set_output_outlets(&outputs).unwrap();
+        let mut state = SimpleState::new(SimplePlan::new(model).unwrap()).unwrap();
+        if halves {
+            let input = input.to_array_view::<f32>().unwrap();
+            let half = input.shape()[0] / 2;
+            let first = state
+                .run(tvec!(input.slice_axis(Axis(0), (..half).into()).to_owned().into_tensor()))
+                .unwrap();
+            let mut second = state
+                .run(tvec!(input.slice_axis(Axis(0), (half..).into()).to_owned().into_tensor()))
+                .unwrap();
+            let y = stack(
+                Axis(0),
+                &[
+                    first[0].to_array_view::<f32>().unwrap(),
+                    second[0].to_array_view::<f32>().unwrap(),
+                ],
+            )
+            .unwrap();
+            second[0] = y.into_arc_tensor();
+            second
+        } else {
+            state.run(tvec!(input.clone())).unwrap()
+        }
+    }
+
+    #[test]
+    fn fused_lstm_vs_generic_scan() {
+        let scan = lstm_scan(3, 2);
+        let fused = scan.to_fused_recurrent().unwrap().expect("Should be fused");
+        let input = values(&[6, 1, 3], 6);
+        let generic = run(Box::new(scan.to_codegen_op().unwrap()), &input, false);
+        let fused_full = run(Box::new(fused.clone()), &input, false);
+        let fused_halves = run(Box::new(fused), &input, true);
+        for (g, (f, h)) in generic.iter().zip(fused_full.iter().zip(fused_halves.iter())) {
+            g.close_enough(f, true).unwrap();
+            g.close_enough(h, true).unwrap();
+        }
+    }
+}
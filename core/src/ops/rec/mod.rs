@@ -0,0 +1,17 @@
+//! Fused recurrent layers.
+//!
+//! LSTM, GRU and RNN layers with constant weights can use a `RecurrentCell`
+//! as their Scan body. The Scan then switches to `FusedRecurrent` to run,
+//! looping over time without the generic Scan machinery.
+//!
+//! The backward direction of bidirectional layers is a `ReverseRecurrent`.
+//! It can be pulsified when it is latency-controlled: the backward pass then
+//! runs over fixed chunks with a bounded right context.
+
+mod cell;
+mod fused;
+mod reverse;
+
+pub use self::cell::{CellBuffers, CellKind, RecurrentCell};
+pub use self::fused::FusedRecurrent;
+pub use self::reverse::{LatencyControl, PulsedReverseRecurrent, ReverseRecurrent};
@@ -0,0 +1,450 @@
+use crate::internal::*;
+use ndarray::*;
+
+use super::RecurrentCell;
+
+/// Chunking of a backward recurrent pass, as in latency-controlled
+/// bidirectional layers (LC-BLSTM).
+///
+/// The sequence is cut in chunks of `chunk` frames. The backward pass for
+/// each chunk starts from the initial states, `right_context` frames after
+/// the end of the chunk, so it only needs a bounded lookahead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, new)]
+pub struct LatencyControl {
+    pub chunk: usize,
+    pub right_context: usize,
+}
+
+impl LatencyControl {
+    /// Frames of lookahead needed by the first frame of a chunk.
+    pub fn delay(&self) -> usize {
+        self.chunk + self.right_context - 1
+    }
+}
+
+/// Backward direction of a recurrent layer.
+///
+/// Input is x [seq_length, batch_size, input_size], there is one output for
+/// each of the cell states, [seq_length, batch_size, hidden_size]: frame t is
+/// the state after processing frames t and after.
+///
+/// Without latency control, the pass runs over the whole sequence, so it can
+/// not be pulsified.
+#[derive(Debug, Clone, new)]
+pub struct ReverseRecurrent {
+    pub cell: RecurrentCell,
+    /// Initial states, [batch_size, hidden_size].
+    pub initial_states: TVec<Arc<Tensor>>,
+    pub latency_control: Option<LatencyControl>,
+}
+
+impl ReverseRecurrent {
+    /// Run the cell backward over x, from its last frame and from the initial
+    /// states. Returns the states for the `emit` first frames.
+    fn backward(
+        &self,
+        x: ArrayView3<f32>,
+        emit: usize,
+        buffers: &mut super::CellBuffers,
+    ) -> TractResult<TVec<Array3<f32>>> {
+        let (batch_size, hidden_size) = (x.shape()[1], self.cell.hidden_size());
+        let mut states: TVec<Tensor> =
+            self.initial_states.iter().map(|s| s.as_ref().clone()).collect();
+        let mut outputs: TVec<Array3<f32>> =
+            states.iter().map(|_| Array3::zeros((emit, batch_size, hidden_size))).collect();
+        for t in (0..x.shape()[0]).rev() {
+            self.cell.step(&x.index_axis(Axis(0), t), &mut states, buffers)?;
+            if t < emit {
+                for (output, state) in outputs.iter_mut().zip(states.iter()) {
+                    output.index_axis_mut(Axis(0), t).assign(&state.to_array_view::<f32>()?);
+                }
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+impl Op for ReverseRecurrent {
+    fn name(&self) -> Cow<str> {
+        "ReverseRecurrent".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut lines = self.cell.info()?;
+        lines.push(format!("latency control: {:?}", self.latency_control));
+        Ok(lines)
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_as_typed_op!();
+    not_a_pulsed_op!();
+}
+
+impl StatelessOp for ReverseRecurrent {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let x = inputs[0].to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let (len, batch_size) = (x.shape()[0], x.shape()[1]);
+        let hidden_size = self.cell.hidden_size();
+        let lc = self.latency_control.unwrap_or(LatencyControl::new(len.max(1), 0));
+        let mut buffers = self.cell.buffers(batch_size)?;
+        let mut outputs: TVec<Array3<f32>> = self
+            .initial_states
+            .iter()
+            .map(|_| Array3::zeros((len, batch_size, hidden_size)))
+            .collect();
+        for start in (0..len).step_by(lc.chunk) {
+            let end = (start + lc.chunk).min(len);
+            let window = x.slice_axis(Axis(0), (start..(end + lc.right_context).min(len)).into());
+            let states = self.backward(window, end - start, &mut buffers)?;
+            for (output, state) in outputs.iter_mut().zip(states.iter()) {
+                output.slice_axis_mut(Axis(0), (start..end).into()).assign(state);
+            }
+        }
+        Ok(outputs.into_iter().map(|o| o.into_arc_tensor()).collect())
+    }
+}
+
+impl TypedOp for ReverseRecurrent {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].datum_type != f32::datum_type() {
+            bail!("ReverseRecurrent only supports f32");
+        }
+        if self.latency_control.map(|lc| lc.chunk == 0).unwrap_or(false) {
+            bail!("Latency control chunk must not be empty");
+        }
+        let x = &inputs[0].shape;
+        let shape = [x.dim(0), x.dim(1), self.cell.hidden_size().to_dim()];
+        let fact = TypedFact::dt_shape(f32::datum_type(), shape.as_ref())?;
+        Ok(self.initial_states.iter().map(|_| fact.clone()).collect())
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let x = &inputs[0].shape;
+        let step = TypedFact::dt_shape(f32::datum_type(), [x.dim(1), x.dim(2)].as_ref())?;
+        let frames = if let Some(lc) = self.latency_control {
+            // the right context is processed once per chunk
+            x.dim(0) * (lc.chunk + lc.right_context) / lc.chunk
+        } else {
+            x.dim(0)
+        };
+        let mut cost = self.cell.cost(&[&step])?;
+        for c in cost.iter_mut() {
+            c.1 = c.1.clone() * &frames;
+        }
+        Ok(cost)
+    }
+
+    fn pulsify(
+        &self,
+        _source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let fact = target.outlet_fact(input)?.clone();
+        if fact.axis != 0 {
+            bail!("ReverseRecurrent pulsification limited to streaming along time axis");
+        }
+        let latency_control = self.latency_control.ok_or_else(|| {
+            format!(
+                "{} is the backward pass of a bidirectional layer, it needs latency control to be pulsified",
+                node.name
+            )
+        })?;
+        let op = PulsedReverseRecurrent {
+            op: self.clone(),
+            latency_control,
+            skip: fact.delay,
+            dim: fact.dim.clone(),
+        };
+        target.wire_node(&*node.name, op, &[input])
+    }
+}
+
+/// Pulsed form of a latency-controlled ReverseRecurrent.
+///
+/// Input frames are buffered until the window of a chunk (its frames and the
+/// right context) is complete, then the backward pass of the chunk is
+/// computed at once. Outputs are delayed by the latency control delay, so
+/// every frame is ready when it is due.
+#[derive(Debug, Clone)]
+pub struct PulsedReverseRecurrent {
+    op: ReverseRecurrent,
+    latency_control: LatencyControl,
+    /// Input delay: garbage frames to ignore at the beginning of the stream.
+    skip: usize,
+    /// Input stream length, to cut the right context of the last chunk.
+    dim: TDim,
+}
+
+impl Op for PulsedReverseRecurrent {
+    fn name(&self) -> Cow<str> {
+        "PulsedReverseRecurrent".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        let mut lines = self.op.info()?;
+        lines.push(format!("skip: {}", self.skip));
+        Ok(lines)
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatefullOp for PulsedReverseRecurrent {
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(ReverseRecurrentState::default())))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ReverseRecurrentState {
+    /// Input frames received so far, including the skipped ones.
+    received: usize,
+    /// Buffered input frames, starting at the beginning of the next chunk.
+    input: Option<Array3<f32>>,
+    /// Index of the next chunk to compute.
+    next_chunk: usize,
+    /// Computed states, starting at frame `output_start`.
+    output: TVec<Array3<f32>>,
+    output_start: usize,
+}
+
+impl OpState for ReverseRecurrentState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = op.downcast_ref::<PulsedReverseRecurrent>().ok_or("Wrong Op type")?;
+        let lc = op.latency_control;
+        let x = inputs[0].to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let (pulse, batch_size) = (x.shape()[0], x.shape()[1]);
+        let hidden_size = op.op.cell.hidden_size();
+
+        // buffer the valid input frames
+        let skipped = op.skip.saturating_sub(self.received).min(pulse);
+        let valid = x.slice_axis(Axis(0), (skipped..).into());
+        self.input = Some(match self.input.take() {
+            Some(buffered) => stack(Axis(0), &[buffered.view(), valid])?,
+            None => valid.to_owned(),
+        });
+        self.received += pulse;
+        if self.output.len() == 0 {
+            self.output = op
+                .op
+                .initial_states
+                .iter()
+                .map(|_| Array3::zeros((0, batch_size, hidden_size)))
+                .collect();
+        }
+
+        // compute all chunks whose window is complete
+        let available = self.received.saturating_sub(op.skip);
+        let len = session
+            .known_stream_len
+            .map(|s| {
+                op.dim.eval(s as i32).map(|l| l as usize).ok_or("Can not evaluate stream length")
+            })
+            .transpose()?;
+        let mut buffers = None;
+        loop {
+            let start = self.next_chunk * lc.chunk;
+            let mut end = start + lc.chunk;
+            let mut window_end = end + lc.right_context;
+            if let Some(len) = len {
+                if start >= len {
+                    break;
+                }
+                end = end.min(len);
+                window_end = window_end.min(len);
+            }
+            if window_end > available {
+                break;
+            }
+            let input = self.input.take().unwrap();
+            let window = input.slice_axis(Axis(0), (..window_end - start).into());
+            if buffers.is_none() {
+                buffers = Some(op.op.cell.buffers(batch_size)?);
+            }
+            let buffers = buffers.as_mut().unwrap();
+            let states = op.op.backward(window, end - start, buffers)?;
+            for (output, state) in self.output.iter_mut().zip(states.iter()) {
+                *output = stack(Axis(0), &[output.view(), state.view()])?;
+            }
+            self.input = Some(
+                input
+                    .slice_axis(Axis(0), ((end - start).min(input.shape()[0])..).into())
+                    .to_owned(),
+            );
+            self.next_chunk += 1;
+        }
+
+        // emit the frames due in this pulse
+        let delay = op.skip + lc.delay();
+        let mut outputs: TVec<Array3<f32>> =
+            self.output.iter().map(|_| Array3::zeros((pulse, batch_size, hidden_size))).collect();
+        for i in 0..pulse {
+            let position = self.received - pulse + i;
+            if position < delay {
+                continue;
+            }
+            let frame = position - delay;
+            if frame >= self.output_start && frame - self.output_start < self.output[0].shape()[0] {
+                for (output, computed) in outputs.iter_mut().zip(self.output.iter()) {
+                    output
+                        .index_axis_mut(Axis(0), i)
+                        .assign(&computed.index_axis(Axis(0), frame - self.output_start));
+                }
+            }
+        }
+        let next_frame = self.received.saturating_sub(delay);
+        let emitted = next_frame.saturating_sub(self.output_start).min(self.output[0].shape()[0]);
+        for computed in self.output.iter_mut() {
+            *computed = computed.slice_axis(Axis(0), (emitted..).into()).to_owned();
+        }
+        self.output_start += emitted;
+        Ok(outputs.into_iter().map(|o| o.into_arc_tensor()).collect())
+    }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        let mut tensors = tvec!(
+            tensor0(self.received as i64),
+            tensor0(self.next_chunk as i64),
+            tensor0(self.output_start as i64),
+        );
+        tensors.extend(self.input.iter().map(|i| i.clone().into_tensor()));
+        tensors.extend(self.output.iter().map(|o| o.clone().into_tensor()));
+        Ok(OpStateSnapshot::new(tensors))
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        self.received = snapshot.usize(0)?;
+        self.next_chunk = snapshot.usize(1)?;
+        self.output_start = snapshot.usize(2)?;
+        let mut tensors = snapshot.tensors[3..]
+            .iter()
+            .map(|t| Ok(t.to_array_view::<f32>()?.into_dimensionality::<Ix3>()?.to_owned()));
+        if snapshot.tensors.len() > 3 {
+            self.input = Some(tensors.next().unwrap()?);
+        }
+        self.output = tensors.collect::<TractResult<_>>()?;
+        Ok(())
+    }
+}
+
+impl TypedOp for PulsedReverseRecurrent {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        self.op.output_facts(inputs)
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let mut cost = self.op.cost(inputs)?;
+        // input frames waiting for their window, and computed states waiting
+        // to be emitted
+        let x = &inputs[0].shape;
+        let lc = self.latency_control;
+        let frames = lc.chunk + lc.right_context;
+        let buffered = x.dim(1)
+            * (x.dim(2) * frames
+                + self.op.initial_states.len() * self.op.cell.hidden_size() * frames);
+        cost.push((Cost::Buffer(f32::datum_type()), buffered));
+        Ok(cost)
+    }
+}
+
+impl PulsedOp for PulsedReverseRecurrent {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape[2] = self.op.cell.hidden_size();
+        fact.delay += self.latency_control.delay();
+        Ok(self.op.initial_states.iter().map(|_| fact.clone()).collect())
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::rec::CellKind;
+
+    fn rnn(latency_control: Option<LatencyControl>) -> ReverseRecurrent {
+        let cell = RecurrentCell::new(
+            CellKind::Rnn,
+            &arr2(&[[0.5f32]]).into_tensor(),
+            &arr2(&[[0.8f32]]).into_tensor(),
+            None,
+            tvec!(crate::ops::math::tanh().0),
+        )
+        .unwrap();
+        ReverseRecurrent::new(cell, tvec!(arr2(&[[0.1f32]]).into_arc_tensor()), latency_control)
+    }
+
+    fn run(op: &ReverseRecurrent, x: &[f32]) -> Array1<f32> {
+        let x = Array1::from(x.to_vec()).into_shape((x.len(), 1, 1)).unwrap();
+        let y = op.eval(tvec!(x.into_arc_tensor())).unwrap().remove(0);
+        y.to_array_view::<f32>().unwrap().iter().cloned().collect()
+    }
+
+    #[test]
+    fn latency_control_restarts_on_chunks() {
+        let x = [1.0f32, -2.0, 0.5, 3.0, -1.0];
+        let op = rnn(Some(LatencyControl::new(2, 1)));
+        let y = run(&op, &x);
+        let full = rnn(None);
+        assert_eq!(y.slice(s![0..2]), run(&full, &x[0..3]).slice(s![0..2]));
+        assert_eq!(y.slice(s![2..4]), run(&full, &x[2..5]).slice(s![0..2]));
+        assert_eq!(y.slice(s![4..5]), run(&full, &x[4..5]));
+    }
+
+    #[test]
+    fn pulsed_state_matches_latency_control() {
+        let x = [1.0f32, -2.0, 0.5, 3.0, -1.0];
+        let lc = LatencyControl::new(2, 1);
+        let expected = run(&rnn(Some(lc)), &x);
+        let skip = 1;
+        let op =
+            PulsedReverseRecurrent { op: rnn(Some(lc)), latency_control: lc, skip, dim: TDim::s() };
+        let mut state = ReverseRecurrentState::default();
+        let mut session = SessionState::default();
+        let mut input: Vec<f32> = vec![9.0; skip];
+        input.extend(x.iter());
+        let delay = skip + lc.delay();
+        input.resize(delay + x.len() + 1, 0.0);
+        let mut output = vec![];
+        for (ix, pulse) in input.chunks(2).enumerate() {
+            if (ix + 1) * 2 >= skip + x.len() {
+                session.known_stream_len = Some(x.len());
+            }
+            let pulse = Array1::from(pulse.to_vec()).into_shape((pulse.len(), 1, 1)).unwrap();
+            let y = state.eval(&mut session, &op, tvec!(pulse.into_arc_tensor())).unwrap();
+            output.extend(y[0].to_array_view::<f32>().unwrap().iter().cloned());
+        }
+        assert_eq!(Array1::from(output[delay..][..x.len()].to_vec()), expected);
+    }
+}
@@ -234,6 +234,18 @@ impl OpState for State {
 
         Ok(outputs.into_iter().map(Arc::new).collect())
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        let mut tensors = tvec!(tensor0(self.position as i64));
+        tensors.extend(self.hidden_state.iter().cloned());
+        Ok(OpStateSnapshot { tensors, nested: tvec!(self.model_state.snapshot()?) })
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        self.position = snapshot.usize(0)?;
+        self.hidden_state = snapshot.tensors[1..].into();
+        self.model_state.restore(snapshot.nested.get(0).ok_or("Missing scan body snapshot")?)
+    }
 }
 
 impl TypedOp for Codegen {
@@ -271,6 +283,18 @@ impl TypedOp for Codegen {
         Ok(outputs)
     }
 
+    fn cost(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        // hidden states are kept from one run to the next
+        let mut cost = tvec!();
+        for (ix, input) in self.input_mapping.iter().enumerate() {
+            if input.as_state().is_some() {
+                let fact = self.plan.model().input_fact(ix)?;
+                cost.push((Cost::Buffer(fact.datum_type), fact.shape.iter().product()));
+            }
+        }
+        Ok(cost)
+    }
+
     fn nested_model_multipliers(&self, inputs: &[&TypedFact]) -> Vec<(Cow<str>, f32)> {
         let iters = {
             let (outside_slot, axis, chunk) = self
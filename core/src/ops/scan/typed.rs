@@ -1,6 +1,7 @@
 use super::codegen::Codegen;
 
 use super::*;
+use crate::ops::rec::{FusedRecurrent, RecurrentCell};
 
 #[derive(Debug, Clone, Default)]
 pub struct TypedScan {
@@ -53,6 +54,83 @@ impl TypedScan {
         Ok(Codegen::new(self.skip, Arc::new(plan), input_mapping, output_mapping))
     }
 
+    /// Recognize a body made of a single `RecurrentCell`, its inputs and
+    /// outputs only going through RmDim(0) and AddDim(0) for the chunk axis,
+    /// and build the equivalent `FusedRecurrent`.
+    pub fn to_fused_recurrent(&self) -> TractResult<Option<FusedRecurrent>> {
+        use crate::ops::array::{AddDim, RmDim};
+        if self.seq_length_input_slot.is_some() {
+            return Ok(None);
+        }
+        let cells =
+            self.body.nodes().iter().filter(|n| n.op_is::<RecurrentCell>()).collect::<Vec<_>>();
+        if cells.len() != 1
+            || self.body.nodes().len() != 1 + 2 * cells[0].inputs.len() + cells[0].outputs.len()
+        {
+            return Ok(None);
+        }
+        let cell_node = cells[0];
+        let cell = cell_node.op_as::<RecurrentCell>().unwrap();
+        let body_inputs = self.body.input_outlets()?;
+        let body_outputs = self.body.output_outlets()?;
+        if body_inputs.len() != cell_node.inputs.len()
+            || body_outputs.len() != cell_node.outputs.len()
+        {
+            return Ok(None);
+        }
+        let mut input_mapping = vec![];
+        let mut state_inputs = vec![];
+        for (ix, input) in cell_node.inputs.iter().enumerate() {
+            let rm = self.body.node(input.node);
+            if rm.op_as::<RmDim>().map(|rm| rm.axis) != Some(0) {
+                return Ok(None);
+            }
+            let body_input = if let Some(pos) = body_inputs.iter().position(|i| *i == rm.inputs[0])
+            {
+                pos
+            } else {
+                return Ok(None);
+            };
+            let mapping = match &self.input_mapping[body_input] {
+                InputMapping::Scan { slot, axis: 0, chunk } if ix == 0 && chunk == &1.to_dim() => {
+                    InputMapping::Scan { slot: *slot, axis: 0, chunk: 1 }
+                }
+                InputMapping::State { initializer } if ix > 0 => {
+                    state_inputs.push(body_input);
+                    InputMapping::State { initializer: initializer.clone() }
+                }
+                _ => return Ok(None),
+            };
+            input_mapping.push(mapping);
+        }
+        // states are paired by order: cell input i+1 is fed by cell output i
+        if state_inputs.windows(2).any(|w| w[0] > w[1]) {
+            return Ok(None);
+        }
+        let mut output_mapping = vec![];
+        for (ix, output) in body_outputs.iter().enumerate() {
+            let add = self.body.node(output.node);
+            if add.op_as::<AddDim>().map(|add| add.axis) != Some(0)
+                || add.inputs[0] != OutletId::new(cell_node.id, ix)
+            {
+                return Ok(None);
+            }
+            let om = &self.output_mapping[ix];
+            if !om.state || om.axis != 0 || om.chunk != 1.to_dim() {
+                return Ok(None);
+            }
+            output_mapping.push(OutputMapping {
+                state: om.state,
+                axis: om.axis,
+                full_slot: om.full_slot,
+                full_dim_hint: om.full_dim_hint.clone(),
+                last_value_slot: om.last_value_slot,
+                chunk: 1,
+            });
+        }
+        Ok(Some(FusedRecurrent::new(self.skip, cell.clone(), input_mapping, output_mapping)))
+    }
+
     pub fn new(
         body: TypedModel,
         input_mapping: Vec<InputMapping<TDim>>,
@@ -428,6 +506,9 @@ impl StatefullOp for TypedScan {
         session: &mut SessionState,
         node_id: usize,
     ) -> TractResult<Option<Box<dyn OpState>>> {
+        if let Some(fused) = self.to_fused_recurrent()? {
+            return fused.state(session, node_id);
+        }
         self.to_codegen_op()?.state(session, node_id)
     }
 }
@@ -467,6 +548,18 @@ impl TypedOp for TypedScan {
         Ok(outputs)
     }
 
+    fn cost(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        // hidden states are kept from one run to the next
+        let mut cost = tvec!();
+        for (ix, input) in self.input_mapping.iter().enumerate() {
+            if input.as_state().is_some() {
+                let fact = self.body.input_fact(ix)?;
+                cost.push((Cost::Buffer(fact.datum_type), fact.shape.iter().product()));
+            }
+        }
+        Ok(cost)
+    }
+
     fn invariants(&self, _model: &TypedModel, _node: &TypedNode) -> TractResult<Invariants> {
         let mut invariants = tvec!();
         let body_invs = self.body.invariants()?;
@@ -638,6 +731,14 @@ impl TypedOp for TypedScan {
         model: &TypedModel,
         node: &TypedNode,
     ) -> TractResult<Option<TypedModelPatch>> {
+        if let Some(fused) = self.to_fused_recurrent()? {
+            return Ok(Some(TypedModelPatch::replace_single_op(
+                &model,
+                node,
+                &node.inputs,
+                fused,
+            )?));
+        }
         Ok(Some(TypedModelPatch::replace_single_op(
             &model,
             node,
@@ -674,10 +775,15 @@ impl PulsedOp for TypedScan {
             axis: output_mapping.axis,
             dim: inputs[0].dim.clone(),
             delay: inputs[0].delay,
+            variable_pulse: inputs[0].variable_pulse,
         };
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
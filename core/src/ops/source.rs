@@ -12,6 +12,14 @@ impl OpState for SourceState {
     ) -> TractResult<TVec<Arc<Tensor>>> {
         Ok(tvec!(session.inputs[&self.0].clone()))
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        Ok(OpStateSnapshot::default())
+    }
+
+    fn restore(&mut self, _snapshot: &OpStateSnapshot) -> TractResult<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, new)]
@@ -143,9 +151,11 @@ impl PulsedOp for PulsedSource {
     }
 
     fn to_typed(&self) -> Box<dyn TypedOp> {
-        Box::new(TypedSource::new(
-            TypedFact::dt_shape(self.fact.datum_type, &*self.fact.shape).unwrap(),
-        ))
+        Box::new(TypedSource::new((&self.fact).into()))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
     }
 
     pulsed_op_as_op!();
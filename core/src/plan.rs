@@ -9,6 +9,8 @@ use crate::model::{Fact, ModelImpl, OutletId};
 #[derive(Debug, Default)]
 pub struct SessionState {
     pub inputs: HashMap<usize, Arc<Tensor>>,
+    /// Length of the input stream, once known. Set by `PulsedSession::finish`,
+    /// pulsed operators use it to handle the end of the stream.
     pub known_stream_len: Option<usize>,
     pub tensors: HashMap<String, Tensor>,
 }
@@ -163,6 +165,49 @@ where
         Ok(())
     }
 
+    /// Export op states and session tensors.
+    pub fn snapshot(&self) -> TractResult<StateSnapshot> {
+        let states = self
+            .states
+            .iter()
+            .enumerate()
+            .map(|(id, state)| {
+                state
+                    .as_ref()
+                    .map(|s| s.snapshot().chain_err(|| format!("Snapshot of node #{}", id)))
+                    .transpose()
+            })
+            .collect::<TractResult<_>>()?;
+        let mut tensors: Vec<(String, Tensor)> =
+            self.session_state.tensors.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        tensors.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(StateSnapshot { known_stream_len: self.session_state.known_stream_len, tensors, states })
+    }
+
+    /// Restore a snapshot taken on a state of the same plan. The next run
+    /// resumes where the snapshot was taken.
+    pub fn restore(&mut self, snapshot: &StateSnapshot) -> TractResult<()> {
+        if snapshot.states.len() != self.states.len() {
+            bail!(
+                "Snapshot is for a model with {} nodes, this one has {}",
+                snapshot.states.len(),
+                self.states.len()
+            );
+        }
+        for (id, (state, saved)) in self.states.iter_mut().zip(snapshot.states.iter()).enumerate() {
+            match (state, saved) {
+                (Some(state), Some(saved)) => {
+                    state.restore(saved).chain_err(|| format!("Restoring node #{}", id))?
+                }
+                (None, None) => (),
+                _ => bail!("Snapshot does not match state for node #{}", id),
+            }
+        }
+        self.session_state.known_stream_len = snapshot.known_stream_len;
+        self.session_state.tensors = snapshot.tensors.iter().cloned().collect();
+        self.reset_wires()
+    }
+
     pub fn run(&mut self, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
         self.run_plan(inputs, 0)
     }
@@ -213,12 +258,7 @@ where
                         );
                     }
                     for (ix, (v, f)) in inputs.iter().zip(facts.iter()).enumerate() {
-                        if f.to_tensor_fact().shape.is_concrete()
-                            && f.to_tensor_fact().stream_info()?.is_some()
-                        {
-                            continue;
-                        }
-                        if let Err(e) = f.to_tensor_fact().unify(&v.clone().into()) {
+                        if let Err(e) = f.check_tensor(v) {
                             bail!(
                                 "Evaluating {}: input {:?}, expected {:?}, got {:?} ({})",
                                 node,
@@ -251,12 +291,7 @@ where
                         if node.outputs[ix].successors.len() == 0 {
                             continue;
                         }
-                        if f.to_tensor_fact().shape.is_concrete()
-                            && f.to_tensor_fact().stream_info()?.is_some()
-                        {
-                            continue;
-                        }
-                        if let Err(e) = f.to_tensor_fact().unify(&v.clone().into()) {
+                        if let Err(e) = f.check_tensor(v) {
                             bail!(
                                 "Evaluating {}: output {:?}, expected {:?}, got {:?} ({})",
                                 node,
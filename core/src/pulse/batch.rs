@@ -0,0 +1,458 @@
+use crate::internal::*;
+use crate::model::compact;
+use crate::ops::matmul::MatMulUnary;
+
+/// How a node of the model is evaluated on a batch of streams.
+#[derive(Clone, Debug, PartialEq)]
+enum Mode {
+    /// Model input: the pulses of the streams are stacked.
+    Source,
+    /// Independent of the streams, computed once.
+    Shared,
+    /// Stateless and keeping the streams independent, computed once for all
+    /// streams.
+    Batched,
+    /// Computed for each stream, with the stream own state.
+    PerStream,
+}
+
+#[derive(Debug)]
+struct Stream {
+    states: Vec<Option<Box<dyn OpState>>>,
+    session: SessionState,
+}
+
+/// Executor running many independent streams through the same pulsed model.
+///
+/// The model is expected to have a batch axis of size 1 in its inputs. It is
+/// optimized once, and at each pulse the streams are stacked along this axis,
+/// so stateless operators are computed once for all streams. Matrix products
+/// keep their packed weights and run a single product over the columns of
+/// all the streams. Stateful operators (like delays) and operators that can
+/// not be proven to keep streams independent are computed stream by stream,
+/// each stream having its own state.
+///
+/// Streams can join and leave at any pulse. A pulse can be computed for any
+/// subset of the active streams.
+#[derive(Debug)]
+pub struct BatchedPulsedExecutor {
+    plan: TypedSimplePlan<TypedModel>,
+    modes: Vec<Mode>,
+    batch_axes: Vec<TVec<Option<usize>>>,
+    streams: Vec<Option<Stream>>,
+}
+
+impl BatchedPulsedExecutor {
+    pub fn new(model: PulsedModel, batch_axis: usize) -> TractResult<BatchedPulsedExecutor> {
+        for input in model.input_outlets()? {
+            let fact = model.outlet_fact(*input)?;
+            if batch_axis >= fact.shape.len()
+                || batch_axis == fact.axis
+                || fact.shape[batch_axis] != 1
+            {
+                bail!(
+                    "Input {} has no batch axis of size 1 at {}: {:?}",
+                    model.node(input.node),
+                    batch_axis,
+                    fact
+                );
+            }
+        }
+        let model = stack_products(model.into_typed()?.declutter()?, batch_axis)?;
+        let plan = SimplePlan::new(model.codegen()?)?;
+        let (modes, batch_axes) = analyse(plan.model(), &plan.order, batch_axis)?;
+        for output in plan.model().output_outlets()? {
+            if batch_axes[output.node][output.slot].is_none() {
+                bail!("Output {:?} does not depend on the streams", output);
+            }
+        }
+        Ok(BatchedPulsedExecutor { plan, modes, batch_axes, streams: vec![] })
+    }
+
+    /// The optimized model the streams are run through.
+    pub fn model(&self) -> &TypedModel {
+        self.plan.model()
+    }
+
+    /// Start a new stream. Returns its identifier.
+    pub fn join(&mut self) -> TractResult<usize> {
+        let mut session = SessionState::default();
+        let states = self
+            .plan
+            .model()
+            .nodes()
+            .iter()
+            .map(|n| {
+                if self.modes[n.id] == Mode::PerStream {
+                    n.op().state(&mut session, n.id)
+                } else {
+                    Ok(None)
+                }
+            })
+            .collect::<TractResult<_>>()?;
+        let stream = Some(Stream { states, session });
+        if let Some(free) = self.streams.iter().position(|s| s.is_none()) {
+            self.streams[free] = stream;
+            Ok(free)
+        } else {
+            self.streams.push(stream);
+            Ok(self.streams.len() - 1)
+        }
+    }
+
+    /// Terminate a stream, releasing its state.
+    pub fn leave(&mut self, stream: usize) -> TractResult<()> {
+        self.stream_mut(stream)?;
+        self.streams[stream] = None;
+        Ok(())
+    }
+
+    /// Identifiers of the active streams.
+    pub fn streams(&self) -> Vec<usize> {
+        (0..self.streams.len()).filter(|&s| self.streams[s].is_some()).collect()
+    }
+
+    /// Session state of a stream, to signal its end with `known_stream_len`.
+    pub fn session_state_mut(&mut self, stream: usize) -> TractResult<&mut SessionState> {
+        Ok(&mut self.stream_mut(stream)?.session)
+    }
+
+    fn stream_mut(&mut self, stream: usize) -> TractResult<&mut Stream> {
+        self.streams
+            .get_mut(stream)
+            .and_then(|s| s.as_mut())
+            .ok_or_else(|| format!("No active stream {}", stream).into())
+    }
+
+    /// Compute one pulse for each of the given streams, from their input
+    /// pulses. Returns the output pulses for each stream, in the same order.
+    pub fn run(
+        &mut self,
+        inputs: Vec<(usize, TVec<Tensor>)>,
+    ) -> TractResult<Vec<(usize, TVec<Arc<Tensor>>)>> {
+        if inputs.is_empty() {
+            return Ok(vec![]);
+        }
+        let ids: Vec<usize> = inputs.iter().map(|i| i.0).collect();
+        for (ix, id) in ids.iter().enumerate() {
+            self.stream_mut(*id)?;
+            if ids[..ix].contains(id) {
+                bail!("Stream {} appears twice in batch", id);
+            }
+        }
+        let model = self.plan.model();
+        let model_inputs = model.input_outlets()?;
+        let mut values: Vec<Option<TVec<Arc<Tensor>>>> = vec![None; model.nodes().len()];
+        for &id in &self.plan.order {
+            let node = model.node(id);
+            let node_inputs: TVec<Arc<Tensor>> =
+                node.inputs
+                    .iter()
+                    .map(|i| {
+                        values[i.node].as_ref().map(|v| v[i.slot].clone()).ok_or_else(|| {
+                            format!("Computing {}, missing input {:?}", node, i).into()
+                        })
+                    })
+                    .collect::<TractResult<_>>()?;
+            let outputs = match self.modes[id] {
+                Mode::Source => {
+                    let ix = model_inputs.iter().position(|o| o.node == id).unwrap();
+                    let pulses = inputs
+                        .iter()
+                        .map(|(_, i)| {
+                            i.get(ix)
+                                .ok_or_else(|| format!("Missing input #{}", ix).into())
+                                .map(|t| Arc::new(t.clone()))
+                        })
+                        .collect::<TractResult<Vec<_>>>()?;
+                    tvec!(stack(self.batch_axes[id][0].unwrap(), &pulses)?)
+                }
+                Mode::Shared | Mode::Batched => node
+                    .op()
+                    .as_stateless()
+                    .ok_or_else(|| format!("{} is not stateless", node))?
+                    .eval(node_inputs)
+                    .chain_err(|| format!("Evaluating {}", node))?,
+                Mode::PerStream => {
+                    let mut per_stream: Vec<TVec<Arc<Tensor>>> = vec![];
+                    for (batch_ix, stream_id) in ids.iter().enumerate() {
+                        let stream_inputs = node
+                            .inputs
+                            .iter()
+                            .zip(node_inputs.iter())
+                            .map(|(i, t)| match self.batch_axes[i.node][i.slot] {
+                                Some(axis) => {
+                                    Ok(Arc::new(t.slice(axis, batch_ix, batch_ix + 1)?))
+                                }
+                                None => Ok(t.clone()),
+                            })
+                            .collect::<TractResult<TVec<_>>>()?;
+                        let stream = self.streams[*stream_id].as_mut().unwrap();
+                        let outputs = match stream.states[id] {
+                            Some(ref mut state) => {
+                                state.eval(&mut stream.session, node.op(), stream_inputs)
+                            }
+                            None => node
+                                .op()
+                                .as_stateless()
+                                .ok_or_else(|| format!("{} is not stateless", node))?
+                                .eval(stream_inputs),
+                        }
+                        .chain_err(|| format!("Evaluating {} for stream {}", node, stream_id))?;
+                        per_stream.push(outputs);
+                    }
+                    (0..node.outputs.len())
+                        .map(|slot| {
+                            let tensors: Vec<Arc<Tensor>> =
+                                per_stream.iter().map(|o| o[slot].clone()).collect();
+                            stack(self.batch_axes[id][slot].unwrap(), &tensors)
+                        })
+                        .collect::<TractResult<_>>()?
+                }
+            };
+            values[id] = Some(outputs);
+        }
+        let outputs = model
+            .output_outlets()?
+            .iter()
+            .map(|o| {
+                Ok((
+                    values[o.node].as_ref().unwrap()[o.slot].clone(),
+                    self.batch_axes[o.node][o.slot].unwrap(),
+                ))
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        ids.iter()
+            .enumerate()
+            .map(|(batch_ix, id)| {
+                let outputs = outputs
+                    .iter()
+                    .map(|(t, axis)| Ok(Arc::new(t.slice(*axis, batch_ix, batch_ix + 1)?)))
+                    .collect::<TractResult<_>>()?;
+                Ok((*id, outputs))
+            })
+            .collect()
+    }
+}
+
+/// Mode of each node, and the axis along which the streams are stacked in
+/// each of its outputs.
+fn analyse(
+    model: &TypedModel,
+    order: &[usize],
+    batch_axis: usize,
+) -> TractResult<(Vec<Mode>, Vec<TVec<Option<usize>>>)> {
+    let inputs: Vec<usize> = model.input_outlets()?.iter().map(|o| o.node).collect();
+    let mut modes = vec![Mode::Shared; model.nodes().len()];
+    let mut batch_axes: Vec<TVec<Option<usize>>> =
+        model.nodes().iter().map(|n| n.outputs.iter().map(|_| None).collect()).collect();
+    for &id in order {
+        let node = model.node(id);
+        if inputs.contains(&id) {
+            modes[id] = Mode::Source;
+            batch_axes[id] = tvec!(Some(batch_axis));
+            continue;
+        }
+        let input_axes: TVec<Option<usize>> =
+            node.inputs.iter().map(|i| batch_axes[i.node][i.slot]).collect();
+        let stateful = node.op().state(&mut SessionState::default(), id)?.is_some();
+        if !stateful && input_axes.iter().all(|a| a.is_none()) {
+            continue;
+        }
+        if !stateful {
+            let invariants = node.op.invariants(model, node)?;
+            let axis = invariants.axes.iter().find(|axis| {
+                axis.period == 1
+                    && axis.outputs.len() == node.outputs.len()
+                    && axis.outputs.iter().all(|o| o.is_some())
+                    && input_axes
+                        .iter()
+                        .enumerate()
+                        .all(|(ix, a)| a.is_none() || axis.inputs.get(ix).cloned().flatten() == *a)
+            });
+            if let Some(axis) = axis {
+                modes[id] = Mode::Batched;
+                batch_axes[id] = axis.outputs.clone();
+                continue;
+            }
+        }
+        // computed stream by stream: outputs are stacked along an axis of
+        // size 1, preferably the one of the input
+        let input_axis = input_axes.iter().find_map(|a| *a);
+        batch_axes[id] = node
+            .outputs
+            .iter()
+            .map(|o| {
+                let shape = &o.fact.shape;
+                input_axis
+                    .filter(|&a| a < shape.rank() && shape.dim(a) == 1.to_dim())
+                    .or_else(|| shape.iter().position(|d| d == 1.to_dim()))
+                    .map(Some)
+                    .ok_or_else(|| format!("Can not batch node {}, no axis of size 1", node))
+            })
+            .collect::<Result<_, String>>()?;
+        modes[id] = Mode::PerStream;
+    }
+    Ok((modes, batch_axes))
+}
+
+/// Replace the matrix products computed once for all streams by products
+/// over the stacked columns of the streams.
+fn stack_products(mut model: TypedModel, batch_axis: usize) -> TractResult<TypedModel> {
+    let order = model.eval_order()?;
+    let (modes, batch_axes) = analyse(&model, &order, batch_axis)?;
+    for id in order {
+        if modes[id] != Mode::Batched {
+            continue;
+        }
+        let node = model.node(id);
+        let input = node.inputs[0];
+        let stacked = match (node.op_as::<MatMulUnary>(), batch_axes[input.node][input.slot]) {
+            (Some(op), Some(axis)) => op.stacked(model.outlet_fact(input)?, axis)?,
+            _ => None,
+        };
+        if let Some(stacked) = stacked {
+            let patch = TypedModelPatch::single_unary_op(&model, node, stacked)?;
+            patch.apply(&mut model)?;
+        }
+    }
+    compact::compact(&model)
+}
+
+fn stack(axis: usize, tensors: &[Arc<Tensor>]) -> TractResult<Arc<Tensor>> {
+    if tensors.len() == 1 {
+        return Ok(tensors[0].clone());
+    }
+    fn stack_t<T: Datum>(axis: usize, tensors: &[Arc<Tensor>]) -> TractResult<Arc<Tensor>> {
+        let views =
+            tensors.iter().map(|t| t.to_array_view::<T>()).collect::<TractResult<Vec<_>>>()?;
+        Ok(T::stack_views(axis, &*views)?.into_arc_tensor())
+    }
+    dispatch_datum!(stack_t(tensors[0].datum_type())(axis, tensors))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::matmul::phy::MatMatMulUnaryStacked;
+    use crate::ops::matmul::MatMulUnary;
+    use crate::pulse::delay::Delay;
+    use ndarray::*;
+
+    // input -> delay -> matmul -> delay -> output
+    fn matmul_model() -> PulsedModel {
+        let mut model = PulsedModel::default();
+        let fact = PulsedFact {
+            datum_type: f32::datum_type(),
+            shape: tvec![1, 2, 3],
+            axis: 1,
+            dim: TDim::s(),
+            delay: 0,
+            variable_pulse: None,
+        };
+        let source = model.add_source("source", fact.clone()).unwrap();
+        let delayed = model.wire_node("delay-1", Delay::new(&fact, 1, 1), &[source]).unwrap();
+        let w = tensor2(&[[1f32, 0.5, -1.0], [0.0, 2.0, 1.0]]);
+        let mm = model
+            .wire_node(
+                "mm",
+                MatMulUnary::new(w.into_arc_tensor(), false, true, true, None),
+                &delayed,
+            )
+            .unwrap();
+        let fact = model.outlet_fact(mm[0]).unwrap().clone();
+        let delayed = model.wire_node("delay-2", Delay::new(&fact, 2, 0), &mm).unwrap();
+        model.set_output_outlets(&delayed).unwrap();
+        model
+    }
+
+    // grouped convolution over [1, 4, S], in NCHW
+    fn conv_model() -> PulsedModel {
+        use crate::ops::cnn::Conv;
+        let mut model = InferenceModel::default();
+        let fact =
+            InferenceFact::dt_shape(f32::datum_type(), tvec!(1.to_dim(), 4.into(), TDim::s()));
+        let source = model.add_source("source", fact).unwrap();
+        let kernel = (0..24).map(|x| (x % 7) as f32 - 3.0).collect::<Vec<_>>();
+        let kernel = Array3::from_shape_vec((4, 2, 3), kernel).unwrap();
+        let kernel = model.add_const("kernel", kernel).unwrap();
+        let conv = model.wire_node("conv", Conv::default().group(2), &[source, kernel]).unwrap();
+        model.set_output_outlets(&conv).unwrap();
+        PulsedModel::new(&model.into_normalized().unwrap(), 2).unwrap()
+    }
+
+    fn pulse(shape: &[usize], stream: usize, i: usize) -> Tensor {
+        let len = shape.iter().product::<usize>();
+        let data = (0..len).map(|x| ((stream * 100 + i * 10 + x) % 17) as f32).collect();
+        ArrayD::from_shape_vec(shape, data).unwrap().into_tensor()
+    }
+
+    fn check_against_independent_states(model: PulsedModel) -> BatchedPulsedExecutor {
+        let plan = SimplePlan::new(&model).unwrap();
+        let mut executor = BatchedPulsedExecutor::new(model.clone(), 0).unwrap();
+        // only stateful nodes are computed stream by stream
+        for node in executor.model().nodes() {
+            if executor.modes[node.id] == Mode::PerStream {
+                assert!(node.op().state(&mut SessionState::default(), node.id).unwrap().is_some());
+            }
+        }
+
+        let input_shape = model.input_fact(0).unwrap().shape.clone();
+        let output_fact = model.output_fact(0).unwrap().clone();
+        // reference states, with the count of pulses they have computed
+        let mut references: Vec<Option<(SimpleState<_, _, _, _>, usize)>> = vec![];
+        for i in 0..8 {
+            // stream 1 joins at pulse 2, stream 0 leaves at pulse 5
+            if i == 0 || i == 2 {
+                let id = executor.join().unwrap();
+                assert_eq!(id, references.len());
+                references.push(Some((SimpleState::new(&plan).unwrap(), 0)));
+            }
+            if i == 5 {
+                executor.leave(0).unwrap();
+                references[0] = None;
+                // the freed slot is reused
+                assert_eq!(executor.join().unwrap(), 0);
+                references[0] = Some((SimpleState::new(&plan).unwrap(), 0));
+            }
+            // stream 1 skips pulse 3
+            let active: Vec<usize> =
+                executor.streams().into_iter().filter(|&s| i != 3 || s != 1).collect();
+            let inputs = active.iter().map(|&s| (s, tvec!(pulse(&input_shape, s, i)))).collect();
+            let outputs = executor.run(inputs).unwrap();
+            assert_eq!(outputs.iter().map(|o| o.0).collect::<Vec<_>>(), active);
+            for (s, found) in outputs {
+                let (state, pulses) = references[s].as_mut().unwrap();
+                let expected = state.run(tvec!(pulse(&input_shape, s, i))).unwrap();
+                // skip the frames before the output delay
+                let skip = output_fact
+                    .delay
+                    .saturating_sub(*pulses * output_fact.pulse())
+                    .min(output_fact.pulse());
+                *pulses += 1;
+                let axis = output_fact.axis;
+                let found = found[0].slice(axis, skip, output_fact.pulse()).unwrap();
+                let expected = expected[0].slice(axis, skip, output_fact.pulse()).unwrap();
+                assert_eq!(found, expected, "stream {} pulse {}", s, i);
+            }
+        }
+        assert!(executor.run(vec![(3, tvec!(pulse(&input_shape, 0, 0)))]).is_err());
+        executor
+    }
+
+    #[test]
+    fn batched_matmul_matches_independent_states() {
+        let executor = check_against_independent_states(matmul_model());
+        let mm = executor.model().node_by_name("mm").unwrap();
+        assert!(mm.op_is::<MatMatMulUnaryStacked>());
+        assert_eq!(executor.modes[mm.id], Mode::Batched);
+        let delay = executor.model().node_by_name("delay-1").unwrap();
+        assert_eq!(executor.modes[delay.id], Mode::PerStream);
+    }
+
+    #[test]
+    fn batched_conv_matches_independent_states() {
+        let executor = check_against_independent_states(conv_model());
+        assert!(executor.model().nodes().iter().any(|n| n.op().name() == "Conv::Im2col"));
+    }
+}
@@ -0,0 +1,31 @@
+//! Latency control for bidirectional recurrent layers.
+//!
+//! The backward pass of a bidirectional layer depends on the end of the
+//! sequence, so it can not be pulsified as is. `latency_controlled` turns
+//! every `ReverseRecurrent` into its chunked form: the backward pass runs over
+//! chunks of `chunk` frames, starting from the initial states
+//! `right_context` frames after each chunk, as in LC-BLSTM. The pulsed
+//! network is then delayed by `chunk + right_context - 1` frames.
+//!
+//! This changes the semantics of the network, so it is opt-in.
+
+use crate::internal::*;
+use crate::ops::rec::{LatencyControl, ReverseRecurrent};
+
+/// Set latency control on the backward pass of bidirectional layers.
+pub fn latency_controlled(
+    model: &TypedModel,
+    chunk: usize,
+    right_context: usize,
+) -> TractResult<TypedModel> {
+    if chunk == 0 {
+        bail!("Latency control chunk must not be empty");
+    }
+    let mut model = model.clone();
+    for node in model.nodes_mut() {
+        if let Some(op) = node.op_as_mut::<ReverseRecurrent>() {
+            op.latency_control = Some(LatencyControl::new(chunk, right_context));
+        }
+    }
+    Ok(model)
+}
@@ -0,0 +1,124 @@
+//! Causal rewrites of reductions over the streaming axis.
+//!
+//! A reduction over the whole stream can only be computed once the stream is
+//! over, so it can not be pulsified. `running_time_pooling` replaces these
+//! reductions by their cumulative counterpart: each frame of the output is the
+//! reduction of the input frames up to it, and the last frame holds the global
+//! value. Downstream ops see a running value instead of a global one, which
+//! turns global mean normalization into running normalization, for instance.
+//!
+//! The rewrite turns the reduced streaming axis from 1 to the stream length,
+//! so it is only applied where this is transparent to the consumers: the
+//! reduction must keep the streaming axis, and all its consumers must be
+//! binary ops broadcasting it against a tensor streaming along the same axis
+//! (like `x - mean(x)`). Other reductions, for instance the ones followed by a
+//! `RmDim` of the streaming axis, are left untouched.
+//!
+//! This changes the semantics of the network: the global value is never
+//! emitted at the end of the stream, every frame gets the running value
+//! instead. It is only applied on explicit request.
+
+use crate::internal::*;
+use crate::model::translator::Translate;
+use crate::ops::binary::TypedBinOp;
+use crate::ops::nn::{CumReduce, GlobalAvgPool, GlobalMaxPool, Reducer, TypedReduce};
+
+#[derive(Debug)]
+struct RunningTimePooling;
+
+impl RunningTimePooling {
+    fn reduction(
+        source: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<(TVec<usize>, Reducer)>> {
+        if node.inputs.len() != 1 {
+            return Ok(None);
+        }
+        let rank = source.outlet_fact(node.inputs[0])?.shape.rank();
+        if let Some(op) = node.op_as::<TypedReduce>() {
+            Ok(Some((op.axes.clone(), op.reducer)))
+        } else if node.op_is::<GlobalAvgPool>() {
+            Ok(Some(((2..rank).collect(), Reducer::Mean)))
+        } else if node.op_is::<GlobalMaxPool>() {
+            Ok(Some(((2..rank).collect(), Reducer::Max)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check every consumer of the reduction broadcasts its streaming axis
+    /// against a streaming input, so it will not notice the axis growing.
+    fn broadcast_consumers(
+        source: &TypedModel,
+        node: &TypedNode,
+        stream: usize,
+    ) -> TractResult<bool> {
+        if source.output_outlets()?.contains(&OutletId::new(node.id, 0)) {
+            return Ok(false);
+        }
+        for succ in &node.outputs[0].successors {
+            let consumer = source.node(succ.node);
+            if !consumer.op_is::<TypedBinOp>() {
+                return Ok(false);
+            }
+            let other = source.outlet_fact(consumer.inputs[1 - succ.slot])?;
+            if other.shape.stream_info.as_ref().map(|s| s.axis) != Some(stream) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Translate<TypedFact, Box<dyn TypedOp>, TypedFact, Box<dyn TypedOp>> for RunningTimePooling {
+    fn translate_node(
+        &self,
+        source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if source.input_outlets()?.contains(&node.id.into()) {
+            let fact = source.outlet_fact(node.id.into())?.clone();
+            return Ok(tvec!(target.add_source(&*node.name, fact)?.into()));
+        }
+        let inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+        if let Some((axes, reducer)) = Self::reduction(source, node)? {
+            let fact = target.outlet_fact(inputs[0])?;
+            let stream =
+                fact.shape.stream_info.as_ref().map(|s| s.axis).filter(|s| axes.contains(s));
+            let stream = match stream {
+                Some(s) if Self::broadcast_consumers(source, node, s)? => Some(s),
+                _ => None,
+            };
+            if let Some(stream) = stream {
+                if let Reducer::Sum | Reducer::Mean | Reducer::Max | Reducer::Min | Reducer::Prod =
+                    reducer
+                {
+                    let mut wire = inputs[0];
+                    let others: TVec<usize> = axes.into_iter().filter(|&a| a != stream).collect();
+                    if others.len() > 0 {
+                        wire = target.wire_node(
+                            format!("{}-other-axes", node.name),
+                            TypedReduce::new(others, reducer),
+                            &[wire],
+                        )?[0];
+                    }
+                    let op = CumReduce::new(stream, reducer, false, false);
+                    return target.wire_node(&*node.name, op, &[wire]);
+                }
+            }
+        }
+        target.wire_node(&*node.name, node.op.clone(), &*inputs)
+    }
+}
+
+/// Replace reductions and global pools over the streaming axis by cumulative
+/// reductions, emitting a running value on every frame instead of a global
+/// value at end of stream.
+///
+/// Only the reductions keeping the streaming axis and consumed by
+/// broadcasting binary ops are rewritten (see the module documentation).
+pub fn running_time_pooling(model: &TypedModel) -> TractResult<TypedModel> {
+    RunningTimePooling.translate_model(model)
+}
@@ -57,6 +57,20 @@ impl OpState for DelayState {
         let op = op.downcast_ref::<Delay>().ok_or("Wrong Op type")?;
         Ok(tvec!(dispatch_datum!(Self::eval_t(input.datum_type())(self, op, input))?))
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        Ok(OpStateSnapshot::new(tvec!(self.buffer.clone())))
+    }
+
+    fn restore(&mut self, snapshot: &OpStateSnapshot) -> TractResult<()> {
+        let buffer = snapshot.tensor(0)?;
+        if buffer.datum_type() != self.buffer.datum_type() || buffer.shape() != self.buffer.shape()
+        {
+            bail!("Delay buffer mismatch: expected {:?}, got {:?}", self.buffer, buffer);
+        }
+        self.buffer = buffer.clone();
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -131,6 +145,10 @@ impl PulsedOp for Delay {
         Ok(tvec!(fact))
     }
 
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
     pulsed_op_as_op!();
     pulsed_op_to_typed_op!();
 }
@@ -148,6 +166,7 @@ mod test {
             axis: 0,
             dim: TDim::s(),
             delay: 0,
+            variable_pulse: None,
         };
         let source = model.add_source("source", fact1.clone()).unwrap();
         model.wire_node("delay", Delay::new(&fact1, delay, overlap), &[source]).unwrap();
@@ -197,6 +216,7 @@ mod test {
             axis: 0,
             dim: TDim::s(),
             delay: 0,
+            variable_pulse: None,
         };
         let source = model.add_source("source", fact_0.clone()).unwrap();
         let delay_1 = model.wire_node("delay-1", Delay::new(&fact_0, 2, 0), &[source]).unwrap()[0];
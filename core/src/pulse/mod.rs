@@ -2,7 +2,12 @@ use crate::internal::*;
 use crate::model::translator::Translate;
 use std::fmt;
 
+pub mod batch;
+pub mod bidi;
+pub mod causal;
 pub mod delay;
+pub mod report;
+pub mod session;
 
 #[derive(Clone, PartialEq)]
 pub struct PulsedFact {
@@ -11,6 +16,10 @@ pub struct PulsedFact {
     pub axis: usize,
     pub dim: TDim,
     pub delay: usize,
+    /// For a model running with a variable pulse, the number of frames each
+    /// extra pulse adds to the streaming axis. `shape` holds the shape for one
+    /// pulse.
+    pub variable_pulse: Option<usize>,
 }
 
 impl fmt::Debug for PulsedFact {
@@ -19,7 +28,7 @@ impl fmt::Debug for PulsedFact {
         write!(
             fmt,
             "{}x{:?} [pulse axis:{} ∂:{} full dim:{:?}]",
-            self.shape.iter().join("x"),
+            self.pulse_shape().iter().join("x"),
             self.datum_type,
             self.axis,
             self.delay,
@@ -30,13 +39,36 @@ impl fmt::Debug for PulsedFact {
 
 impl Fact for PulsedFact {
     fn to_tensor_fact(&self) -> InferenceFact {
-        InferenceFact::dt_shape(self.datum_type, &self.shape)
+        InferenceFact::dt_shape(self.datum_type, self.pulse_shape())
+    }
+
+    /// With a variable pulse, tensors spanning any positive number of pulses
+    /// along the streaming axis are accepted.
+    fn check_tensor(&self, tensor: &Tensor) -> TractResult<()> {
+        let shape = tensor.shape();
+        if tensor.datum_type() != self.datum_type
+            || shape.len() != self.shape.len()
+            || match self.variable_pulse {
+                Some(step) => {
+                    shape[self.axis] < self.pulse() || (shape[self.axis] - self.pulse()) % step != 0
+                }
+                None => shape[self.axis] != self.pulse(),
+            }
+            || shape
+                .iter()
+                .zip(self.shape.iter())
+                .enumerate()
+                .any(|(axis, (t, f))| axis != self.axis && t != f)
+        {
+            bail!("Expected {:?}, got {:?}", self, tensor)
+        }
+        Ok(())
     }
 }
 
 impl<'a> From<&'a PulsedFact> for TypedFact {
     fn from(fact: &'a PulsedFact) -> TypedFact {
-        TypedFact::dt_shape(fact.datum_type, &*fact.shape).unwrap()
+        TypedFact::dt_shape(fact.datum_type, &*fact.pulse_shape()).unwrap()
     }
 }
 
@@ -53,13 +85,37 @@ impl PulsedFact {
             tf.shape.stream_info.as_ref().ok_or("Can not pulse a tensor with no streaming dim")?;
         let shape =
             tf.shape.iter().map(|d| d.to_integer().map(|d| d as usize).unwrap_or(pulse)).collect();
-        Ok(PulsedFact { datum_type, shape, axis: stream.axis, dim: stream.len.clone(), delay: 0 })
+        Ok(PulsedFact {
+            datum_type,
+            shape,
+            axis: stream.axis,
+            dim: stream.len.clone(),
+            delay: 0,
+            variable_pulse: None,
+        })
     }
 
     pub fn pulse(&self) -> usize {
         self.shape[self.axis]
     }
 
+    /// Length of the streaming axis at runtime, in terms of the number P of
+    /// pulses in a run for a variable pulse.
+    pub fn pulse_dim(&self) -> TDim {
+        match self.variable_pulse {
+            Some(step) => TDim::p() * step + (self.pulse() - step),
+            None => self.pulse().to_dim(),
+        }
+    }
+
+    pub fn pulse_shape(&self) -> TVec<TDim> {
+        self.shape
+            .iter()
+            .enumerate()
+            .map(|(ix, &d)| if ix == self.axis { self.pulse_dim() } else { d.to_dim() })
+            .collect()
+    }
+
     pub fn to_pulse_fact(&self) -> NormalizedFact {
         NormalizedFact::dt_shape(self.datum_type, &*self.shape).unwrap()
     }
@@ -94,11 +150,83 @@ impl PulsedModel {
         Pulsifier(pulse).translate_model_with_mappings(source)
     }
 
+    /// Check the model can run on chunks of any multiple of its pulse.
+    ///
+    /// Buffers are sized for the pulse the model was built for, which is then
+    /// the minimum pulse: stateful ops (Delay, Pad, Concat...) adapt to the
+    /// actual length of their inputs.
+    pub fn check_variable_pulse(&self) -> TractResult<()> {
+        if let Some(node) = self.nodes().iter().find(|n| !n.op.supports_variable_pulse()) {
+            bail!("{} does not support variable pulse", node)
+        }
+        Ok(())
+    }
+
+    /// Convert to a model running on chunks of any multiple of its pulse.
+    ///
+    /// The streaming axis of the inputs becomes P times the pulse, P being the
+    /// number of pulses in a run, and the facts are propagated through the
+    /// model.
+    pub fn into_variable_pulse(mut self) -> TractResult<PulsedModel> {
+        use crate::ops::source::PulsedSource;
+        self.check_variable_pulse()?;
+        for id in self.eval_order()? {
+            let node = self.node(id);
+            let facts = if node.op_is::<PulsedSource>() {
+                let mut fact = node.outputs[0].fact.clone();
+                fact.variable_pulse = Some(fact.pulse());
+                self.node_mut(id).op = Box::new(PulsedSource::new(fact.clone()));
+                tvec!(fact)
+            } else {
+                let inputs = self.node_input_facts(id)?;
+                node.op.pulsed_output_facts(&*inputs)?
+            };
+            for (ix, fact) in facts.into_iter().enumerate() {
+                self.set_outlet_fact(OutletId::new(id, ix), fact)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Delay of each output, and contribution of each node to the delay and
+    /// to the state memory.
+    pub fn latency_report(&self) -> TractResult<report::LatencyReport> {
+        report::LatencyReport::new(self)
+    }
+
     pub fn into_typed(self) -> TractResult<TypedModel> {
         crate::model::translator::IntoTranslator.translate_model(&self)
     }
 }
 
+/// Invariants of a normalized node, computed on a typed copy of the node.
+pub fn normalized_invariants(
+    source: &NormalizedModel,
+    node: &NormalizedNode,
+) -> TractResult<Invariants> {
+    let mut model = TypedModel::default();
+    let inputs = node
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(ix, i)| {
+            model.add_source(format!("{}-input-{}", node.name, ix), source.outlet_fact(*i)?.into())
+        })
+        .collect::<TractResult<TVec<_>>>()?;
+    let wires = model.wire_node(&*node.name, node.op.clone(), &*inputs)?;
+    let typed_node = model.node(wires[0].node);
+    typed_node.op.invariants(&model, typed_node)
+}
+
+/// Axes of the outputs the streaming axis of the first input is mapped to,
+/// if the invariants prove it passes through the op unchanged.
+pub fn track_stream_axis(invariants: &Invariants, axis: usize) -> Option<TVec<usize>> {
+    invariants
+        .track_input_axis(0, axis)
+        .filter(|info| info.period == 1)
+        .and_then(|info| info.outputs.iter().cloned().collect())
+}
+
 #[derive(Debug)]
 struct Pulsifier(usize);
 impl
@@ -172,4 +300,36 @@ mod tests {
             InferenceFact::dt_shape(DatumType::F32, vec!(4, 2, 3))
         );
     }
+
+    #[test]
+    fn test_variable_pulse() {
+        let mut model = PulsedModel::default();
+        let fact = PulsedFact {
+            datum_type: f32::datum_type(),
+            shape: tvec![3, 2],
+            axis: 0,
+            dim: TDim::s(),
+            delay: 0,
+            variable_pulse: None,
+        };
+        let source = model.add_source("source", fact.clone()).unwrap();
+        let delay = model.wire_node("delay", delay::Delay::new(&fact, 4, 1), &[source]).unwrap()[0];
+        model.set_output_outlets(&[delay]).unwrap();
+        let frames = |n: usize| ndarray::Array2::<f32>::zeros((n, 2)).into_tensor();
+        assert!(model.input_fact(0).unwrap().check_tensor(&frames(6)).is_err());
+
+        let model = model.into_variable_pulse().unwrap();
+        let (input, output) = (model.input_fact(0).unwrap(), model.output_fact(0).unwrap());
+        assert_eq!(input.pulse_shape(), tvec!(TDim::p() * 3, 2.to_dim()));
+        assert_eq!(output.pulse_shape(), tvec!(TDim::p() * 3 + 1, 2.to_dim()));
+        assert!(input.check_tensor(&frames(3)).is_ok());
+        assert!(input.check_tensor(&frames(6)).is_ok());
+        assert!(input.check_tensor(&frames(5)).is_err());
+        assert!(input.check_tensor(&frames(0)).is_err());
+        assert!(output.check_tensor(&frames(7)).is_ok());
+        assert!(output.check_tensor(&frames(6)).is_err());
+
+        let mut state = SimpleState::new(SimplePlan::new(model).unwrap()).unwrap();
+        assert_eq!(state.run(tvec!(frames(6))).unwrap()[0].shape(), &[7, 2]);
+    }
 }
@@ -0,0 +1,191 @@
+//! Algorithmic latency and state memory of pulsed models.
+
+use crate::internal::*;
+use std::fmt;
+
+/// Delay of an output of a pulsed model, in frames.
+#[derive(Clone, Debug)]
+pub struct OutputLatency {
+    pub outlet: OutletId,
+    pub name: String,
+    pub delay: usize,
+}
+
+impl OutputLatency {
+    /// Delay in milliseconds, for a stream of `frame_rate` frames per second.
+    pub fn delay_ms(&self, frame_rate: f32) -> f32 {
+        self.delay as f32 * 1000.0 / frame_rate
+    }
+}
+
+/// Contribution of a node to the model latency and state.
+#[derive(Clone, Debug)]
+pub struct NodeLatency {
+    pub node: usize,
+    pub name: String,
+    pub op: String,
+    /// Output delay minus the biggest input delay. Negative when the node
+    /// consumes some delay, like a padding.
+    pub increment: isize,
+    /// Bytes held by the node state between pulses.
+    pub state_memory: usize,
+}
+
+/// Summary of the latency of a pulsed model: delay of each output, and
+/// contribution of each node adding some delay or holding some state.
+#[derive(Clone, Debug)]
+pub struct LatencyReport {
+    pub pulse: usize,
+    pub outputs: Vec<OutputLatency>,
+    pub nodes: Vec<NodeLatency>,
+    pub state_memory: usize,
+    /// Frames per second of the input stream, used to display delays in
+    /// milliseconds.
+    pub frame_rate: Option<f32>,
+}
+
+impl LatencyReport {
+    pub fn new(model: &PulsedModel) -> TractResult<LatencyReport> {
+        let pulse = model.input_fact(0)?.pulse();
+        let outputs = model
+            .output_outlets()?
+            .iter()
+            .map(|&outlet| {
+                let name = model.node(outlet.node).name.clone();
+                Ok(OutputLatency { outlet, name, delay: model.outlet_fact(outlet)?.delay })
+            })
+            .collect::<TractResult<_>>()?;
+        let mut nodes = vec![];
+        for &id in model.eval_order()?.iter() {
+            let node = model.node(id);
+            let input_facts = model.node_input_facts(id)?;
+            let output_facts = model.node_output_facts(id)?;
+            let input_delay = input_facts.iter().map(|f| f.delay).max().unwrap_or(0);
+            let output_delay = output_facts.iter().map(|f| f.delay).max().unwrap_or(0);
+            // costs for the pulse the model was built for
+            let typed_facts = input_facts
+                .iter()
+                .map(|f| TypedFact::dt_shape(f.datum_type, &*f.shape))
+                .collect::<TractResult<TVec<_>>>()?;
+            let typed_facts = typed_facts.iter().collect::<TVec<_>>();
+            let mut state_memory = 0;
+            for (cost, count) in node.op.to_typed().cost(&*typed_facts)? {
+                if let Cost::Buffer(dt) = cost {
+                    state_memory += count.to_integer()? as usize * dt.size_of();
+                }
+            }
+            let increment = output_delay as isize - input_delay as isize;
+            if increment != 0 || state_memory != 0 {
+                nodes.push(NodeLatency {
+                    node: id,
+                    name: node.name.clone(),
+                    op: node.op.name().to_string(),
+                    increment,
+                    state_memory,
+                })
+            }
+        }
+        let state_memory = nodes.iter().map(|n| n.state_memory).sum();
+        Ok(LatencyReport { pulse, outputs, nodes, state_memory, frame_rate: None })
+    }
+
+    pub fn with_frame_rate(self, frame_rate: f32) -> LatencyReport {
+        LatencyReport { frame_rate: Some(frame_rate), ..self }
+    }
+}
+
+impl fmt::Display for LatencyReport {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(fmt, "Pulse: {} frames", self.pulse)?;
+        for output in &self.outputs {
+            write!(
+                fmt,
+                "Output {} ({:?}): delay {} frames",
+                output.name, output.outlet, output.delay
+            )?;
+            if let Some(frame_rate) = self.frame_rate {
+                write!(fmt, " ({:.1}ms)", output.delay_ms(frame_rate))?;
+            }
+            writeln!(fmt)?;
+        }
+        for node in &self.nodes {
+            writeln!(
+                fmt,
+                "  #{} {} [{}]: delay {:+}, state {} bytes",
+                node.node, node.name, node.op, node.increment, node.state_memory
+            )?;
+        }
+        write!(fmt, "Total state memory: {} bytes", self.state_memory)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pulse::delay::Delay;
+
+    #[test]
+    fn delays_and_buffers() {
+        let mut model = PulsedModel::default();
+        let fact = PulsedFact {
+            datum_type: f32::datum_type(),
+            shape: tvec![4, 2],
+            axis: 0,
+            dim: TDim::s(),
+            delay: 0,
+            variable_pulse: None,
+        };
+        let source = model.add_source("source", fact.clone()).unwrap();
+        let delayed = model.wire_node("delay", Delay::new(&fact, 3, 0), &[source]).unwrap();
+        let delayed_fact = model.outlet_fact(delayed[0]).unwrap().clone();
+        let overlap =
+            model.wire_node("overlap", Delay::new(&delayed_fact, 0, 2), &delayed).unwrap();
+        model.set_output_outlets(&[delayed[0], overlap[0]]).unwrap();
+
+        let report = model.latency_report().unwrap();
+        assert_eq!(report.pulse, 4);
+        assert_eq!(report.outputs.iter().map(|o| o.delay).collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(report.outputs[1].delay_ms(100.0), 50.0);
+        assert_eq!(report.nodes.iter().map(|n| n.increment).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(report.nodes.iter().map(|n| n.state_memory).collect::<Vec<_>>(), vec![24, 16]);
+        assert_eq!(report.state_memory, 40);
+        let display = report.with_frame_rate(100.0).to_string();
+        assert!(display.contains("delay 5 frames (50.0ms)"), "{}", display);
+    }
+
+    #[test]
+    fn pad_and_scan_state() {
+        use crate::ops::array::{Pad, PadMode};
+        use crate::ops::math::add;
+        use crate::ops::scan::{InputMapping, OutputMapping, StateInitializer, TypedScan};
+
+        let mut body = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), [1, 2].as_ref()).unwrap();
+        let x = body.add_source("x", fact.clone()).unwrap();
+        let h = body.add_source("h", fact).unwrap();
+        let y = body.wire_node("y", add::bin(), &[x, h]).unwrap();
+        body.set_output_outlets(&y).unwrap();
+
+        let mut model = TypedModel::default();
+        let fact =
+            TypedFact::dt_shape(f32::datum_type(), [TDim::s(), 2.to_dim()].as_ref()).unwrap();
+        let source = model.add_source("source", fact).unwrap();
+        let pad = Pad::new(vec![(0, 2), (0, 0)], PadMode::Edge);
+        let padded = model.wire_node("pad", pad, &[source]).unwrap();
+        let h0 = tensor2(&[[0f32, 0.0]]);
+        let input_mapping = vec![
+            InputMapping::Scan { slot: 0, axis: 0, chunk: 1.to_dim() },
+            InputMapping::State { initializer: StateInitializer::Value(h0.into_arc_tensor()) },
+        ];
+        let output_mapping = vec![OutputMapping::new(Some(0), 0, 1.to_dim(), None, None, true)];
+        let scan = TypedScan::new(body, input_mapping, output_mapping, None).unwrap();
+        let scanned = model.wire_node("scan", scan, &padded).unwrap();
+        model.set_output_outlets(&scanned).unwrap();
+
+        let pulsed = PulsedModel::new(&model.into_normalized().unwrap(), 4).unwrap();
+        let report = pulsed.latency_report().unwrap();
+        // pad keeps the last frame, scan its hidden state
+        assert_eq!(report.nodes.iter().map(|n| n.state_memory).collect::<Vec<_>>(), vec![8, 8]);
+        assert_eq!(report.state_memory, 16);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::internal::*;
+use crate::plan::SimpleState;
+use ndarray::*;
+
+type PulsedPlan = SimplePlan<PulsedFact, Box<dyn PulsedOp>, PulsedModel>;
+type PulsedState = SimpleState<PulsedFact, Box<dyn PulsedOp>, PulsedModel, Arc<PulsedPlan>>;
+
+#[derive(Clone, Debug)]
+struct SessionInput {
+    fact: PulsedFact,
+    pending: Tensor,
+    pushed: usize,
+}
+
+#[derive(Clone, Debug)]
+struct SessionOutput {
+    fact: PulsedFact,
+    produced: usize,
+    pending: Tensor,
+    emitted: usize,
+}
+
+/// Streaming driver for a pulsed model.
+///
+/// Inputs can be pushed by chunks of any length along their streaming axis:
+/// they are buffered and fed to the model one pulse at a time. Outputs are
+/// compensated for the model delay, so only valid frames are returned.
+/// `finish` signals the end of the stream, and flushes the remaining frames.
+///
+/// With a variable pulse, all the complete pulses available are computed by a
+/// single run of the model.
+#[derive(Clone, Debug)]
+pub struct PulsedSession {
+    state: PulsedState,
+    inputs: TVec<SessionInput>,
+    outputs: TVec<SessionOutput>,
+    pulses: usize,
+    finished: bool,
+    variable_pulse: bool,
+}
+
+impl PulsedSession {
+    pub fn new(model: PulsedModel) -> TractResult<PulsedSession> {
+        let inputs = model
+            .input_outlets()?
+            .iter()
+            .map(|o| {
+                let fact = model.outlet_fact(*o)?.clone();
+                let pending = empty(&fact)?;
+                Ok(SessionInput { fact, pending, pushed: 0 })
+            })
+            .collect::<TractResult<_>>()?;
+        let outputs = model
+            .output_outlets()?
+            .iter()
+            .map(|o| {
+                let fact = model.outlet_fact(*o)?.clone();
+                let pending = empty(&fact)?;
+                Ok(SessionOutput { fact, produced: 0, pending, emitted: 0 })
+            })
+            .collect::<TractResult<_>>()?;
+        let state = SimpleState::new(Arc::new(SimplePlan::new(model)?))?;
+        Ok(PulsedSession {
+            state,
+            inputs,
+            outputs,
+            pulses: 0,
+            finished: false,
+            variable_pulse: false,
+        })
+    }
+
+    /// Session running the model on chunks of any multiple of its pulse.
+    pub fn new_with_variable_pulse(model: PulsedModel) -> TractResult<PulsedSession> {
+        let mut session = PulsedSession::new(model.into_variable_pulse()?)?;
+        session.variable_pulse = true;
+        Ok(session)
+    }
+
+    pub fn model(&self) -> &PulsedModel {
+        self.state.model()
+    }
+
+    /// Total number of frames pushed so far, on the streaming axis of the
+    /// inputs.
+    pub fn pushed(&self) -> usize {
+        self.inputs.get(0).map(|i| i.pushed).unwrap_or(0)
+    }
+
+    /// Push one chunk for each input, and compute as many pulses as possible.
+    ///
+    /// Returns one tensor for each output, containing the valid frames made
+    /// available by this chunk (possibly none). Frames are only emitted once
+    /// they are known to be in the output whatever the final stream length.
+    pub fn push(&mut self, inputs: TVec<Tensor>) -> TractResult<TVec<Tensor>> {
+        if self.finished {
+            bail!("Can not push to a finished pulsed session");
+        }
+        if inputs.len() != self.inputs.len() {
+            bail!("Expected {} inputs, got {}", self.inputs.len(), inputs.len());
+        }
+        for (ix, (input, chunk)) in self.inputs.iter_mut().zip(inputs.into_iter()).enumerate() {
+            let fact = &input.fact;
+            if chunk.datum_type() != fact.datum_type
+                || chunk.rank() != fact.shape.len()
+                || chunk
+                    .shape()
+                    .iter()
+                    .zip(fact.shape.iter())
+                    .enumerate()
+                    .any(|(axis, (c, f))| axis != fact.axis && c != f)
+            {
+                bail!(
+                    "Input #{}: chunk {:?}x{:?} is incompatible with {:?}",
+                    ix,
+                    chunk.shape(),
+                    chunk.datum_type(),
+                    fact
+                );
+            }
+            input.pushed += chunk.shape()[fact.axis];
+            input.pending = concat(fact.axis, &[&input.pending, &chunk])?;
+        }
+        let mut available = self
+            .inputs
+            .iter()
+            .map(|i| i.pending.shape()[i.fact.axis] / i.fact.pulse())
+            .min()
+            .unwrap_or(0);
+        while available > 0 {
+            let count = if self.variable_pulse { available } else { 1 };
+            self.run_pulses(count)?;
+            available -= count;
+        }
+        self.emit(self.pushed())
+    }
+
+    /// Signal the end of the stream, and return the remaining valid frames of
+    /// each output.
+    ///
+    /// The stream length is made available to the model operators, and the
+    /// last pulses are computed with zero-padded inputs.
+    pub fn finish(&mut self) -> TractResult<TVec<Tensor>> {
+        if self.finished {
+            bail!("Pulsed session is already finished");
+        }
+        self.finished = true;
+        let len = self.pushed();
+        if self.inputs.iter().any(|i| i.pushed != len) {
+            bail!(
+                "Inputs have different lengths: {:?}",
+                self.inputs.iter().map(|i| i.pushed).collect::<Vec<_>>()
+            );
+        }
+        self.state.session_state.known_stream_len = Some(len);
+        let mut pulses = self.pulses;
+        for output in &self.outputs {
+            let needed = output.fact.delay + output_len(&output.fact, len)?;
+            pulses = pulses.max((needed + output.fact.pulse() - 1) / output.fact.pulse());
+        }
+        while self.pulses < pulses {
+            let count = if self.variable_pulse { pulses - self.pulses } else { 1 };
+            self.run_pulses(count)?;
+        }
+        self.emit(len)
+    }
+
+    /// Run the model on the next `count` pulses of pending input, padded with
+    /// zeros if the input is short.
+    fn run_pulses(&mut self, count: usize) -> TractResult<()> {
+        let inputs = self
+            .inputs
+            .iter_mut()
+            .map(|i| {
+                let (axis, wanted) = (i.fact.axis, i.fact.pulse() * count);
+                let len = i.pending.shape()[axis];
+                let valid = i.pending.slice(axis, 0, len.min(wanted))?;
+                i.pending = i.pending.slice(axis, len.min(wanted), len)?;
+                if valid.shape()[axis] == wanted {
+                    return Ok(valid);
+                }
+                let mut shape: TVec<usize> = i.fact.shape.clone();
+                shape[axis] = wanted - valid.shape()[axis];
+                concat(axis, &[&valid, &zeros(i.fact.datum_type, &shape)?])
+            })
+            .collect::<TractResult<_>>()?;
+        let outputs = self.state.run(inputs)?;
+        self.pulses += count;
+        for (output, computed) in self.outputs.iter_mut().zip(outputs.into_iter()) {
+            let (axis, delay) = (output.fact.axis, output.fact.delay);
+            let pulse = output.fact.pulse() * count;
+            let begin = output.produced;
+            output.produced += pulse;
+            let skip = delay.max(begin).min(begin + pulse) - begin;
+            if skip < pulse {
+                let valid = computed.slice(axis, skip, pulse)?;
+                output.pending = concat(axis, &[&output.pending, &valid])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit the computed frames that are valid for a stream of (at least)
+    /// `len` frames.
+    fn emit(&mut self, len: usize) -> TractResult<TVec<Tensor>> {
+        self.outputs
+            .iter_mut()
+            .map(|output| {
+                let axis = output.fact.axis;
+                let available = output.pending.shape()[axis];
+                let count =
+                    output_len(&output.fact, len)?.saturating_sub(output.emitted).min(available);
+                let emitted = output.pending.slice(axis, 0, count)?;
+                output.pending = output.pending.slice(axis, count, available)?;
+                output.emitted += count;
+                Ok(emitted)
+            })
+            .collect()
+    }
+}
+
+fn output_len(fact: &PulsedFact, len: usize) -> TractResult<usize> {
+    let output_len = fact
+        .dim
+        .eval(len as i32)
+        .ok_or_else(|| format!("Can not evaluate {:?} for stream length {}", fact.dim, len))?;
+    Ok(output_len.max(0) as usize)
+}
+
+fn empty(fact: &PulsedFact) -> TractResult<Tensor> {
+    let mut shape = fact.shape.clone();
+    shape[fact.axis] = 0;
+    zeros(fact.datum_type, &*shape)
+}
+
+fn zeros(dt: DatumType, shape: &[usize]) -> TractResult<Tensor> {
+    fn zeros_t<T: Datum>(shape: &[usize]) -> TractResult<Tensor> {
+        Ok(ArrayD::<T>::default(shape).into_tensor())
+    }
+    dispatch_datum!(zeros_t(dt)(shape))
+}
+
+fn concat(axis: usize, tensors: &[&Tensor]) -> TractResult<Tensor> {
+    fn concat_t<T: Datum>(axis: usize, tensors: &[&Tensor]) -> TractResult<Tensor> {
+        let views =
+            tensors.iter().map(|t| t.to_array_view::<T>()).collect::<TractResult<Vec<_>>>()?;
+        Ok(T::stack_views(axis, &*views)?.into_tensor())
+    }
+    dispatch_datum!(concat_t(tensors[0].datum_type())(axis, tensors))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pulse::delay::Delay;
+
+    fn delay_model(pulse: usize, delay: usize) -> PulsedModel {
+        let mut model = PulsedModel::default();
+        let fact = PulsedFact {
+            datum_type: i32::datum_type(),
+            shape: tvec![2, pulse],
+            axis: 1,
+            dim: TDim::s(),
+            delay: 0,
+            variable_pulse: None,
+        };
+        let source = model.add_source("source", fact.clone()).unwrap();
+        let delayed = model.wire_node("delay", Delay::new(&fact, delay, 0), &[source]).unwrap();
+        model.set_output_outlets(&[source, delayed[0]]).unwrap();
+        model
+    }
+
+    fn input(range: std::ops::Range<i32>) -> Tensor {
+        let len = range.len();
+        Array2::from_shape_fn((2, len), |(r, c)| r as i32 * 100 + range.start + c as i32)
+            .into_tensor()
+    }
+
+    #[test]
+    fn arbitrary_chunks() {
+        for &(pulse, delay) in &[(1, 0), (3, 2), (4, 5), (2, 8)] {
+            let mut session = PulsedSession::new(delay_model(pulse, delay)).unwrap();
+            let mut got: TVec<TVec<Tensor>> = tvec!(tvec!(), tvec!());
+            let mut offset = 0;
+            for &len in &[0, 1, 5, 2, 0, 7, 3] {
+                let outputs = session.push(tvec!(input(offset..offset + len))).unwrap();
+                offset += len;
+                for (ix, o) in outputs.into_iter().enumerate() {
+                    got[ix].push(o);
+                }
+            }
+            for (ix, o) in session.finish().unwrap().into_iter().enumerate() {
+                got[ix].push(o);
+            }
+            let expected = input(0..offset);
+            for got in got {
+                let got = concat(1, &*got.iter().collect::<Vec<_>>()).unwrap();
+                assert_eq!(got, expected, "pulse: {} delay: {}", pulse, delay);
+            }
+        }
+    }
+
+    #[test]
+    fn variable_pulse() {
+        for &(pulse, delay) in &[(1, 0), (3, 2), (2, 8)] {
+            let model = delay_model(pulse, delay);
+            let mut session = PulsedSession::new_with_variable_pulse(model).unwrap();
+            let mut got = vec![];
+            let mut offset = 0;
+            for &len in &[7, 1, 12, 0, 3] {
+                got.push(session.push(tvec!(input(offset..offset + len))).unwrap().remove(1));
+                offset += len;
+            }
+            got.push(session.finish().unwrap().remove(1));
+            let got = concat(1, &*got.iter().collect::<Vec<_>>()).unwrap();
+            assert_eq!(got, input(0..offset), "pulse: {} delay: {}", pulse, delay);
+        }
+    }
+
+    #[test]
+    fn emits_only_valid_frames() {
+        let mut session = PulsedSession::new(delay_model(2, 3)).unwrap();
+        let outputs = session.push(tvec!(input(0..4))).unwrap();
+        assert_eq!(outputs[0], input(0..4));
+        assert_eq!(outputs[1], input(0..1));
+        let outputs = session.finish().unwrap();
+        assert_eq!(outputs[0], input(4..4));
+        assert_eq!(outputs[1], input(1..4));
+        assert!(session.push(tvec!(input(0..1))).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_chunks() {
+        let mut session = PulsedSession::new(delay_model(2, 0)).unwrap();
+        assert!(session.push(tvec!(tensor2(&[[1f32], [2f32]]))).is_err());
+        assert!(session.push(tvec!(tensor2(&[[1i32], [2], [3]]))).is_err());
+        assert!(session.push(tvec!(input(0..1), input(0..1))).is_err());
+    }
+}
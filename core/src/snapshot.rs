@@ -0,0 +1,447 @@
+//! Snapshots of the state of a running model.
+//!
+//! A `StateSnapshot` captures everything a `SimpleState` carries from one run
+//! to the next: the state of each stateful op, and the session tensors (like
+//! variables). Restoring a snapshot in a state built from the same plan
+//! resumes the computation exactly where it was taken.
+//!
+//! Snapshots can be encoded to and decoded from bytes, to be stored or moved
+//! to another process.
+
+use crate::internal::*;
+
+/// Exported state of one op.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpStateSnapshot {
+    pub tensors: TVec<Tensor>,
+    /// States of nested models (like a Scan body).
+    pub nested: TVec<StateSnapshot>,
+}
+
+impl OpStateSnapshot {
+    pub fn new(tensors: TVec<Tensor>) -> OpStateSnapshot {
+        OpStateSnapshot { tensors, nested: tvec!() }
+    }
+
+    pub fn tensor(&self, ix: usize) -> TractResult<&Tensor> {
+        self.tensors
+            .get(ix)
+            .ok_or_else(|| format!("Snapshot has no tensor #{} ({:?})", ix, self).into())
+    }
+
+    /// Read back a position or counter, stored as an i64 scalar.
+    pub fn usize(&self, ix: usize) -> TractResult<usize> {
+        Ok(*self.tensor(ix)?.to_scalar::<i64>()? as usize)
+    }
+}
+
+/// Exported state of a `SimpleState`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateSnapshot {
+    pub known_stream_len: Option<usize>,
+    /// Session tensors, sorted by name.
+    pub tensors: Vec<(String, Tensor)>,
+    /// For each node, the snapshot of its op state, if the op is stateful.
+    pub states: Vec<Option<OpStateSnapshot>>,
+}
+
+const MAGIC: &[u8] = b"tract-state-1";
+
+impl StateSnapshot {
+    pub fn to_bytes(&self) -> TractResult<Vec<u8>> {
+        let mut buf = MAGIC.to_vec();
+        write_state(&mut buf, self)?;
+        Ok(buf)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> TractResult<StateSnapshot> {
+        if !bytes.starts_with(MAGIC) {
+            bail!("Not a tract state snapshot");
+        }
+        let mut reader = Reader(&bytes[MAGIC.len()..]);
+        let snapshot = reader.state()?;
+        if reader.0.len() > 0 {
+            bail!("Trailing bytes after state snapshot");
+        }
+        Ok(snapshot)
+    }
+}
+
+fn write_usize(buf: &mut Vec<u8>, v: usize) {
+    buf.extend_from_slice(&(v as u64).to_le_bytes());
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_usize(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+fn write_state(buf: &mut Vec<u8>, state: &StateSnapshot) -> TractResult<()> {
+    match state.known_stream_len {
+        Some(len) => {
+            buf.push(1);
+            write_usize(buf, len);
+        }
+        None => buf.push(0),
+    }
+    write_usize(buf, state.tensors.len());
+    for (name, tensor) in &state.tensors {
+        write_bytes(buf, name.as_bytes());
+        write_tensor(buf, tensor)?;
+    }
+    write_usize(buf, state.states.len());
+    for op_state in &state.states {
+        if let Some(op_state) = op_state {
+            buf.push(1);
+            write_usize(buf, op_state.tensors.len());
+            for t in &op_state.tensors {
+                write_tensor(buf, t)?;
+            }
+            write_usize(buf, op_state.nested.len());
+            for nested in &op_state.nested {
+                write_state(buf, nested)?;
+            }
+        } else {
+            buf.push(0);
+        }
+    }
+    Ok(())
+}
+
+fn datum_type_code(dt: DatumType) -> TractResult<u8> {
+    use DatumType::*;
+    Ok(match dt {
+        Bool => 0,
+        U8 => 1,
+        U16 => 2,
+        I8 => 3,
+        I16 => 4,
+        I32 => 5,
+        I64 => 6,
+        F16 => 7,
+        F32 => 8,
+        F64 => 9,
+        String => 10,
+        _ => bail!("Can not snapshot {:?} tensors", dt),
+    })
+}
+
+fn datum_type_from_code(code: u8) -> TractResult<DatumType> {
+    use DatumType::*;
+    Ok(match code {
+        0 => Bool,
+        1 => U8,
+        2 => U16,
+        3 => I8,
+        4 => I16,
+        5 => I32,
+        6 => I64,
+        7 => F16,
+        8 => F32,
+        9 => F64,
+        10 => String,
+        _ => bail!("Invalid datum type code {} in snapshot", code),
+    })
+}
+
+fn write_tensor(buf: &mut Vec<u8>, tensor: &Tensor) -> TractResult<()> {
+    fn raw_bytes<T: Datum>(t: &Tensor) -> TractResult<&[u8]> {
+        let slice = t.as_slice::<T>()?;
+        Ok(unsafe {
+            std::slice::from_raw_parts(
+                slice.as_ptr() as *const u8,
+                slice.len() * std::mem::size_of::<T>(),
+            )
+        })
+    }
+    buf.push(datum_type_code(tensor.datum_type())?);
+    write_usize(buf, tensor.rank());
+    for &d in tensor.shape() {
+        write_usize(buf, d);
+    }
+    if tensor.datum_type() == DatumType::String {
+        for s in tensor.as_slice::<String>()? {
+            write_bytes(buf, s.as_bytes());
+        }
+    } else if tensor.len() == 0 {
+        write_bytes(buf, &[]);
+    } else {
+        write_bytes(buf, dispatch_copy!(raw_bytes(tensor.datum_type())(tensor))?);
+    }
+    Ok(())
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> TractResult<&'a [u8]> {
+        if self.0.len() < len {
+            bail!("Truncated state snapshot");
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> TractResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn usize(&mut self) -> TractResult<usize> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes) as usize)
+    }
+
+    fn bytes(&mut self) -> TractResult<&'a [u8]> {
+        let len = self.usize()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> TractResult<String> {
+        Ok(std::str::from_utf8(self.bytes()?).map_err(|e| format!("{}", e))?.to_string())
+    }
+
+    fn tensor(&mut self) -> TractResult<Tensor> {
+        let dt = datum_type_from_code(self.u8()?)?;
+        let rank = self.usize()?;
+        let shape = (0..rank).map(|_| self.usize()).collect::<TractResult<TVec<usize>>>()?;
+        let len = shape.iter().product::<usize>();
+        if dt == DatumType::String {
+            let strings = (0..len).map(|_| self.string()).collect::<TractResult<Vec<_>>>()?;
+            return Ok(ndarray::ArrayD::from_shape_vec(&*shape, strings)?.into_tensor());
+        }
+        let bytes = self.bytes()?;
+        if bytes.len() != len * dt.size_of() {
+            bail!("Inconsistent tensor size in state snapshot");
+        }
+        if dt == DatumType::Bool && bytes.iter().any(|&b| b > 1) {
+            bail!("Invalid boolean in state snapshot");
+        }
+        if len == 0 {
+            unsafe { Tensor::uninitialized_dt(dt, &*shape) }
+        } else {
+            unsafe { Tensor::from_raw_dt(dt, &*shape, bytes) }
+        }
+    }
+
+    fn state(&mut self) -> TractResult<StateSnapshot> {
+        let known_stream_len = if self.u8()? == 1 { Some(self.usize()?) } else { None };
+        let tensors = (0..self.usize()?)
+            .map(|_| Ok((self.string()?, self.tensor()?)))
+            .collect::<TractResult<_>>()?;
+        let states = (0..self.usize()?)
+            .map(|_| {
+                if self.u8()? == 0 {
+                    return Ok(None);
+                }
+                let tensors =
+                    (0..self.usize()?).map(|_| self.tensor()).collect::<TractResult<_>>()?;
+                let nested =
+                    (0..self.usize()?).map(|_| self.state()).collect::<TractResult<_>>()?;
+                Ok(Some(OpStateSnapshot { tensors, nested }))
+            })
+            .collect::<TractResult<_>>()?;
+        Ok(StateSnapshot { known_stream_len, tensors, states })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let half = f16(half::f16::from_f32(0.5));
+        let snapshot = StateSnapshot {
+            known_stream_len: Some(12),
+            tensors: vec![
+                ("var".to_string(), tensor2(&[[1f32, -0.0], [std::f32::NAN, 4.5]])),
+                ("flags".to_string(), tensor1(&[true, false])),
+            ],
+            states: vec![
+                None,
+                Some(OpStateSnapshot::new(tvec!(tensor0(3i64), tensor1(&[1u8, 2, 3])))),
+                Some(OpStateSnapshot {
+                    tensors: tvec!(tensor1(&["a".to_string(), "bc".to_string()])),
+                    nested: tvec!(StateSnapshot {
+                        known_stream_len: None,
+                        tensors: vec![],
+                        states: vec![Some(OpStateSnapshot::new(tvec!(tensor0(half))))],
+                    }),
+                }),
+            ],
+        };
+        let bytes = snapshot.to_bytes().unwrap();
+        let decoded = StateSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes().unwrap(), bytes);
+        assert_eq!(decoded.states, snapshot.states);
+        assert_eq!(decoded.tensors[1], snapshot.tensors[1]);
+        let (found, expected) = (&decoded.tensors[0].1, &snapshot.tensors[0].1);
+        let found = found.as_slice::<f32>().unwrap().iter().map(|f| f.to_bits());
+        let expected = expected.as_slice::<f32>().unwrap().iter().map(|f| f.to_bits());
+        assert!(found.eq(expected));
+        assert!(StateSnapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn resume_delay() {
+        use crate::pulse::delay::Delay;
+        let mut model = PulsedModel::default();
+        let fact = PulsedFact {
+            datum_type: f32::datum_type(),
+            shape: tvec![3],
+            axis: 0,
+            dim: TDim::s(),
+            delay: 0,
+            variable_pulse: None,
+        };
+        let source = model.add_source("source", fact.clone()).unwrap();
+        let delay = model.wire_node("delay", Delay::new(&fact, 4, 1), &[source]).unwrap();
+        model.set_output_outlets(&delay).unwrap();
+        let plan = Arc::new(SimplePlan::new(model).unwrap());
+        let pulse = |i: usize| tensor1(&[i as f32 + 0.1, i as f32 + 0.2, i as f32 + 0.3]);
+
+        let mut state = SimpleState::new(plan.clone()).unwrap();
+        for i in 0..3 {
+            state.run(tvec!(pulse(i))).unwrap();
+        }
+        state.session_state.known_stream_len = Some(42);
+        let bytes = state.snapshot().unwrap().to_bytes().unwrap();
+        let expected: Vec<_> = (3..6).map(|i| state.run(tvec!(pulse(i))).unwrap()).collect();
+
+        let mut resumed = SimpleState::new(plan).unwrap();
+        resumed.restore(&StateSnapshot::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(resumed.session_state.known_stream_len, Some(42));
+        let found: Vec<_> = (3..6).map(|i| resumed.run(tvec!(pulse(i))).unwrap()).collect();
+        assert_eq!(found, expected);
+        assert!(resumed.restore(&StateSnapshot::default()).is_err());
+    }
+
+    fn stream_fact(shape: &[usize]) -> TypedFact {
+        let shape: TVec<TDim> =
+            std::iter::once(TDim::s()).chain(shape.iter().map(|d| d.to_dim())).collect();
+        TypedFact::dt_shape(f32::datum_type(), &*shape).unwrap()
+    }
+
+    fn values(shape: &[usize], seed: usize) -> Tensor {
+        let len = shape.iter().product();
+        let v = (0..len).map(|i| ((i * 7 + seed) % 11) as f32 / 10.0 - 0.5).collect::<Vec<_>>();
+        ndarray::ArrayD::from_shape_vec(shape, v).unwrap().into_tensor()
+    }
+
+    // Runs eight pulses through the pulsified model, snapshotting after the
+    // third one, then checks that a fresh state restored from the snapshot
+    // produces exactly the same five remaining pulses.
+    fn check_resume(model: TypedModel, pulse: usize) {
+        let model = PulsedModel::new(&model.into_normalized().unwrap(), pulse).unwrap();
+        let shape = model.input_fact(0).unwrap().shape.clone();
+        let plan = Arc::new(SimplePlan::new(model).unwrap());
+        let input = |i: usize| values(&shape, i);
+
+        let mut state = SimpleState::new(plan.clone()).unwrap();
+        state.session_state.known_stream_len = Some(6 * pulse - 1);
+        for i in 0..3 {
+            state.run(tvec!(input(i))).unwrap();
+        }
+        let bytes = state.snapshot().unwrap().to_bytes().unwrap();
+        let expected: Vec<_> = (3..8).map(|i| state.run(tvec!(input(i))).unwrap()).collect();
+
+        let mut resumed = SimpleState::new(plan).unwrap();
+        resumed.restore(&StateSnapshot::from_bytes(&bytes).unwrap()).unwrap();
+        let found: Vec<_> = (3..8).map(|i| resumed.run(tvec!(input(i))).unwrap()).collect();
+        for (found, expected) in found.iter().flatten().zip(expected.iter().flatten()) {
+            let found = found.as_slice::<f32>().unwrap().iter().map(|f| f.to_bits());
+            let expected = expected.as_slice::<f32>().unwrap().iter().map(|f| f.to_bits());
+            assert!(found.eq(expected));
+        }
+    }
+
+    #[test]
+    fn resume_pad() {
+        use crate::ops::array::{Pad, PadMode};
+        let mut model = TypedModel::default();
+        let source = model.add_source("source", stream_fact(&[2])).unwrap();
+        let pad = Pad::new(vec![(1, 2), (0, 0)], PadMode::Edge);
+        let pad = model.wire_node("pad", pad, &[source]).unwrap();
+        model.set_output_outlets(&pad).unwrap();
+        check_resume(model, 3);
+    }
+
+    #[test]
+    fn resume_concat() {
+        use crate::ops::array::{NormConcat, NormConcatSlice};
+        let mut model = TypedModel::default();
+        let source = model.add_source("source", stream_fact(&[2])).unwrap();
+        let slices = tvec!(
+            NormConcatSlice::Const(values(&[2, 2], 1)),
+            NormConcatSlice::Var,
+            NormConcatSlice::Const(values(&[3, 2], 2))
+        );
+        let concat = model.wire_node("concat", NormConcat::new(0, slices), &[source]).unwrap();
+        model.set_output_outlets(&concat).unwrap();
+        check_resume(model, 4);
+    }
+
+    fn scan(body: TypedModel) -> TypedModel {
+        use crate::ops::scan::{InputMapping, OutputMapping, StateInitializer, TypedScan};
+        let mut model = TypedModel::default();
+        let x = body.input_fact(0).unwrap().shape.iter().skip(1).collect::<Vec<_>>();
+        let x = x.iter().map(|d| d.to_integer().unwrap() as usize).collect::<Vec<_>>();
+        let h = body.input_fact(1).unwrap().shape.as_finite().unwrap().to_vec();
+        let source = model.add_source("source", stream_fact(&x)).unwrap();
+        let input_mapping = vec![
+            InputMapping::Scan { slot: 0, axis: 0, chunk: 1.to_dim() },
+            InputMapping::State {
+                initializer: StateInitializer::Value(values(&h, 3).into_arc_tensor()),
+            },
+        ];
+        let output_mapping = vec![OutputMapping::new(Some(0), 0, 1.to_dim(), None, None, true)];
+        let scan = TypedScan::new(body, input_mapping, output_mapping, None).unwrap();
+        let scan = model.wire_node("scan", scan, &[source]).unwrap();
+        model.set_output_outlets(&scan).unwrap();
+        model
+    }
+
+    #[test]
+    fn resume_generic_scan() {
+        let mut body = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), [1, 2].as_ref()).unwrap();
+        let x = body.add_source("x", fact.clone()).unwrap();
+        let h = body.add_source("h", fact).unwrap();
+        let sum = body.wire_node("sum", crate::ops::math::add::bin(), &[x, h]).unwrap();
+        body.set_output_outlets(&sum).unwrap();
+        check_resume(scan(body), 2);
+    }
+
+    #[test]
+    fn resume_fused_recurrent() {
+        use crate::ops::array::{AddDim, RmDim};
+        use crate::ops::rec::{CellKind, RecurrentCell};
+        use crate::ops::scan::TypedScan;
+        let cell = RecurrentCell::new(
+            CellKind::Rnn,
+            &values(&[3, 2], 4),
+            &values(&[3, 3], 5),
+            Some(&values(&[6], 6)),
+            tvec!(crate::ops::math::tanh().0),
+        )
+        .unwrap();
+        let mut body = TypedModel::default();
+        let x = body
+            .add_source("x", TypedFact::dt_shape(f32::datum_type(), [1, 1, 2].as_ref()).unwrap())
+            .unwrap();
+        let h = body
+            .add_source("h", TypedFact::dt_shape(f32::datum_type(), [1, 1, 3].as_ref()).unwrap())
+            .unwrap();
+        let x = body.wire_node("rm-x", RmDim::new(0), &[x]).unwrap()[0];
+        let h = body.wire_node("rm-h", RmDim::new(0), &[h]).unwrap()[0];
+        let h = body.wire_node("cell", cell, &[x, h]).unwrap()[0];
+        let h = body.wire_node("add-h", AddDim::new(0), &[h]).unwrap();
+        body.set_output_outlets(&h).unwrap();
+        let model = scan(body);
+        let scan = model.node(model.output_outlets().unwrap()[0].node).op_as::<TypedScan>();
+        assert!(scan.unwrap().to_fused_recurrent().unwrap().is_some());
+        check_resume(model, 2);
+    }
+}
@@ -0,0 +1,89 @@
+use proptest::proptest;
+use tract_core::dimfact;
+use tract_core::internal::*;
+use tract_core::ndarray::*;
+use tract_core::ops::array::{Pad, PadMode};
+use tract_core::ops::nn::{CumReduce, Reduce, Reducer};
+use tract_core::shapefact;
+
+use super::*;
+
+fn input(len: usize) -> ArrayD<f32> {
+    Array2::from_shape_fn((len, 2), |(i, j)| ((i * 7 + j * 3) % 5) as f32 - 2.0).into_dyn()
+}
+
+fn cum_model(reducer: Reducer, exclusive: bool, pad: usize) -> InferenceModel {
+    let mut model = InferenceModel::default();
+    let mut wire = model
+        .add_source("a", InferenceFact::dt_shape(f32::datum_type(), shapefact!(S, 2)))
+        .unwrap();
+    if pad > 0 {
+        let mode = PadMode::Constant(Arc::new(Tensor::from(1f32)));
+        wire = model.wire_node("pad", Pad::new(vec![(pad, 0), (0, 0)], mode), &[wire]).unwrap()[0];
+    }
+    model.wire_node("cum", CumReduce::new(0, reducer, exclusive, false), &[wire]).unwrap();
+    model.auto_outputs().unwrap();
+    model
+}
+
+proptest! {
+    #[test]
+    fn proptest_cum_sum(pulse in 1usize..4, len in 0usize..10, exclusive in any::<bool>()) {
+        let model = cum_model(Reducer::Sum, exclusive, 0);
+        proptest_regular_against_pulse(model, pulse, input(len), 0)?;
+    }
+
+    #[test]
+    fn proptest_cum_max(pulse in 1usize..4, len in 1usize..10) {
+        let model = cum_model(Reducer::Max, false, 0);
+        proptest_regular_against_pulse(model, pulse, input(len), 0)?;
+    }
+
+    #[test]
+    fn proptest_pad_plus_cum_mean(pulse in 1usize..4, len in 0usize..10, pad in 1usize..4) {
+        let model = cum_model(Reducer::Mean, false, pad);
+        proptest_regular_against_pulse(model, pulse, input(len), 0)?;
+    }
+}
+
+#[test]
+fn test_running_mean_normalization() {
+    let mut model = InferenceModel::default();
+    let a = model
+        .add_source("a", InferenceFact::dt_shape(f32::datum_type(), shapefact!(S, 2)))
+        .unwrap();
+    let mean =
+        model.wire_node("mean", Reduce::new(Some(vec![0, 1]), true, Reducer::Mean), &[a]).unwrap();
+    model.wire_node("sub", tract_core::ops::math::sub::bin(), &[a, mean[0]]).unwrap();
+    model.auto_outputs().unwrap();
+
+    let typed = model.into_typed().unwrap().declutter().unwrap();
+    let running = tract_core::pulse::causal::running_time_pooling(&typed).unwrap();
+    PulsedModel::new(&running.clone().into_normalized().unwrap(), 2).unwrap();
+
+    let input = input(5);
+    let frames: Vec<f32> = input.outer_iter().map(|f| f.mean().unwrap()).collect();
+    let expected = Array2::from_shape_fn((5, 2), |(i, j)| {
+        input[[i, j]] - frames[0..=i].iter().sum::<f32>() / (i + 1) as f32
+    });
+    let outputs = SimplePlan::new(&running).unwrap().run(tvec!(input.into_tensor())).unwrap();
+    outputs[0].close_enough(&expected.into_tensor(), true).unwrap();
+}
+
+#[test]
+fn test_running_pooling_skips_removed_stream_axis() {
+    let mut model = InferenceModel::default();
+    let a = model
+        .add_source("a", InferenceFact::dt_shape(f32::datum_type(), shapefact!(S, 2)))
+        .unwrap();
+    let mean =
+        model.wire_node("mean", Reduce::new(Some(vec![0]), false, Reducer::Mean), &[a]).unwrap();
+    model.wire_node("neg", tract_core::ops::math::neg(), &mean).unwrap();
+    model.auto_outputs().unwrap();
+
+    let typed = model.into_typed().unwrap().declutter().unwrap();
+    let running = tract_core::pulse::causal::running_time_pooling(&typed).unwrap();
+    assert!(running.nodes().iter().all(|n| !n.op_is::<CumReduce>()));
+    assert_eq!(running.output_fact(0).unwrap().shape, typed.output_fact(0).unwrap().shape);
+    assert!(PulsedModel::new(&running.into_normalized().unwrap(), 2).is_err());
+}
@@ -0,0 +1,84 @@
+use proptest::proptest;
+use tract_core::dimfact;
+use tract_core::internal::*;
+use tract_core::ndarray::*;
+use tract_core::ops::array;
+use tract_core::shapefact;
+
+use super::*;
+
+fn input(len: usize, width: usize) -> ArrayD<f32> {
+    Array2::from_shape_fn((len, width), |(i, j)| (i * width + j) as f32).into_dyn()
+}
+
+fn model_on(shape: ShapeFact) -> (InferenceModel, OutletId) {
+    let mut model = InferenceModel::default();
+    let a = model.add_source("a", InferenceFact::dt_shape(f32::datum_type(), shape)).unwrap();
+    (model, a)
+}
+
+proptest! {
+    #[test]
+    fn proptest_reshape(pulse in 1usize..4, len in 0usize..10) {
+        let (mut model, a) = model_on(shapefact!(S, 6));
+        let shape = model.add_const("shape", tensor1(&[0i64, 2, 3])).unwrap();
+        let reshape = model.wire_node("reshape", array::Reshape::new(), &[a, shape]).unwrap();
+        model.set_output_outlets(&reshape)?;
+        proptest_regular_against_pulse(model, pulse, input(len, 6), 0)?;
+    }
+
+    #[test]
+    fn proptest_permute_axes(pulse in 1usize..4, len in 0usize..10) {
+        let (mut model, a) = model_on(shapefact!(2, S, 3));
+        let permute =
+            model.wire_node("permute", array::PermuteAxes::new(Some(vec![2, 0, 1])), &[a]).unwrap();
+        model.set_output_outlets(&permute)?;
+        let input = Array3::from_shape_fn((2, len, 3), |(i, j, k)| (i * 100 + j * 3 + k) as f32);
+        proptest_regular_against_pulse(model, pulse, input.into_dyn(), 1)?;
+    }
+
+    #[test]
+    fn proptest_tile(pulse in 1usize..4, len in 0usize..10) {
+        let (mut model, a) = model_on(shapefact!(S, 2));
+        let multipliers = model.add_const("multipliers", tensor1(&[1i64, 3])).unwrap();
+        let tile = model.wire_node("tile", array::Tile::new(), &[a, multipliers]).unwrap();
+        model.set_output_outlets(&tile)?;
+        proptest_regular_against_pulse(model, pulse, input(len, 2), 0)?;
+    }
+
+    #[test]
+    fn proptest_gather(pulse in 1usize..4, len in 0usize..10) {
+        let (mut model, a) = model_on(shapefact!(S, 4));
+        let indices = model.add_const("indices", tensor2(&[[3i64, 0], [0, 2]])).unwrap();
+        let gather = model.wire_node("gather", array::Gather::new(1), &[a, indices]).unwrap();
+        model.set_output_outlets(&gather)?;
+        proptest_regular_against_pulse(model, pulse, input(len, 4), 0)?;
+    }
+
+    #[test]
+    fn proptest_split(pulse in 1usize..4, len in 0usize..10) {
+        let (mut model, a) = model_on(shapefact!(S, 5));
+        let split =
+            model.wire_node("split", array::Split::new(1, 2, Some(vec![2, 3])), &[a]).unwrap();
+        model.set_output_outlets(&split[1..])?;
+        proptest_regular_against_pulse(model, pulse, input(len, 5), 0)?;
+    }
+
+    #[test]
+    fn proptest_strided_slice(pulse in 1usize..4, len in 0usize..10) {
+        let (mut model, a) = model_on(shapefact!(S, 7));
+        let begin = model.add_const("begin", tensor1(&[1i32])).unwrap();
+        let end = model.add_const("end", tensor1(&[7i32])).unwrap();
+        let axes = model.add_const("axes", tensor1(&[1i32])).unwrap();
+        let steps = model.add_const("steps", tensor1(&[2i32])).unwrap();
+        let slice = model
+            .wire_node(
+                "slice",
+                array::StridedSlice::onnx10(Some(3), Some(4)),
+                &[a, begin, end, axes, steps],
+            )
+            .unwrap();
+        model.set_output_outlets(&slice)?;
+        proptest_regular_against_pulse(model, pulse, input(len, 7), 0)?;
+    }
+}
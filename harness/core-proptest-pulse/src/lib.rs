@@ -10,7 +10,9 @@ use tract_core::ndarray::*;
 use tract_core::shapefact;
 
 mod conv_plus_conv;
+mod cum_reduce;
 mod delay_plus_pool;
+mod layout;
 mod pad_plus_conv;
 
 #[allow(dead_code)]
@@ -24,6 +26,7 @@ fn proptest_regular_against_pulse(
     input_array: ArrayD<f32>,
     axis: usize,
 ) -> TestCaseResult {
+    use tract_core::pulse::session::PulsedSession;
     setup_test_logger();
     let mut ref_model = model.clone();
     ref_model.set_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), input_array.shape()))?;
@@ -33,59 +36,40 @@ fn proptest_regular_against_pulse(
 
     let model = model.into_normalized().unwrap();
     let pulsed = PulsedModel::new(&model, pulse).unwrap();
-    let output_fact = pulsed.output_fact(0).unwrap().clone();
-
-    let output_stream_axis = output_fact.axis;
-    let delay = output_fact.delay;
-    let mut initial_output_shape = output_fact.shape.clone();
-    initial_output_shape[output_stream_axis] = 0;
-
-    let pulsed_plan = SimplePlan::new(pulsed).unwrap();
-    let mut state = SimpleState::new(&pulsed_plan).unwrap();
-
-    let mut got: ArrayD<f32> = ArrayD::zeros(&*initial_output_shape);
-    let mut output_len = None;
-
-    let mut written = 0;
-    loop {
-        let to_write_in_chunk = pulse.min(input_array.shape()[axis].saturating_sub(written));
-        let mut chunk: ArrayD<f32> = input_array
-            .slice_axis(Axis(axis), (written..written + to_write_in_chunk).into())
-            .to_owned();
-        written += to_write_in_chunk;
-        if to_write_in_chunk < pulse {
-            let mut filler_shape = input_array.shape().to_vec();
-            filler_shape[axis] = pulse - to_write_in_chunk;
-            chunk = stack(
-                Axis(axis),
-                &[chunk.view(), ArrayD::from_elem(filler_shape, std::f32::NAN).view()],
-            )
-            .unwrap();
-            output_len = output_fact.dim.eval(written as _);
-            state.session_state.known_stream_len = Some(written)
-        }
-        let mut outputs = state.run(tvec!(Tensor::from(chunk.to_owned()).into())).unwrap();
-        got = stack(
-            Axis(output_stream_axis),
-            &[got.view(), outputs.remove(0).to_array_view::<f32>().unwrap()],
-        )
-        .unwrap();
-        if let Some(output_len) = output_len {
-            if got.shape()[output_stream_axis] >= output_len as usize + delay {
+    let output_stream_axis = pulsed.output_fact(0).unwrap().axis;
+
+    // chunks of one pulse, then chunks of varying lengths, then chunks of
+    // several pulses computed in one run
+    let mut sessions = vec![
+        (PulsedSession::new(pulsed.clone()).unwrap(), pulse + 1),
+        (PulsedSession::new(pulsed.clone()).unwrap(), 3),
+    ];
+    if pulsed.check_variable_pulse().is_ok() {
+        let session = PulsedSession::new_with_variable_pulse(pulsed.clone()).unwrap();
+        sessions.push((session, 3 * pulse + 1));
+    }
+    for (mut session, max_chunk) in sessions {
+        let mut chunks = vec![];
+        let mut pushed = 0;
+        for len in (1..).map(|i| i % max_chunk) {
+            let len = len.min(input_array.shape()[axis] - pushed);
+            let chunk = input_array.slice_axis(Axis(axis), (pushed..pushed + len).into());
+            chunks.push(session.push(tvec!(chunk.to_owned().into_tensor())).unwrap().remove(0));
+            pushed += len;
+            if pushed == input_array.shape()[axis] {
                 break;
             }
         }
+        chunks.push(session.finish().unwrap().remove(0));
+        let chunks: Vec<_> = chunks.iter().map(|c| c.to_array_view::<f32>().unwrap()).collect();
+        let session_output = stack(Axis(output_stream_axis), &*chunks).unwrap().into_tensor();
+        prop_assert!(
+            session_output.close_enough(&outputs[0], true).is_ok(),
+            "{:?} == {:?}",
+            session_output,
+            outputs[0]
+        );
     }
-
-    let pulsed_output = got
-        .slice_axis(
-            Axis(output_stream_axis),
-            (output_fact.delay..output_fact.delay + output_len.unwrap() as usize).into(),
-        )
-        .to_owned()
-        .into_tensor();
-
-    prop_assert!(&pulsed_output.close_enough(&*outputs[0], true).is_ok(), "{:?} == {:?}", pulsed_output, outputs[0]);
     Ok(())
 }
 
@@ -212,3 +196,18 @@ fn test_pad_before_2() {
     let input = arr1(&[1.0, 2.0]);
     proptest_regular_against_pulse(model, 2, input.into_dyn(), 0).unwrap();
 }
+
+#[test]
+fn test_concat_const_around_stream() {
+    use tract_core::ops::array::Concat;
+    let mut model = InferenceModel::default();
+    let a =
+        model.add_source("a", InferenceFact::dt_shape(f32::datum_type(), shapefact!(S))).unwrap();
+    let pre = model.add_const("pre", tensor1(&[-1f32, -2.0])).unwrap();
+    let post = model.add_const("post", tensor1(&[-3f32])).unwrap();
+    model.wire_node("concat", Concat::new(0), &[pre, a, post]).unwrap();
+    model.auto_outputs().unwrap();
+
+    let input = arr1(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    proptest_regular_against_pulse(model, 2, input.into_dyn(), 0).unwrap();
+}
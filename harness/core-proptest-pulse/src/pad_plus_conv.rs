@@ -26,8 +26,8 @@ impl Arbitrary for PadPlusConvProblem {
     type Strategy = BoxedStrategy<Self>;
 
     fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<PadPlusConvProblem> {
-        (1usize..3, vec(1usize..3), 1usize..3, 0usize..15, 0usize..15, 1usize..3, bool::ANY)
-            .prop_flat_map(|(stride, ker, dil, pad_before, pad_after, pulse_factor, edge)| {
+        (1usize..3, vec(1usize..3), 1usize..3, 0usize..15, 0usize..15, 1usize..3, 0usize..3)
+            .prop_flat_map(|(stride, ker, dil, pad_before, pad_after, pulse_factor, mode)| {
                 let min_input = (ker.len() * dil).max(pulse_factor * stride);
                 (
                     Just(stride),
@@ -37,12 +37,14 @@ impl Arbitrary for PadPlusConvProblem {
                     Just(pad_after),
                     Just(stride * pulse_factor),
                     vec(min_input..3 * min_input),
-                    Just(edge),
+                    Just(mode),
                 )
             })
-            .prop_map(|(stride, ker, dilation, pad_before, pad_after, pulse, input, edge)| {
-                let pad_mode = if edge && pad_before < pulse {
+            .prop_map(|(stride, ker, dilation, pad_before, pad_after, pulse, input, mode)| {
+                let pad_mode = if mode == 1 && pad_before < pulse {
                     PadMode::Edge
+                } else if mode == 2 && 2 * pad_before < pulse && pad_after < input.len() {
+                    PadMode::Reflect
                 } else {
                     PadMode::Constant(Tensor::from(9999f32).into())
                 };
@@ -241,3 +243,51 @@ fn conv_kaldi_librispeech() {
     .run()
     .unwrap()
 }
+
+#[test]
+fn reflect_1() {
+    PadPlusConvProblem {
+        pad_before: 0,
+        pad_after: 2,
+        pad_mode: PadMode::Reflect,
+        stride: 1,
+        dilation: 1,
+        pulse: 1,
+        ker: arr3(&[[[1.0f32]]]),
+        input: arr3(&[[[1.0f32, 2.0, 3.0]]]),
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn reflect_2() {
+    PadPlusConvProblem {
+        pad_before: 1,
+        pad_after: 3,
+        pad_mode: PadMode::Reflect,
+        stride: 1,
+        dilation: 1,
+        pulse: 3,
+        ker: arr3(&[[[1.0f32, 2.0]]]),
+        input: arr3(&[[[1.0f32, 2.0, 3.0, 4.0, 5.0]]]),
+    }
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn reflect_3() {
+    PadPlusConvProblem {
+        pad_before: 2,
+        pad_after: 4,
+        pad_mode: PadMode::Reflect,
+        stride: 2,
+        dilation: 1,
+        pulse: 6,
+        ker: arr3(&[[[1.0f32, 0.0, 0.0]]]),
+        input: Array3::from_shape_vec((1, 1, 7), (1..=7).map(|i| i as f32).collect()).unwrap(),
+    }
+    .run()
+    .unwrap()
+}
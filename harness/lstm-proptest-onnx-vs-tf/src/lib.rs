@@ -4,6 +4,7 @@ use proptest::prelude::*;
 
 use tract_core::internal::*;
 use tract_core::ndarray::*;
+use tract_core::{dimfact, shapefact};
 
 #[derive(Clone, Debug)]
 pub struct LstmProblem {
@@ -19,6 +20,29 @@ pub struct LstmProblem {
 
 impl LstmProblem {
     pub fn onnx_model(&self) -> TractResult<TypedModel> {
+        let x_fact = InferenceFact::dt_shape(self.x.datum_type(), self.x.shape());
+        let mut model = self.onnx_inference_model(x_fact, 1)?;
+        model.analyse(false)?;
+        Ok(model.into_typed()?)
+    }
+
+    fn onnx_inference_model(
+        &self,
+        x_fact: InferenceFact,
+        directions: usize,
+    ) -> TractResult<InferenceModel> {
+        self.onnx_inference_model_with(x_fact, directions, false, false)
+    }
+
+    /// Feeding W as a model input instead of a constant keeps the LSTM off
+    /// the fused path. With `last_states`, the model also outputs Y_h and Y_c.
+    fn onnx_inference_model_with(
+        &self,
+        x_fact: InferenceFact,
+        directions: usize,
+        w_as_input: bool,
+        last_states: bool,
+    ) -> TractResult<InferenceModel> {
         let mut model = InferenceModel::default();
         let s = self.cell_size;
         let mut w_iofc = Array2::zeros((s, 4 * s));
@@ -40,23 +64,41 @@ impl LstmProblem {
         let w_iofc = w_iofc.t().into_shape((1, 4 * s, s))?.to_owned();
         let r_iofc = r_iofc.t().into_shape((1, 4 * s, s))?.to_owned();
         let b_iofc = b_iofc.into_shape((1, 8 * s))?;
+        let h0 = self.h0.clone().insert_axis(Axis(0));
+        let c0 = self.c0.clone().insert_axis(Axis(0));
+        // the backward direction gets its own weights and initial states
+        fn directions_t<D: RemoveAxis>(a: Array<f32, D>, n: usize) -> TractResult<Array<f32, D>> {
+            let backward = a.mapv(|v| 0.1 - 0.5 * v);
+            Ok(stack(Axis(0), &[a.view(), backward.view()][..n])?)
+        }
+        let w_iofc = directions_t(w_iofc, directions)?;
+        let r_iofc = directions_t(r_iofc, directions)?;
+        let b_iofc = directions_t(b_iofc, directions)?;
+        let h0 = directions_t(h0, directions)?;
+        let c0 = directions_t(c0, directions)?;
 
-        let x =
-            model.add_source("x", InferenceFact::dt_shape(self.x.datum_type(), self.x.shape()))?;
+        let x = model.add_source("x", x_fact)?;
         let mut op = tract_onnx::ops::rec::lstm::LSTM::default();
         op.optional_y_output = Some(0);
         op.optional_bias_input = Some(3);
         op.optional_initial_h_input = Some(4);
         op.optional_initial_c_input = Some(5);
-        let w = model.add_const("w", w_iofc)?;
+        if last_states {
+            op.optional_y_h_output = Some(1);
+            op.optional_y_c_output = Some(2);
+        }
+        let w = if w_as_input {
+            model.add_source("w", InferenceFact::dt_shape(f32::datum_type(), w_iofc.shape()))?
+        } else {
+            model.add_const("w", w_iofc)?
+        };
         let r = model.add_const("r", r_iofc)?;
         let b = model.add_const("b", b_iofc)?;
-        let h0 = model.add_const("h0", self.h0.clone().insert_axis(Axis(0)))?;
-        let c0 = model.add_const("c0", self.c0.clone().insert_axis(Axis(0)))?;
+        let h0 = model.add_const("h0", h0)?;
+        let c0 = model.add_const("c0", c0)?;
         let lstm = model.wire_node("lstm", op, &[x, w, r, b, h0, c0]).unwrap();
         model.set_output_outlets(&lstm).unwrap();
-        model.analyse(false)?;
-        Ok(model.into_typed()?)
+        Ok(model)
     }
 
     pub fn tf_model(&self) -> TractResult<TypedModel> {
@@ -182,6 +224,111 @@ impl LstmProblem {
         Ok(y.into_arc_tensor())
     }
 
+    /// Y of the LSTM, in ONNX layout: [seq_length, num_directions, batch_size,
+    /// hidden_size]. Runs the fused cell, or the generic Scan if `fused` is
+    /// false.
+    pub fn onnx_y(&self, fused: bool) -> TractResult<Arc<Tensor>> {
+        let x_fact = InferenceFact::dt_shape(self.x.datum_type(), self.x.shape());
+        let with_const_w = self.onnx_inference_model(x_fact.clone(), 1)?;
+        let w = with_const_w.outlet_fact(OutletId::new(with_const_w.node_by_name("w")?.id, 0))?;
+        let w = w.value.concretize().ok_or("w should be a constant")?;
+        let model = self.onnx_inference_model_with(x_fact, 1, !fused, false)?.into_optimized()?;
+        if model.nodes().iter().any(|n| n.op_is::<tract_core::ops::rec::FusedRecurrent>()) != fused
+        {
+            return Err(format!("Expected fused={} LSTM", fused).into());
+        }
+        let mut inputs = tvec!(self.x.clone().into_tensor());
+        if !fused {
+            inputs.push(w.into_tensor());
+        }
+        let mut state = SimpleState::new(SimplePlan::new(model)?)?;
+        Ok(state.run(inputs)?.remove(0))
+    }
+
+    pub fn onnx_pulsed_run(&self, pulse: usize) -> TractResult<Arc<Tensor>> {
+        let x_fact = InferenceFact::dt_shape(
+            self.x.datum_type(),
+            shapefact!(S, (self.batch_size), (self.cell_size)),
+        );
+        let model = self.onnx_inference_model(x_fact, 1)?.into_typed()?.declutter()?;
+        let pulsed = PulsedModel::new(&model.into_normalized()?, pulse)?;
+        let fact = pulsed.output_fact(0)?.clone();
+        let optimized = pulsed.into_typed()?.into_optimized()?;
+        if !optimized.nodes().iter().any(|n| n.op_is::<tract_core::ops::rec::FusedRecurrent>()) {
+            return Err("Expected the LSTM to run as a FusedRecurrent".into());
+        }
+        let mut state = SimpleState::new(SimplePlan::new(optimized)?)?;
+        let x = self.x.to_array_view::<f32>()?;
+        let mut chunks = vec![];
+        for start in (0..self.length).step_by(pulse) {
+            let mut chunk = Array3::<f32>::zeros((pulse, self.batch_size, self.cell_size));
+            let end = (start + pulse).min(self.length);
+            chunk
+                .slice_axis_mut(Axis(0), (0..end - start).into())
+                .assign(&x.slice_axis(Axis(0), (start..end).into()));
+            let y = state.run(tvec!(chunk.into_tensor()))?.remove(0);
+            chunks.push(y.into_tensor().into_array::<f32>()?);
+        }
+        let views = chunks.iter().map(|c| c.view()).collect::<Vec<_>>();
+        let y = stack(Axis(fact.axis), &views)?;
+        let y = y.slice_axis(Axis(fact.axis), (..self.length).into()).to_owned();
+        let y = y.into_shape((self.length, self.batch_size, self.cell_size)).unwrap();
+        Ok(y.into_arc_tensor())
+    }
+
+    /// Y, Y_h and Y_c of a bidirectional LSTM, as computed by the ONNX
+    /// operator itself.
+    pub fn onnx_bidi_reference_run(&self) -> TractResult<TVec<Arc<Tensor>>> {
+        let x_fact = InferenceFact::dt_shape(self.x.datum_type(), self.x.shape());
+        let model = self.onnx_inference_model_with(x_fact, 2, false, true)?;
+        let mut state = SimpleState::new(SimplePlan::new(model)?)?;
+        state.run(tvec!(self.x.clone().into_tensor()))
+    }
+
+    /// Y, Y_h and Y_c of a bidirectional LSTM, translated to core operators,
+    /// with an optional (chunk, right context) latency control.
+    pub fn onnx_bidi_run(
+        &self,
+        latency_control: Option<(usize, usize)>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let x_fact = InferenceFact::dt_shape(self.x.datum_type(), self.x.shape());
+        let mut model = self.onnx_inference_model_with(x_fact, 2, false, true)?.into_typed()?;
+        if !model.nodes().iter().any(|n| n.op_is::<tract_core::ops::rec::ReverseRecurrent>()) {
+            return Err("Expected the backward LSTM to run as a ReverseRecurrent".into());
+        }
+        if let Some((chunk, right_context)) = latency_control {
+            model = tract_core::pulse::bidi::latency_controlled(&model, chunk, right_context)?;
+        }
+        let mut state = SimpleState::new(SimplePlan::new(model.into_optimized()?)?)?;
+        state.run(tvec!(self.x.clone().into_tensor()))
+    }
+
+    pub fn onnx_bidi_pulsed_run(
+        &self,
+        pulse: usize,
+        chunk: usize,
+        right_context: usize,
+    ) -> TractResult<Arc<Tensor>> {
+        let x_fact = InferenceFact::dt_shape(
+            self.x.datum_type(),
+            shapefact!(S, (self.batch_size), (self.cell_size)),
+        );
+        let model = self.onnx_inference_model(x_fact, 2)?.into_typed()?.declutter()?;
+        let model = tract_core::pulse::bidi::latency_controlled(&model, chunk, right_context)?;
+        let pulsed = PulsedModel::new(&model.into_normalized()?, pulse)?;
+        let mut session = tract_core::pulse::session::PulsedSession::new(pulsed)?;
+        let x = self.x.to_array_view::<f32>()?;
+        let mut chunks = vec![];
+        for start in (0..self.length).step_by(pulse) {
+            let end = (start + pulse).min(self.length);
+            let input = x.slice_axis(Axis(0), (start..end).into()).to_owned().into_tensor();
+            chunks.push(session.push(tvec!(input))?.remove(0).into_array::<f32>()?);
+        }
+        chunks.push(session.finish()?.remove(0).into_array::<f32>()?);
+        let views = chunks.iter().map(|c| c.view()).collect::<Vec<_>>();
+        Ok(stack(Axis(0), &views)?.into_arc_tensor())
+    }
+
     pub fn tf_run(&self) -> TractResult<Arc<Tensor>> {
         let model = self.tf_model()?;
         let init_id = model.node_by_name("init")?.id;
@@ -238,6 +385,70 @@ proptest::proptest! {
         let t = pb.tf_run().unwrap();
         prop_assert!(o.close_enough(&t, true).is_ok(), "\nonnx:{:?}\n tf :{:?}\n", o, t);
     }
+
+    #[test]
+    fn test_pulsed(pb in strat(), pulse in 1usize..3) {
+        let o = pb.onnx_run().unwrap();
+        let p = pb.onnx_pulsed_run(pulse).unwrap();
+        prop_assert!(o.close_enough(&p, true).is_ok(), "\nonnx:{:?}\npulsed:{:?}\n", o, p);
+    }
+
+    #[test]
+    fn test_bidi(pb in strat()) {
+        let r = pb.onnx_bidi_reference_run().unwrap();
+        let o = pb.onnx_bidi_run(None).unwrap();
+        prop_assert_eq!((r.len(), o.len()), (3, 3));
+        for (r, o) in r.iter().zip(o.iter()) {
+            prop_assert!(r.close_enough(o, true).is_ok(), "\nreference:{:?}\nonnx:{:?}\n", r, o);
+        }
+    }
+
+    #[test]
+    fn test_bidi_pulsed(pb in strat(), pulse in 1usize..3, chunk in 1usize..4, right_context in 0usize..3) {
+        let o = pb.onnx_bidi_run(Some((chunk, right_context))).unwrap().remove(0);
+        let p = pb.onnx_bidi_pulsed_run(pulse, chunk, right_context).unwrap();
+        prop_assert!(o.close_enough(&p, true).is_ok(), "\nonnx:{:?}\npulsed:{:?}\n", o, p);
+    }
+}
+
+#[test]
+fn test_bidi_latency_control_over_whole_sequence() {
+    let pb = LstmProblem {
+        length: 3,
+        batch_size: 1,
+        cell_size: 1,
+        x: rctensor3(&[[[1f32]], [[-2.0]], [[3.0]]]),
+        w_xh_icfo: arr2(&[[1.0f32, -1.0, 2.0, 1.0], [0.5, 1.0, -1.0, 0.0]]),
+        b_icfo: arr1(&[0.0f32, 1.0, 0.0, 0.0]),
+        h0: arr2(&[[0.0f32]]),
+        c0: arr2(&[[0.0f32]]),
+    };
+    let r = pb.onnx_bidi_reference_run().unwrap();
+    let o = pb.onnx_bidi_run(Some((3, 0))).unwrap();
+    for (r, o) in r.iter().zip(o.iter()) {
+        r.close_enough(o, true).unwrap();
+    }
+    let p = pb.onnx_bidi_pulsed_run(1, 3, 0).unwrap();
+    r[0].close_enough(&p, true).unwrap();
+}
+
+#[test]
+fn test_fused_y_against_scan() {
+    let pb = LstmProblem {
+        length: 3,
+        batch_size: 2,
+        cell_size: 1,
+        x: rctensor3(&[[[1f32], [-1.0]], [[2.0], [0.5]], [[-3.0], [1.5]]]),
+        w_xh_icfo: arr2(&[[0.5f32, -1.0, 0.25, 1.0], [1.0, 0.0, -0.5, 0.5]]),
+        b_icfo: arr1(&[0.0f32, 0.1, 0.0, -0.1]),
+        h0: arr2(&[[0.0f32], [0.5]]),
+        c0: arr2(&[[0.0f32], [-0.5]]),
+    };
+    let fused = pb.onnx_y(true).unwrap();
+    let scan = pb.onnx_y(false).unwrap();
+    assert_eq!(fused.shape(), &[3, 1, 2, 1]);
+    assert_eq!(scan.shape(), &[3, 1, 2, 1]);
+    fused.close_enough(&scan, true).unwrap();
 }
 
 #[test]
@@ -7,12 +7,14 @@ pub struct KaldiProtoModel {
     pub config_lines: ConfigLines,
     pub components: HashMap<String, Component>,
     pub adjust_final_offset: isize,
+    /// Number of frames an input row is held for by the ReplaceIndex
+    /// descriptors reading it, like the period an ivector is updated at.
+    pub input_periods: HashMap<String, usize>,
 }
 
 #[derive(Clone, Debug)]
 pub struct ConfigLines {
-    pub input_name: String,
-    pub input_dim: usize,
+    pub input_nodes: Vec<(String, usize)>,
     pub nodes: Vec<(String, NodeLine)>,
     pub outputs: Vec<OutputLine>,
 }
@@ -32,25 +34,50 @@ pub struct OutputLine {
 #[derive(Clone, Debug, PartialEq)]
 pub enum GeneralDescriptor {
     Append(Vec<GeneralDescriptor>),
+    Const(f32, usize),
+    Failover(Box<GeneralDescriptor>, Box<GeneralDescriptor>),
     IfDefined(Box<GeneralDescriptor>),
     Name(String),
     Offset(Box<GeneralDescriptor>, isize),
+    ReplaceIndex(Box<GeneralDescriptor>, isize),
+    Round(Box<GeneralDescriptor>, usize),
+    Scale(f32, Box<GeneralDescriptor>),
+    Sum(Box<GeneralDescriptor>, Box<GeneralDescriptor>),
+    Switch(Vec<GeneralDescriptor>),
 }
 
 impl GeneralDescriptor {
     pub fn inputs(&self) -> TVec<&str> {
-        match self {
-            GeneralDescriptor::Append(ref gds) => gds.iter().fold(tvec!(), |mut acc, gd| {
+        use GeneralDescriptor::*;
+        fn union<'a>(gds: &[&'a GeneralDescriptor]) -> TVec<&'a str> {
+            gds.iter().fold(tvec!(), |mut acc, gd| {
                 gd.inputs().iter().for_each(|i| {
                     if !acc.contains(i) {
                         acc.push(i)
                     }
                 });
                 acc
-            }),
-            GeneralDescriptor::IfDefined(ref gd) => gd.inputs(),
-            GeneralDescriptor::Name(ref s) => tvec!(&**s),
-            GeneralDescriptor::Offset(ref gd, _) => gd.inputs(),
+            })
+        }
+        match self {
+            Append(ref gds) | Switch(ref gds) => union(&*gds.iter().collect::<Vec<_>>()),
+            Const(..) => tvec!(),
+            Failover(ref a, ref b) | Sum(ref a, ref b) => union(&[a, b]),
+            IfDefined(ref gd) => gd.inputs(),
+            Name(ref s) => tvec!(&**s),
+            Offset(ref gd, _) | ReplaceIndex(ref gd, _) | Round(ref gd, _) | Scale(_, ref gd) => {
+                gd.inputs()
+            }
+        }
+    }
+
+    /// Splits a chain of Offset into the inner descriptor and the total offset.
+    fn strip_offset(&self) -> (&GeneralDescriptor, isize) {
+        if let GeneralDescriptor::Offset(ref gd, offset) = self {
+            let (inner, inner_offset) = gd.strip_offset();
+            (inner, inner_offset + offset)
+        } else {
+            (self, 0)
         }
     }
 
@@ -96,19 +123,103 @@ impl GeneralDescriptor {
                     tract_core::ops::array::Concat::new(1),
                     tvec!(InferenceFact::default()),
                 )?;
-                model.add_edge(OutletId::new(id, 0), inlet)?;
-                for (ix, appendee) in appendees.iter().enumerate() {
-                    let name = format!("{}-{}", name, ix);
-                    appendee.wire(
-                        InletId::new(id, ix),
-                        &*name,
-                        model,
-                        deferred,
-                        adjust_final_offset,
-                    )?;
-                }
+                deferred.insert(inlet, name.to_string());
+                let appendees: Vec<&GeneralDescriptor> = appendees.iter().collect();
+                return Self::wire_operands(
+                    &appendees,
+                    id,
+                    &*name,
+                    model,
+                    deferred,
+                    adjust_final_offset,
+                );
+            }
+            &Sum(a, b) => {
+                let name = format!("{}-Sum", name);
+                let id = model.add_node(
+                    &*name,
+                    tract_core::ops::math::add::bin(),
+                    tvec!(InferenceFact::default()),
+                )?;
+                deferred.insert(inlet, name.to_string());
+                return Self::wire_operands(
+                    &[a, b],
+                    id,
+                    &*name,
+                    model,
+                    deferred,
+                    adjust_final_offset,
+                );
+            }
+            &Switch(operands) => {
+                let name = format!("{}-Switch", name);
+                let id = model.add_node(
+                    &*name,
+                    crate::ops::descriptors::Switch::new(operands.len()),
+                    tvec!(InferenceFact::default()),
+                )?;
+                deferred.insert(inlet, name.to_string());
+                let operands: Vec<&GeneralDescriptor> = operands.iter().collect();
+                return Self::wire_operands(
+                    &operands,
+                    id,
+                    &*name,
+                    model,
+                    deferred,
+                    adjust_final_offset,
+                );
+            }
+            &Scale(scale, gd) => {
+                let name = format!("{}-Scale", name);
+                let factor = format!("{}-factor", name);
+                model.add_const(&*factor, tensor0(*scale))?;
+                let id = model.add_node(
+                    &*name,
+                    tract_core::ops::math::mul::bin(),
+                    tvec!(InferenceFact::default()),
+                )?;
+                deferred.insert(InletId::new(id, 0), factor);
+                deferred.insert(inlet, name.to_string());
+                return gd.wire(InletId::new(id, 1), &*name, model, deferred, adjust_final_offset);
+            }
+            &Const(value, dim) => {
+                let konst = tract_core::ndarray::Array2::from_elem((1, *dim), *value);
+                let name = format!("{}-Const", name);
+                model.add_const(&*name, konst)?;
+                deferred.insert(inlet, name);
                 return Ok(());
             }
+            &Round(gd, modulus) => {
+                let name = format!("{}-Round", name);
+                let id = model.add_node(
+                    &*name,
+                    crate::ops::descriptors::Round::new(*modulus),
+                    tvec!(InferenceFact::default()),
+                )?;
+                deferred.insert(inlet, name.to_string());
+                return gd.wire(InletId::new(id, 0), &*name, model, deferred, adjust_final_offset);
+            }
+            &ReplaceIndex(gd, frame) => {
+                if *frame < 0 {
+                    bail!("Unsupported negative index replacement in {:?}", self)
+                }
+                let name = format!("{}-ReplaceIndex", name);
+                let id = model.add_node(
+                    &*name,
+                    crate::ops::descriptors::ReplaceIndex::new(*frame as usize, None),
+                    tvec!(InferenceFact::default()),
+                )?;
+                deferred.insert(inlet, name.to_string());
+                return gd.wire(InletId::new(id, 0), &*name, model, deferred, adjust_final_offset);
+            }
+            // without a time offset, the first descriptor is defined on every
+            // frame and the fallback is never used
+            &Failover(gd, _) if gd.strip_offset().1 == 0 => {
+                return gd.wire(inlet, name, model, deferred, adjust_final_offset);
+            }
+            &Failover(..) => {
+                bail!("Unsupported Failover with an out-of-range first descriptor: {:?}", self)
+            }
             &IfDefined(ref o) => {
                 if let &Offset(ref n, ref o) = &**o {
                     if let Name(n) = &**n {
@@ -134,7 +245,7 @@ impl GeneralDescriptor {
                     tract_core::ops::array::Crop::new(0, crop as usize, 0),
                     tvec!(InferenceFact::default()),
                 )?;
-                model.add_edge(OutletId::new(id, 0), inlet)?;
+                deferred.insert(inlet, name.to_string());
                 n.wire(InletId::new(id, 0), &*name, model, deferred, adjust_final_offset)?;
                 return Ok(());
             }
@@ -142,6 +253,93 @@ impl GeneralDescriptor {
         }
         bail!("Unhandled input descriptor: {:?}", self)
     }
+
+    /// Wires the operands of a multi-input descriptor to the inputs of node
+    /// `id`. Offset operands are cropped to the frames all of them cover, and
+    /// time-invariant ones (Const, ReplaceIndex) are repeated over these
+    /// frames.
+    fn wire_operands(
+        operands: &[&GeneralDescriptor],
+        id: usize,
+        name: &str,
+        model: &mut InferenceModel,
+        deferred: &mut BTreeMap<InletId, String>,
+        adjust_final_offset: Option<isize>,
+    ) -> TractResult<()> {
+        use GeneralDescriptor::*;
+        let is_invariant = |gd: &GeneralDescriptor| match gd {
+            Const(..) | ReplaceIndex(..) => true,
+            _ => false,
+        };
+        let offsets: TVec<isize> =
+            operands.iter().filter(|gd| !is_invariant(gd)).map(|gd| gd.strip_offset().1).collect();
+        let min = offsets.iter().cloned().min().unwrap_or(0);
+        let max = offsets.iter().cloned().max().unwrap_or(0);
+        let mut reference: Option<String> = None;
+        let needs_reference = operands.iter().any(|gd| is_invariant(gd));
+        for (ix, operand) in operands.iter().enumerate() {
+            if is_invariant(operand) {
+                continue;
+            }
+            let name = format!("{}-{}", name, ix);
+            let (inner, offset) = operand.strip_offset();
+            if offset == min && offset == max && (reference.is_some() || !needs_reference) {
+                inner.wire(InletId::new(id, ix), &*name, model, deferred, adjust_final_offset)?;
+                continue;
+            }
+            let crop_name = format!("{}-Crop", name);
+            let crop = model.add_node(
+                &*crop_name,
+                tract_core::ops::array::Crop::new(
+                    0,
+                    (offset - min) as usize,
+                    (max - offset) as usize,
+                ),
+                tvec!(InferenceFact::default()),
+            )?;
+            deferred.insert(InletId::new(id, ix), crop_name.clone());
+            reference.get_or_insert(crop_name);
+            inner.wire(InletId::new(crop, 0), &*name, model, deferred, adjust_final_offset)?;
+        }
+        for (ix, operand) in operands.iter().enumerate() {
+            if !is_invariant(operand) {
+                continue;
+            }
+            let name = format!("{}-{}", name, ix);
+            let reference = reference.clone().ok_or_else(|| {
+                format!("No time-dependent operand to repeat {:?} along", operand)
+            })?;
+            let (value, frame) = match operand {
+                ReplaceIndex(gd, frame) if *frame >= 0 => (&**gd, *frame as usize),
+                ReplaceIndex(..) => {
+                    bail!("Unsupported negative index replacement in {:?}", operand)
+                }
+                Const(value, dim) => {
+                    let const_name = format!("{}-Const", name);
+                    let row = tract_core::ndarray::Array2::from_elem((1, *dim), *value);
+                    let konst = model.add_node(
+                        &*const_name,
+                        crate::ops::descriptors::ConstFrames::new(row.into_arc_tensor()),
+                        tvec!(InferenceFact::default()),
+                    )?;
+                    deferred.insert(InletId::new(id, ix), const_name.clone());
+                    deferred.insert(InletId::new(konst, 0), reference);
+                    continue;
+                }
+                _ => unreachable!(),
+            };
+            let repeat_name = format!("{}-Repeat", name);
+            let repeat = model.add_node(
+                &*repeat_name,
+                crate::ops::descriptors::ReplaceIndex::new(frame, None),
+                tvec!(InferenceFact::default()),
+            )?;
+            deferred.insert(InletId::new(id, ix), repeat_name);
+            deferred.insert(InletId::new(repeat, 1), reference);
+            value.wire(InletId::new(repeat, 0), &*name, model, deferred, adjust_final_offset)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -163,10 +361,38 @@ pub struct Component {
     pub attributes: HashMap<String, Arc<Tensor>>,
 }
 
+impl Component {
+    pub fn attribute(&self, name: &str) -> TractResult<&Arc<Tensor>> {
+        Ok(self
+            .attributes
+            .get(name)
+            .ok_or_else(|| format!("missing attribute {} in {}", name, self.klass))?)
+    }
+
+    pub fn usize_attribute(&self, name: &str) -> TractResult<usize> {
+        Ok(*self.attribute(name)?.cast_to::<i32>()?.to_scalar::<i32>()? as usize)
+    }
+
+    pub fn f32_attribute(&self, name: &str) -> TractResult<f32> {
+        Ok(*self.attribute(name)?.cast_to::<f32>()?.to_scalar::<f32>()?)
+    }
+}
+
 pub struct ParsingContext<'a> {
     pub proto_model: &'a KaldiProtoModel,
 }
 
+impl<'a> ParsingContext<'a> {
+    pub fn component_for_node(&self, name: &str) -> TractResult<&'a Component> {
+        let node = self.proto_model.config_lines.nodes.iter().find(|l| l.0 == name);
+        if let Some((_, NodeLine::Component(line))) = node {
+            Ok(&self.proto_model.components[&line.component])
+        } else {
+            bail!("Could not find component {}", name);
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct KaldiOpRegister(
     pub HashMap<String, fn(&ParsingContext, node: &str) -> TractResult<Box<dyn InferenceOp>>>,
@@ -198,13 +424,12 @@ impl Framework<KaldiProtoModel> for Kaldi {
     fn model_for_proto_model(&self, proto_model: &KaldiProtoModel) -> TractResult<InferenceModel> {
         let ctx = ParsingContext { proto_model };
         let mut model = InferenceModel::default();
-        model.add_source(
-            proto_model.config_lines.input_name.clone(),
-            InferenceFact::dt_shape(
-                f32::datum_type(),
-                shapefact!(S, (proto_model.config_lines.input_dim)),
-            ),
-        )?;
+        for (name, dim) in &proto_model.config_lines.input_nodes {
+            model.add_source(
+                name.clone(),
+                InferenceFact::dt_shape(f32::datum_type(), shapefact!(S, (*dim))),
+            )?;
+        }
         let mut inputs_to_wire: BTreeMap<InletId, String> = Default::default();
         for (name, node) in &proto_model.config_lines.nodes {
             match node {
@@ -283,6 +508,14 @@ impl Framework<KaldiProtoModel> for Kaldi {
             let src = OutletId::new(model.node_by_name(&*name)?.id, 0);
             model.add_edge(src, inlet)?;
         }
+        use crate::ops::descriptors::ReplaceIndex;
+        for id in 0..model.nodes().len() {
+            let input = model.node(id).inputs.first().map(|i| &*model.node(i.node).name);
+            let period = input.and_then(|name| proto_model.input_periods.get(name)).cloned();
+            if let Some(op) = model.node_mut(id).op_as_mut::<ReplaceIndex>() {
+                op.period = period;
+            }
+        }
         model.set_output_outlets(&*outputs)?;
         Ok(model)
     }
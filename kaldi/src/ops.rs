@@ -1,12 +1,18 @@
 use crate::model::KaldiOpRegister;
 
 pub(crate) mod affine;
+pub(crate) mod descriptors;
+mod elementwise_product;
 pub(crate) mod lstm_nonlin;
+mod maxpooling;
 pub(crate) mod memory;
 mod renorm;
+mod reshape_frames;
+mod scale_and_offset;
+mod time_height_conv;
 
 pub const AFFINE: &'static [&'static str] =
-    &["FixedAffineComponent", "NaturalGradientAffineComponent"];
+    &["FixedAffineComponent", "NaturalGradientAffineComponent", "LinearComponent", "TdnnComponent"];
 
 pub fn register_all_ops(reg: &mut KaldiOpRegister) {
     for affine in AFFINE {
@@ -15,9 +21,136 @@ pub fn register_all_ops(reg: &mut KaldiOpRegister) {
     reg.insert("BackpropTruncationComponent", |_, _| {
         Ok(Box::new(tract_core::ops::identity::Identity::default()))
     });
+    reg.insert("BatchNormComponent", scale_and_offset::batch_norm);
+    reg.insert("ElementwiseProductComponent", elementwise_product::elementwise_product);
+    reg.insert("GeneralDropoutComponent", |_, _| {
+        Ok(Box::new(tract_core::ops::identity::Identity::default()))
+    });
     reg.insert("NormalizeComponent", renorm::renorm);
     reg.insert("LstmNonlinearityComponent", lstm_nonlin::lstm_nonlin);
+    reg.insert("MaxpoolingComponent", maxpooling::maxpooling);
     reg.insert("RectifiedLinearComponent", |_, _| {
         Ok(Box::new(tract_core::ops::math::scalar_max((0.0).into())))
     });
+    reg.insert("ScaleAndOffsetComponent", scale_and_offset::scale_and_offset);
+    reg.insert("SigmoidComponent", |_, _| Ok(Box::new(tract_core::ops::nn::sigmoid())));
+    reg.insert("TanhComponent", |_, _| Ok(Box::new(tract_core::ops::math::tanh())));
+    reg.insert("TimeHeightConvolutionComponent", time_height_conv::time_height_convolution);
+}
+
+#[cfg(test)]
+mod tests {
+    use tract_core::internal::*;
+
+    const TDNNF: &str = r#"<Nnet3>
+input-node name=input dim=4
+component-node name=tdnn1.affine component=tdnn1.affine input=input
+component-node name=tdnn1.relu component=tdnn1.relu input=tdnn1.affine
+component-node name=tdnn1.batchnorm component=tdnn1.batchnorm input=tdnn1.relu
+component-node name=tdnn1.dropout component=tdnn1.dropout input=tdnn1.batchnorm
+component-node name=tdnnf2.linear component=tdnnf2.linear input=Append(Offset(tdnn1.dropout, -1), tdnn1.dropout)
+component-node name=tdnnf2.affine component=tdnnf2.affine input=tdnnf2.linear
+component-node name=tdnnf2.noop component=tdnnf2.noop input=tdnnf2.affine
+component-node name=sigmoid component=sigmoid input=tdnnf2.noop
+component-node name=tanh component=tanh input=tdnnf2.noop
+component-node name=product component=product input=Append(sigmoid, tanh)
+component-node name=pool component=pool input=product
+output-node name=output input=pool objective=linear
+
+<NumComponents> 11
+<ComponentName> tdnn1.affine <TdnnComponent> <MaxChange> 0.75 <LearningRate> 0.001 <TimeOffsets> [ -1 0 1 ]
+<LinearParams>  [
+  0.1 0.2 0.3 0.4 0.5 0.6 0.7 0.8 0.9 1.0 1.1 1.2
+  -0.1 0.2 -0.3 0.4 -0.5 0.6 -0.7 0.8 -0.9 1.0 -1.1 1.2
+  0.3 0.2 0.1 0.0 -0.1 -0.2 -0.3 -0.2 -0.1 0.0 0.1 0.2 ]
+<BiasParams>  [ 0.1 -0.2 0.3 ]
+<OrthonormalConstraint> 0 <UseNaturalGradient> T <NumSamplesHistory> 2000 <AlphaInOut> 4 4 <RankInOut> 20 80 </TdnnComponent>
+<ComponentName> tdnn1.relu <RectifiedLinearComponent> <Dim> 3 <ValueAvg>  [ ]
+<DerivAvg>  [ ]
+<Count> 0 <NumDimsSelfRepaired> 0 <NumDimsProcessed> 0 <SelfRepairScale> 1e-05 </RectifiedLinearComponent>
+<ComponentName> tdnn1.batchnorm <BatchNormComponent> <Dim> 3 <BlockDim> 3 <Epsilon> 0.001 <TargetRms> 1 <TestMode> F <Count> 100 <StatsMean>  [ 0.5 0.1 0.2 ]
+<StatsVar>  [ 0.2 0.3 0.4 ]
+</BatchNormComponent>
+<ComponentName> tdnn1.dropout <GeneralDropoutComponent> <Dim> 3 <BlockDim> 3 <TimePeriod> 0 <DropoutProportion> 0.5 <Continuous> <TestMode> F </GeneralDropoutComponent>
+<ComponentName> tdnnf2.linear <LinearComponent> <LearningRate> 0.001 <Params>  [
+  0.1 0.2 0.3 0.4 0.5 0.6
+  0.6 0.5 0.4 0.3 0.2 0.1 ]
+<OrthonormalConstraint> -1 <UseNaturalGradient> T <RankInOut> 20 80 <Alpha> 4 <NumSamplesHistory> 2000 <UpdatePeriod> 4 </LinearComponent>
+<ComponentName> tdnnf2.affine <TdnnComponent> <LearningRate> 0.001 <TimeOffsets> [ 0 1 ]
+<LinearParams>  [
+  0.1 0.2 0.3 0.4
+  0.5 0.6 0.7 0.8
+  -0.1 -0.2 -0.3 -0.4 ]
+<BiasParams>  [ ]
+<OrthonormalConstraint> 0 <UseNaturalGradient> T <NumSamplesHistory> 2000 <AlphaInOut> 4 4 <RankInOut> 20 80 </TdnnComponent>
+<ComponentName> tdnnf2.noop <ScaleAndOffsetComponent> <LearningRate> 0.001 <Dim> 3 <Scales>  [ 1 2 3 ]
+<Offsets>  [ 0.1 0.2 0.3 ]
+<UseNaturalGradient> T <Rank> 20 </ScaleAndOffsetComponent>
+<ComponentName> sigmoid <SigmoidComponent> <Dim> 3 <ValueAvg>  [ ]
+<DerivAvg>  [ ]
+<Count> 0 <NumDimsSelfRepaired> 0 <NumDimsProcessed> 0 </SigmoidComponent>
+<ComponentName> tanh <TanhComponent> <Dim> 3 <ValueAvg>  [ ]
+<DerivAvg>  [ ]
+<Count> 0 <NumDimsSelfRepaired> 0 <NumDimsProcessed> 0 </TanhComponent>
+<ComponentName> product <ElementwiseProductComponent> <InputDim> 6 <OutputDim> 3 </ElementwiseProductComponent>
+<ComponentName> pool <MaxpoolingComponent> <InputXDim> 3 <InputYDim> 1 <InputZDim> 1 <PoolXSize> 2 <PoolYSize> 1 <PoolZSize> 1 <PoolXStride> 1 <PoolYStride> 1 <PoolZStride> 1 </MaxpoolingComponent>
+</Nnet3>"#;
+
+    #[test]
+    fn tdnnf() {
+        use tract_core::ndarray::*;
+        let model = crate::kaldi().model_for_read(&mut TDNNF.as_bytes()).unwrap();
+        let input = Array2::from_shape_fn((10, 4), |(t, i)| ((t * 4 + i) as f32).sin());
+        let plan = SimplePlan::new(&model).unwrap();
+        let expected = plan.run(tvec!(input.clone().into_tensor())).unwrap().remove(0);
+        let reference = tensor2(&[
+            [0.851024f32, 0.851024],
+            [-0.061135, 0.554426],
+            [0.087706, 0.078765],
+            [0.710947, 0.710947],
+            [0.149971, 0.178566],
+            [0.745851, 0.745851],
+        ]);
+        expected.close_enough(&reference, true).unwrap();
+        let optimized = model.clone().into_optimized().unwrap();
+        let plan = SimplePlan::new(&optimized).unwrap();
+        let found = plan.run(tvec!(input.into_tensor())).unwrap().remove(0);
+        found.close_enough(&expected, true).unwrap();
+
+        let pulsed = PulsedModel::new(&model.into_normalized().unwrap(), 2).unwrap();
+        assert_eq!(pulsed.output_fact(0).unwrap().shape, tvec!(2, 2));
+    }
+
+    fn failover(descriptor: &str) -> TractResult<InferenceModel> {
+        let nnet = format!(
+            r#"<Nnet3>
+input-node name=input dim=2
+component-node name=relu component=relu input={}
+output-node name=output input=relu objective=linear
+
+<NumComponents> 1
+<ComponentName> relu <RectifiedLinearComponent> <Dim> 2 <ValueAvg>  [ ]
+<DerivAvg>  [ ]
+<Count> 0 <NumDimsSelfRepaired> 0 <NumDimsProcessed> 0 <SelfRepairScale> 1e-05 </RectifiedLinearComponent>
+</Nnet3>"#,
+            descriptor
+        );
+        crate::kaldi().model_for_read(&mut nnet.as_bytes())
+    }
+
+    #[test]
+    fn failover_always_defined() {
+        use tract_core::ndarray::*;
+        let model = failover("Failover(input, Offset(input, 1))").unwrap();
+        let input = Array2::from_shape_fn((3, 2), |(t, i)| t as f32 - i as f32);
+        let plan = SimplePlan::new(&model).unwrap();
+        let found = plan.run(tvec!(input.clone().into_tensor())).unwrap().remove(0);
+        assert_eq!(found, input.mapv(|x| x.max(0.0)).into_arc_tensor());
+    }
+
+    #[test]
+    fn failover_out_of_range() {
+        let err = failover("Failover(Offset(input, 1), input)").unwrap_err();
+        assert!(err.to_string().contains("Failover"));
+    }
 }
@@ -12,22 +12,60 @@ pub fn affine_component(ctx: &ParsingContext, name: &str) -> TractResult<Box<dyn
         bail!("Could not find component {}", name);
     };
     let component = &ctx.proto_model.components[&line.component];
-    let (kernel_len, dilation) = line.input.as_conv_shape_dilation().unwrap_or((1, 1));
-    let kernel: &Tensor =
-        component.attributes.get("LinearParams").ok_or("missing attribute LinearParams")?;
-    let bias = component.attributes.get("BiasParams").ok_or("missing attribute BiasParams")?;
+    let (kernel_len, dilation) = if let Some(offsets) = component.attributes.get("TimeOffsets") {
+        if line.input.as_conv_shape_dilation() != Some((1, 1)) {
+            bail!("{} has time offsets, its input descriptor must be a plain node name", name)
+        }
+        time_offsets_shape_dilation(offsets)?
+    } else {
+        line.input.as_conv_shape_dilation().unwrap_or((1, 1))
+    };
+    // LinearComponent calls its kernel Params
+    let kernel: &Tensor = component
+        .attributes
+        .get("LinearParams")
+        .or_else(|| component.attributes.get("Params"))
+        .ok_or("missing attribute LinearParams")?;
+    let output_dim = kernel.shape()[0];
+    let bias = match component.attributes.get("BiasParams") {
+        Some(bias) if bias.len() == output_dim => Arc::clone(bias),
+        // TdnnComponent with use-bias=false writes an empty BiasParams
+        Some(bias) if bias.len() == 0 => tensor1(&vec![0f32; output_dim]).into_arc_tensor(),
+        Some(bias) => bail!(
+            "{} has {} bias params for an output dimension of {}",
+            name,
+            bias.len(),
+            output_dim
+        ),
+        // LinearComponent has no bias
+        None => tensor1(&vec![0f32; output_dim]).into_arc_tensor(),
+    };
     // O•TI -> t -> TI•O -> T•I•O = HWIO
     let o_ti = kernel.to_array_view::<f32>()?;
-    let t_i_o_shape = (kernel_len, kernel.len() / kernel_len / bias.len(), bias.len());
+    let t_i_o_shape = (kernel_len, kernel.len() / kernel_len / output_dim, output_dim);
     let t_i_o = ndarray::Array::from_shape_vec(t_i_o_shape, o_ti.t().iter().cloned().collect())?;
     Ok(Box::new(Affine {
         kernel_len,
         dilation,
         linear_params: t_i_o.into_arc_tensor(),
-        bias_params: Arc::clone(bias),
+        bias_params: bias,
     }))
 }
 
+/// TdnnComponent time offsets must be evenly spaced to map to a dilated convolution.
+fn time_offsets_shape_dilation(offsets: &Tensor) -> TractResult<(usize, usize)> {
+    let offsets = offsets.cast_to::<i32>()?;
+    let offsets = offsets.as_slice::<i32>()?;
+    if offsets.len() < 2 {
+        return Ok((1, 1));
+    }
+    let dilation = offsets[1] - offsets[0];
+    if dilation <= 0 || offsets.windows(2).any(|pair| pair[1] - pair[0] != dilation) {
+        bail!("Unsupported time offsets {:?}", offsets)
+    }
+    Ok((offsets.len(), dilation as usize))
+}
+
 #[derive(Clone, Debug, new)]
 struct Affine {
     kernel_len: usize,
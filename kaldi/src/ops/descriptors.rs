@@ -0,0 +1,511 @@
+use tract_core::internal::*;
+use tract_core::ndarray;
+use tract_core::ndarray::Axis;
+use tract_core::pulse::delay::Delay;
+
+/// Repeats one frame of the first input over the frames of the second (or
+/// once if there is no second input). Kaldi ReplaceIndex(x, t, frame)
+/// descriptor.
+///
+/// Rows of the first input are paired with the frames of the second. Without
+/// a period, like Kaldi in online mode, the frame is picked in the current
+/// chunk: the whole input in a plain model, each pulse in a pulsed model.
+/// With a period, as for an ivector input updated every `period` frames,
+/// output frame t reads row `t / period * period + frame` in both models.
+#[derive(Clone, Debug, new)]
+pub struct ReplaceIndex {
+    frame: usize,
+    pub period: Option<usize>,
+}
+
+impl ReplaceIndex {
+    fn source_frame(&self, t: usize) -> usize {
+        self.period.map(|p| t / p * p).unwrap_or(0) + self.frame
+    }
+}
+
+impl Op for ReplaceIndex {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.ReplaceIndex".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("frame: {} period: {:?}", self.frame, self.period)])
+    }
+
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatelessOp for ReplaceIndex {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let frames = inputs.get(1).map(|r| r.shape()[0]).unwrap_or(1);
+        let input = inputs[0].to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix2>()?;
+        let last = self.source_frame(frames.saturating_sub(1));
+        if last >= input.shape()[0] {
+            bail!("Frame {} is not available (input has {} frames)", last, input.shape()[0])
+        }
+        let output = ndarray::Array2::from_shape_fn((frames, input.shape()[1]), |(t, d)| {
+            input[(self.source_frame(t), d)]
+        });
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for ReplaceIndex {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        if inputs.len() != 1 && inputs.len() != 2 {
+            bail!("ReplaceIndex expects one or two inputs, got {}", inputs.len())
+        }
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], &outputs[0].shape[1])?;
+        if inputs.len() == 2 {
+            s.equals(&inputs[1].shape[0], &outputs[0].shape[0])?;
+        } else {
+            s.equals(&outputs[0].shape[0], 1.to_dim())?;
+        }
+        Ok(())
+    }
+
+    inference_op_as_op!();
+    to_typed!();
+}
+
+impl TypedOp for ReplaceIndex {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let frames = inputs.get(1).map(|r| r.shape.dim(0)).unwrap_or(1.to_dim());
+        Ok(tvec!(TypedFact::dt_shape(
+            f32::datum_type(),
+            [frames, inputs[0].shape.dim(1)].as_ref()
+        )?))
+    }
+
+    fn pulsify(
+        &self,
+        _source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        if node.inputs.len() != 2 {
+            bail!("ReplaceIndex can only be pulsified along a time reference")
+        }
+        let mut inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        let facts = inputs
+            .iter()
+            .map(|i| target.outlet_fact(*i).cloned())
+            .collect::<TractResult<TVec<_>>>()?;
+        if facts[0].axis != 0 {
+            bail!("ReplaceIndex can only be pulsified along time")
+        }
+        // align the rows of the input with the frames of the reference
+        let delay = facts.iter().map(|f| f.delay).max().unwrap();
+        let period = self.period.unwrap_or(pulse);
+        if self.frame >= period || !pulse.is_multiple_of(period) {
+            bail!("ReplaceIndex expects its frame ({}) in each time pulse", self.frame)
+        }
+        if !delay.is_multiple_of(period) {
+            bail!("ReplaceIndex period ({}) does not divide the stream delay ({})", period, delay)
+        }
+        for (ix, fact) in facts.iter().enumerate() {
+            if fact.delay < delay {
+                inputs[ix] = target.wire_node(
+                    format!("{}/Delay-{}", &*node.name, ix),
+                    Delay::new(fact, delay - fact.delay, 0),
+                    &[inputs[ix]],
+                )?[0];
+            }
+        }
+        pulsify_along_reference(target, &*node.name, self.clone(), &inputs)
+    }
+}
+
+impl PulsedOp for ReplaceIndex {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        along_reference(inputs[1], inputs[0].shape[1])
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+/// Output fact of an op repeating a (1, dim) value over the frames of a
+/// reference.
+fn along_reference(reference: &PulsedFact, dim: usize) -> TractResult<TVec<PulsedFact>> {
+    if reference.axis != 0 {
+        bail!("Time reference must stream along axis 0")
+    }
+    let mut fact = reference.clone();
+    fact.datum_type = f32::datum_type();
+    fact.shape = tvec!(reference.shape[0], dim);
+    Ok(tvec!(fact))
+}
+
+fn pulsify_along_reference<O: PulsedOp + Clone>(
+    target: &mut PulsedModel,
+    name: &str,
+    op: O,
+    inputs: &[OutletId],
+) -> TractResult<TVec<OutletId>> {
+    if target.outlet_fact(*inputs.last().unwrap())?.axis != 0 {
+        bail!("{} can only be pulsified along time", op.name())
+    }
+    target.wire_node(name, op, inputs)
+}
+
+/// Constant frame, repeated over the frames of its input. Kaldi Const(value,
+/// dim) descriptor when it is appended or summed to time-dependent operands.
+#[derive(Clone, Debug, new)]
+pub struct ConstFrames {
+    row: Arc<Tensor>,
+}
+
+impl Op for ConstFrames {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.ConstFrames".into()
+    }
+
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatelessOp for ConstFrames {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let row = self.row.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix2>()?;
+        let output = row.broadcast((inputs[0].shape()[0], row.shape()[1])).unwrap().to_owned();
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for ConstFrames {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], self.row.shape()[1].to_dim())?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+    to_typed!();
+}
+
+impl TypedOp for ConstFrames {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(
+            f32::datum_type(),
+            [inputs[0].shape.dim(0), self.row.shape()[1].to_dim()].as_ref()
+        )?))
+    }
+
+    fn pulsify(
+        &self,
+        _source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        pulsify_along_reference(target, &*node.name, self.clone(), &[mapping[&node.inputs[0]]])
+    }
+}
+
+impl PulsedOp for ConstFrames {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        along_reference(inputs[0], self.row.shape()[1])
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+/// Kaldi Round(x, modulus) descriptor: frame t reads frame (t / modulus) * modulus.
+#[derive(Clone, Debug, new)]
+pub struct Round {
+    modulus: usize,
+}
+
+impl Op for Round {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.Round".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("modulus: {}", self.modulus)])
+    }
+
+    op_as_typed_op!();
+    op_as_pulsed_op!();
+}
+
+impl StatelessOp for Round {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix2>()?;
+        let output = ndarray::Array2::from_shape_fn(input.raw_dim(), |(t, f)| {
+            input[((t / self.modulus) * self.modulus, f)]
+        });
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for Round {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+    to_typed!();
+}
+
+impl TypedOp for Round {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    fn pulsify(
+        &self,
+        _source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let fact = target.outlet_fact(input)?;
+        if fact.axis != 0 || pulse % self.modulus != 0 || fact.delay % self.modulus != 0 {
+            bail!("Round({}) can only be pulsified along time, by whole periods", self.modulus)
+        }
+        target.wire_node(&*node.name, self.clone(), &[input])
+    }
+}
+
+impl PulsedOp for Round {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
+
+/// Kaldi Switch(x0, x1, ...) descriptor: frame t reads input t % n.
+#[derive(Clone, Debug, new)]
+pub struct Switch {
+    n: usize,
+}
+
+impl Op for Switch {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.Switch".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl StatelessOp for Switch {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let inputs = inputs
+            .iter()
+            .map(|t| Ok(t.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix2>()?))
+            .collect::<TractResult<TVec<_>>>()?;
+        let output = ndarray::Array2::from_shape_fn(inputs[0].raw_dim(), |(t, f)| {
+            inputs[t % self.n][(t, f)]
+        });
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for Switch {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, self.n)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].rank, 2)?;
+        for input in inputs {
+            s.equals(&input.datum_type, f32::datum_type())?;
+            s.equals(&input.shape, &outputs[0].shape)?;
+        }
+        Ok(())
+    }
+
+    inference_op_as_op!();
+    to_typed!();
+}
+
+impl TypedOp for Switch {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tract_core::internal::*;
+    use tract_core::ndarray::*;
+
+    const MODEL: &str = r#"<Nnet3>
+input-node name=input dim=2
+input-node name=ivector dim=1
+component-node name=id component=id input=Append(Sum(Offset(input, -1), Scale(2.0, input)), ReplaceIndex(ivector, t, 0), Const(1.0, 1))
+output-node name=output input=id
+
+<NumComponents> 1
+<ComponentName> id <BackpropTruncationComponent> <Dim> 4 </BackpropTruncationComponent>
+</Nnet3>"#;
+
+    #[test]
+    fn ivector_sum_and_const() {
+        let mut model = crate::kaldi().model_for_read(&mut MODEL.as_bytes()).unwrap();
+        let input = Array2::from_shape_fn((4, 2), |(t, i)| (t * 2 + i) as f32);
+        let ivector = arr2(&[[10f32], [20.0]]);
+        let expected =
+            arr2(&[[4f32, 7.0, 10.0, 1.0], [10.0, 13.0, 10.0, 1.0], [16.0, 19.0, 10.0, 1.0]])
+                .into_arc_tensor();
+        let plan = SimplePlan::new(&model).unwrap();
+        let found = plan
+            .run(tvec!(input.clone().into_tensor(), ivector.clone().into_tensor()))
+            .unwrap()
+            .remove(0);
+        assert_eq!(found, expected);
+        model
+            .set_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), shapefact!(4, 2)))
+            .unwrap();
+        model
+            .set_input_fact(1, InferenceFact::dt_shape(f32::datum_type(), shapefact!(2, 1)))
+            .unwrap();
+        let optimized = model.into_optimized().unwrap();
+        let plan = SimplePlan::new(&optimized).unwrap();
+        let found = plan.run(tvec!(input.into_tensor(), ivector.into_tensor())).unwrap().remove(0);
+        assert_eq!(found, expected);
+    }
+
+    /// Runs the model on the whole input, then pulse by pulse, and compares.
+    fn check_pulsed(model: InferenceModel, pulse: usize, input: Array2<f32>, ivector: Array2<f32>) {
+        let len = input.shape()[0];
+        let plan = SimplePlan::new(&model).unwrap();
+        let expected = plan
+            .run(tvec!(input.clone().into_tensor(), ivector.clone().into_tensor()))
+            .unwrap()
+            .remove(0);
+
+        let pulsed = PulsedModel::new(&model.into_normalized().unwrap(), pulse).unwrap();
+        let fact = pulsed.output_fact(0).unwrap().clone();
+        let plan = SimplePlan::new(pulsed).unwrap();
+        let mut state = SimpleState::new(&plan).unwrap();
+        let mut got = Array2::<f32>::zeros((0, fact.shape[1]));
+        for t in (0..len + fact.delay).step_by(pulse) {
+            let chunk = |a: &Array2<f32>| {
+                Array2::from_shape_fn((pulse, a.shape()[1]), |(dt, f)| {
+                    a[((t + dt).min(len - 1), f)]
+                })
+            };
+            let output = state
+                .run(tvec!(chunk(&input).into_tensor(), chunk(&ivector).into_tensor()))
+                .unwrap()
+                .remove(0);
+            let output = output.to_array_view::<f32>().unwrap().into_dimensionality().unwrap();
+            got = stack(Axis(0), &[got.view(), output]).unwrap();
+        }
+        let frames = expected.shape()[0];
+        let got = got.slice(s![fact.delay..fact.delay + frames, ..]).to_owned().into_tensor();
+        assert_eq!(got, expected.into_tensor());
+    }
+
+    fn model_with_period(nnet: &str, period: usize) -> InferenceModel {
+        let kaldi = crate::kaldi();
+        let mut proto = kaldi.proto_model_for_read(&mut nnet.as_bytes()).unwrap();
+        proto.input_periods.insert("ivector".to_string(), period);
+        kaldi.model_for_proto_model(&proto).unwrap()
+    }
+
+    #[test]
+    fn ivector_pulsified() {
+        let model = model_with_period(MODEL, 1);
+        let input = Array2::from_shape_fn((6, 2), |(t, i)| (t * 2 + i) as f32);
+        let ivector = Array2::from_elem((6, 1), 10f32);
+        check_pulsed(model, 2, input, ivector);
+    }
+
+    const PERIOD_MODEL: &str = r#"<Nnet3>
+input-node name=input dim=1
+input-node name=ivector dim=1
+component-node name=id component=id input=Append(Offset(input, -2), input, ReplaceIndex(ivector, t, 0))
+output-node name=output input=id
+
+<NumComponents> 1
+<ComponentName> id <BackpropTruncationComponent> <Dim> 3 </BackpropTruncationComponent>
+</Nnet3>"#;
+
+    #[test]
+    fn time_varying_ivector_pulsified() {
+        let model = model_with_period(PERIOD_MODEL, 2);
+        let input = Array2::from_shape_fn((10, 1), |(t, _)| t as f32);
+        let ivector = Array2::from_shape_fn((10, 1), |(t, _)| 10.0 * t as f32);
+        let plan = SimplePlan::new(&model).unwrap();
+        let found = plan
+            .run(tvec!(input.clone().into_tensor(), ivector.clone().into_tensor()))
+            .unwrap()
+            .remove(0);
+        let held = found.to_array_view::<f32>().unwrap().index_axis(Axis(1), 2).to_owned();
+        assert_eq!(held, arr1(&[0f32, 0.0, 20.0, 20.0, 40.0, 40.0, 60.0, 60.0]).into_dyn());
+        check_pulsed(model, 4, input, ivector);
+    }
+
+    #[test]
+    fn round() {
+        let round = super::Round::new(2);
+        let input = Array2::from_shape_fn((5, 1), |(t, _)| t as f32).into_arc_tensor();
+        let found = round.eval(tvec!(input)).unwrap().remove(0);
+        assert_eq!(found, arr2(&[[0f32], [0.0], [2.0], [2.0], [4.0]]).into_arc_tensor());
+    }
+}
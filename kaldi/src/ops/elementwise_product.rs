@@ -0,0 +1,97 @@
+use tract_core::internal::*;
+use tract_core::ndarray;
+
+use crate::model::ParsingContext;
+
+pub fn elementwise_product(ctx: &ParsingContext, name: &str) -> TractResult<Box<dyn InferenceOp>> {
+    let component = ctx.component_for_node(name)?;
+    let input_dim = component.usize_attribute("InputDim")?;
+    let output_dim = component.usize_attribute("OutputDim")?;
+    if output_dim == 0 || input_dim % output_dim != 0 {
+        bail!("InputDim ({}) must be a multiple of OutputDim ({})", input_dim, output_dim)
+    }
+    Ok(Box::new(ElementwiseProduct::new(input_dim, output_dim)))
+}
+
+/// Multiplies together the input_dim / output_dim consecutive chunks of the
+/// input features.
+#[derive(Clone, Debug, new)]
+pub struct ElementwiseProduct {
+    input_dim: usize,
+    output_dim: usize,
+}
+
+impl Op for ElementwiseProduct {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.ElementwiseProduct".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for ElementwiseProduct {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        use ndarray::{s, Axis};
+        let input = args_1!(inputs);
+        let input = input.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix2>()?;
+        let mut output = input.slice(s![.., 0..self.output_dim]).to_owned();
+        for chunk in 1..self.input_dim / self.output_dim {
+            let start = chunk * self.output_dim;
+            output *= &input.slice_axis(Axis(1), (start..start + self.output_dim).into());
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for ElementwiseProduct {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[1], self.input_dim.to_dim())?;
+        s.equals(&outputs[0].shape[1], self.output_dim.to_dim())?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        use tract_core::ops::array::Slice;
+        use tract_core::ops::math::mul;
+        let input = mapping[&node.inputs[0]];
+        let chunks = self.input_dim / self.output_dim;
+        let first = if chunks == 1 { node.name.clone() } else { format!("{}-chunk-0", node.name) };
+        let mut wire =
+            target.wire_node(first, Slice::new(1, 0, self.output_dim), [input].as_ref())?[0];
+        for chunk in 1..chunks {
+            let start = chunk * self.output_dim;
+            let slice = target.wire_node(
+                format!("{}-chunk-{}", node.name, chunk),
+                Slice::new(1, start, start + self.output_dim),
+                [input].as_ref(),
+            )?[0];
+            let name = if chunk == chunks - 1 {
+                node.name.clone()
+            } else {
+                format!("{}-product-{}", node.name, chunk)
+            };
+            wire = target.wire_node(name, mul::bin(), [wire, slice].as_ref())?[0];
+        }
+        Ok(tvec!(wire))
+    }
+}
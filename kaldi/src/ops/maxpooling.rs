@@ -0,0 +1,105 @@
+use tract_core::internal::*;
+
+use crate::model::ParsingContext;
+use crate::ops::reshape_frames::ReshapeFrames;
+
+pub fn maxpooling(ctx: &ParsingContext, name: &str) -> TractResult<Box<dyn InferenceOp>> {
+    let component = ctx.component_for_node(name)?;
+    let attrs = |names: [&str; 3]| -> TractResult<TVec<usize>> {
+        names.iter().map(|n| component.usize_attribute(n)).collect()
+    };
+    let input_dims = attrs(["InputXDim", "InputYDim", "InputZDim"])?;
+    let pool_sizes = attrs(["PoolXSize", "PoolYSize", "PoolZSize"])?;
+    let pool_strides = attrs(["PoolXStride", "PoolYStride", "PoolZStride"])?;
+    Ok(Box::new(Maxpooling::new(input_dims, pool_sizes, pool_strides)))
+}
+
+/// Max pooling over the (x, y, z) volume of each frame, z being the fastest
+/// varying feature index.
+#[derive(Clone, Debug, new)]
+pub struct Maxpooling {
+    input_dims: TVec<usize>,
+    pool_sizes: TVec<usize>,
+    pool_strides: TVec<usize>,
+}
+
+impl Maxpooling {
+    fn as_pool(&self) -> tract_core::ops::cnn::MaxPool {
+        use tract_core::ops::cnn::*;
+        let pool_spec = PoolSpec::new(
+            tract_core::ops::nn::DataFormat::NHWC,
+            self.pool_sizes.clone(),
+            PaddingSpec::Valid,
+            None,
+            Some(self.pool_strides.clone()),
+            None,
+        );
+        MaxPool::new(pool_spec, None)
+    }
+
+    fn output_dim(&self) -> usize {
+        (0..3)
+            .map(|ax| (self.input_dims[ax] - self.pool_sizes[ax]) / self.pool_strides[ax] + 1)
+            .product()
+    }
+
+    fn volume_shape(&self) -> TVec<usize> {
+        let mut shape = self.input_dims.clone();
+        shape.push(1);
+        shape
+    }
+}
+
+impl Op for Maxpooling {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.Maxpooling".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for Maxpooling {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let volume = ReshapeFrames::new(self.volume_shape()).eval(tvec!(input))?;
+        let pooled = self.as_pool().eval(volume)?;
+        ReshapeFrames::new(tvec!(self.output_dim())).eval(pooled)
+    }
+}
+
+impl InferenceRulesOp for Maxpooling {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[1], self.input_dims.iter().product::<usize>().to_dim())?;
+        s.equals(&outputs[0].shape[1], self.output_dim().to_dim())?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let wire = target.wire_node(
+            format!("{}-Volume", node.name),
+            ReshapeFrames::new(self.volume_shape()),
+            [mapping[&node.inputs[0]]].as_ref(),
+        )?;
+        let wire = target.wire_node(format!("{}-Pool", node.name), self.as_pool(), &*wire)?;
+        target.wire_node(&*node.name, ReshapeFrames::new(tvec!(self.output_dim())), &*wire)
+    }
+}
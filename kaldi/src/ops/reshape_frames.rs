@@ -0,0 +1,74 @@
+use tract_core::internal::*;
+
+/// Reshapes each frame of a (T, ...) tensor, leaving the time axis alone.
+#[derive(Clone, Debug, new)]
+pub struct ReshapeFrames {
+    shape: TVec<usize>,
+}
+
+impl Op for ReshapeFrames {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.ReshapeFrames".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("frame shape: {:?}", self.shape)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl StatelessOp for ReshapeFrames {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let mut shape = self.shape.clone();
+        shape.insert(0, input.shape()[0]);
+        let output = unsafe { input.into_tensor().into_shape(&*shape)? };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for ReshapeFrames {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let mut shape: TVec<TDim> = self.shape.iter().map(|d| d.to_dim()).collect();
+        shape.insert(0, inputs[0].shape.dim(0));
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*shape)?))
+    }
+
+    fn invariants(&self, _model: &TypedModel, _node: &TypedNode) -> TractResult<Invariants> {
+        Ok(vec![AxisInfo::simple(0)].into_iter().collect())
+    }
+
+    fn pulsify(
+        &self,
+        _source: &NormalizedModel,
+        node: &NormalizedNode,
+        target: &mut PulsedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        _pulse: usize,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        if target.outlet_fact(input)?.axis != 0 {
+            bail!("ReshapeFrames can only be pulsified along the time axis")
+        }
+        target.wire_node(&*node.name, self.clone(), &[input])
+    }
+}
+
+impl PulsedOp for ReshapeFrames {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape = self.shape.clone();
+        fact.shape.insert(0, inputs[0].shape[0]);
+        Ok(tvec!(fact))
+    }
+
+    fn supports_variable_pulse(&self) -> bool {
+        true
+    }
+
+    pulsed_op_as_op!();
+    pulsed_op_to_typed_op!();
+}
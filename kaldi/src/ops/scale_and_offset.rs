@@ -0,0 +1,109 @@
+use tract_core::internal::*;
+use tract_core::ndarray;
+
+use crate::model::ParsingContext;
+
+pub fn scale_and_offset(ctx: &ParsingContext, name: &str) -> TractResult<Box<dyn InferenceOp>> {
+    let component = ctx.component_for_node(name)?;
+    let dim = component.usize_attribute("Dim")?;
+    let scales = component.attribute("Scales")?.as_slice::<f32>()?;
+    let offsets = component.attribute("Offsets")?.as_slice::<f32>()?;
+    Ok(Box::new(ScaleAndOffset::for_dim(dim, scales, offsets)?))
+}
+
+pub fn batch_norm(ctx: &ParsingContext, name: &str) -> TractResult<Box<dyn InferenceOp>> {
+    let component = ctx.component_for_node(name)?;
+    let dim = component.usize_attribute("Dim")?;
+    let epsilon = component.f32_attribute("Epsilon")?;
+    let target_rms = component.f32_attribute("TargetRms")?;
+    let mean = component.attribute("StatsMean")?.as_slice::<f32>()?;
+    let var = component.attribute("StatsVar")?.as_slice::<f32>()?;
+    // (x - mean) * target_rms / sqrt(var + epsilon)
+    let scales: Vec<f32> = var.iter().map(|v| target_rms / (v + epsilon).sqrt()).collect();
+    let offsets: Vec<f32> = mean.iter().zip(scales.iter()).map(|(m, s)| -m * s).collect();
+    Ok(Box::new(ScaleAndOffset::for_dim(dim, &scales, &offsets)?))
+}
+
+/// Per-feature affine transform, x * scales + offsets.
+#[derive(Clone, Debug, new)]
+pub struct ScaleAndOffset {
+    scales: Arc<Tensor>,
+    offsets: Arc<Tensor>,
+}
+
+impl ScaleAndOffset {
+    /// Kaldi parameters may cover a block of the features, repeated over dim.
+    fn for_dim(dim: usize, scales: &[f32], offsets: &[f32]) -> TractResult<ScaleAndOffset> {
+        if scales.len() == 0 || dim % scales.len() != 0 || scales.len() != offsets.len() {
+            bail!(
+                "Inconsistent dimensions: dim={}, {} scales, {} offsets",
+                dim,
+                scales.len(),
+                offsets.len()
+            )
+        }
+        let tile = |v: &[f32]| v.iter().cycle().take(dim).cloned().collect::<Vec<f32>>();
+        Ok(ScaleAndOffset::new(
+            tensor1(&*tile(scales)).into_arc_tensor(),
+            tensor1(&*tile(offsets)).into_arc_tensor(),
+        ))
+    }
+}
+
+impl Op for ScaleAndOffset {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.ScaleAndOffset".into()
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for ScaleAndOffset {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let mut input = input.into_tensor().into_array::<f32>()?;
+        input *= &self.scales.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix1>()?;
+        input += &self.offsets.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix1>()?;
+        Ok(tvec!(input.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for ScaleAndOffset {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], self.scales.len().to_dim())?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        use tract_core::ops::math::{add, mul};
+        let wire = target.wire_node(
+            format!("{}-scale", node.name),
+            mul::unary(self.scales.clone()),
+            [mapping[&node.inputs[0]]].as_ref(),
+        )?;
+        target.wire_node(&*node.name, add::unary(self.offsets.clone()), &*wire)
+    }
+}
@@ -0,0 +1,271 @@
+use tract_core::internal::*;
+use tract_core::ndarray;
+
+use crate::model::ParsingContext;
+use crate::ops::reshape_frames::ReshapeFrames;
+
+pub fn time_height_convolution(
+    ctx: &ParsingContext,
+    name: &str,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let component = ctx.component_for_node(name)?;
+    let filters_in = component.usize_attribute("NumFiltersIn")?;
+    let filters_out = component.usize_attribute("NumFiltersOut")?;
+    let height_in = component.usize_attribute("HeightIn")?;
+    let height_out = component.usize_attribute("HeightOut")?;
+    let subsample = component.usize_attribute("HeightSubsampleOut")?;
+    let offsets = component.attribute("Offsets")?.cast_to::<i32>()?.into_owned();
+    let offsets = offsets.into_array::<i32>()?.into_dimensionality::<ndarray::Ix2>()?;
+    let params = component.attribute("LinearParams")?;
+    let params = params.to_array_view::<f32>()?.into_dimensionality::<ndarray::Ix2>()?;
+    if params.shape() != &[filters_out, offsets.shape()[0] * filters_in] {
+        bail!(
+            "Expected {}x{} linear params, got {:?}",
+            filters_out,
+            offsets.shape()[0] * filters_in,
+            params.shape()
+        )
+    }
+    let bias = match component.attributes.get("BiasParams") {
+        Some(bias) if bias.len() == filters_out => Arc::clone(bias),
+        _ => tensor1(&*vec![0f32; filters_out]).into_arc_tensor(),
+    };
+
+    let (t_min, dt, kt) = grid(offsets.column(0).iter().cloned());
+    // height grid starts at or before 0, so that it maps to padding before
+    let (h_min, dh, mut kh) = grid(offsets.column(1).iter().cloned().chain(Some(0)));
+    let before = (-h_min) as usize;
+    let span = |kh: usize| (kh - 1) * dh + 1;
+    let mut after =
+        ((height_out - 1) * subsample + span(kh)) as isize - (height_in + before) as isize;
+    if after < 0 {
+        // too many output rows: stretch the kernel with zeroes instead
+        let extra = ((-after) as usize + dh - 1) / dh;
+        kh += extra;
+        after += (extra * dh) as isize;
+    }
+
+    let mut kernel = ndarray::Array4::<f32>::zeros((kt, kh, filters_in, filters_out));
+    for (ix, offset) in offsets.outer_iter().enumerate() {
+        let t = (offset[0] - t_min) as usize / dt;
+        let h = (offset[1] - h_min) as usize / dh;
+        for fi in 0..filters_in {
+            for fo in 0..filters_out {
+                kernel[(t, h, fi, fo)] = params[(fo, ix * filters_in + fi)];
+            }
+        }
+    }
+
+    Ok(Box::new(TimeHeightConv {
+        height_in,
+        filters_in,
+        height_out,
+        filters_out,
+        dilations: tvec!(dt, dh),
+        subsample,
+        padding: (before, after as usize),
+        kernel: kernel.into_arc_tensor(),
+        bias,
+    }))
+}
+
+/// Smallest evenly spaced grid covering the values: (origin, step, len).
+fn grid(values: impl Iterator<Item = i32> + Clone) -> (i32, usize, usize) {
+    fn gcd(a: usize, b: usize) -> usize {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+    let min = values.clone().min().unwrap_or(0);
+    let max = values.clone().max().unwrap_or(0);
+    let step = values.fold(0, |acc, v| gcd(acc, (v - min) as usize)).max(1);
+    (min, step, (max - min) as usize / step + 1)
+}
+
+/// Kaldi time-height convolution, as a 2D convolution over (time, height)
+/// with filters as channels.
+#[derive(Clone, Debug)]
+pub struct TimeHeightConv {
+    height_in: usize,
+    filters_in: usize,
+    height_out: usize,
+    filters_out: usize,
+    dilations: TVec<usize>,
+    subsample: usize,
+    padding: (usize, usize),
+    kernel: Arc<Tensor>, // HWIO
+    bias: Arc<Tensor>,
+}
+
+impl TimeHeightConv {
+    fn as_conv(&self) -> tract_core::ops::cnn::Conv {
+        use tract_core::ops::cnn::*;
+        let conv = Conv::default()
+            .nhwc()
+            .hwio()
+            .bias_input(2)
+            .kernel_shape(self.kernel.shape()[0..2].into())
+            .dilations(self.dilations.clone())
+            .strides(tvec!(1, self.subsample))
+            .padding(PaddingSpec::Explicit(tvec!(0, self.padding.0), tvec!(0, self.padding.1)));
+        trace!("{:?} -> {:?}", self, conv);
+        conv
+    }
+}
+
+impl Op for TimeHeightConv {
+    fn name(&self) -> std::borrow::Cow<str> {
+        "kaldi.TimeHeightConv".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for TimeHeightConv {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let frames = input.shape()[0];
+        let input = unsafe {
+            input.into_tensor().into_shape(&[1, frames, self.height_in, self.filters_in])?
+        };
+        let output = self
+            .as_conv()
+            .eval(tvec!(input.into_arc_tensor(), self.kernel.clone(), self.bias.clone()))?
+            .remove(0);
+        let frames = output.shape()[1];
+        let output = unsafe {
+            output.into_tensor().into_shape(&[frames, self.height_out * self.filters_out])?
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for TimeHeightConv {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, f32::datum_type())?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], (self.height_in * self.filters_in).to_dim())?;
+        s.equals(&outputs[0].shape[1], (self.height_out * self.filters_out).to_dim())?;
+        s.given(&inputs[0].shape[0], move |s, frames| {
+            let ishape = [1.to_dim(), frames, self.height_in.to_dim(), self.filters_in.to_dim()];
+            let oshape = self.as_conv().output_shape(&ishape, self.kernel.shape());
+            s.equals(&outputs[0].shape[0], &oshape[1])
+        })?;
+        Ok(())
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        use tract_core::ops::array::{AddDim, RmDim};
+        let input = mapping[&node.inputs[0]];
+        let wire = target.wire_node(
+            format!("{}-SplitHeight", node.name),
+            ReshapeFrames::new(tvec!(self.height_in, self.filters_in)),
+            [input].as_ref(),
+        )?;
+        let wire =
+            target.wire_node(format!("{}-AddBatchDim", node.name), AddDim::new(0), &*wire)?;
+        let kernel = target.add_const(format!("{}-Kernel", node.name), self.kernel.clone())?;
+        let bias = target.add_const(format!("{}-Bias", node.name), self.bias.clone())?;
+        let wire = target.wire_node(
+            format!("{}-Conv", node.name),
+            self.as_conv(),
+            [wire[0], kernel.into(), bias.into()].as_ref(),
+        )?;
+        let wire = target.wire_node(format!("{}-RmBatchDim", node.name), RmDim::new(0), &*wire)?;
+        target.wire_node(
+            &*node.name,
+            ReshapeFrames::new(tvec!(self.height_out * self.filters_out)),
+            &*wire,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_of_offsets() {
+        assert_eq!(grid(vec![-1, 0, 1].into_iter()), (-1, 1, 3));
+        assert_eq!(grid(vec![-3, 3, 0].into_iter()), (-3, 3, 3));
+        assert_eq!(grid(vec![2, 4].into_iter().chain(Some(0))), (0, 2, 3));
+        assert_eq!(grid(vec![0].into_iter()), (0, 1, 1));
+    }
+
+    // 3x3 (time, height) kernel, 2 filters in, 1 filter out, height 3.
+    const MODEL: &str = r#"<Nnet3>
+input-node name=input dim=6
+component-node name=conv input=input component=conv
+output-node name=output input=conv
+
+<NumComponents> 1
+<ComponentName> conv <TimeHeightConvolutionComponent> <LearningRate> 0.001 <Model> <ConvolutionModel> <NumFiltersIn> 2 <NumFiltersOut> 1 <HeightIn> 3 <HeightOut> 3 <HeightSubsampleOut> 1 <Offsets> [ -1,-1 -1,0 -1,1 0,-1 0,0 0,1 1,-1 1,0 1,1 ]
+<RequiredTimeOffsets> [ -1 0 1 ]
+</ConvolutionModel> <LinearParams>  [
+  1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 ]
+<BiasParams>  [ 0.5 ]
+<MaxMemoryMb> 200 <UseNaturalGradient> T <NumMinibatchesHistory> 4 <AlphaInOut> 4 4 <RankInOut> 20 80 </TimeHeightConvolutionComponent>
+</Nnet3>"#;
+
+    #[test]
+    fn time_height_conv_matches_naive() {
+        use tract_core::ndarray::*;
+        let model = crate::kaldi().model_for_read(&mut MODEL.as_bytes()).unwrap();
+        let frames = 5;
+        let input = Array2::from_shape_fn((frames, 6), |(t, i)| (t * 6 + i) as f32 / 10.0);
+        let offsets: Vec<(isize, isize)> =
+            (-1..=1).flat_map(|t| (-1..=1).map(move |h| (t, h))).collect();
+        let mut expected = Array2::<f32>::from_elem((frames - 2, 3), 0.5);
+        for t in 0..frames - 2 {
+            for h in 0..3 {
+                for (k, (dt, dh)) in offsets.iter().enumerate() {
+                    let hi = h as isize + dh;
+                    if hi < 0 || hi >= 3 {
+                        continue;
+                    }
+                    for f in 0..2 {
+                        let x = input[((t as isize + 1 + dt) as usize, hi as usize * 2 + f)];
+                        expected[(t, h)] += (k * 2 + f + 1) as f32 * x;
+                    }
+                }
+            }
+        }
+        let expected = expected.into_arc_tensor();
+        let plan = SimplePlan::new(&model).unwrap();
+        let found = plan.run(tvec!(input.clone().into_tensor())).unwrap().remove(0);
+        found.close_enough(&expected, true).unwrap();
+        let optimized = model.into_optimized().unwrap();
+        let plan = SimplePlan::new(&optimized).unwrap();
+        let found = plan.run(tvec!(input.into_tensor())).unwrap().remove(0);
+        found.close_enough(&expected, true).unwrap();
+    }
+
+    #[test]
+    fn time_height_conv_pulsifies() {
+        let model = crate::kaldi().model_for_read(&mut MODEL.as_bytes()).unwrap();
+        let model = model.into_normalized().unwrap();
+        let pulsed = PulsedModel::new(&model, 2).unwrap();
+        let fact = pulsed.output_fact(0).unwrap();
+        assert_eq!(fact.shape, tvec!(2, 3));
+        assert_eq!(fact.delay, 2);
+    }
+}
@@ -31,7 +31,12 @@ pub fn nnet3(slice: &[u8]) -> TractResult<KaldiProtoModel> {
         e => format!("{:?}", e),
     })?;
     let config_lines = config_lines::parse_config(config)?;
-    Ok(KaldiProtoModel { config_lines, components, adjust_final_offset: 0 })
+    Ok(KaldiProtoModel {
+        config_lines,
+        components,
+        adjust_final_offset: 0,
+        input_periods: HashMap::new(),
+    })
 }
 
 pub fn if_then_else<'a, T>(
@@ -69,7 +74,8 @@ fn num_components(bin: bool, i: &[u8]) -> IResult<&[u8], usize> {
 fn component(bin: bool) -> impl Fn(&[u8]) -> IResult<&[u8], Component> {
     move |i: &[u8]| {
         let (i, klass) = open_any(i)?;
-        let (i, attributes) = if bin { bin::attributes(i, klass)? } else { text::attributes(i)? };
+        let (i, attributes) =
+            if bin { bin::attributes(i, klass)? } else { text::attributes(i, klass)? };
         let (i, _) = close(i, klass)?;
         Ok((i, Component { klass: klass.to_string(), attributes }))
     }
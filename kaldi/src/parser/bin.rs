@@ -1,12 +1,31 @@
 use tract_core::internal::*;
 
 use nom::combinator::*;
+use nom::sequence::*;
 use nom::IResult;
 
 use super::components::COMPONENTS;
 
 pub fn attributes<'a>(i: &'a [u8], klass: &str) -> IResult<&'a [u8], HashMap<String, Arc<Tensor>>> {
-    map(nom::multi::many0(|j| attribute(j, klass)), |v| v.into_iter().collect())(i)
+    map(nom::multi::many0(|j| attribute_group(j, klass)), |v| {
+        v.into_iter().flat_map(|v| v.into_iter()).collect()
+    })(i)
+}
+
+fn attribute_group<'a>(i: &'a [u8], klass: &str) -> IResult<&'a [u8], Vec<(String, Arc<Tensor>)>> {
+    nom::branch::alt((nested_model, map(|j| attribute(j, klass), |a| vec![a])))(i)
+}
+
+/// Attributes of a nested <ConvolutionModel> are flattened in the component.
+fn nested_model(i: &[u8]) -> IResult<&[u8], Vec<(String, Arc<Tensor>)>> {
+    preceded(
+        |i| super::open(i, "Model"),
+        delimited(
+            |i| super::open(i, "ConvolutionModel"),
+            nom::multi::many0(|j| attribute(j, "ConvolutionModel")),
+            |i| super::close(i, "ConvolutionModel"),
+        ),
+    )(i)
 }
 
 fn attribute<'a>(i: &'a [u8], klass: &str) -> IResult<&'a [u8], (String, Arc<Tensor>)> {
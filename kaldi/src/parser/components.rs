@@ -10,15 +10,20 @@ use nom::{
     bytes::complete::*,
     combinator::*,
     multi::many_m_n,
-    number::complete::{le_f32, le_f64},
+    number::complete::{le_f32, le_f64, le_i32},
     sequence::*,
     IResult,
 };
 
 pub enum KaldiAttributeKind {
     Bool,
+    Flag,
     Int,
+    IntPair,
+    IntVector,
+    IntPairVector,
     Float,
+    FloatPair,
     FloatVector,
     FloatMatrix,
 }
@@ -30,8 +35,17 @@ impl KaldiAttributeKind {
                 map(tag("F"), |_| Tensor::from(false)),
                 map(tag("T"), |_| Tensor::from(true)),
             ))(i),
+            Flag => Ok((i, Tensor::from(true))),
             Int => map(super::integer(true), Tensor::from)(i),
+            IntPair => {
+                map(pair(super::integer(true), super::integer(true)), |(a, b)| tensor1(&[a, b]))(i)
+            }
+            IntVector => Self::parse_int_vector(i),
+            IntPairVector => Self::parse_int_pair_vector(i),
             Float => map(Self::parse_float_value, Tensor::from)(i),
+            FloatPair => map(pair(Self::parse_float_value, Self::parse_float_value), |(a, b)| {
+                tensor1(&[a, b])
+            })(i),
             FloatVector => preceded(multispaced(tag("FV")), Self::parse_float_vector)(i),
             FloatMatrix => preceded(multispaced(tag("FM")), Self::parse_float_matrix)(i),
         }
@@ -41,11 +55,37 @@ impl KaldiAttributeKind {
         alt((preceded(tag([4]), le_f32), map(preceded(tag([8]), le_f64), |f| f as f32)))(i)
     }
 
+    fn parse_int_vector<'a>(i: &'a [u8]) -> IResult<&'a [u8], Tensor> {
+        let (i, len) = preceded(tag([4]), le_i32)(i)?;
+        // FIXME pending merge of https://github.com/Geal/nom/pull/995
+        if len == 0 {
+            Ok((i, tensor1::<i32>(&[])))
+        } else {
+            map(many_m_n(len as usize, len as usize, le_i32), |data| tensor1(&*data))(i)
+        }
+    }
+
+    fn parse_int_pair_vector<'a>(i: &'a [u8]) -> IResult<&'a [u8], Tensor> {
+        let (i, len) = preceded(tag([4]), le_i32)(i)?;
+        let len = len as usize;
+        // FIXME pending merge of https://github.com/Geal/nom/pull/995
+        if len == 0 {
+            Ok((i, tensor2(&[[0i32; 2]; 0])))
+        } else {
+            map(
+                map_res(many_m_n(2 * len, 2 * len, le_i32), move |buf| {
+                    tract_core::ndarray::Array2::from_shape_vec((len, 2), buf)
+                }),
+                Tensor::from,
+            )(i)
+        }
+    }
+
     fn parse_float_vector<'a>(i: &'a [u8]) -> IResult<&'a [u8], Tensor> {
         let (i, len) = super::integer(true)(i)?;
         // FIXME pending merge of https://github.com/Geal/nom/pull/995
         if len == 0 {
-            Ok((i, tensor1(&[0.0f32])))
+            Ok((i, tensor1::<f32>(&[])))
         } else {
             map(many_m_n(len as usize, len as usize, le_f32), |data| tensor1(&*data))(i)
         }
@@ -71,6 +111,38 @@ impl KaldiAttributeKind {
 
 use KaldiAttributeKind::*;
 
+/// Attributes written by kaldi UpdatableComponent::WriteUpdatableCommon.
+fn updatable(
+    mut attributes: HashMap<&'static str, KaldiAttributeKind>,
+) -> HashMap<&'static str, KaldiAttributeKind> {
+    attributes.extend(hashmap! {
+        "LearningRateFactor" => Float,
+        "IsGradient" => Bool,
+        "MaxChange" => Float,
+        "L2Regularize" => Float,
+        "LearningRate" => Float,
+    });
+    attributes
+}
+
+/// Attributes written by kaldi NonlinearComponent::Write.
+fn nonlinear() -> HashMap<&'static str, KaldiAttributeKind> {
+    hashmap! {
+        "Dim" => Int,
+        "BlockDim" => Int,
+        "ValueAvg" => FloatVector,
+        "DerivAvg" => FloatVector,
+        "Count" => Float,
+        "OderivRms" => FloatVector,
+        "OderivCount" => Float,
+        "NumDimsSelfRepaired" => Float,
+        "NumDimsProcessed" => Float,
+        "SelfRepairLowerThreshold" => Float,
+        "SelfRepairUpperThreshold" => Float,
+        "SelfRepairScale" => Float,
+    }
+}
+
 lazy_static::lazy_static! {
     pub static ref COMPONENTS: HashMap<&'static str, HashMap<&'static str, KaldiAttributeKind>> = hashmap! {
         "FixedAffineComponent" => hashmap! {
@@ -139,6 +211,112 @@ lazy_static::lazy_static! {
             "NumDimsSelfRepaired" => Float,
             "NumDimsProcessed" => Float,
             "SelfRepairScale" => Float,
+        },
+        "SigmoidComponent" => nonlinear(),
+        "TanhComponent" => nonlinear(),
+        "TdnnComponent" => updatable(hashmap! {
+            "TimeOffsets" => IntVector,
+            "LinearParams" => FloatMatrix,
+            "BiasParams" => FloatVector,
+            "OrthonormalConstraint" => Float,
+            "UseNaturalGradient" => Bool,
+            "NumSamplesHistory" => Float,
+            "AlphaInOut" => FloatPair,
+            "RankInOut" => IntPair,
+        }),
+        "LinearComponent" => updatable(hashmap! {
+            "Params" => FloatMatrix,
+            "OrthonormalConstraint" => Float,
+            "UseNaturalGradient" => Bool,
+            "NumSamplesHistory" => Float,
+            "Alpha" => Float,
+            "AlphaInOut" => FloatPair,
+            "RankInOut" => IntPair,
+            "UpdatePeriod" => Int,
+        }),
+        "BatchNormComponent" => hashmap! {
+            "Dim" => Int,
+            "BlockDim" => Int,
+            "Epsilon" => Float,
+            "TargetRms" => Float,
+            "TestMode" => Bool,
+            "Count" => Float,
+            "StatsMean" => FloatVector,
+            "StatsVar" => FloatVector,
+        },
+        "ScaleAndOffsetComponent" => updatable(hashmap! {
+            "Dim" => Int,
+            "Scales" => FloatVector,
+            "Offsets" => FloatVector,
+            "UseNaturalGradient" => Bool,
+            "Rank" => Int,
+        }),
+        "GeneralDropoutComponent" => hashmap! {
+            "Dim" => Int,
+            "BlockDim" => Int,
+            "TimePeriod" => Int,
+            "DropoutProportion" => Float,
+            "SpecAugmentMaxProportion" => Float,
+            "SpecAugmentMaxRegions" => Int,
+            "Continuous" => Flag,
+            "TestMode" => Bool,
+        },
+        "ElementwiseProductComponent" => hashmap! {
+            "InputDim" => Int,
+            "OutputDim" => Int,
+        },
+        "TimeHeightConvolutionComponent" => updatable(hashmap! {
+            "LinearParams" => FloatMatrix,
+            "BiasParams" => FloatVector,
+            "MaxMemoryMb" => Float,
+            "UseNaturalGradient" => Bool,
+            "NumMinibatchesHistory" => Float,
+            "AlphaInOut" => FloatPair,
+            "RankInOut" => IntPair,
+        }),
+        // Not a component: nested in TimeHeightConvolutionComponent <Model>.
+        "ConvolutionModel" => hashmap! {
+            "NumFiltersIn" => Int,
+            "NumFiltersOut" => Int,
+            "HeightIn" => Int,
+            "HeightOut" => Int,
+            "HeightSubsampleOut" => Int,
+            "Offsets" => IntPairVector,
+            "RequiredTimeOffsets" => IntVector,
+        },
+        "MaxpoolingComponent" => hashmap! {
+            "InputXDim" => Int,
+            "InputYDim" => Int,
+            "InputZDim" => Int,
+            "PoolXSize" => Int,
+            "PoolYSize" => Int,
+            "PoolZSize" => Int,
+            "PoolXStride" => Int,
+            "PoolYStride" => Int,
+            "PoolZStride" => Int,
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bin_int_vector() {
+        let bytes = [4, 3, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 1, 0, 0, 0];
+        assert_eq!(IntVector.parse_bin(&bytes).unwrap().1, tensor1(&[-1i32, 0, 1]));
+    }
+
+    #[test]
+    fn bin_int_pair_vector() {
+        let bytes = [4, 1, 0, 0, 0, 255, 255, 255, 255, 2, 0, 0, 0];
+        assert_eq!(IntPairVector.parse_bin(&bytes).unwrap().1, tensor2(&[[-1i32, 2]]));
+    }
+
+    #[test]
+    fn bin_empty_float_vector() {
+        let bytes = b"FV \x04\x00\x00\x00\x00";
+        assert_eq!(FloatVector.parse_bin(bytes).unwrap().1, tensor1::<f32>(&[]));
+    }
+}
@@ -7,7 +7,7 @@ use crate::model::{ComponentNode, ConfigLines, DimRangeNode, NodeLine, OutputLin
 use crate::parser::spaced;
 
 pub fn parse_config(s: &str) -> TractResult<ConfigLines> {
-    let mut input_node: Option<(String, usize)> = None;
+    let mut input_nodes = vec![];
     let mut nodes = vec![];
     let mut outputs = vec![];
     for line in s.lines() {
@@ -20,13 +20,11 @@ pub fn parse_config(s: &str) -> TractResult<ConfigLines> {
         }
         let line_kind = line.split(" ").next().unwrap();
         match line_kind {
-            "input-node" => {
-                input_node = Some(
-                    parse_input_node_line(line)
-                        .map_err(|e| format!("Error {:?} while parsing {}", e, line))?
-                        .1,
-                )
-            }
+            "input-node" => input_nodes.push(
+                parse_input_node_line(line)
+                    .map_err(|e| format!("Error {:?} while parsing {}", e, line))?
+                    .1,
+            ),
             "dim-range-node" => {
                 let (name, it) = parse_dim_range_node_line(line)
                     .map_err(|e| format!("Error {:?} while parsing {}", e, line))?
@@ -47,8 +45,10 @@ pub fn parse_config(s: &str) -> TractResult<ConfigLines> {
             _ => bail!("Unknown config line {}", line_kind),
         }
     }
-    let (input_name, input_dim) = input_node.unwrap();
-    Ok(ConfigLines { input_dim, input_name, nodes, outputs })
+    if input_nodes.is_empty() {
+        bail!("No input-node found in config")
+    }
+    Ok(ConfigLines { input_nodes, nodes, outputs })
 }
 
 fn parse_input_node_line(i: &str) -> IResult<&str, (String, usize)> {
@@ -1,46 +1,102 @@
 use nom::IResult;
 use nom::{
-    bytes::complete::*, character::complete::*, combinator::*, multi::separated_list, sequence::*,
+    branch::alt, bytes::complete::*, character::complete::*, combinator::*, multi::separated_list,
+    number::complete::float, sequence::*,
 };
 
 use crate::model::GeneralDescriptor;
+use crate::parser::config_lines::uinteger;
 use crate::parser::spaced;
 
 pub fn parse_general(i: &str) -> IResult<&str, GeneralDescriptor> {
-    spaced(nom::branch::alt((
+    spaced(alt((
+        map(preceded(keyword("Append"), cut(list)), GeneralDescriptor::Append),
+        preceded(
+            keyword("Offset"),
+            cut(map_res(
+                args(tuple((
+                    parse_general,
+                    preceded(comma, integer),
+                    opt(preceded(comma, integer)),
+                ))),
+                |(inner, t, x)| {
+                    // x indexes are not used by speech models
+                    if x.unwrap_or(0) != 0 {
+                        Err("Only null x-offsets are supported")
+                    } else {
+                        Ok(GeneralDescriptor::Offset(Box::new(inner), t as isize))
+                    }
+                },
+            )),
+        ),
+        map(preceded(keyword("IfDefined"), cut(args(parse_general))), |inner| {
+            GeneralDescriptor::IfDefined(Box::new(inner))
+        }),
         map(
             preceded(
-                tag("Append"),
-                cut(delimited(
-                    spaced(tag("(")),
-                    separated_list(spaced(tag(",")), parse_general),
-                    spaced(tag(")")),
-                )),
+                keyword("Sum"),
+                cut(args(separated_pair(parse_general, comma, parse_general))),
             ),
-            GeneralDescriptor::Append,
+            |(a, b)| GeneralDescriptor::Sum(Box::new(a), Box::new(b)),
         ),
         map(
-            preceded(
-                tag("Offset"),
-                cut(delimited(
-                    spaced(tag("(")),
-                    separated_pair(parse_general, spaced(tag(",")), integer),
-                    spaced(tag(")")),
-                )),
-            ),
-            |(inner, offset)| GeneralDescriptor::Offset(Box::new(inner), offset as isize),
+            preceded(keyword("Scale"), cut(args(separated_pair(float, comma, parse_general)))),
+            |(s, d)| GeneralDescriptor::Scale(s, Box::new(d)),
+        ),
+        map(
+            preceded(keyword("Const"), cut(args(separated_pair(float, comma, uinteger)))),
+            |(v, d)| GeneralDescriptor::Const(v, d),
+        ),
+        map(
+            preceded(keyword("Round"), cut(args(separated_pair(parse_general, comma, uinteger)))),
+            |(d, m)| GeneralDescriptor::Round(Box::new(d), m),
+        ),
+        preceded(
+            keyword("ReplaceIndex"),
+            cut(map_res(
+                args(tuple((
+                    parse_general,
+                    preceded(comma, alt((tag("t"), tag("x")))),
+                    preceded(comma, integer),
+                ))),
+                |(d, var, value)| match var {
+                    "t" => Ok(GeneralDescriptor::ReplaceIndex(Box::new(d), value as isize)),
+                    // x indexes are not used by speech models, x=0 is a no-op
+                    "x" if value == 0 => Ok(d),
+                    _ => Err("Only null x-index replacement is supported"),
+                },
+            )),
         ),
         map(
             preceded(
-                tag("IfDefined"),
-                cut(delimited(spaced(tag("(")), parse_general, spaced(tag(")")))),
+                keyword("Failover"),
+                cut(args(separated_pair(parse_general, comma, parse_general))),
             ),
-            |inner| GeneralDescriptor::IfDefined(Box::new(inner)),
+            |(a, b)| GeneralDescriptor::Failover(Box::new(a), Box::new(b)),
         ),
+        map(preceded(keyword("Switch"), cut(list)), GeneralDescriptor::Switch),
         map(super::config_lines::identifier, |i| GeneralDescriptor::Name(i.to_string())),
     )))(i)
 }
 
+fn keyword<'a>(k: &'static str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag(k), peek(spaced(tag("("))))
+}
+
+fn comma(i: &str) -> IResult<&str, &str> {
+    spaced(tag(","))(i)
+}
+
+fn args<'a, O>(
+    inner: impl Fn(&'a str) -> IResult<&'a str, O>,
+) -> impl Fn(&'a str) -> IResult<&'a str, O> {
+    delimited(spaced(tag("(")), inner, spaced(tag(")")))
+}
+
+fn list(i: &str) -> IResult<&str, Vec<GeneralDescriptor>> {
+    args(separated_list(comma, parse_general))(i)
+}
+
 pub fn integer(i: &str) -> IResult<&str, i32> {
     map_res(recognize(pair(opt(tag("-")), digit1)), |s: &str| s.parse::<i32>())(i)
 }
@@ -78,4 +134,36 @@ mod test {
             Append(vec!(name("input"), IfDefined(Offset(name("lstm1.c").into(), -1).into())))
         )
     }
+
+    #[test]
+    fn test_ivector() {
+        assert_eq!(
+            parse_general("Append(input, ReplaceIndex(ivector, t, 0))").unwrap().1,
+            Append(vec!(name("input"), ReplaceIndex(name("ivector").into(), 0)))
+        )
+    }
+
+    #[test]
+    fn test_sum_scale_const() {
+        assert_eq!(
+            parse_general("Sum(Scale(0.5, a), Const(1.0, 4))").unwrap().1,
+            Sum(Scale(0.5, name("a").into()).into(), Const(1.0, 4).into())
+        )
+    }
+
+    #[test]
+    fn test_round_switch_failover() {
+        assert_eq!(
+            parse_general("Switch(Round(a, 3), Failover(Offset(b, 1, 0), b))").unwrap().1,
+            Switch(vec!(
+                Round(name("a").into(), 3),
+                Failover(Offset(name("b").into(), 1).into(), name("b").into())
+            ))
+        )
+    }
+
+    #[test]
+    fn test_offset_x_is_rejected() {
+        assert!(parse_general("Offset(a, 0, 1)").is_err())
+    }
 }
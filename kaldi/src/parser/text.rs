@@ -2,38 +2,96 @@ use tract_core::internal::*;
 
 use nom::IResult;
 use nom::{
-    bytes::complete::*, character::complete::*, combinator::*, multi::separated_list,
-    number::complete::float, sequence::*,
+    bytes::complete::*,
+    character::complete::*,
+    combinator::*,
+    multi::{many1, separated_list},
+    number::complete::float,
+    sequence::*,
 };
 
-use super::{integer, multispaced, open_any, spaced};
+use super::components::{KaldiAttributeKind, COMPONENTS};
+use super::{close, integer, multispaced, open, open_any, spaced};
 
-pub fn attributes(i: &[u8]) -> IResult<&[u8], HashMap<String, Arc<Tensor>>> {
-    let (i, attributes) = nom::multi::many0(map(pair(open_any, tensor), |(k, v)| {
-        (k.to_string(), v.into_arc_tensor())
-    }))(i)?;
+pub fn attributes<'a>(i: &'a [u8], klass: &str) -> IResult<&'a [u8], HashMap<String, Arc<Tensor>>> {
+    let (i, attributes) = attribute_list(i, klass)?;
     Ok((i, attributes.into_iter().collect()))
 }
 
+fn attribute_list<'a>(i: &'a [u8], klass: &str) -> IResult<&'a [u8], Vec<(String, Arc<Tensor>)>> {
+    let (i, groups) = nom::multi::many0(nom::branch::alt((
+        nested_model,
+        map(|j| attribute(j, klass), |a| vec![a]),
+    )))(i)?;
+    Ok((i, groups.into_iter().flat_map(|g| g.into_iter()).collect()))
+}
+
+/// Attributes of a nested <ConvolutionModel> are flattened in the component.
+fn nested_model(i: &[u8]) -> IResult<&[u8], Vec<(String, Arc<Tensor>)>> {
+    preceded(
+        |i| open(i, "Model"),
+        delimited(
+            |i| open(i, "ConvolutionModel"),
+            |i| attribute_list(i, "ConvolutionModel"),
+            |i| close(i, "ConvolutionModel"),
+        ),
+    )(i)
+}
+
+/// A valueless attribute, like <Continuous>, is only accepted where the
+/// component declares it as a flag: its presence sets it to true.
+fn attribute<'a>(i: &'a [u8], klass: &str) -> IResult<&'a [u8], (String, Arc<Tensor>)> {
+    let (i, name) = open_any(i)?;
+    let kind = COMPONENTS.get(klass).and_then(|attributes| attributes.get(name));
+    let (i, value) = if let Some(KaldiAttributeKind::Flag) = kind {
+        (i, Tensor::from(true))
+    } else {
+        tensor(i)?
+    };
+    Ok((i, (name.to_string(), value.into_arc_tensor())))
+}
+
 pub fn tensor(i: &[u8]) -> IResult<&[u8], Tensor> {
-    nom::branch::alt((scalar, vector, matrix))(i)
+    nom::branch::alt((scalars, scalar, vector, pair_vector, matrix))(i)
 }
 
-pub fn scalar(i: &[u8]) -> IResult<&[u8], Tensor> {
+/// A boolean attribute value, written T or F.
+pub fn flag(i: &[u8]) -> IResult<&[u8], Tensor> {
     nom::branch::alt((
-        map(float, Tensor::from),
-        map(integer(false), Tensor::from),
         map(tag("F"), |_| Tensor::from(false)),
         map(tag("T"), |_| Tensor::from(true)),
     ))(i)
 }
 
+/// Several space separated values, like <RankInOut> 20 20.
+pub fn scalars(i: &[u8]) -> IResult<&[u8], Tensor> {
+    map(pair(float, many1(preceded(space1, float))), |(first, mut rest)| {
+        rest.insert(0, first);
+        tensor1(&*rest)
+    })(i)
+}
+
+pub fn scalar(i: &[u8]) -> IResult<&[u8], Tensor> {
+    nom::branch::alt((map(float, Tensor::from), map(integer(false), Tensor::from), flag))(i)
+}
+
 pub fn vector(i: &[u8]) -> IResult<&[u8], Tensor> {
     map(delimited(spaced(tag("[")), separated_list(space1, float), spaced(tag("]"))), |t| {
         tensor1(&*t)
     })(i)
 }
 
+pub fn pair_vector(i: &[u8]) -> IResult<&[u8], Tensor> {
+    let (i, v) = delimited(
+        spaced(tag("[")),
+        separated_list(space1, separated_pair(integer(false), tag(","), integer(false))),
+        multispaced(tag("]")),
+    )(i)?;
+    let data: Vec<i32> = v.iter().flat_map(|&(a, b)| vec![a, b]).collect();
+    let t = tract_core::ndarray::Array2::from_shape_vec((v.len(), 2), data).unwrap();
+    Ok((i, t.into_tensor()))
+}
+
 pub fn matrix(i: &[u8]) -> IResult<&[u8], Tensor> {
     let (i, v) = delimited(
         multispaced(tag("[")),
@@ -89,6 +147,18 @@ output-node name=output input=fixed1
         );
     }
 
+    #[test]
+    fn test_flag_and_bool() {
+        let slice = b"<Continuous> <TestMode> F ";
+        let (_, found) = attributes(slice, "GeneralDropoutComponent").unwrap();
+        assert_eq!(*found["Continuous"], Tensor::from(true));
+        assert_eq!(*found["TestMode"], Tensor::from(false));
+        // a valueless attribute is not a flag unless the component declares it so
+        let (rest, found) = attribute_list(slice, "SigmoidComponent").unwrap();
+        assert!(found.is_empty());
+        assert_eq!(rest, &slice[..]);
+    }
+
     #[test]
     fn fixed_affine_40x10_T40_S3() {
         let slice = std::fs::read("test_cases/fixed_affine_40x10_T40_S3/model.raw.txt").unwrap();
@@ -10,17 +10,33 @@ pub fn vec(len: usize, align: usize) -> *mut f32 {
 
 fn mat_vec_mul(c: &mut Criterion) {
     let mut group = c.benchmark_group("mat_vec_mul");
-    for (m, k) in [(64usize, 64usize)].iter() {
+    for (m, k) in [(64usize, 64usize), (256, 256), (1024, 1024)].iter() {
         group.throughput(Throughput::Elements((m * k) as u64));
         group.bench_with_input(
-            BenchmarkId::from_parameter(format!("{}x{}", m, k)),
+            BenchmarkId::new("mmm", format!("{}x{}", m, k)),
             &(m, k),
             |be, (&m, &k)| {
                 let mut mm = (tract_linalg::ops().smmm)(m, k, 1);
                 let pa = vec(mm.a_pack().len(), mm.a_pack().alignment());
                 let b = vec![0.0; k];
                 let mut c = vec![0.0; m];
-                unsafe { mm.b_vec_from_data(); }
+                unsafe {
+                    mm.b_vec_from_data();
+                }
+                be.iter(move || unsafe { mm.run(pa, b.as_ptr(), c.as_mut_ptr(), &[]) });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("mmv", format!("{}x{}", m, k)),
+            &(m, k),
+            |be, (&m, &k)| {
+                let mut mm = (tract_linalg::ops().smmv)(m, k);
+                let pa = vec(mm.a_pack().len(), mm.a_pack().alignment());
+                let b = vec![0.0; k];
+                let mut c = vec![0.0; m];
+                unsafe {
+                    mm.b_vec_from_data_and_stride(1);
+                }
                 be.iter(move || unsafe { mm.run(pa, b.as_ptr(), c.as_mut_ptr(), &[]) });
             },
         );
@@ -1,6 +1,7 @@
 use std::{env, fs};
 mod armv7neon;
 mod armvfpv2;
+use crate::frame::F16MatMatMulImpl;
 use crate::frame::MatMatMulImpl;
 use crate::frame::QMatMatMulImpl;
 use crate::frame::SigmoidImpl;
@@ -28,6 +29,9 @@ pub fn plug(ops: &mut Ops) {
         ops.smmm = Box::new(|m, k, n| {
             Box::new(MatMatMulImpl::<armv7neon::SMatMatMul8x4, f32, f32, f32, f32>::new(m, k, n))
         });
+        ops.smmm_f16 = Box::new(|m, k, n| {
+            Box::new(F16MatMatMulImpl::<armv7neon::SMatMatMul8x4>::new(m, k, n))
+        });
         ops.qmmm_i8_i8 = Box::new(|m, k, n| {
             Box::new(QMatMatMulImpl::from(MatMatMulImpl::<
                 armv7neon::I8MatMatMul8x4,
@@ -44,6 +48,8 @@ pub fn plug(ops: &mut Ops) {
         ops.smmm = Box::new(|m, k, n| {
             Box::new(MatMatMulImpl::<armvfpv2::SMatMatMul4x4, f32, f32, f32, f32>::new(m, k, n))
         });
+        ops.smmm_f16 =
+            Box::new(|m, k, n| Box::new(F16MatMatMulImpl::<armvfpv2::SMatMatMul4x4>::new(m, k, n)));
     }
 }
 
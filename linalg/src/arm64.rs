@@ -2,11 +2,13 @@ mod arm64simd;
 
 use crate::Ops;
 
-use crate::frame::MatMatMulImpl;
+use crate::frame::{F16MatMatMulImpl, MatMatMulImpl};
 
 pub fn plug(ops: &mut Ops) {
     log::info!("arm64simd activated for smmm");
     ops.smmm = Box::new(|m, k, n| {
         Box::new(MatMatMulImpl::<arm64simd::SMatMatMul8x8, f32, f32, f32, f32>::new(m, k, n))
     });
+    ops.smmm_f16 =
+        Box::new(|m, k, n| Box::new(F16MatMatMulImpl::<arm64simd::SMatMatMul8x8>::new(m, k, n)));
 }
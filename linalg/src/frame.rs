@@ -1,4 +1,6 @@
 #[macro_use]
+pub mod exp;
+#[macro_use]
 pub mod lut;
 #[macro_use]
 pub mod mmm;
@@ -7,6 +9,8 @@ pub mod pack_b;
 #[macro_use]
 pub mod sigmoid;
 #[macro_use]
+pub mod sparse;
+#[macro_use]
 pub mod tanh;
 
 pub use pack_a::PackA;
@@ -15,5 +19,7 @@ pub use pack_b::PackB;
 pub use self::mmm::*;
 pub use self::qmmm::*;
 
+pub use self::exp::ExpImpl;
 pub use self::sigmoid::SigmoidImpl;
+pub use self::sparse::SparseMatMulImpl;
 pub use self::tanh::TanhImpl;
@@ -4,6 +4,8 @@ pub(crate) mod fuse;
 pub(crate) mod kernel;
 #[macro_use]
 pub(crate) mod mmm;
+mod mmm_f16;
+mod mmv;
 #[macro_use]
 pub(crate) mod qmmm;
 mod storage;
@@ -11,5 +13,7 @@ mod storage;
 pub use fuse::*;
 pub use kernel::*;
 pub use mmm::*;
+pub use mmm_f16::*;
+pub use mmv::*;
 pub use qmmm::*;
 pub use storage::*;
@@ -19,6 +19,7 @@ pub enum FusedSpec<TI: Copy + Debug> {
     ScalarAdd(TI),
     QTowardsEven(TI, usize),
     QTowardsPlusInf(TI, usize),
+    PerRowQTowardsPlusInf(Vec<TI>, Vec<usize>),
 }
 
 impl<TI: Copy + Debug> Debug for FusedSpec<TI> {
@@ -36,6 +37,7 @@ impl<TI: Copy + Debug> Debug for FusedSpec<TI> {
             FusedSpec::ScalarAdd(_) => write!(fmt, "ScalarAdd"),
             FusedSpec::QTowardsEven(_, _) => write!(fmt, "QTowardsEven"),
             FusedSpec::QTowardsPlusInf(_, _) => write!(fmt, "QTowardsPlusInf"),
+            FusedSpec::PerRowQTowardsPlusInf(_, _) => write!(fmt, "PerRowQTowardsPlusInf"),
         }
     }
 }
@@ -56,16 +58,22 @@ pub enum FusedKerSpec<TI: Copy> {
     ScalarAdd(TI),
     QTowardsEven(TI, usize),
     QTowardsPlusInf(TI, usize),
+    PerRowQTowardsPlusInf(*const TI, *const usize),
 }
 
 pub struct ScratchSpaceFusedNonLinear<TI: Copy> {
     uspecs: Vec<FusedKerSpec<TI>>,
     non_linear_buffers: Vec<Vec<TI>>,
+    shift_buffers: Vec<Vec<usize>>,
 }
 
 impl<TI: Copy> Default for ScratchSpaceFusedNonLinear<TI> {
     fn default() -> ScratchSpaceFusedNonLinear<TI> {
-        ScratchSpaceFusedNonLinear { uspecs: vec![], non_linear_buffers: vec![] }
+        ScratchSpaceFusedNonLinear {
+            uspecs: vec![],
+            non_linear_buffers: vec![],
+            shift_buffers: vec![],
+        }
     }
 }
 
@@ -167,6 +175,22 @@ impl<TI: Copy> ScratchSpaceFusedNonLinear<TI> {
                 FusedSpec::ScalarAdd(t) => FusedKerSpec::ScalarAdd(*t),
                 FusedSpec::QTowardsEven(m, s) => FusedKerSpec::QTowardsEven(*m, *s),
                 FusedSpec::QTowardsPlusInf(m, s) => FusedKerSpec::QTowardsPlusInf(*m, *s),
+                FusedSpec::PerRowQTowardsPlusInf(mults, shifts) => {
+                    let have = mults.len() - down * K::mr();
+                    let (mult_ptr, shift_ptr) = if have < K::mr() {
+                        let mut buf = vec![TI::zero(); K::mr()];
+                        buf[..have].copy_from_slice(&mults[down * K::mr()..][..have]);
+                        let mut shift_buf = vec![0; K::mr()];
+                        shift_buf[..have].copy_from_slice(&shifts[down * K::mr()..][..have]);
+                        let ptrs = (buf.as_ptr(), shift_buf.as_ptr());
+                        self.non_linear_buffers.push(buf);
+                        self.shift_buffers.push(shift_buf);
+                        ptrs
+                    } else {
+                        (mults.as_ptr().add(down * K::mr()), shifts.as_ptr().add(down * K::mr()))
+                    };
+                    FusedKerSpec::PerRowQTowardsPlusInf(mult_ptr, shift_ptr)
+                }
             };
             self.uspecs.push(s);
         }
@@ -287,6 +311,13 @@ pub mod test {
                         test::return_q_towards_plusinf::<$ker, $ta, $tb, $tc, $ti>()
                     }
                 }
+
+                #[test]
+                fn return_per_row_q_towards_plusinf() {
+                    if $cond {
+                        test::return_per_row_q_towards_plusinf::<$ker, $ta, $tb, $tc, $ti>()
+                    }
+                }
             }
         };
     }
@@ -645,4 +676,48 @@ pub mod test {
             trunc.as_() == found
         }));
     }
+
+    pub fn return_per_row_q_towards_plusinf<K, TA, TB, TC, TI>()
+    where
+        K: MatMatMulKer<TA, TB, TC, TI>,
+        TA: Copy,
+        TB: Copy,
+        TC: Copy + PartialEq + 'static + Bounded + Debug + AsPrimitive<TI>,
+        TI: Copy + Sub<Output = TI> + Debug + Ord + 'static + AsPrimitive<TC> + AsPrimitive<i64>,
+        usize: AsPrimitive<TC> + AsPrimitive<TI>,
+        i64: AsPrimitive<TC> + AsPrimitive<TI>,
+    {
+        let len = K::mr() * K::nr();
+        let half_len: TI = (len / 2).as_();
+        let v: Vec<TC> = (0..len)
+            .map(|f| {
+                (<usize as AsPrimitive<TI>>::as_(f) - half_len)
+                    .min(TC::max_value().as_())
+                    .max(TC::min_value().as_())
+                    .as_()
+            })
+            .collect();
+        let mults: Vec<i64> = (0..K::mr() as i64).map(|i| (1 << 30) + i * 0x1234567).collect();
+        let shifts: Vec<usize> = (0..K::mr()).map(|i| 8 + i % 3).collect();
+        let ti_mults: Vec<TI> = mults.iter().map(|m| m.as_()).collect();
+        let found = fused_ops::<K, TA, TB, TC, TI>(
+            &*v,
+            &[
+                FusedKerSpec::ScalarMul(1000i64.as_()),
+                FusedKerSpec::PerRowQTowardsPlusInf(ti_mults.as_ptr(), shifts.as_ptr()),
+            ],
+        );
+        let expected: Vec<TC> = v
+            .iter()
+            .enumerate()
+            .map(|(ix, input)| {
+                let row = ix / K::nr();
+                let input: TI = input.as_();
+                let input: i64 = input.as_();
+                let v = (input * 1000 * mults[row]) >> (30 + shifts[row]);
+                ((v + 1) >> 1).as_()
+            })
+            .collect();
+        assert_eq!(found, expected);
+    }
 }
@@ -114,6 +114,103 @@ where
     }
 }
 
+impl<K, TA, TB, TC, TI> MatMatMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero + Debug,
+    TB: Copy + Zero + Debug,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+    K: MatMatMulKer<TA, TB, TC, TI>,
+{
+    /// Run the product, getting each row panel of A from `panel_a` right
+    /// before the kernel goes through it.
+    pub(crate) unsafe fn run_with_a_panels(
+        &self,
+        mut panel_a: impl FnMut(usize) -> PanelStore<TA>,
+        b: *const TB,
+        c: *mut TC,
+        non_linear: &[FusedSpec<TI>],
+    ) {
+        let mr = K::mr();
+        let nr = K::nr();
+        let m = self.m;
+        let n = self.n;
+        let mut scratch = ScratchSpaceFusedNonLinear::default();
+        let mut tmpc = Vec::with_capacity(mr * nr);
+        tmpc.set_len(mr * nr);
+        let tmp_c_storage = MatrixStoreSpec::Strides {
+            row_byte_stride: (std::mem::size_of::<TC>() * nr) as isize,
+            col_byte_stride: std::mem::size_of::<TC>() as isize,
+            mr,
+            nr,
+        };
+        let ref mut tmp_tile = tmp_c_storage.wrap(tmpc.as_ptr());
+        let b = self.b_storage.wrap(b);
+        let mut c = self.c_storage.wrap(c);
+        let ref linear = LinearSpec::k(self.k);
+        for ia in 0..m / mr {
+            let ref a = panel_a(ia);
+            for ib in 0..n / nr {
+                let ref b = b.panel_b(nr, ib, nr);
+                let ref direct_c = c.tile_c(ia, ib);
+                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, ia, ib);
+                let err = K::kernel(&MatMatMulKerSpec {
+                    a: a as _,
+                    b: b as _,
+                    c: direct_c as _,
+                    linear,
+                    non_linear,
+                });
+                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+            }
+            if n % nr != 0 {
+                let ref b = b.panel_b(nr, n / nr, n % nr);
+                let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
+                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, ia, n / nr);
+                let err = K::kernel(&MatMatMulKerSpec {
+                    a: a as _,
+                    b: b as _,
+                    c: tmp_tile_c as _,
+                    linear,
+                    non_linear,
+                });
+                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                c.set_from_tile(ia, n / nr, mr, n % nr, &*tmpc);
+            }
+        }
+        if m % mr != 0 {
+            let ref panel_a = panel_a(m / mr);
+            let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
+            for ib in 0..n / nr {
+                let ref b = b.panel_b(nr, ib, nr);
+                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, m / mr, ib);
+                let err = K::kernel(&MatMatMulKerSpec {
+                    a: panel_a as _,
+                    b: b as _,
+                    c: tmp_tile_c as _,
+                    linear,
+                    non_linear,
+                });
+                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                c.set_from_tile(m / mr, ib, m % mr, nr, &*tmpc);
+            }
+            if n % nr != 0 {
+                let ref b = b.panel_b(nr, n / nr, n % nr);
+                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, m / mr, n / nr);
+                let err = K::kernel(&MatMatMulKerSpec {
+                    a: panel_a as _,
+                    b: b as _,
+                    c: tmp_tile_c as _,
+                    linear,
+                    non_linear,
+                });
+                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                c.set_from_tile(m / mr, n / nr, m % mr, n % nr, &*tmpc);
+            }
+        }
+    }
+}
+
 impl<K, TA, TB, TC, TI> MatMatMul<TA, TB, TC, TI> for MatMatMulImpl<K, TA, TB, TC, TI>
 where
     TA: Copy + Zero + Debug,
@@ -211,84 +308,8 @@ where
     }
 
     unsafe fn run(&self, a: *const TA, b: *const TB, c: *mut TC, non_linear: &[FusedSpec<TI>]) {
-        let mr = K::mr();
-        let nr = K::nr();
-        let m = self.m;
-        let n = self.n;
-        let mut scratch = ScratchSpaceFusedNonLinear::default();
-        let mut tmpc = Vec::with_capacity(mr * nr);
-        tmpc.set_len(mr * nr);
-        let tmp_c_storage = MatrixStoreSpec::Strides {
-            row_byte_stride: (std::mem::size_of::<TC>() * nr) as isize,
-            col_byte_stride: std::mem::size_of::<TC>() as isize,
-            mr,
-            nr,
-        };
-        let ref mut tmp_tile = tmp_c_storage.wrap(tmpc.as_ptr());
         let a = self.a_storage.wrap(a);
-        let b = self.b_storage.wrap(b);
-        let mut c = self.c_storage.wrap(c);
-        let ref linear = LinearSpec::k(self.k);
-        for ia in 0..m / mr {
-            let ref a = a.panel_a(ia);
-            for ib in 0..n / nr {
-                let ref b = b.panel_b(nr, ib, nr);
-                let ref direct_c = c.tile_c(ia, ib);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, ia, ib);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: a as _,
-                    b: b as _,
-                    c: direct_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-            }
-            if n % nr != 0 {
-                let ref b = b.panel_b(nr, n / nr, n % nr);
-                let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, ia, n / nr);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: a as _,
-                    b: b as _,
-                    c: tmp_tile_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-                c.set_from_tile(ia, n / nr, mr, n % nr, &*tmpc);
-            }
-        }
-        if m % mr != 0 {
-            let ref panel_a = a.panel_a(m / mr);
-            let ref tmp_tile_c = tmp_tile.tile_c(0, 0);
-            for ib in 0..n / nr {
-                let ref b = b.panel_b(nr, ib, nr);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, m / mr, ib);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: panel_a as _,
-                    b: b as _,
-                    c: tmp_tile_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-                c.set_from_tile(m / mr, ib, m % mr, nr, &*tmpc);
-            }
-            if n % nr != 0 {
-                let ref b = b.panel_b(nr, n / nr, n % nr);
-                let non_linear = scratch.for_tile::<TA, TB, TC, K>(non_linear, m / mr, n / nr);
-                let err = K::kernel(&MatMatMulKerSpec {
-                    a: panel_a as _,
-                    b: b as _,
-                    c: tmp_tile_c as _,
-                    linear,
-                    non_linear,
-                });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
-                c.set_from_tile(m / mr, n / nr, m % mr, n % nr, &*tmpc);
-            }
-        }
+        self.run_with_a_panels(|ia| a.panel_a(ia), b, c, non_linear)
     }
 }
 
@@ -340,6 +361,13 @@ pub mod test {
                         }
                     }
 
+                    #[test]
+                    fn mat_vec_panels((m, k, ref a, ref b) in strat_mat_vec_mul_panels()) {
+                        if $cond {
+                            test_mat_vec_mul_prep::<$ker>(m, k, a, b)?
+                        }
+                    }
+
                     #[test]
                     fn conv_prepacked(pb in strat_conv_1d()) {
                         if $cond {
@@ -475,7 +503,20 @@ pub mod test {
     }
 
     pub fn strat_mat_vec_mul() -> BoxedStrategy<(usize, usize, Vec<f32>, Vec<f32>)> {
-        (1usize..5, 1usize..5)
+        strat_mat_vec_mul_sized(1..5, 1..5)
+    }
+
+    /// Wide enough to span several panels of the matrix-vector kernels and
+    /// their unrolled k loops.
+    pub fn strat_mat_vec_mul_panels() -> BoxedStrategy<(usize, usize, Vec<f32>, Vec<f32>)> {
+        strat_mat_vec_mul_sized(1..80, 1..20)
+    }
+
+    fn strat_mat_vec_mul_sized(
+        m: std::ops::Range<usize>,
+        k: std::ops::Range<usize>,
+    ) -> BoxedStrategy<(usize, usize, Vec<f32>, Vec<f32>)> {
+        (m, k)
             .prop_flat_map(move |(m, k)| {
                 (
                     Just(m),
@@ -542,7 +583,7 @@ pub mod test {
         b: &[f32],
     ) -> Result<(), proptest::test_runner::TestCaseError> {
         unsafe {
-            let mut op = MatMatMulImpl::<K, f32, f32, f32, f32>::new(m, k, 1);
+            let mut op = MatVecMulImpl::<K, f32, f32, f32, f32>::new(m, k);
             op.b_vec_from_data_and_stride(1);
             op.c_vec_from_data_and_stride(1);
             let mut packed_a = Buffer::uninitialized(op.a_pack().len(), op.a_pack().alignment());
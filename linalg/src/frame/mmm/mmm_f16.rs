@@ -0,0 +1,162 @@
+use std::fmt;
+
+use crate::align::Buffer;
+use crate::f16::f16;
+use crate::frame::{PackA, PackB};
+
+use super::*;
+
+/// Product of f16 weights by f32 values, accumulated in f32 by an f32 kernel.
+///
+/// A is packed as f16, in the panel layout of the f32 kernel, so the packed
+/// weights take half the memory and half the bandwidth. Each panel is
+/// converted to f32 (with F16C where available) right before the kernel goes
+/// through it.
+#[derive(Debug, Clone)]
+pub struct F16MatMatMulImpl<K>
+where
+    K: MatMatMulKer<f32, f32, f32, f32>,
+{
+    pub mmm: MatMatMulImpl<K, f32, f32, f32, f32>,
+}
+
+impl<K> F16MatMatMulImpl<K>
+where
+    K: MatMatMulKer<f32, f32, f32, f32>,
+{
+    pub fn new(m: usize, k: usize, n: usize) -> F16MatMatMulImpl<K> {
+        F16MatMatMulImpl { mmm: MatMatMulImpl::new(m, k, n) }
+    }
+}
+
+impl<K> MatMatMul<f16, f32, f32, f32> for F16MatMatMulImpl<K>
+where
+    K: MatMatMulKer<f32, f32, f32, f32>,
+{
+    fn a_pack(&self) -> PackA<f16> {
+        PackA::new(self.mmm.k, self.mmm.m, K::mr(), K::alignment_bytes_packed_a())
+    }
+
+    fn b_pack(&self) -> PackB<f32> {
+        self.mmm.b_pack()
+    }
+
+    fn a_storage(&self) -> &MatrixStoreSpec {
+        self.mmm.a_storage()
+    }
+
+    fn b_storage(&self) -> &MatrixStoreSpec {
+        self.mmm.b_storage()
+    }
+
+    fn c_storage(&self) -> &MatrixStoreSpec {
+        self.mmm.c_storage()
+    }
+
+    fn m(&self) -> usize {
+        self.mmm.m
+    }
+
+    fn k(&self) -> usize {
+        self.mmm.k
+    }
+
+    fn n(&self) -> usize {
+        self.mmm.n
+    }
+
+    unsafe fn b_from_data_and_offsets(&mut self, rows_offsets: &[isize], cols_offsets: &[isize]) {
+        self.mmm.b_from_data_and_offsets(rows_offsets, cols_offsets)
+    }
+
+    unsafe fn b_vec_from_data_and_stride(&mut self, stride: isize) {
+        self.mmm.b_vec_from_data_and_stride(stride)
+    }
+
+    unsafe fn b_vec_from_data(&mut self) {
+        self.mmm.b_vec_from_data()
+    }
+
+    unsafe fn c_from_data_and_strides(&mut self, row_stride: isize, col_stride: isize) {
+        self.mmm.c_from_data_and_strides(row_stride, col_stride)
+    }
+
+    unsafe fn c_vec_from_data_and_stride(&mut self, stride: isize) {
+        self.mmm.c_vec_from_data_and_stride(stride)
+    }
+
+    unsafe fn c_vec_from_data(&mut self) {
+        self.mmm.c_vec_from_data()
+    }
+
+    unsafe fn run(&self, a: *const f16, b: *const f32, c: *mut f32, non_linear: &[FusedSpec<f32>]) {
+        let panel_len = self.mmm.k * K::mr();
+        let mut panel = Buffer::uninitialized(panel_len, K::alignment_bytes_packed_a());
+        let convert = &crate::ops().f16_to_f32;
+        self.mmm.run_with_a_panels(
+            |ia| {
+                convert(std::slice::from_raw_parts(a.add(ia * panel_len), panel_len), &mut panel);
+                PanelStore::Packed { ptr: panel.as_ptr() }
+            },
+            b,
+            c,
+            non_linear,
+        )
+    }
+}
+
+impl<K> fmt::Display for F16MatMatMulImpl<K>
+where
+    K: MatMatMulKer<f32, f32, f32, f32>,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} (f16 A)", self.mmm)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generic::GenericMmm4x4;
+    use proptest::prelude::*;
+
+    fn strategy() -> BoxedStrategy<(usize, usize, usize, Vec<f32>, Vec<f32>)> {
+        (1usize..10, 1usize..10, 1usize..10)
+            .prop_flat_map(|(m, k, n)| {
+                let small = (-10i8..10).prop_map(|x| x as f32);
+                (
+                    Just(m),
+                    Just(k),
+                    Just(n),
+                    proptest::collection::vec(small.clone(), m * k..=m * k),
+                    proptest::collection::vec(small, k * n..=k * n),
+                )
+            })
+            .boxed()
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn f16_a((m, k, n, a, b) in strategy()) {
+            let mmm = F16MatMatMulImpl::<GenericMmm4x4<f32, f32, f32, f32>>::new(m, k, n);
+            let halves: Vec<f16> = a.iter().map(|x| f16(half::f16::from_f32(*x))).collect();
+            let mut expected = vec![0f32; m * n];
+            let mut found = vec![0f32; m * n];
+            unsafe {
+                let mut pa = Buffer::uninitialized(mmm.a_pack().len(), mmm.a_pack().alignment());
+                mmm.a_pack().pack(pa.as_mut_ptr(), halves.as_ptr(), k as isize, 1);
+                let mut pb = Buffer::uninitialized(mmm.b_pack().len(), mmm.b_pack().alignment());
+                mmm.b_pack().pack(pb.as_mut_ptr(), b.as_ptr(), n as isize, 1);
+                mmm.run(pa.as_ptr(), pb.as_ptr(), found.as_mut_ptr(), &[]);
+            }
+            for row in 0..m {
+                for col in 0..n {
+                    for i in 0..k {
+                        expected[row * n + col] += a[row * k + i] * b[i * n + col];
+                    }
+                }
+            }
+            prop_assert_eq!(found, expected);
+        }
+    }
+}
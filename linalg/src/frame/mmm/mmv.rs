@@ -0,0 +1,121 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::ops::{Add, Mul};
+
+use num_traits::Zero;
+
+use crate::frame::PackA;
+
+use super::*;
+
+/// Product of a packed matrix by a single column, given either as a packed
+/// panel or in place with `b_vec_from_data_and_stride`.
+pub trait MatVecMul<TA, TB, TC, TI>: Debug + fmt::Display + dyn_clone::DynClone + Send + Sync
+where
+    TA: Copy + Zero,
+    TB: Copy + Zero,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+{
+    fn as_mmm(&self) -> &dyn MatMatMul<TA, TB, TC, TI>;
+    fn as_mmm_mut(&mut self) -> &mut dyn MatMatMul<TA, TB, TC, TI>;
+
+    fn a_pack(&self) -> PackA<TA> {
+        self.as_mmm().a_pack()
+    }
+
+    fn m(&self) -> usize {
+        self.as_mmm().m()
+    }
+
+    fn k(&self) -> usize {
+        self.as_mmm().k()
+    }
+
+    unsafe fn b_vec_from_data_and_stride(&mut self, stride: isize) {
+        self.as_mmm_mut().b_vec_from_data_and_stride(stride)
+    }
+
+    unsafe fn c_vec_from_data_and_stride(&mut self, stride: isize) {
+        self.as_mmm_mut().c_vec_from_data_and_stride(stride)
+    }
+
+    unsafe fn run(&self, a: *const TA, b: *const TB, c: *mut TC, non_linear: &[FusedSpec<TI>]) {
+        self.as_mmm().run(a, b, c, non_linear)
+    }
+}
+
+dyn_clone::clone_trait_object!(<TA, TB, TC, TI> MatVecMul<TA, TB, TC, TI> where
+    TA: Copy + Zero,
+    TB: Copy + Zero,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+);
+
+#[derive(Debug, Clone)]
+pub struct MatVecMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero,
+    TB: Copy + Zero,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+    K: MatMatMulKer<TA, TB, TC, TI>,
+{
+    pub mmm: MatMatMulImpl<K, TA, TB, TC, TI>,
+}
+
+impl<K, TA, TB, TC, TI> MatVecMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero,
+    TB: Copy + Zero,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+    K: MatMatMulKer<TA, TB, TC, TI>,
+{
+    pub fn new(m: usize, k: usize) -> MatVecMulImpl<K, TA, TB, TC, TI> {
+        MatVecMulImpl { mmm: MatMatMulImpl::new(m, k, 1) }
+    }
+}
+
+impl<K, TA, TB, TC, TI> MatVecMul<TA, TB, TC, TI> for MatVecMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero + Debug,
+    TB: Copy + Zero + Debug,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+    K: MatMatMulKer<TA, TB, TC, TI>,
+{
+    fn as_mmm(&self) -> &dyn MatMatMul<TA, TB, TC, TI> {
+        &self.mmm
+    }
+
+    fn as_mmm_mut(&mut self) -> &mut dyn MatMatMul<TA, TB, TC, TI> {
+        &mut self.mmm
+    }
+}
+
+impl<K, TA, TB, TC, TI> From<MatVecMulImpl<K, TA, TB, TC, TI>> for QMatMatMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero + Debug,
+    TB: Copy + Zero + Debug,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+    K: MatMatMulKer<TA, TB, TC, TI>,
+{
+    fn from(mmv: MatVecMulImpl<K, TA, TB, TC, TI>) -> QMatMatMulImpl<K, TA, TB, TC, TI> {
+        QMatMatMulImpl::from(mmv.mmm)
+    }
+}
+
+impl<K, TA, TB, TC, TI> fmt::Display for MatVecMulImpl<K, TA, TB, TC, TI>
+where
+    TA: Copy + Zero,
+    TB: Copy + Zero,
+    TC: Copy + Debug,
+    TI: Copy + Add + Mul + Zero + Debug,
+    K: MatMatMulKer<TA, TB, TC, TI>,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} (mat-vec)", self.mmm)
+    }
+}
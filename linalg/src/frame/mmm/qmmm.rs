@@ -25,8 +25,25 @@ where
 
     unsafe fn set_zero_point_c_scalar(&mut self, value: TC);
     unsafe fn set_scale_factor(&mut self, factor: f32);
+    unsafe fn set_scale_factor_per_row(&mut self, factors: &[f32]);
+
+    /// Quantization parameters set on the operator.
+    fn q_params(&self) -> &QMatMatMulParams<TA, TB, TC, TI>;
+
+    /// Run the product with quantization parameters given at call time
+    /// instead of the ones set on the operator.
+    unsafe fn run_with_q_params(
+        &self,
+        a: *const TA,
+        b: *const TB,
+        c: *mut TC,
+        non_linear: &[FusedSpec<TI>],
+        params: &QMatMatMulParams<TA, TB, TC, TI>,
+    );
 
-    unsafe fn run(&self, a: *const TA, b: *const TB, c: *mut TC, non_linear: &[FusedSpec<TI>]);
+    unsafe fn run(&self, a: *const TA, b: *const TB, c: *mut TC, non_linear: &[FusedSpec<TI>]) {
+        self.run_with_q_params(a, b, c, non_linear, self.q_params())
+    }
 }
 
 dyn_clone::clone_trait_object!(<TA, TB, TC, TI> QMatMatMul<TA, TB, TC, TI> where
@@ -36,12 +53,87 @@ dyn_clone::clone_trait_object!(<TA, TB, TC, TI> QMatMatMul<TA, TB, TC, TI> where
     TI: Copy + Add + Mul + Zero + fmt::Debug,
 );
 
+/// Fixed point multiplier and shift for QTowardsEven and QTowardsPlusInf.
+pub fn scale_factor_as_mult_shift(factor: f32) -> (i32, usize) {
+    // https://github.com/microsoft/onnxruntime/blob/master/onnxruntime/core/util/gemmlowp_common.h#L16
+    let factor_bits = factor.to_bits();
+    let current_exponent = factor_bits >> 23;
+    let bumped_multi = f32::from_bits(factor_bits & 0x007fffff | 0x3f000000);
+    let int_multi = (bumped_multi * (1i64 << 31) as f32).round() as i32;
+    let shift = 126 - current_exponent;
+    (int_multi, shift as usize)
+}
+
+/// Fused operations scaling each row of the accumulator by its own factor,
+/// with one fixed point multiplier and shift per row.
+pub fn per_row_scale_ops<TI>(factors: &[f32]) -> Vec<FusedSpec<TI>>
+where
+    TI: Copy + fmt::Debug + 'static,
+    i32: AsPrimitive<TI>,
+{
+    let (mults, shifts) = factors
+        .iter()
+        .map(|&f| {
+            if f == 0.0 {
+                (0.as_(), 0)
+            } else {
+                // past 32, the shifted product is always rounded to zero
+                let (mult, shift) = scale_factor_as_mult_shift(f);
+                (mult.as_(), shift.min(32))
+            }
+        })
+        .unzip();
+    vec![FusedSpec::PerRowQTowardsPlusInf(mults, shifts)]
+}
+
 #[derive(Debug, Clone)]
 pub enum QuantizedParam<TI> {
     Scalar(TI),
     Vector(Vec<TI>),
 }
 
+impl<TI: Copy> QuantizedParam<TI> {
+    /// Extend a vector parameter over `len` rows or columns to a whole number
+    /// of kernel panels, repeating its last value. Scalars are left alone.
+    fn padded(&self, len: usize, panel: usize) -> QuantizedParam<TI> {
+        match self {
+            QuantizedParam::Scalar(_) => self.clone(),
+            QuantizedParam::Vector(values) => {
+                let wanted = len.div_ceil(panel) * panel;
+                let mut values = values.clone();
+                while values.len() < wanted {
+                    values.push(values[values.len() - 1])
+                }
+                QuantizedParam::Vector(values)
+            }
+        }
+    }
+}
+
+/// Zero points of the operands and of the result, and requantization of the
+/// accumulators.
+#[derive(Debug, Clone)]
+pub struct QMatMatMulParams<TA, TB, TC, TI: Copy + Debug> {
+    pub zero_point_a: Option<QuantizedParam<TA>>,
+    pub zero_point_b: Option<QuantizedParam<TB>>,
+
+    pub zero_point_c: Option<TC>,
+    pub scale_factor: Option<(TI, usize)>,
+    pub scale_factor_per_row: Option<Vec<FusedSpec<TI>>>,
+}
+
+impl<TA, TB, TC, TI: Copy + Debug> Default for QMatMatMulParams<TA, TB, TC, TI> {
+    fn default() -> QMatMatMulParams<TA, TB, TC, TI> {
+        QMatMatMulParams {
+            zero_point_a: None,
+            zero_point_b: None,
+            zero_point_c: None,
+            scale_factor: None,
+            scale_factor_per_row: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct QMatMatMulImpl<K, TA, TB, TC, TI>
 where
@@ -52,11 +144,7 @@ where
     K: MatMatMulKer<TA, TB, TC, TI>,
 {
     pub mmm: MatMatMulImpl<K, TA, TB, TC, TI>,
-    pub zero_point_a: Option<QuantizedParam<TA>>,
-    pub zero_point_b: Option<QuantizedParam<TB>>,
-
-    pub zero_point_c: Option<TC>,
-    pub scale_factor: Option<(TI, usize)>,
+    pub params: QMatMatMulParams<TA, TB, TC, TI>,
 }
 
 impl<K, TA, TB, TC, TI> QMatMatMulImpl<K, TA, TB, TC, TI>
@@ -148,13 +236,7 @@ where
     K: MatMatMulKer<TA, TB, TC, TI>,
 {
     fn from(mmm: MatMatMulImpl<K, TA, TB, TC, TI>) -> QMatMatMulImpl<K, TA, TB, TC, TI> {
-        QMatMatMulImpl {
-            mmm,
-            zero_point_a: None,
-            zero_point_b: None,
-            zero_point_c: None,
-            scale_factor: None,
-        }
+        QMatMatMulImpl { mmm, params: QMatMatMulParams::default() }
     }
 }
 
@@ -194,10 +276,17 @@ where
 
 impl<K, TA, TB, TC, TI> QMatMatMul<TA, TB, TC, TI> for QMatMatMulImpl<K, TA, TB, TC, TI>
 where
-    TA: Copy + Zero + fmt::Debug + AsPrimitive<TI>,
-    TB: Copy + Zero + fmt::Debug + AsPrimitive<TI>,
+    TA: Copy + Zero + fmt::Debug + Bounded + AsPrimitive<TI>,
+    TB: Copy + Zero + fmt::Debug + Bounded + AsPrimitive<TI>,
     TC: Copy + fmt::Debug + Bounded + AsPrimitive<TI>,
-    TI: Copy + Add + Mul<Output = TI> + Zero + Neg<Output = TI> + fmt::Debug + 'static,
+    TI: Copy
+        + Add
+        + Mul<Output = TI>
+        + Zero
+        + Neg<Output = TI>
+        + fmt::Debug
+        + AsPrimitive<f32>
+        + 'static,
     K: MatMatMulKer<TA, TB, TC, TI>,
     usize: AsPrimitive<TI>,
     i32: AsPrimitive<TI>,
@@ -211,51 +300,55 @@ where
     }
 
     unsafe fn set_zero_point_a_scalar(&mut self, value: TA) {
-        self.zero_point_a = Some(QuantizedParam::Scalar(value))
+        self.params.zero_point_a = Some(QuantizedParam::Scalar(value))
     }
 
     unsafe fn set_zero_point_b_scalar(&mut self, value: TB) {
-        self.zero_point_b = Some(QuantizedParam::Scalar(value))
+        self.params.zero_point_b = Some(QuantizedParam::Scalar(value))
     }
 
     unsafe fn set_zero_point_c_scalar(&mut self, value: TC) {
-        self.zero_point_c = Some(value)
+        self.params.zero_point_c = Some(value)
     }
 
-    unsafe fn set_zero_point_a_vector(&mut self, mut values: Vec<TA>) {
-        let wanted = self.m() + K::mr() - 1 / K::mr() * K::mr();
-        while values.len() < wanted {
-            values.push(values[values.len() - 1])
-        }
-        self.zero_point_a = Some(QuantizedParam::Vector(values))
+    unsafe fn set_zero_point_a_vector(&mut self, values: Vec<TA>) {
+        self.params.zero_point_a = Some(QuantizedParam::Vector(values).padded(self.m, K::mr()))
     }
 
-    unsafe fn set_zero_point_b_vector(&mut self, mut values: Vec<TB>) {
-        let wanted = self.n() + K::nr() - 1 / K::nr() * K::nr();
-        while values.len() < wanted {
-            values.push(values[values.len() - 1])
-        }
-        self.zero_point_b = Some(QuantizedParam::Vector(values))
+    unsafe fn set_zero_point_b_vector(&mut self, values: Vec<TB>) {
+        self.params.zero_point_b = Some(QuantizedParam::Vector(values).padded(self.n, K::nr()))
     }
 
     unsafe fn set_scale_factor(&mut self, factor: f32) {
-        // https://github.com/microsoft/onnxruntime/blob/master/onnxruntime/core/util/gemmlowp_common.h#L16
-        let factor_bits = factor.to_bits();
-        let current_exponent = factor_bits >> 23;
-        let bumped_multi = f32::from_bits(factor_bits & 0x007fffff | 0x3f000000);
-        let int_multi = (bumped_multi * (1i64 << 31) as f32).round() as i32;
-        let shift = 126 - current_exponent;
-        self.scale_factor = Some((int_multi.as_(), shift as usize));
+        let (mult, shift) = scale_factor_as_mult_shift(factor);
+        self.params.scale_factor = Some((mult.as_(), shift));
     }
 
-    unsafe fn run(&self, a: *const TA, b: *const TB, c: *mut TC, non_linear: &[FusedSpec<TI>]) {
+    unsafe fn set_scale_factor_per_row(&mut self, factors: &[f32]) {
+        self.params.scale_factor_per_row = Some(per_row_scale_ops(factors));
+    }
+
+    fn q_params(&self) -> &QMatMatMulParams<TA, TB, TC, TI> {
+        &self.params
+    }
+
+    unsafe fn run_with_q_params(
+        &self,
+        a: *const TA,
+        b: *const TB,
+        c: *mut TC,
+        non_linear: &[FusedSpec<TI>],
+        params: &QMatMatMulParams<TA, TB, TC, TI>,
+    ) {
         /* SUM_k( A[m,k] * B[k,n] )
             = SUM_k( A'[m,k] * B'[k,n] )
             - A0[m] * SUM_k(B'[k,n])
             + (A0[m].K - SUM_k(A'[m,k])) * B0[n]
         */
         let mut non_linear = non_linear.to_vec();
-        if let Some(ref a0) = self.zero_point_a {
+        let zero_point_a = params.zero_point_a.as_ref().map(|a0| a0.padded(self.m, K::mr()));
+        let zero_point_b = params.zero_point_b.as_ref().map(|b0| b0.padded(self.n, K::nr()));
+        if let Some(ref a0) = zero_point_a {
             let mut sum_b_over_k = self.sum_b_over_k(b);
             for n in 0..self.n {
                 sum_b_over_k[n] = sum_b_over_k[n].neg();
@@ -274,11 +367,11 @@ where
             };
             non_linear.insert(0, term);
         }
-        if let Some(ref b0) = self.zero_point_b {
+        if let Some(ref b0) = zero_point_b {
             let mut sum_a_over_k = self.sum_a_over_k(a);
             for m in 0..self.m {
                 sum_a_over_k[m] = sum_a_over_k[m].neg();
-                if let Some(ref a0) = self.zero_point_a {
+                if let Some(ref a0) = zero_point_a {
                     match a0 {
                         QuantizedParam::Scalar(a0) => {
                             sum_a_over_k[m] = a0.as_() * self.k.as_() + sum_a_over_k[m];
@@ -303,10 +396,13 @@ where
             };
             non_linear.insert(0, term);
         }
-        if let Some(scale) = self.scale_factor {
+        if let Some(scale) = params.scale_factor {
             non_linear.push(FusedSpec::QTowardsPlusInf(scale.0, scale.1));
         }
-        if let Some(c0) = self.zero_point_c {
+        if let Some(ref ops) = params.scale_factor_per_row {
+            non_linear.extend(ops.iter().cloned());
+        }
+        if let Some(c0) = params.zero_point_c {
             non_linear.push(FusedSpec::ScalarAdd(c0.as_()));
         }
         non_linear.push(FusedSpec::Min(TC::max_value().as_()));
@@ -407,14 +503,15 @@ pub mod test {
 
     impl<TA, TB, TC, TI> QMatMulProblem<TA, TB, TC, TI>
     where
-        TA: Arbitrary + 'static + Debug + AsPrimitive<TI> + Zero + Copy,
-        TB: Arbitrary + 'static + Debug + AsPrimitive<TI> + Zero + Copy,
+        TA: Arbitrary + 'static + Debug + AsPrimitive<TI> + Zero + Copy + Bounded,
+        TB: Arbitrary + 'static + Debug + AsPrimitive<TI> + Zero + Copy + Bounded,
         TC: Arbitrary + 'static + Debug + Copy + Bounded + AsPrimitive<TI> + Zero,
         TI: Arbitrary
             + 'static
             + Debug
             + Copy
             + AsPrimitive<TC>
+            + AsPrimitive<f32>
             + Add<Output = TI>
             + Mul<Output = TI>
             + Sub<Output = TI>
@@ -424,8 +521,28 @@ pub mod test {
             + Ord,
         usize: AsPrimitive<TI>,
         i32: AsPrimitive<TI>,
+        f32: AsPrimitive<TI>,
     {
         pub fn reference(&self) -> Vec<TC> {
+            self.accumulate().into_iter().map(Self::saturate).collect()
+        }
+
+        pub fn reference_per_row_scale(&self, scales: &[f32]) -> Vec<TC> {
+            self.accumulate()
+                .into_iter()
+                .enumerate()
+                .map(|(ix, i)| {
+                    let scaled = AsPrimitive::<f32>::as_(i) * scales[ix / self.n];
+                    Self::saturate(scaled.round().as_())
+                })
+                .collect()
+        }
+
+        fn saturate(i: TI) -> TC {
+            i.max(TC::min_value().as_()).min(TC::max_value().as_()).as_()
+        }
+
+        fn accumulate(&self) -> Vec<TI> {
             let mut i = vec![TI::zero(); self.m * self.n];
             for m in 0..self.m {
                 for n in 0..self.n {
@@ -444,12 +561,17 @@ pub mod test {
                     }
                 }
             }
-            i.iter()
-                .map(|i| i.max(&TC::min_value().as_()).min(&TC::max_value().as_()).as_())
-                .collect()
+            i
         }
 
         pub fn run<K: MatMatMulKer<TA, TB, TC, TI>>(&self) -> Vec<TC> {
+            self.run_with_scales::<K>(None)
+        }
+
+        pub fn run_with_scales<K: MatMatMulKer<TA, TB, TC, TI>>(
+            &self,
+            per_row_scale: Option<&[f32]>,
+        ) -> Vec<TC> {
             unsafe {
                 let mut c = vec![TC::zero(); self.m * self.n];
                 let mut mmm = QMatMatMulImpl::from(MatMatMulImpl::<K, TA, TB, TC, TI>::new(
@@ -469,10 +591,41 @@ pub mod test {
                     QuantizedParam::Scalar(b0) => mmm.set_zero_point_b_scalar(*b0),
                     QuantizedParam::Vector(b0) => mmm.set_zero_point_b_vector(b0.clone()),
                 }
+                if let Some(scales) = per_row_scale {
+                    mmm.set_scale_factor_per_row(scales);
+                }
                 mmm.run(packed_a.as_ptr(), packed_b.as_ptr(), c.as_mut_ptr(), &[]);
                 c
             }
         }
+
+        pub fn run_with_runtime_params<K: MatMatMulKer<TA, TB, TC, TI>>(&self) -> Vec<TC> {
+            unsafe {
+                let mut c = vec![TC::zero(); self.m * self.n];
+                let mmm = QMatMatMulImpl::from(MatMatMulImpl::<K, TA, TB, TC, TI>::new(
+                    self.m, self.k, self.n,
+                ));
+                let mut packed_a =
+                    Buffer::uninitialized(mmm.a_pack().len(), mmm.a_pack().alignment());
+                mmm.a_pack().pack(packed_a.as_mut_ptr(), self.a.as_ptr(), self.k as isize, 1);
+                let mut packed_b =
+                    Buffer::uninitialized(mmm.b_pack().len(), mmm.b_pack().alignment());
+                mmm.b_pack().pack(packed_b.as_mut_ptr(), self.b.as_ptr(), self.n as isize, 1);
+                let params = QMatMatMulParams {
+                    zero_point_a: Some(self.a0.clone()),
+                    zero_point_b: Some(self.b0.clone()),
+                    ..QMatMatMulParams::default()
+                };
+                mmm.run_with_q_params(
+                    packed_a.as_ptr(),
+                    packed_b.as_ptr(),
+                    c.as_mut_ptr(),
+                    &[],
+                    &params,
+                );
+                c
+            }
+        }
     }
 
     #[macro_export]
@@ -492,6 +645,15 @@ pub mod test {
                             prop_assert_eq!(pb.run::<$ker>(), pb.reference())
                         }
                     }
+
+                    #[test]
+                    fn q_mat_mul_runtime_params_prop(
+                        pb in any::<QMatMulProblem<$ta, $tb, $tc, $ti>>()
+                    ) {
+                        if $cond {
+                            prop_assert_eq!(pb.run_with_runtime_params::<$ker>(), pb.reference())
+                        }
+                    }
                 }
 
                 #[test]
@@ -561,6 +723,87 @@ pub mod test {
                         assert_eq!(pb.run::<$ker>(), pb.reference());
                     }
                 }
+
+                #[test]
+                fn q_mat_mul_runtime_short_vectors() {
+                    if $cond {
+                        let mut pb = QMatMulProblem {
+                            m: 5,
+                            k: 3,
+                            n: 3,
+                            a: (0..15).map(|x| x as _).collect(),
+                            a0: QuantizedParam::Vector(vec![3]),
+                            b: (0..9).map(|x| x as _).collect(),
+                            b0: QuantizedParam::Vector(vec![2]),
+                            boo: PhantomData,
+                        };
+                        let found = pb.run_with_runtime_params::<$ker>();
+                        assert_eq!(found, pb.run::<$ker>());
+                        pb.a0 = QuantizedParam::Scalar(3);
+                        pb.b0 = QuantizedParam::Scalar(2);
+                        assert_eq!(found, pb.reference());
+                    }
+                }
+
+                #[test]
+                fn q_mat_mul_per_row_scale() {
+                    if $cond {
+                        let pb = QMatMulProblem {
+                            m: 3,
+                            k: 4,
+                            n: 2,
+                            a: (1..=12).map(|x| x as _).collect(),
+                            a0: QuantizedParam::Scalar(1 as _),
+                            b: (0..8).map(|x| x as _).collect(),
+                            b0: QuantizedParam::Vector(vec![2 as _, 3 as _]),
+                            boo: PhantomData,
+                        };
+                        let scales = [0.3, 0.07, 0.011];
+                        assert_eq!(
+                            pb.run_with_scales::<$ker>(Some(&scales)),
+                            pb.reference_per_row_scale(&scales)
+                        );
+                    }
+                }
+
+                #[test]
+                fn q_mat_mul_per_row_scale_large_k() {
+                    if $cond {
+                        let (m, k, n) = (3, 2304, 4);
+                        let zp_a = (<$ta>::min_value() as i32 + <$ta>::max_value() as i32 + 1) / 2;
+                        let zp_b = (<$tb>::min_value() as i32 + <$tb>::max_value() as i32 + 1) / 2;
+                        // same signs for a and b, so that all accumulators are positive
+                        let sign = |k: usize| if k % 3 == 0 { -1 } else { 1 };
+                        let a = (0..m * k).map(|ix| {
+                            let (m, k) = (ix / k, ix % k);
+                            let delta = match m {
+                                0 => (k % 12 == 0) as i32,
+                                1 => (k % 5 == 0) as i32,
+                                _ => 1 + (k % 3) as i32,
+                            };
+                            (zp_a + sign(k) * delta) as _
+                        });
+                        let b = (0..k * n).map(|ix| {
+                            let (k, n) = (ix / n, ix % n);
+                            (zp_b + sign(k) * (1 + (k * 13 + n * 7) % 60) as i32) as _
+                        });
+                        let pb = QMatMulProblem {
+                            m,
+                            k,
+                            n,
+                            a: a.collect(),
+                            a0: QuantizedParam::Scalar(zp_a as _),
+                            b: b.collect(),
+                            b0: QuantizedParam::Scalar(zp_b as _),
+                            boo: PhantomData,
+                        };
+                        let scales = [0.01, 0.004, 0.0005];
+                        assert_eq!(
+                            pb.run_with_scales::<$ker>(Some(&scales)),
+                            pb.reference_per_row_scale(&scales)
+                        );
+                    }
+                }
             }
         };
     }
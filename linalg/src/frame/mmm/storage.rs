@@ -102,6 +102,10 @@ impl<'a, T: Copy> MatrixStore<'a, T> {
                     col_byte_stride: *col_byte_stride,
                 }
             }
+            MatrixStore::VecStride { ptr, byte_stride, mr, .. } => PanelStore::VecStride {
+                ptr: ((*ptr as isize) + (*byte_stride * (down * mr) as isize)) as *const T,
+                byte_stride: *byte_stride,
+            },
             _ => unimplemented!(),
         }
     }
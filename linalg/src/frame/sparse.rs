@@ -0,0 +1,317 @@
+use num_traits::Zero;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul};
+
+/// Block compressed sparse row storage of a m x k matrix.
+///
+/// Rows are grouped by `block_rows`. For each group, only the columns with at
+/// least one non zero value are stored, as blocks of `block_rows` values. The
+/// last group is padded with zeros if `block_rows` does not divide m.
+#[derive(Clone, Debug)]
+pub struct BlockSparseMatrix<T> {
+    pub m: usize,
+    pub k: usize,
+    pub block_rows: usize,
+    /// For each group of rows, offset of its first block in `cols`.
+    pub row_ptr: Vec<usize>,
+    /// Column of each block.
+    pub cols: Vec<usize>,
+    /// Values of each block, `block_rows` by block.
+    pub values: Vec<T>,
+}
+
+impl<T: Copy + Zero + PartialEq> BlockSparseMatrix<T> {
+    /// Build the sparse storage of a matrix from a function returning the
+    /// value at (row, col).
+    pub fn from_fn(
+        m: usize,
+        k: usize,
+        block_rows: usize,
+        a: impl Fn(usize, usize) -> T,
+    ) -> BlockSparseMatrix<T> {
+        let mut row_ptr = vec![0];
+        let mut cols = vec![];
+        let mut values = vec![];
+        for row in (0..m).step_by(block_rows) {
+            for col in 0..k {
+                let block: Vec<T> = (row..row + block_rows)
+                    .map(|r| if r < m { a(r, col) } else { T::zero() })
+                    .collect();
+                if block.iter().any(|v| !v.is_zero()) {
+                    cols.push(col);
+                    values.extend(block);
+                }
+            }
+            row_ptr.push(cols.len());
+        }
+        BlockSparseMatrix { m, k, block_rows, row_ptr, cols, values }
+    }
+
+    /// Number of non zero blocks.
+    pub fn nnz_blocks(&self) -> usize {
+        self.cols.len()
+    }
+
+    /// Ratio of the stored values over the size of the dense matrix.
+    pub fn density(&self) -> f32 {
+        (self.nnz_blocks() * self.block_rows) as f32 / (self.m * self.k).max(1) as f32
+    }
+}
+
+/// Product of a constant block sparse matrix A by a dense matrix B.
+pub trait SparseMatMul<T>: fmt::Debug + fmt::Display + dyn_clone::DynClone + Send + Sync {
+    fn a(&self) -> &BlockSparseMatrix<T>;
+
+    /// Compute C = A.B, with B a k x n matrix and C a m x n matrix, given by
+    /// their row and column strides.
+    unsafe fn run(
+        &self,
+        b: *const T,
+        rsb: isize,
+        csb: isize,
+        c: *mut T,
+        rsc: isize,
+        csc: isize,
+        n: usize,
+    );
+}
+
+dyn_clone::clone_trait_object!(<T> SparseMatMul<T> where T: Copy);
+
+/// Kernel computing one group of rows of a block sparse product.
+pub trait SparseMatMulKer<T>: Copy + Clone + fmt::Debug + Send + Sync {
+    fn name() -> &'static str;
+    fn block_rows() -> usize;
+
+    /// Compute the `block_rows()` rows of C from the `cols.len()` blocks of
+    /// a group of rows of A.
+    unsafe fn kernel(
+        cols: &[usize],
+        values: *const T,
+        b: *const T,
+        rsb: isize,
+        csb: isize,
+        c: *mut T,
+        rsc: isize,
+        csc: isize,
+        n: usize,
+    );
+}
+
+#[derive(Clone, Debug)]
+pub struct SparseMatMulImpl<K, T>
+where
+    K: SparseMatMulKer<T>,
+    T: Copy + fmt::Debug,
+{
+    pub a: BlockSparseMatrix<T>,
+    _boo: PhantomData<K>,
+}
+
+impl<K, T> SparseMatMulImpl<K, T>
+where
+    K: SparseMatMulKer<T>,
+    T: Copy + Zero + PartialEq + fmt::Debug,
+{
+    pub fn new(m: usize, k: usize, a: impl Fn(usize, usize) -> T) -> SparseMatMulImpl<K, T> {
+        SparseMatMulImpl {
+            a: BlockSparseMatrix::from_fn(m, k, K::block_rows(), a),
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<K, T> SparseMatMul<T> for SparseMatMulImpl<K, T>
+where
+    K: SparseMatMulKer<T>,
+    T: Copy + Zero + Add<Output = T> + Mul<Output = T> + fmt::Debug + Send + Sync,
+{
+    fn a(&self) -> &BlockSparseMatrix<T> {
+        &self.a
+    }
+
+    unsafe fn run(
+        &self,
+        b: *const T,
+        rsb: isize,
+        csb: isize,
+        c: *mut T,
+        rsc: isize,
+        csc: isize,
+        n: usize,
+    ) {
+        let block_rows = K::block_rows();
+        let full_groups = self.a.m / block_rows;
+        for group in 0..full_groups {
+            let blocks = self.a.row_ptr[group]..self.a.row_ptr[group + 1];
+            K::kernel(
+                &self.a.cols[blocks.clone()],
+                self.a.values.as_ptr().add(blocks.start * block_rows),
+                b,
+                rsb,
+                csb,
+                c.offset((group * block_rows) as isize * rsc),
+                rsc,
+                csc,
+                n,
+            );
+        }
+        if full_groups * block_rows < self.a.m {
+            // last rows, padded in storage, computed without the kernel
+            let blocks = self.a.row_ptr[full_groups]..self.a.row_ptr[full_groups + 1];
+            for row in 0..(self.a.m - full_groups * block_rows) {
+                for j in 0..n {
+                    let mut acc = T::zero();
+                    for block in blocks.clone() {
+                        let a = self.a.values[block * block_rows + row];
+                        acc = acc
+                            + a * *b.offset(self.a.cols[block] as isize * rsb + j as isize * csb);
+                    }
+                    *c.offset((full_groups * block_rows + row) as isize * rsc + j as isize * csc) =
+                        acc;
+                }
+            }
+        }
+    }
+}
+
+impl<K, T> fmt::Display for SparseMatMulImpl<K, T>
+where
+    K: SparseMatMulKer<T>,
+    T: Copy + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "(m:{}, k:{}) {}x1 blocks, {} non zero, {}",
+            self.a.m,
+            self.a.k,
+            K::block_rows(),
+            self.a.cols.len(),
+            K::name()
+        )
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug)]
+    pub struct SparseMatMulProblem {
+        pub m: usize,
+        pub k: usize,
+        pub n: usize,
+        pub a: Vec<f32>,
+        pub b: Vec<f32>,
+    }
+
+    impl Arbitrary for SparseMatMulProblem {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_p: ()) -> Self::Strategy {
+            (1usize..20, 1usize..20, 1usize..10)
+                .prop_flat_map(|(m, k, n)| {
+                    // mostly zeros in a
+                    let a = proptest::collection::vec(
+                        prop_oneof![4 => Just(0f32), 1 => (-10i32..10).prop_map(|x| x as f32)],
+                        m * k,
+                    );
+                    let b = proptest::collection::vec((-10i32..10).prop_map(|x| x as f32), k * n);
+                    (Just(m), Just(k), Just(n), a, b)
+                })
+                .prop_map(|(m, k, n, a, b)| SparseMatMulProblem { m, k, n, a, b })
+                .boxed()
+        }
+    }
+
+    impl SparseMatMulProblem {
+        pub fn reference(&self) -> Vec<f32> {
+            let mut c = vec![0f32; self.m * self.n];
+            for row in 0..self.m {
+                for col in 0..self.n {
+                    for i in 0..self.k {
+                        c[row * self.n + col] +=
+                            self.a[row * self.k + i] * self.b[i * self.n + col];
+                    }
+                }
+            }
+            c
+        }
+
+        pub fn run<K: SparseMatMulKer<f32>>(&self) -> Vec<f32> {
+            let mm = SparseMatMulImpl::<K, f32>::new(self.m, self.k, |r, c| self.a[r * self.k + c]);
+            let mut c = vec![std::f32::NAN; self.m * self.n];
+            unsafe {
+                mm.run(
+                    self.b.as_ptr(),
+                    self.n as isize,
+                    1,
+                    c.as_mut_ptr(),
+                    self.n as isize,
+                    1,
+                    self.n,
+                );
+            }
+            c
+        }
+
+        pub fn run_transposed<K: SparseMatMulKer<f32>>(&self) -> Vec<f32> {
+            let mm = SparseMatMulImpl::<K, f32>::new(self.m, self.k, |r, c| self.a[r * self.k + c]);
+            let bt: Vec<f32> = (0..self.n * self.k)
+                .map(|ix| self.b[(ix % self.k) * self.n + ix / self.k])
+                .collect();
+            let mut ct = vec![std::f32::NAN; self.m * self.n];
+            unsafe {
+                mm.run(
+                    bt.as_ptr(),
+                    1,
+                    self.k as isize,
+                    ct.as_mut_ptr(),
+                    1,
+                    self.m as isize,
+                    self.n,
+                );
+            }
+            (0..self.m * self.n).map(|ix| ct[(ix % self.n) * self.m + ix / self.n]).collect()
+        }
+    }
+
+    #[macro_export]
+    macro_rules! sparse_frame_tests {
+        ($cond:expr, $ker:ty) => {
+            mod sparse {
+                use proptest::prelude::*;
+                #[allow(unused_imports)]
+                use $crate::frame::sparse::test::*;
+
+                proptest::proptest! {
+                    #[test]
+                    fn sparse_mat_mul_prop(pb in any::<SparseMatMulProblem>()) {
+                        if $cond {
+                            prop_assert_eq!(pb.run::<$ker>(), pb.reference())
+                        }
+                    }
+
+                    #[test]
+                    fn sparse_mat_mul_transposed_prop(pb in any::<SparseMatMulProblem>()) {
+                        if $cond {
+                            prop_assert_eq!(pb.run_transposed::<$ker>(), pb.reference())
+                        }
+                    }
+                }
+
+                #[test]
+                fn sparse_mat_mul_all_zeros() {
+                    let pb =
+                        SparseMatMulProblem { m: 5, k: 3, n: 2, a: vec![0.0; 15], b: vec![1.0; 6] };
+                    assert_eq!(pb.run::<$ker>(), pb.reference())
+                }
+            }
+        };
+    }
+}
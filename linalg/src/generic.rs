@@ -1,9 +1,14 @@
+pub mod exp;
+pub mod f16;
 pub mod lut;
 pub mod mmm;
 pub mod sigmoid;
+pub mod sparse;
 pub mod tanh;
 
+pub use self::exp::SExp4;
 pub use self::lut::GenericLut8;
 pub use self::mmm::GenericMmm4x4;
 pub use self::sigmoid::SSigmoid4;
+pub use self::sparse::{GenericSparse4x1, GenericSparse8x1};
 pub use self::tanh::STanh4;
@@ -0,0 +1,6 @@
+use crate::f16::f16;
+
+pub fn f16_to_f32(src: &[f16], dst: &mut [f32]) {
+    debug_assert_eq!(src.len(), dst.len());
+    src.iter().zip(dst.iter_mut()).for_each(|(s, d)| *d = (s.0).to_f32())
+}
@@ -0,0 +1,64 @@
+use num_traits::Zero;
+use std::marker::PhantomData;
+use std::{fmt, ops};
+
+use crate::frame::sparse::SparseMatMulKer;
+
+macro_rules! generic_sparse_kernel {
+    ($name:ident, $rows:expr) => {
+        #[derive(Copy, Clone, Debug)]
+        pub struct $name<T>(PhantomData<T>);
+
+        impl<T> SparseMatMulKer<T> for $name<T>
+        where
+            T: Copy + Zero + ops::Add<Output = T> + ops::Mul<Output = T> + fmt::Debug + Send + Sync,
+        {
+            fn name() -> &'static str {
+                "generic"
+            }
+
+            fn block_rows() -> usize {
+                $rows
+            }
+
+            unsafe fn kernel(
+                cols: &[usize],
+                values: *const T,
+                b: *const T,
+                rsb: isize,
+                csb: isize,
+                c: *mut T,
+                rsc: isize,
+                csc: isize,
+                n: usize,
+            ) {
+                for j in 0..n as isize {
+                    let mut acc = [T::zero(); $rows];
+                    for (ix, &col) in cols.iter().enumerate() {
+                        let b = *b.offset(col as isize * rsb + j * csb);
+                        let block = values.add(ix * $rows);
+                        for r in 0..$rows {
+                            acc[r] = acc[r] + *block.add(r) * b;
+                        }
+                    }
+                    for r in 0..$rows {
+                        *c.offset(r as isize * rsc + j * csc) = acc[r];
+                    }
+                }
+            }
+        }
+    };
+}
+
+generic_sparse_kernel!(GenericSparse4x1, 4);
+generic_sparse_kernel!(GenericSparse8x1, 8);
+
+#[cfg(test)]
+mod test_4x1 {
+    sparse_frame_tests!(true, crate::generic::GenericSparse4x1<f32>);
+}
+
+#[cfg(test)]
+mod test_8x1 {
+    sparse_frame_tests!(true, crate::generic::GenericSparse8x1<f32>);
+}
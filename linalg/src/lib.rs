@@ -22,15 +22,22 @@ pub mod arm64;
 #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
 pub mod arm32;
 
+pub use self::frame::exp;
 pub use self::frame::lut;
 pub use self::frame::mmm;
 pub use self::frame::sigmoid;
+pub use self::frame::sparse;
 pub use self::frame::tanh;
 
 pub struct Ops {
     pub smmm: Box<
         dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul<f32, f32, f32, f32>> + Send + Sync,
     >,
+    pub smmm_f16: Box<
+        dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul<f16::f16, f32, f32, f32>>
+            + Send
+            + Sync,
+    >,
     pub qmmm_i8_i32: Box<
         dyn Fn(usize, usize, usize) -> Box<dyn mmm::QMatMatMul<i8, i8, i32, i32>> + Send + Sync,
     >,
@@ -43,9 +50,26 @@ pub struct Ops {
     pub qmmm_i8_i8: Box<
         dyn Fn(usize, usize, usize) -> Box<dyn mmm::QMatMatMul<i8, i8, i8, i32>> + Send + Sync,
     >,
+    pub smmv:
+        Box<dyn Fn(usize, usize) -> Box<dyn mmm::MatVecMul<f32, f32, f32, f32>> + Send + Sync>,
+    pub qmmv_i8_i32:
+        Box<dyn Fn(usize, usize) -> Box<dyn mmm::QMatMatMul<i8, i8, i32, i32>> + Send + Sync>,
+    pub qmmv_u8_i32:
+        Box<dyn Fn(usize, usize) -> Box<dyn mmm::QMatMatMul<u8, u8, i32, i32>> + Send + Sync>,
+    pub qmmv_u8_u8:
+        Box<dyn Fn(usize, usize) -> Box<dyn mmm::QMatMatMul<u8, u8, u8, i32>> + Send + Sync>,
+    pub qmmv_i8_i8:
+        Box<dyn Fn(usize, usize) -> Box<dyn mmm::QMatMatMul<i8, i8, i8, i32>> + Send + Sync>,
+    pub ssparse: Box<
+        dyn Fn(usize, usize, &dyn Fn(usize, usize) -> f32) -> Box<dyn sparse::SparseMatMul<f32>>
+            + Send
+            + Sync,
+    >,
+    pub sexp: Box<dyn Fn() -> Box<dyn exp::Exp<f32>> + Send + Sync>,
     pub ssigmoid: Box<dyn Fn() -> Box<dyn sigmoid::Sigmoid<f32>> + Send + Sync>,
     pub stanh: Box<dyn Fn() -> Box<dyn tanh::Tanh<f32>> + Send + Sync>,
     pub lut_u8: Box<dyn Fn(&[u8]) -> Box<dyn lut::Lut> + Send + Sync>,
+    pub f16_to_f32: Box<dyn Fn(&[f16::f16], &mut [f32]) + Send + Sync>,
 }
 
 pub fn generic() -> Ops {
@@ -59,6 +83,11 @@ pub fn generic() -> Ops {
                 f32,
             >::new(m, k, n))
         }),
+        smmm_f16: Box::new(|m, k, n| {
+            Box::new(mmm::F16MatMatMulImpl::<generic::GenericMmm4x4<f32, f32, f32, f32>>::new(
+                m, k, n,
+            ))
+        }),
         qmmm_i8_i32: Box::new(|m, k, n| {
             Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
                 generic::GenericMmm4x4<i8, i8, i32, i32>,
@@ -95,9 +124,67 @@ pub fn generic() -> Ops {
                 i32,
             >::new(m, k, n)))
         }),
+        smmv: Box::new(|m, k| {
+            Box::new(mmm::MatVecMulImpl::<
+                generic::GenericMmm4x4<f32, f32, f32, f32>,
+                f32,
+                f32,
+                f32,
+                f32,
+            >::new(m, k))
+        }),
+        qmmv_i8_i32: Box::new(|m, k| {
+            Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                generic::GenericMmm4x4<i8, i8, i32, i32>,
+                i8,
+                i8,
+                i32,
+                i32,
+            >::new(m, k)))
+        }),
+        qmmv_u8_i32: Box::new(|m, k| {
+            Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                generic::GenericMmm4x4<u8, u8, i32, i32>,
+                u8,
+                u8,
+                i32,
+                i32,
+            >::new(m, k)))
+        }),
+        qmmv_u8_u8: Box::new(|m, k| {
+            Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                generic::GenericMmm4x4<u8, u8, u8, i32>,
+                u8,
+                u8,
+                u8,
+                i32,
+            >::new(m, k)))
+        }),
+        qmmv_i8_i8: Box::new(|m, k| {
+            Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                generic::GenericMmm4x4<i8, i8, i8, i32>,
+                i8,
+                i8,
+                i8,
+                i32,
+            >::new(m, k)))
+        }),
+        ssparse: Box::new(|m, k, a| {
+            if m % 8 == 0 {
+                Box::new(sparse::SparseMatMulImpl::<generic::GenericSparse8x1<f32>, f32>::new(
+                    m, k, a,
+                ))
+            } else {
+                Box::new(sparse::SparseMatMulImpl::<generic::GenericSparse4x1<f32>, f32>::new(
+                    m, k, a,
+                ))
+            }
+        }),
+        sexp: Box::new(|| Box::new(exp::ExpImpl::<generic::SExp4, f32>::new())),
         ssigmoid: Box::new(|| Box::new(sigmoid::SigmoidImpl::<generic::SSigmoid4, f32>::new())),
         stanh: Box::new(|| Box::new(tanh::TanhImpl::<generic::STanh4, f32>::new())),
         lut_u8: Box::new(|table: &[u8]| Box::new(lut::LutImpl::<generic::GenericLut8>::new(table))),
+        f16_to_f32: Box::new(generic::f16::f16_to_f32),
     }
 }
 
@@ -114,8 +201,121 @@ pub fn best() -> Ops {
                     ),
                 )
             });
+            ops.smmm_f16 = Box::new(|m, k, n| {
+                Box::new(mmm::F16MatMatMulImpl::<x86_64_fma::mmm::SMatMatMul16x6>::new(m, k, n))
+            });
+            ops.smmv = Box::new(|m, k| {
+                Box::new(
+                    mmm::MatVecMulImpl::<x86_64_fma::mmm::SMatVecMul32x1, f32, f32, f32, f32>::new(
+                        m, k,
+                    ),
+                )
+            });
             log::info!("x86_64/fma activated");
         }
+        // SExp8 builds its powers of two with avx2 integer instructions, keep the
+        // nonlinearity kernels together on cpus that have both
+        if is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2") {
+            ops.sexp = Box::new(|| Box::new(exp::ExpImpl::<x86_64_fma::exp::SExp8, f32>::new()));
+            ops.ssigmoid = Box::new(|| {
+                Box::new(sigmoid::SigmoidImpl::<x86_64_fma::sigmoid::SSigmoid8, f32>::new())
+            });
+            ops.stanh =
+                Box::new(|| Box::new(tanh::TanhImpl::<x86_64_fma::tanh::STanh8, f32>::new()));
+            log::info!("x86_64/fma+avx2 activated");
+        }
+        if is_x86_feature_detected!("avx512f") {
+            ops.smmm = Box::new(|m, k, n| {
+                Box::new(
+                    mmm::MatMatMulImpl::<x86_64_fma::mmm::SMatMatMul32x12, f32, f32, f32, f32>::new(
+                        m, k, n,
+                    ),
+                )
+            });
+            ops.smmm_f16 = Box::new(|m, k, n| {
+                Box::new(mmm::F16MatMatMulImpl::<x86_64_fma::mmm::SMatMatMul32x12>::new(m, k, n))
+            });
+            log::info!("x86_64/avx512f activated");
+        }
+        if is_x86_feature_detected!("f16c") {
+            ops.f16_to_f32 = Box::new(x86_64_fma::f16::f16_to_f32);
+            log::info!("x86_64/f16c activated");
+        }
+        if is_x86_feature_detected!("avx2") {
+            ops.qmmm_i8_i8 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::I8MatMatMul8x8,
+                    i8,
+                    i8,
+                    i8,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_i8_i32 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::I8I32MatMatMul8x8,
+                    i8,
+                    i8,
+                    i32,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_u8_u8 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::U8MatMatMul8x8,
+                    u8,
+                    u8,
+                    u8,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmm_u8_i32 = Box::new(|m, k, n| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatMatMulImpl::<
+                    x86_64_fma::mmm::U8I32MatMatMul8x8,
+                    u8,
+                    u8,
+                    i32,
+                    i32,
+                >::new(m, k, n)))
+            });
+            ops.qmmv_i8_i8 = Box::new(|m, k| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                    x86_64_fma::mmm::I8MatVecMul32x1,
+                    i8,
+                    i8,
+                    i8,
+                    i32,
+                >::new(m, k)))
+            });
+            ops.qmmv_i8_i32 = Box::new(|m, k| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                    x86_64_fma::mmm::I8I32MatVecMul32x1,
+                    i8,
+                    i8,
+                    i32,
+                    i32,
+                >::new(m, k)))
+            });
+            ops.qmmv_u8_u8 = Box::new(|m, k| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                    x86_64_fma::mmm::U8MatVecMul32x1,
+                    u8,
+                    u8,
+                    u8,
+                    i32,
+                >::new(m, k)))
+            });
+            ops.qmmv_u8_i32 = Box::new(|m, k| {
+                Box::new(mmm::QMatMatMulImpl::from(mmm::MatVecMulImpl::<
+                    x86_64_fma::mmm::U8I32MatVecMul32x1,
+                    u8,
+                    u8,
+                    i32,
+                    i32,
+                >::new(m, k)))
+            });
+            log::info!("x86_64/avx2 activated");
+        }
     }
     #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
     arm32::plug(&mut ops);
@@ -1 +1,5 @@
+pub mod exp;
+pub mod f16;
 pub mod mmm;
+pub mod sigmoid;
+pub mod tanh;
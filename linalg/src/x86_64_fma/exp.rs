@@ -0,0 +1,36 @@
+use crate::frame::exp::*;
+
+extern "C" {
+    #[no_mangle]
+    fn fma_sexp_8(ptr: *mut f32, count: usize);
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SExp8;
+
+impl ExpKer<f32> for SExp8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        32
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f32]) {
+        unsafe { fma_sexp_8(buf.as_mut_ptr(), buf.len()) }
+    }
+}
+
+#[cfg(test)]
+mod test_exp {
+    exp_frame_tests!(
+        is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2"),
+        crate::x86_64_fma::exp::SExp8
+    );
+}
@@ -0,0 +1,34 @@
+use crate::f16::f16;
+
+extern "C" {
+    #[no_mangle]
+    fn f16c_f16_to_f32_8(src: *const f16, dst: *mut f32, count: usize);
+}
+
+pub fn f16_to_f32(src: &[f16], dst: &mut [f32]) {
+    debug_assert_eq!(src.len(), dst.len());
+    let len = src.len() / 8 * 8;
+    if len > 0 {
+        unsafe { f16c_f16_to_f32_8(src.as_ptr(), dst.as_mut_ptr(), len) }
+    }
+    for i in len..src.len() {
+        dst[i] = (src[i].0).to_f32();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    proptest::proptest! {
+        #[test]
+        fn f16_to_f32(xs in proptest::collection::vec(-100f32..100.0, 0..100)) {
+            if is_x86_feature_detected!("f16c") {
+                let halves: Vec<f16> = xs.iter().map(|x| f16(half::f16::from_f32(*x))).collect();
+                let mut found = vec![0f32; xs.len()];
+                super::f16_to_f32(&halves, &mut found);
+                let expected: Vec<f32> = halves.iter().map(|x| (x.0).to_f32()).collect();
+                proptest::prop_assert_eq!(found, expected);
+            }
+        }
+    }
+}
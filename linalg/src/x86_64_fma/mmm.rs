@@ -3,6 +3,26 @@ use crate::frame::mmm::*;
 extern "C" {
     #[no_mangle]
     fn fma_smmm16x6(op: *const MatMatMulKerSpec<f32, f32, f32, f32>) -> isize;
+    #[no_mangle]
+    fn avx512_smmm32x12(op: *const MatMatMulKerSpec<f32, f32, f32, f32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmm_i8_i8_8x8(op: *const MatMatMulKerSpec<i8, i8, i8, i32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmm_i8_i32_8x8(op: *const MatMatMulKerSpec<i8, i8, i32, i32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmm_u8_u8_8x8(op: *const MatMatMulKerSpec<u8, u8, u8, i32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmm_u8_i32_8x8(op: *const MatMatMulKerSpec<u8, u8, i32, i32>) -> isize;
+    #[no_mangle]
+    fn fma_smmv32x1(op: *const MatMatMulKerSpec<f32, f32, f32, f32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmv_i8_i8_32x1(op: *const MatMatMulKerSpec<i8, i8, i8, i32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmv_i8_i32_32x1(op: *const MatMatMulKerSpec<i8, i8, i32, i32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmv_u8_u8_32x1(op: *const MatMatMulKerSpec<u8, u8, u8, i32>) -> isize;
+    #[no_mangle]
+    fn avx2_mmv_u8_i32_32x1(op: *const MatMatMulKerSpec<u8, u8, i32, i32>) -> isize;
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -39,3 +59,159 @@ test_mmm_kernel_f32!(
     is_x86_feature_detected!("fma")
 );
 
+#[derive(Copy, Clone, Debug)]
+pub struct SMatMatMul32x12;
+
+impl MatMatMulKer<f32, f32, f32, f32> for SMatMatMul32x12 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "avx512"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        12
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        64
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<f32, f32, f32, f32>) -> isize {
+        unsafe { avx512_smmm32x12(spec) }
+    }
+}
+
+test_mmm_kernel_f32!(
+    crate::x86_64_fma::mmm::SMatMatMul32x12,
+    test_SMatMatMul32x12,
+    is_x86_feature_detected!("avx512f")
+);
+
+#[derive(Copy, Clone, Debug)]
+pub struct SMatVecMul32x1;
+
+impl MatMatMulKer<f32, f32, f32, f32> for SMatVecMul32x1 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        32
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        1
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        32
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<f32, f32, f32, f32>) -> isize {
+        unsafe { fma_smmv32x1(spec) }
+    }
+}
+
+test_mmm_kernel_f32!(
+    crate::x86_64_fma::mmm::SMatVecMul32x1,
+    test_SMatVecMul32x1,
+    is_x86_feature_detected!("fma")
+);
+
+macro_rules! avx2_q_kernel {
+    ($id: ident, $func: ident, $ta: ty, $tc: ty, $mr: expr, $nr: expr) => {
+        #[derive(Copy, Clone, Debug)]
+        pub struct $id;
+
+        impl MatMatMulKer<$ta, $ta, $tc, i32> for $id {
+            #[inline(always)]
+            fn name() -> &'static str {
+                "avx2"
+            }
+            #[inline(always)]
+            fn mr() -> usize {
+                $mr
+            }
+            #[inline(always)]
+            fn nr() -> usize {
+                $nr
+            }
+            fn alignment_bytes_packed_a() -> usize {
+                1
+            }
+            fn alignment_bytes_packed_b() -> usize {
+                1
+            }
+            #[inline(never)]
+            fn kernel(spec: &MatMatMulKerSpec<$ta, $ta, $tc, i32>) -> isize {
+                unsafe { $func(spec) }
+            }
+        }
+    };
+}
+
+avx2_q_kernel!(I8MatMatMul8x8, avx2_mmm_i8_i8_8x8, i8, i8, 8, 8);
+avx2_q_kernel!(I8I32MatMatMul8x8, avx2_mmm_i8_i32_8x8, i8, i32, 8, 8);
+avx2_q_kernel!(U8MatMatMul8x8, avx2_mmm_u8_u8_8x8, u8, u8, 8, 8);
+avx2_q_kernel!(U8I32MatMatMul8x8, avx2_mmm_u8_i32_8x8, u8, i32, 8, 8);
+avx2_q_kernel!(I8MatVecMul32x1, avx2_mmv_i8_i8_32x1, i8, i8, 32, 1);
+avx2_q_kernel!(I8I32MatVecMul32x1, avx2_mmv_i8_i32_32x1, i8, i32, 32, 1);
+avx2_q_kernel!(U8MatVecMul32x1, avx2_mmv_u8_u8_32x1, u8, u8, 32, 1);
+avx2_q_kernel!(U8I32MatVecMul32x1, avx2_mmv_u8_i32_32x1, u8, i32, 32, 1);
+
+test_mmm_kernel_i8!(
+    crate::x86_64_fma::mmm::I8MatMatMul8x8,
+    test_I8MatMatMul8x8,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_i8_i32!(
+    crate::x86_64_fma::mmm::I8I32MatMatMul8x8,
+    test_I8I32MatMatMul8x8,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_u8!(
+    crate::x86_64_fma::mmm::U8MatMatMul8x8,
+    test_U8MatMatMul8x8,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_u8_i32!(
+    crate::x86_64_fma::mmm::U8I32MatMatMul8x8,
+    test_U8I32MatMatMul8x8,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_i8!(
+    crate::x86_64_fma::mmm::I8MatVecMul32x1,
+    test_I8MatVecMul32x1,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_i8_i32!(
+    crate::x86_64_fma::mmm::I8I32MatVecMul32x1,
+    test_I8I32MatVecMul32x1,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_u8!(
+    crate::x86_64_fma::mmm::U8MatVecMul32x1,
+    test_U8MatVecMul32x1,
+    is_x86_feature_detected!("avx2")
+);
+
+test_mmm_kernel_u8_i32!(
+    crate::x86_64_fma::mmm::U8I32MatVecMul32x1,
+    test_U8I32MatVecMul32x1,
+    is_x86_feature_detected!("avx2")
+);
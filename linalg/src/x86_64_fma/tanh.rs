@@ -0,0 +1,36 @@
+use crate::frame::tanh::*;
+
+extern "C" {
+    #[no_mangle]
+    fn fma_stanh_8(ptr: *mut f32, count: usize);
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct STanh8;
+
+impl TanhKer<f32> for STanh8 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        32
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f32]) {
+        unsafe { fma_stanh_8(buf.as_mut_ptr(), buf.len()) }
+    }
+}
+
+#[cfg(test)]
+mod test_tanh {
+    tanh_frame_tests!(
+        is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2"),
+        crate::x86_64_fma::tanh::STanh8
+    );
+}
@@ -5,6 +5,7 @@ use crate::pb::*;
 use tract_core::internal::*;
 use tract_core::ops::binary::Nary;
 
+mod cum_sum;
 mod mat_mul_integer;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
@@ -48,6 +49,8 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Sign", |_, _| Ok((Box::new(tractops::math::sign()), vec![])));
     reg.insert("Reciprocal", |_, _| Ok((Box::new(tractops::math::recip()), vec![])));
 
+    reg.insert("CumSum", cum_sum::cum_sum);
+
     reg.insert("Pow", |_, _| Ok((Box::new(tractops::math::pow::bin()), vec![])));
 
     reg.insert("MatMul", |_, _| Ok((Box::new(tractops::matmul::MatMul::default()), vec![])));
@@ -0,0 +1,76 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_core::internal::*;
+use tract_core::ops::nn::{CumReduce, Reducer};
+
+pub fn cum_sum(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let exclusive = node.get_attr_opt::<i64>("exclusive")?.unwrap_or(0) == 1;
+    let reverse = node.get_attr_opt::<i64>("reverse")?.unwrap_or(0) == 1;
+    Ok((Box::new(CumSum::new(exclusive, reverse)), vec![]))
+}
+
+#[derive(Debug, Clone, new)]
+struct CumSum {
+    pub exclusive: bool,
+    pub reverse: bool,
+}
+
+impl CumSum {
+    fn core_op(&self, axis: &Tensor, rank: usize) -> TractResult<CumReduce> {
+        let axis = axis.cast_to_scalar::<i64>()?;
+        let axis = if axis < 0 { axis + rank as i64 } else { axis } as usize;
+        Ok(CumReduce::new(axis, Reducer::Sum, self.exclusive, self.reverse))
+    }
+}
+
+impl Op for CumSum {
+    fn name(&self) -> Cow<str> {
+        "onnx.CumSum".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for CumSum {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, axis) = args_2!(inputs);
+        self.core_op(&axis, input.rank())?.eval(tvec!(input))
+    }
+}
+
+impl InferenceRulesOp for CumSum {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> TractResult<()> {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let rank = target.outlet_fact(input)?.shape.rank();
+        if let Some(axis) = target.outlet_fact(mapping[&node.inputs[1]])?.konst.clone() {
+            target.wire_node(&*node.name, self.core_op(&axis, rank)?, &[input])
+        } else {
+            bail!("CumSum is only supported with a constant axis")
+        }
+    }
+
+    inference_op_as_op!();
+}
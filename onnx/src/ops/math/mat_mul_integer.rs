@@ -1,6 +1,7 @@
 use crate::model::ParsingContext;
 use crate::pb::*;
 use tract_core::internal::*;
+use tract_core::ndarray::Axis;
 use tract_core::ops::quant::QParams;
 
 pub fn mat_mul_integer(
@@ -92,34 +93,100 @@ impl InferenceRulesOp for MatMulInteger {
         target: &mut TypedModel,
         mapping: &HashMap<OutletId, OutletId>,
     ) -> TractResult<TVec<OutletId>> {
+        let a = mapping[&node.inputs[0]];
+        let b = mapping[&node.inputs[1]];
         let mut qp = QParams::new(i32::datum_type());
+        // a zero point computed at runtime, and whether it is the one of a
+        let mut dynamic_zero_point = None;
         if let Some(ix) = self.optional_a_zero_point_input {
-            let zp = target
-                .outlet_fact(mapping[&node.inputs[ix]])?
-                .konst
-                .as_ref()
-                .ok_or("zero_point_a must be a constant")?;
-            if let Some(zp) = cleanup_zero_point(zp.clone().into_tensor())? {
-                qp = qp.with_zero_point_a(&zp.into_arc_tensor());
+            let zp = mapping[&node.inputs[ix]];
+            if let Some(k) = target.outlet_fact(zp)?.konst.clone() {
+                if let Some(zp) = cleanup_zero_point(k.into_tensor())? {
+                    qp = qp.with_zero_point_a(&zp.into_arc_tensor());
+                }
+            } else {
+                dynamic_zero_point = Some((zp, true));
             }
         };
         if let Some(ix) = self.optional_b_zero_point_input {
-            let zp = target
-                .outlet_fact(mapping[&node.inputs[ix]])?
-                .konst
-                .as_ref()
-                .ok_or("zero_point_b must be a constant")?;
-            if let Some(zp) = cleanup_zero_point(zp.clone().into_tensor())? {
-                qp = qp.with_zero_point_b(&zp.into_arc_tensor());
+            let zp = mapping[&node.inputs[ix]];
+            if let Some(k) = target.outlet_fact(zp)?.konst.clone() {
+                if let Some(zp) = cleanup_zero_point(k.into_tensor())? {
+                    qp = qp.with_zero_point_b(&zp.into_arc_tensor());
+                }
+            } else if dynamic_zero_point.is_some() {
+                bail!("zero_point_a and zero_point_b can not both be computed at runtime")
+            } else {
+                dynamic_zero_point = Some((zp, false));
             }
         };
-        let op = tract_core::ops::matmul::MatMul::default().with_q_params(qp);
-        target.wire_node(&*node.name, op, &[mapping[&node.inputs[0]], mapping[&node.inputs[1]]])
+        let (zp, is_a) = if let Some(dynamic) = dynamic_zero_point {
+            dynamic
+        } else {
+            let op = tract_core::ops::matmul::MatMul::default().with_q_params(qp);
+            return target.wire_node(&*node.name, op, &[a, b]);
+        };
+        /* With a dynamic a0, and B constant:
+            SUM_k( (A[m,k] - a0) * (B[k,n] - b0) )
+            = SUM_k( A[m,k] * (B[k,n] - b0) ) - a0 * SUM_k( B[k,n] - b0 )
+           and symmetrically with a dynamic b0 and A constant.
+        */
+        if target.outlet_fact(zp)?.shape.iter().any(|d| d != 1.to_dim()) {
+            bail!("Zero point computed at runtime must be a single value")
+        }
+        let (other, other_zp) =
+            if is_a { (b, qp.zero_point_b.as_ref()) } else { (a, qp.zero_point_a.as_ref()) };
+        let other = target.outlet_fact(other)?.konst.clone().ok_or(
+            "A zero point computed at runtime requires the other operand to be a constant",
+        )?;
+        let sum = sum_over_k(&other, other_zp, is_a)?;
+        let product = target.wire_node(
+            format!("{}-product", node.name),
+            tract_core::ops::matmul::MatMul::default().with_q_params(qp),
+            &[a, b],
+        )?[0];
+        let zp = target.wire_node(
+            format!("{}-zero-point", node.name),
+            tract_core::ops::cast::Cast::new(i32::datum_type()),
+            &[zp],
+        )?[0];
+        let sum = target.add_const(format!("{}-sum-over-k", node.name), sum)?;
+        let correction = target.wire_node(
+            format!("{}-correction", node.name),
+            tract_core::ops::math::mul::bin(),
+            &[zp, sum],
+        )?[0];
+        target.wire_node(&*node.name, tract_core::ops::math::sub::bin(), &[product, correction])
     }
 
     inference_op_as_op!();
 }
 
+/// Sum over the k axis of a constant operand minus its zero point, shaped to
+/// broadcast against the product.
+fn sum_over_k(t: &Tensor, zero_point: Option<&Arc<Tensor>>, is_b: bool) -> TractResult<Tensor> {
+    let mut t = t.cast_to::<i32>()?.into_owned().into_array::<i32>()?;
+    let k_axis = if t.ndim() == 1 {
+        0
+    } else if is_b {
+        t.ndim() - 2
+    } else {
+        t.ndim() - 1
+    };
+    if let Some(zp) = zero_point {
+        let mut zp = zp.cast_to::<i32>()?.into_owned().into_array::<i32>()?;
+        if zp.ndim() == 1 && !is_b {
+            // one zero point per row of a
+            zp.insert_axis_inplace(Axis(1));
+        }
+        t = &t - &zp;
+    }
+    if t.ndim() == 1 {
+        return Ok(tensor0(t.sum()));
+    }
+    Ok(t.sum_axis(Axis(k_axis)).insert_axis(Axis(k_axis)).into_tensor())
+}
+
 pub fn q_linear_mat_mul(
     _ctx: &ParsingContext,
     _node: &NodeProto,
@@ -141,23 +208,62 @@ impl Op for QLinearMatMul {
 impl StatelessOp for QLinearMatMul {
     fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
         let (a, a_scale, a_zp, b, b_scale, b_zp, y_scale, y_zp) = args_8!(inputs);
-        let scale = a_scale.to_scalar::<f32>()? * b_scale.to_scalar::<f32>()?
-            / y_scale.to_scalar::<f32>()?;
-        let mut qp = QParams::new(y_zp.datum_type()).with_scale_factor(scale);
-        if let Some(zp) = cleanup_zero_point(a_zp.into_tensor())? {
-            qp = qp.with_zero_point_a(&zp.into_arc_tensor())
-        }
-        if let Some(zp) = cleanup_zero_point(b_zp.into_tensor())? {
-            qp = qp.with_zero_point_b(&zp.into_arc_tensor())
-        }
-        if let Some(zp) = cleanup_zero_point(y_zp.into_tensor())? {
-            qp = qp.with_zero_point_c(&zp.into_arc_tensor())
+        let (op, transposed) =
+            q_linear_mat_mul_op(&a_scale, &a_zp, &b_scale, &b_zp, &y_scale, &y_zp)?;
+        if transposed {
+            op.eval(tvec!(b, a))
+        } else {
+            op.eval(tvec!(a, b))
         }
-        let op = tract_core::ops::matmul::MatMul::default().with_q_params(qp);
-        op.eval(tvec!(a, b))
     }
 }
 
+/// Core MatMul computing a QLinearMatMul, and whether it takes b and a
+/// swapped. The MatMul requantizes per row of its result: a per row scale of
+/// a maps to it directly, a per column scale of b once the product is
+/// computed as (b^T·a^T)^T.
+fn q_linear_mat_mul_op(
+    a_scale: &Tensor,
+    a_zp: &Tensor,
+    b_scale: &Tensor,
+    b_zp: &Tensor,
+    y_scale: &Tensor,
+    y_zp: &Tensor,
+) -> TractResult<(tract_core::ops::matmul::MatMul, bool)> {
+    let a_scale = a_scale.as_slice::<f32>()?;
+    let b_scale = b_scale.as_slice::<f32>()?;
+    let y_scale = *y_scale.to_scalar::<f32>()?;
+    if a_scale.is_empty() || b_scale.is_empty() {
+        bail!("QLinearMatMul with empty scales")
+    }
+    let transposed = b_scale.len() > 1;
+    if transposed && a_scale.len() > 1 {
+        bail!("QLinearMatMul quantized both per row of a and per column of b is not supported")
+    }
+    let scales: Vec<f32> = if transposed {
+        b_scale.iter().map(|b| a_scale[0] * b / y_scale).collect()
+    } else {
+        a_scale.iter().map(|a| a * b_scale[0] / y_scale).collect()
+    };
+    let mut qp = QParams::new(y_zp.datum_type()).with_scale_factor_per_row(&rctensor1(&scales))?;
+    let (a_zp, b_zp) = if transposed { (b_zp, a_zp) } else { (a_zp, b_zp) };
+    if let Some(zp) = cleanup_zero_point(a_zp.clone())? {
+        qp = qp.with_zero_point_a(&zp.into_arc_tensor())
+    }
+    if let Some(zp) = cleanup_zero_point(b_zp.clone())? {
+        qp = qp.with_zero_point_b(&zp.into_arc_tensor())
+    }
+    if let Some(zp) = cleanup_zero_point(y_zp.clone())? {
+        qp = qp.with_zero_point_c(&zp.into_arc_tensor())
+    }
+    let op = tract_core::ops::matmul::MatMul::default()
+        .with_a_trans(transposed)
+        .with_b_trans(transposed)
+        .with_c_trans(transposed)
+        .with_q_params(qp);
+    Ok((op, transposed))
+}
+
 impl InferenceRulesOp for QLinearMatMul {
     fn rules<'r, 'p: 'r, 's: 'r>(
         &'s self,
@@ -191,25 +297,21 @@ impl InferenceRulesOp for QLinearMatMul {
         target: &mut TypedModel,
         mapping: &HashMap<OutletId, OutletId>,
     ) -> TractResult<TVec<OutletId>> {
-        let mut qp = QParams::new(i32::datum_type());
-        let a_zp = target
-            .outlet_fact(mapping[&node.inputs[2]])?
-            .konst
-            .as_ref()
-            .ok_or("zero_point_a must be a constant")?;
-        if let Some(zp) = cleanup_zero_point(a_zp.clone().into_tensor())? {
-            qp = qp.with_zero_point_a(&zp.into_arc_tensor());
+        let mut params = tvec!();
+        for ix in &[1, 2, 4, 5, 6, 7] {
+            params.push(
+                target
+                    .outlet_fact(mapping[&node.inputs[*ix]])?
+                    .konst
+                    .clone()
+                    .ok_or("QLinearMatMul scales and zero points must be constants")?,
+            );
         }
-        let b_zp = target
-            .outlet_fact(mapping[&node.inputs[5]])?
-            .konst
-            .as_ref()
-            .ok_or("zero_point_b must be a constant")?;
-        if let Some(zp) = cleanup_zero_point(b_zp.clone().into_tensor())? {
-            qp = qp.with_zero_point_b(&zp.into_arc_tensor());
-        }
-        let op = tract_core::ops::matmul::MatMul::default().with_q_params(qp);
-        target.wire_node(&*node.name, op, &[mapping[&node.inputs[0]], mapping[&node.inputs[3]]])
+        let (op, transposed) = q_linear_mat_mul_op(
+            &params[0], &params[1], &params[2], &params[3], &params[4], &params[5],
+        )?;
+        let (a, b) = (mapping[&node.inputs[0]], mapping[&node.inputs[3]]);
+        target.wire_node(&*node.name, op, &if transposed { [b, a] } else { [a, b] })
     }
 
     inference_op_as_op!();
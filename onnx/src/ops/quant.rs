@@ -6,6 +6,7 @@ use tract_core::ops::quant::*;
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("QuantizeLinear", quantize_linear);
     reg.insert("DequantizeLinear", dequantize_linear);
+    reg.insert("DynamicQuantizeLinear", |_, _| Ok((Box::new(DynamicQuantizeLinearU8), vec![])));
 }
 
 fn quantize_linear(
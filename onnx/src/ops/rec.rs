@@ -1,4 +1,8 @@
 use crate::model::OnnxOpRegister;
+use tract_core::internal::*;
+use tract_core::ndarray::*;
+use tract_core::ops::element_wise::{ElementWiseMiniOp, ElementWiseOp};
+use tract_core::ops::rec::{CellKind, RecurrentCell};
 
 pub mod gru;
 pub mod lstm;
@@ -11,3 +15,243 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("RNN", rnn::rnn);
     reg.insert("Scan", scan::scan);
 }
+
+/// Build the fused cells of a recurrent layer, one per direction, if its
+/// weights are constant and f32, and if it uses plain element-wise
+/// activations.
+///
+/// `weights` are the W, R and optional B inputs of the layer, `kind` gives the
+/// cell kind for a direction.
+fn fused_cells(
+    kind: impl Fn(usize) -> CellKind,
+    target: &TypedModel,
+    weights: &[Option<OutletId>],
+    activations: &[&dyn TypedOp],
+) -> TractResult<Option<TVec<RecurrentCell>>> {
+    let mut konsts = tvec!();
+    for w in weights {
+        if let Some(w) = w {
+            let fact = target.outlet_fact(*w)?;
+            match fact.konst.as_ref() {
+                Some(k) if k.datum_type() == f32::datum_type() => konsts.push(Some(k.clone())),
+                _ => return Ok(None),
+            }
+        } else {
+            konsts.push(None)
+        }
+    }
+    let activations = activations
+        .iter()
+        .map(|a| a.as_op().downcast_ref::<ElementWiseOp>().map(|e| e.0.clone()))
+        .collect::<Option<TVec<Box<dyn ElementWiseMiniOp>>>>();
+    let activations = if let Some(a) = activations { a } else { return Ok(None) };
+    let num_directions = konsts[0].as_ref().unwrap().shape()[0];
+    let mut cells = tvec!();
+    for dir in 0..num_directions {
+        let direction = |t: &Arc<Tensor>| -> TractResult<Tensor> {
+            Ok(t.to_array_view::<f32>()?.index_axis(Axis(0), dir).to_owned().into_tensor())
+        };
+        let w = direction(konsts[0].as_ref().unwrap())?;
+        let r = direction(konsts[1].as_ref().unwrap())?;
+        let b = konsts[2].as_ref().map(direction).transpose()?;
+        cells.push(RecurrentCell::new(kind(dir), &w, &r, b.as_ref(), activations.clone())?);
+    }
+    Ok(Some(cells))
+}
+
+/// Wire a recurrent layer from its fused cells.
+///
+/// X is [seq_length, batch_size, input_size], initial states are
+/// [num_directions, batch_size, hidden_size]. Returns the ONNX outputs: Y if
+/// `y_output` is set, then the last value of each state for which a slot is
+/// given in `last_outputs`.
+///
+/// The forward direction is a Scan over its cell, the backward direction of
+/// bidirectional layers is a `ReverseRecurrent`. It needs constant initial
+/// states.
+fn wire_fused_layer(
+    target: &mut TypedModel,
+    name: &str,
+    mut cells: TVec<RecurrentCell>,
+    x: OutletId,
+    initial_states: &[Option<OutletId>],
+    y_output: Option<usize>,
+    last_outputs: &[Option<usize>],
+) -> TractResult<TVec<OutletId>> {
+    use tract_core::ops::array::{AddDim, NormConcat, NormConcatSlice, Slice};
+    use tract_core::ops::rec::ReverseRecurrent;
+    if cells.len() == 1 {
+        let cell = cells.remove(0);
+        return wire_fused_cell(target, name, cell, x, initial_states, y_output, last_outputs);
+    }
+    if cells.len() != 2 {
+        bail!("{}: expected one or two directions, got {}", name, cells.len());
+    }
+    let backward_cell = cells.pop().unwrap();
+    let forward_cell = cells.pop().unwrap();
+    let b_size = target.outlet_fact(x)?.shape.dim(1).to_integer()? as usize;
+    let h_size = forward_cell.hidden_size();
+
+    let mut forward_initial = tvec!();
+    let mut backward_initial = tvec!();
+    for (ix, initial) in initial_states.iter().enumerate() {
+        if let Some(initial) = initial {
+            let konst = target.outlet_fact(*initial)?.konst.clone().ok_or_else(|| {
+                format!("{}: bidirectional layers need constant initial states", name)
+            })?;
+            let konst = konst.to_array_view::<f32>()?;
+            backward_initial.push(konst.index_axis(Axis(0), 1).to_owned().into_arc_tensor());
+            forward_initial.push(Some(
+                target.wire_node(
+                    format!("{}-forward-initial-{}", name, ix),
+                    Slice::new(0, 0, 1),
+                    &[*initial],
+                )?[0],
+            ));
+        } else {
+            backward_initial.push(Array2::<f32>::zeros((b_size, h_size)).into_arc_tensor());
+            forward_initial.push(None);
+        }
+    }
+
+    let forward = wire_fused_cell(
+        target,
+        &format!("{}-forward", name),
+        forward_cell,
+        x,
+        &forward_initial,
+        y_output,
+        last_outputs,
+    )?;
+    let backward = target.wire_node(
+        format!("{}-backward", name),
+        ReverseRecurrent::new(backward_cell, backward_initial, None),
+        &[x],
+    )?;
+
+    let directions = || tvec!(NormConcatSlice::Var, NormConcatSlice::Var);
+    let mut forward = forward.into_iter();
+    let mut result = tvec!();
+    if y_output.is_some() {
+        let y_backward =
+            target.wire_node(format!("{}-backward-y", name), AddDim::new(1), &[backward[0]])?[0];
+        result.push(
+            target.wire_node(
+                format!("{}-y", name),
+                NormConcat::new(1, directions()),
+                &[forward.next().unwrap(), y_backward],
+            )?[0],
+        );
+    }
+    for (ix, _) in last_outputs.iter().enumerate().filter(|(_, slot)| slot.is_some()) {
+        // backward pass ends on the first frame
+        let last_backward = target.wire_node(
+            format!("{}-backward-last-{}", name, ix),
+            Slice::new(0, 0, 1),
+            &[backward[ix]],
+        )?[0];
+        result.push(
+            target.wire_node(
+                format!("{}-last-{}", name, ix),
+                NormConcat::new(0, directions()),
+                &[forward.next().unwrap(), last_backward],
+            )?[0],
+        );
+    }
+    Ok(result)
+}
+
+/// Wire a recurrent layer as a Scan over a single fused cell.
+///
+/// X is [seq_length, batch_size, input_size], initial states are
+/// [1, batch_size, hidden_size]. Returns the ONNX outputs: Y if `y_output`
+/// is set, as [seq_length, num_directions=1, batch_size, hidden_size], then
+/// the last value of each state for which a slot is given in `last_outputs`.
+fn wire_fused_cell(
+    target: &mut TypedModel,
+    name: &str,
+    cell: RecurrentCell,
+    x: OutletId,
+    initial_states: &[Option<OutletId>],
+    y_output: Option<usize>,
+    last_outputs: &[Option<usize>],
+) -> TractResult<TVec<OutletId>> {
+    use tract_core::ops::{array, scan};
+    let x_fact = target.outlet_fact(x)?.clone();
+    let b_size = x_fact.shape.dim(1).to_integer()? as usize;
+    let h_size = cell.hidden_size();
+
+    let mut body = TypedModel::default();
+    let mut outer_inputs = tvec!(x);
+    let mut input_mapping = vec![scan::InputMapping::Scan { slot: 0, axis: 0, chunk: 1.to_dim() }];
+    let mut x_source_fact = x_fact.clone();
+    x_source_fact.shape.set_dim(0, 1.to_dim())?;
+    let x_source = body.add_source(format!("{}-x", name), x_source_fact)?.into();
+    let mut cell_inputs = tvec!(
+        body.wire_node(format!("{}-x-rm-chunk", name), array::RmDim::new(0), &[x_source])?[0]
+    );
+
+    let state_fact = TypedFact::dt_shape(f32::datum_type(), [1, b_size, h_size].as_ref())?;
+    for (ix, initial) in initial_states.iter().enumerate() {
+        let initializer = if let Some(initial) = initial {
+            outer_inputs.push(*initial);
+            scan::StateInitializer::FromInput(outer_inputs.len() - 1)
+        } else {
+            scan::StateInitializer::Value(
+                tract_core::ndarray::Array3::<f32>::zeros((1, b_size, h_size)).into_arc_tensor(),
+            )
+        };
+        input_mapping.push(scan::InputMapping::State { initializer });
+        let source = body.add_source(format!("{}-state-{}", name, ix), state_fact.clone())?.into();
+        cell_inputs.push(
+            body.wire_node(
+                format!("{}-state-{}-rm-chunk", name, ix),
+                array::RmDim::new(0),
+                &[source],
+            )?[0],
+        );
+    }
+
+    let cell_outputs = body.wire_node(format!("{}-cell", name), cell, &cell_inputs)?;
+    let mut body_outputs = tvec!();
+    let mut output_mapping = vec![];
+    for (ix, state) in cell_outputs.iter().enumerate() {
+        body_outputs.push(
+            body.wire_node(
+                format!("{}-state-{}-add-chunk", name, ix),
+                array::AddDim::new(0),
+                &[*state],
+            )?[0],
+        );
+        output_mapping.push(scan::OutputMapping {
+            state: true,
+            axis: 0,
+            chunk: 1.to_dim(),
+            full_dim_hint: None,
+            last_value_slot: last_outputs[ix],
+            full_slot: if ix == 0 { y_output } else { None },
+        });
+    }
+    body.set_output_outlets(&body_outputs)?;
+
+    let scan_outputs = target.wire_node(
+        name,
+        scan::TypedScan::new(body, input_mapping, output_mapping, None)?,
+        &outer_inputs,
+    )?;
+
+    let mut result = tvec!();
+    if let Some(slot) = y_output {
+        result.push(
+            target.wire_node(
+                format!("{}-y", name),
+                array::AddDim::new(1),
+                &[scan_outputs[slot]],
+            )?[0],
+        );
+    }
+    for slot in last_outputs.iter().filter_map(|s| *s) {
+        result.push(scan_outputs[slot]);
+    }
+    Ok(result)
+}
@@ -4,6 +4,7 @@ use tract_core::internal::*;
 use tract_core::ndarray;
 use tract_core::ndarray::*;
 use tract_core::ops as core_ops;
+use tract_core::ops::rec::{CellKind, RecurrentCell};
 
 pub fn lstm(
     _ctx: &ParsingContext,
@@ -59,6 +60,42 @@ impl Default for LSTM {
     }
 }
 
+impl LSTM {
+    fn fused_cells(
+        &self,
+        target: &TypedModel,
+        node: &InferenceNode,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<Option<TVec<RecurrentCell>>> {
+        if self.optional_sequence_lens_input.is_some() {
+            return Ok(None);
+        }
+        let peepholes: TVec<Arc<Tensor>> = if let Some(slot) = self.optional_p_input {
+            match target.outlet_fact(mapping[&node.inputs[slot]])?.konst.as_ref() {
+                Some(p) if p.datum_type() == f32::datum_type() => {
+                    let shape = [p.shape()[0], 3, p.shape()[1] / 3];
+                    let p = p.to_array_view::<f32>()?.into_shape(&shape[..])?;
+                    p.outer_iter().map(|p| p.to_owned().into_arc_tensor()).collect()
+                }
+                _ => return Ok(None),
+            }
+        } else {
+            tvec!()
+        };
+        let weights = [
+            Some(mapping[&node.inputs[1]]),
+            Some(mapping[&node.inputs[2]]),
+            self.optional_bias_input.map(|slot| mapping[&node.inputs[slot]]),
+        ];
+        super::fused_cells(
+            |dir| CellKind::Lstm { peepholes: peepholes.get(dir).cloned() },
+            target,
+            &weights,
+            &[&*self.f, &*self.g, &*self.h],
+        )
+    }
+}
+
 impl Op for LSTM {
     fn name(&self) -> Cow<str> {
         "LSTM".into()
@@ -171,13 +208,34 @@ impl InferenceRulesOp for LSTM {
     ) -> TractResult<TVec<OutletId>> {
         use tract_core::ops::{array, math, matmul, scan};
 
+        if let Some(cells) = self.fused_cells(target, node, mapping)? {
+            let initial_states = [
+                self.optional_initial_h_input.map(|slot| mapping[&node.inputs[slot]]),
+                self.optional_initial_c_input.map(|slot| mapping[&node.inputs[slot]]),
+            ];
+            return super::wire_fused_layer(
+                target,
+                &*node.name,
+                cells,
+                mapping[&node.inputs[0]],
+                &initial_states,
+                self.optional_y_output,
+                &[self.optional_y_h_output, self.optional_y_c_output],
+            );
+        }
+
         let x_fact = target.outlet_fact(mapping[&node.inputs[0]])?.clone();
         let r_fact = target.outlet_fact(mapping[&node.inputs[2]])?;
 
         let b_size = x_fact.shape.dim(1).to_integer().unwrap() as usize;
         let h_size = r_fact.shape.dim(2).to_integer().unwrap() as usize;
 
-        // FIXME: bidi
+        if r_fact.shape.dim(0) != 1.to_dim() {
+            bail!(
+                "{}: bidirectional layers need constant f32 weights and no sequence lengths",
+                node.name
+            );
+        }
 
         let mut body = TypedModel::default();
         let mut outer_inputs = vec![];
@@ -447,7 +505,7 @@ impl InferenceRulesOp for LSTM {
 
         let mut result = tvec!();
         if let Some(slot) = self.optional_y_output {
-            target_wire!(y = array::AddDim::new(0), scan_outputs[slot]);
+            target_wire!(y = array::AddDim::new(1), scan_outputs[slot]);
             result.push(y);
         }
         if let Some(slot) = self.optional_y_h_output {
@@ -4,6 +4,7 @@ use tract_core::internal::*;
 use tract_core::ndarray;
 use tract_core::ndarray::*;
 use tract_core::ops as core_ops;
+use tract_core::ops::rec::{CellKind, RecurrentCell};
 
 pub fn rnn(
     _ctx: &ParsingContext,
@@ -48,6 +49,25 @@ impl Default for RNN {
     }
 }
 
+impl RNN {
+    fn fused_cells(
+        &self,
+        target: &TypedModel,
+        node: &InferenceNode,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<Option<TVec<RecurrentCell>>> {
+        if self.optional_sequence_lens_input.is_some() {
+            return Ok(None);
+        }
+        let weights = [
+            Some(mapping[&node.inputs[1]]),
+            Some(mapping[&node.inputs[2]]),
+            self.optional_bias_input.map(|slot| mapping[&node.inputs[slot]]),
+        ];
+        super::fused_cells(|_| CellKind::Rnn, target, &weights, &[&*self.fore])
+    }
+}
+
 impl Op for RNN {
     fn name(&self) -> Cow<str> {
         "RNN".into()
@@ -135,13 +155,31 @@ impl InferenceRulesOp for RNN {
     ) -> TractResult<TVec<OutletId>> {
         use tract_core::ops::{array, math, matmul, scan};
 
+        if let Some(cells) = self.fused_cells(target, node, mapping)? {
+            let initial_h = self.optional_initial_h_input.map(|slot| mapping[&node.inputs[slot]]);
+            return super::wire_fused_layer(
+                target,
+                &*node.name,
+                cells,
+                mapping[&node.inputs[0]],
+                &[initial_h],
+                self.optional_y_output,
+                &[self.optional_y_h_output],
+            );
+        }
+
         let x_fact = target.outlet_fact(mapping[&node.inputs[0]])?.clone();
         let r_fact = target.outlet_fact(mapping[&node.inputs[2]])?;
 
         let b_size = x_fact.shape.dim(1).to_integer().unwrap() as usize;
         let h_size = r_fact.shape.dim(2).to_integer().unwrap() as usize;
 
-        // FIXME: bidi
+        if r_fact.shape.dim(0) != 1.to_dim() {
+            bail!(
+                "{}: bidirectional layers need constant f32 weights and no sequence lengths",
+                node.name
+            );
+        }
 
         let mut body = TypedModel::default();
         let mut outer_inputs = vec![];
@@ -279,7 +317,7 @@ impl InferenceRulesOp for RNN {
 
         let mut result = tvec!();
         if let Some(slot) = self.optional_y_output {
-            target_wire!(y = array::AddDim::new(0), scan_outputs[slot]);
+            target_wire!(y = array::AddDim::new(1), scan_outputs[slot]);
             result.push(y);
         }
         if let Some(slot) = self.optional_y_h_output {
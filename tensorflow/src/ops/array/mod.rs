@@ -6,13 +6,17 @@ mod expand_dims;
 mod fill;
 mod gather;
 mod gather_v2;
+mod one_hot;
 mod pack;
 mod pad;
 mod range;
+mod reverse;
 mod slice;
+mod split;
 mod squeeze;
 mod strided_slice;
 mod transpose;
+mod unpack;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("ConcatV2", concatv2::build);
@@ -20,14 +24,20 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Fill", fill::fill);
     reg.insert("GatherNd", gather::gather_nd);
     reg.insert("GatherV2", gather_v2::gather_v2);
+    reg.insert("OneHot", one_hot::one_hot);
     reg.insert("Pack", pack::pack);
     reg.insert("Pad", pad::pad);
     reg.insert("Range", range::range);
     reg.insert("Reshape", |_, _| Ok(Box::new(::tract_core::ops::array::Reshape::new())));
+    reg.insert("ReverseV2", reverse::reverse_v2);
     reg.insert("Shape", |_, _| Ok(Box::new(::tract_core::ops::array::Shape::new(DatumType::I32))));
     reg.insert("Slice", |_, _| Ok(Box::new(slice::Slice)));
+    reg.insert("Split", split::split);
+    reg.insert("SplitV", split::split_v);
     reg.insert("Squeeze", squeeze::squeeze);
     reg.insert("StridedSlice", strided_slice::build);
     reg.insert("Tile", |_, _| Ok(Box::new(::tract_core::ops::array::Tile)));
     reg.insert("Transpose", transpose::transpose);
+    reg.insert("Unpack", unpack::unpack);
+    reg.insert("ZerosLike", |_, _| Ok(Box::new(::tract_core::ops::array::ConstantLike::new(0.0))));
 }
@@ -0,0 +1,169 @@
+use tract_core::internal::*;
+use tract_core::ndarray::prelude::*;
+
+use crate::model::ParsingContext;
+use crate::ops::resolve_axis;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn one_hot(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let axis = pb.get_attr_opt_int("axis")?.unwrap_or(-1);
+    Ok(Box::new(OneHot::new(axis)))
+}
+
+#[derive(Debug, Clone, new)]
+pub struct OneHot {
+    axis: i64,
+}
+
+impl OneHot {
+    fn resolve_axis(&self, indices_rank: usize) -> TractResult<usize> {
+        resolve_axis(self.axis, indices_rank + 1)
+    }
+}
+
+impl Op for OneHot {
+    fn name(&self) -> Cow<str> {
+        "tf.OneHot".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for OneHot {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (indices, depth, on, off) = args_4!(inputs);
+        let depth = *depth.cast_to::<i64>()?.to_scalar::<i64>()? as usize;
+        let axis = self.resolve_axis(indices.rank())?;
+        TypedOneHot::new(axis, depth).eval(tvec!(indices, on, off))
+    }
+}
+
+impl InferenceRulesOp for OneHot {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 4)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(&inputs[2].rank, 0)?;
+        s.equals(&inputs[3].rank, 0)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[3].datum_type, &outputs[0].datum_type)?;
+        s.equals(inputs[0].rank.bex() + 1, &outputs[0].rank)?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, depth| {
+            let depth = *depth.cast_to::<i64>()?.to_scalar::<i64>()? as usize;
+            let mut shape = shape.clone();
+            shape.insert(self.resolve_axis(shape.len())?, depth.to_dim());
+            s.equals(&outputs[0].shape, shape)
+        })
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if let Some(ref depth) = target.outlet_fact(mapping[&node.inputs[1]])?.konst {
+            let depth = *depth.cast_to::<i64>()?.to_scalar::<i64>()? as usize;
+            let rank = target.outlet_fact(mapping[&node.inputs[0]])?.shape.rank();
+            let op = TypedOneHot::new(self.resolve_axis(rank)?, depth);
+            let inputs: TVec<OutletId> =
+                [0, 2, 3].iter().map(|&ix| mapping[&node.inputs[ix]]).collect();
+            target.wire_node(&*node.name, op, &*inputs)
+        } else {
+            bail!("Need depth to be const")
+        }
+    }
+}
+
+#[derive(Debug, Clone, new)]
+pub struct TypedOneHot {
+    axis: usize,
+    depth: usize,
+}
+
+impl TypedOneHot {
+    fn eval_t<T: Datum>(
+        &self,
+        indices: &Tensor,
+        on: &Tensor,
+        off: &Tensor,
+    ) -> TractResult<Arc<Tensor>> {
+        let indices = indices.cast_to::<i64>()?;
+        let indices = indices.to_array_view::<i64>()?;
+        let on = on.to_scalar::<T>()?;
+        let off = off.to_scalar::<T>()?;
+        let mut shape: TVec<usize> = indices.shape().into();
+        shape.insert(self.axis, self.depth);
+        let array = ArrayD::from_shape_fn(&*shape, |coords| {
+            let hot = coords[self.axis];
+            let mut icoords: TVec<usize> = coords.slice().into();
+            icoords.remove(self.axis);
+            if indices[&*icoords] == hot as i64 {
+                on.clone()
+            } else {
+                off.clone()
+            }
+        });
+        Ok(array.into_arc_tensor())
+    }
+}
+
+impl Op for TypedOneHot {
+    fn name(&self) -> Cow<str> {
+        "tf.TypedOneHot".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} depth: {}", self.axis, self.depth)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl StatelessOp for TypedOneHot {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (indices, on, off) = args_3!(inputs);
+        Ok(tvec!(dispatch_datum!(Self::eval_t(on.datum_type())(self, &indices, &on, &off))?))
+    }
+}
+
+impl TypedOp for TypedOneHot {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let mut shape = inputs[0].shape.to_tvec();
+        shape.insert(self.axis, self.depth.to_dim());
+        Ok(tvec!(TypedFact::dt_shape(inputs[1].datum_type, &*shape)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_hot_last_axis() {
+        let op = OneHot::new(-1);
+        let inputs =
+            tvec!(rctensor1(&[0i32, 2]), rctensor0(3i32), rctensor0(1.0f32), rctensor0(0.0f32));
+        let found = op.eval(inputs).unwrap().remove(0);
+        assert_eq!(found, rctensor2(&[[1.0f32, 0.0, 0.0], [0.0, 0.0, 1.0]]));
+    }
+
+    #[test]
+    fn one_hot_first_axis() {
+        let op = OneHot::new(0);
+        let inputs =
+            tvec!(rctensor1(&[0i32, 2]), rctensor0(3i32), rctensor0(1.0f32), rctensor0(0.0f32));
+        let found = op.eval(inputs).unwrap().remove(0);
+        assert_eq!(found, rctensor2(&[[1.0f32, 0.0], [0.0, 0.0], [0.0, 1.0]]));
+    }
+}
@@ -0,0 +1,112 @@
+use tract_core::internal::*;
+use tract_core::ndarray::Axis;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn reverse_v2(_ctx: &ParsingContext, _pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(Box::new(ReverseV2))
+}
+
+fn resolve_axes(axes: &Tensor, rank: usize) -> TractResult<Vec<usize>> {
+    Ok(axes
+        .cast_to::<i64>()?
+        .as_slice::<i64>()?
+        .iter()
+        .map(|&ax| if ax < 0 { ax + rank as i64 } else { ax } as usize)
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct ReverseV2;
+
+impl Op for ReverseV2 {
+    fn name(&self) -> Cow<str> {
+        "tf.ReverseV2".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for ReverseV2 {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, axes) = args_2!(inputs);
+        let axes = resolve_axes(&axes, input.rank())?;
+        Reverse::new(axes).eval(tvec!(input))
+    }
+}
+
+impl InferenceRulesOp for ReverseV2 {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if let Some(ref axes) = target.outlet_fact(mapping[&node.inputs[1]])?.konst {
+            let rank = target.outlet_fact(mapping[&node.inputs[0]])?.shape.rank();
+            let op = Reverse::new(resolve_axes(&axes, rank)?);
+            target.wire_node(&*node.name, op, [mapping[&node.inputs[0]]].as_ref())
+        } else {
+            bail!("Need axes to be const")
+        }
+    }
+}
+
+#[derive(Debug, Clone, new)]
+pub struct Reverse {
+    axes: Vec<usize>,
+}
+
+impl Reverse {
+    fn eval_t<T: Datum>(&self, input: Arc<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut array = input.into_tensor().into_array::<T>()?;
+        for &axis in &self.axes {
+            array.invert_axis(Axis(axis));
+        }
+        Ok(tvec!(array.into_arc_tensor()))
+    }
+}
+
+impl Op for Reverse {
+    fn name(&self) -> Cow<str> {
+        "tf.Reverse".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axes: {:?}", self.axes)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl StatelessOp for Reverse {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        dispatch_datum!(Self::eval_t(input.datum_type())(self, input))
+    }
+}
+
+impl TypedOp for Reverse {
+    typed_op_as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, inputs[0].shape.clone())?))
+    }
+}
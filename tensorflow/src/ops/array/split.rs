@@ -0,0 +1,230 @@
+use tract_core::internal::*;
+
+use crate::model::ParsingContext;
+use crate::ops::resolve_axis_tensor;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn split(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let d = pb.get_attr_int("num_split")?;
+    Ok(Box::new(Split::new(d)))
+}
+
+pub fn split_v(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let d = pb.get_attr_int("num_split")?;
+    Ok(Box::new(SplitV::new(d)))
+}
+
+/// Resolve SplitV size_splits, where at most one entry may be -1.
+fn resolve_splits(splits: &Tensor, dim: usize) -> TractResult<Vec<usize>> {
+    let splits = splits.cast_to::<i64>()?;
+    let splits = splits.as_slice::<i64>()?;
+    let known: i64 = splits.iter().filter(|&&s| s >= 0).sum();
+    let unknown = splits.iter().filter(|&&s| s < 0).count();
+    if unknown > 1 {
+        bail!("SplitV accepts at most one -1 in size_splits, got {:?}", splits);
+    }
+    if known as usize > dim || unknown == 0 && known as usize != dim {
+        bail!("SplitV size_splits {:?} do not add up to the split dimension {}", splits, dim);
+    }
+    Ok(splits.iter().map(|&s| if s < 0 { dim - known as usize } else { s as usize }).collect())
+}
+
+fn wire_slices(
+    name: &str,
+    target: &mut TypedModel,
+    wire: OutletId,
+    axis: usize,
+    splits: &[TDim],
+) -> TractResult<TVec<OutletId>> {
+    let mut outputs = tvec!();
+    let mut current = 0.to_dim();
+    for len in splits {
+        let end = current.clone() + len;
+        outputs.push(
+            target.wire_node(
+                format!("{}-{}..{}", name, current, end),
+                tract_core::ops::array::Slice::new(axis, current, end.clone()),
+                &[wire],
+            )?[0],
+        );
+        current = end;
+    }
+    Ok(outputs)
+}
+
+#[derive(Debug, Clone, new)]
+pub struct Split {
+    d: usize,
+}
+
+impl Op for Split {
+    fn name(&self) -> Cow<str> {
+        "tf.Split".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for Split {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (axis, input) = args_2!(inputs);
+        let axis = resolve_axis_tensor(&axis, input.rank())?;
+        tract_core::ops::array::Split::new(axis, self.d, None).eval(tvec!(input))
+    }
+}
+
+impl InferenceRulesOp for Split {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, self.d)?;
+        s.equals(&inputs[0].rank, 0)?;
+        (0..self.d).try_for_each(|i| {
+            s.equals(&inputs[1].datum_type, &outputs[i].datum_type)?;
+            s.equals(&inputs[1].rank, &outputs[i].rank)
+        })?;
+        s.given_2(&inputs[0].value, &inputs[1].shape, move |s, axis, shape| {
+            let axis = resolve_axis_tensor(&axis, shape.len())?;
+            let dim = shape[axis].clone() / self.d;
+            for i in 0..self.d {
+                let mut shape = shape.clone();
+                shape[axis] = dim.clone();
+                s.equals(&outputs[i].shape, shape)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.d)
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if let Some(ref axis) = target.outlet_fact(mapping[&node.inputs[0]])?.konst {
+            let input = target.outlet_fact(mapping[&node.inputs[1]])?.clone();
+            let axis = resolve_axis_tensor(axis, input.shape.rank())?;
+            let dim = input.shape.dim(axis) / self.d;
+            let splits = vec![dim; self.d];
+            wire_slices(&*node.name, target, mapping[&node.inputs[1]], axis, &splits)
+        } else {
+            bail!("Need axis to be const")
+        }
+    }
+}
+
+#[derive(Debug, Clone, new)]
+pub struct SplitV {
+    d: usize,
+}
+
+impl Op for SplitV {
+    fn name(&self) -> Cow<str> {
+        "tf.SplitV".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for SplitV {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, splits, axis) = args_3!(inputs);
+        let axis = resolve_axis_tensor(&axis, input.rank())?;
+        let splits = resolve_splits(&splits, input.shape()[axis])?;
+        tract_core::ops::array::Split::new(axis, self.d, Some(splits)).eval(tvec!(input))
+    }
+}
+
+impl InferenceRulesOp for SplitV {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 3)?;
+        check_output_arity(&outputs, self.d)?;
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[1].shape[0], self.d.to_dim())?;
+        s.equals(&inputs[2].rank, 0)?;
+        (0..self.d).try_for_each(|i| {
+            s.equals(&inputs[0].datum_type, &outputs[i].datum_type)?;
+            s.equals(&inputs[0].rank, &outputs[i].rank)
+        })?;
+        s.given_3(
+            &inputs[0].shape,
+            &inputs[1].value,
+            &inputs[2].value,
+            move |s, shape, splits, axis| {
+                let axis = resolve_axis_tensor(&axis, shape.len())?;
+                let dim = shape[axis].to_integer()? as usize;
+                let splits = resolve_splits(&splits, dim)?;
+                for i in 0..self.d {
+                    let mut shape = shape.clone();
+                    shape[axis] = splits[i].to_dim();
+                    s.equals(&outputs[i].shape, shape)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.d)
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let splits = target.outlet_fact(mapping[&node.inputs[1]])?.konst.clone();
+        let axis = target.outlet_fact(mapping[&node.inputs[2]])?.konst.clone();
+        if let (Some(splits), Some(axis)) = (splits, axis) {
+            let input = target.outlet_fact(mapping[&node.inputs[0]])?.clone();
+            let axis = resolve_axis_tensor(&axis, input.shape.rank())?;
+            let dim = input.shape.dim(axis).to_integer()? as usize;
+            let splits: Vec<TDim> =
+                resolve_splits(&splits, dim)?.into_iter().map(|d| d.to_dim()).collect();
+            wire_slices(&*node.name, target, mapping[&node.inputs[0]], axis, &splits)
+        } else {
+            bail!("Need size_splits and axis to be const")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_v_infers_missing_size() {
+        let op = SplitV::new(2);
+        let input = rctensor2(&[[0i32, 1, 2], [3, 4, 5]]);
+        let found = op.eval(tvec!(input, rctensor1(&[-1i32, 1]), rctensor0(-1i32))).unwrap();
+        assert_eq!(found[0], rctensor2(&[[0i32, 1], [3, 4]]));
+        assert_eq!(found[1], rctensor2(&[[2i32], [5]]));
+    }
+
+    #[test]
+    fn split_v_rejects_oversized_splits() {
+        assert!(resolve_splits(&tensor1(&[4i32, -1]), 3).is_err());
+        assert!(resolve_splits(&tensor1(&[1i32, 1]), 3).is_err());
+        assert!(resolve_splits(&tensor1(&[-1i32, -1]), 3).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use tract_core::internal::*;
+use tract_core::ndarray::Axis;
+
+use crate::model::ParsingContext;
+use crate::ops::resolve_axis;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn unpack(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let num = pb.get_attr_int("num")?;
+    let axis = pb.get_attr_opt_int("axis")?.unwrap_or(0);
+    Ok(Box::new(Unpack::new(num, axis)))
+}
+
+#[derive(Debug, Clone, new)]
+pub struct Unpack {
+    num: usize,
+    axis: i64,
+}
+
+impl Unpack {
+    fn eval_t<T: Datum>(&self, input: Arc<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        let axis = resolve_axis(self.axis, input.rank())?;
+        let input = input.to_array_view::<T>()?;
+        Ok(input.axis_iter(Axis(axis)).map(|v| v.to_owned().into_arc_tensor()).collect())
+    }
+}
+
+impl Op for Unpack {
+    fn name(&self) -> Cow<str> {
+        "tf.Unpack".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for Unpack {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        dispatch_datum!(Self::eval_t(input.datum_type())(self, input))
+    }
+}
+
+impl InferenceRulesOp for Unpack {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, self.num)?;
+        (0..self.num).try_for_each(|i| {
+            s.equals(&inputs[0].datum_type, &outputs[i].datum_type)?;
+            s.equals(inputs[0].rank.bex() - 1, &outputs[i].rank)
+        })?;
+        s.given(&inputs[0].shape, move |s, shape| {
+            let axis = resolve_axis(self.axis, shape.len())?;
+            s.equals(&shape[axis], self.num.to_dim())?;
+            let mut shape = shape.clone();
+            shape.remove(axis);
+            for i in 0..self.num {
+                s.equals(&outputs[i].shape, shape.clone())?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.num)
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let wire = mapping[&node.inputs[0]];
+        let axis = resolve_axis(self.axis, target.outlet_fact(wire)?.shape.rank())?;
+        (0..self.num)
+            .map(|i| {
+                let slice = target.wire_node(
+                    format!("{}-slice-{}", node.name, i),
+                    tract_core::ops::array::Slice::new(axis, i, i + 1),
+                    &[wire],
+                )?;
+                Ok(target.wire_node(
+                    format!("{}-rm_dim-{}", node.name, i),
+                    tract_core::ops::array::RmDim::new(axis),
+                    &slice,
+                )?[0])
+            })
+            .collect()
+    }
+}
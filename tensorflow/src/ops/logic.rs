@@ -13,9 +13,96 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("LogicalAnd", |_, _| Ok(Box::new(tractops::logic::and::bin())));
     reg.insert("LogicalOr", |_, _| Ok(Box::new(tractops::logic::or::bin())));
     reg.insert("Merge", merge);
+    reg.insert("Select", |_, _| Ok(Box::new(Select)));
+    reg.insert("SelectV2", |_, _| Ok(Box::new(tractops::logic::Iff)));
     reg.insert("Switch", switch);
 }
 
+/// TF v1 Select: unlike SelectV2, a rank 1 condition picks whole rows of
+/// the (higher rank) alternatives instead of broadcasting numpy-style.
+#[derive(Debug, Clone)]
+pub struct Select;
+
+impl Select {
+    fn row_axes(cond_rank: usize, rank: usize) -> Vec<usize> {
+        if cond_rank == 1 && rank > 1 {
+            (1..rank).collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+impl Op for Select {
+    fn name(&self) -> Cow<str> {
+        "tf.Select".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for Select {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (cond, t, f) = args_3!(inputs);
+        let mut cond = cond.into_tensor();
+        for _ in Self::row_axes(cond.rank(), t.rank()) {
+            cond.insert_axis(cond.rank())?;
+        }
+        tractops::logic::Iff.eval(tvec!(cond.into_arc_tensor(), t, f))
+    }
+}
+
+impl InferenceRulesOp for Select {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 3)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, DatumType::Bool)?;
+        s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[1].shape, &inputs[2].shape)?;
+        s.equals(&inputs[1].shape, &outputs[0].shape)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            // a rank 1 condition picks rows, scalar and full shape conditions
+            // are element-wise
+            if rank == 1 {
+                s.equals(&inputs[0].shape[0], &inputs[1].shape[0])?;
+            }
+            Ok(())
+        })
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let mut cond = mapping[&node.inputs[0]];
+        let cond_rank = target.outlet_fact(cond)?.shape.rank();
+        let rank = target.outlet_fact(mapping[&node.inputs[1]])?.shape.rank();
+        for axis in Self::row_axes(cond_rank, rank) {
+            cond = target.wire_node(
+                format!("{}-cond-add_dim-{}", node.name, axis),
+                tractops::array::AddDim::new(axis),
+                &[cond],
+            )?[0];
+        }
+        target.wire_node(
+            &*node.name,
+            tractops::logic::Iff,
+            &[cond, mapping[&node.inputs[1]], mapping[&node.inputs[2]]],
+        )
+    }
+}
+
 fn switch(ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let arity = ctx.node_output_arities[&pb.name];
     Ok(Box::new(Switch::new(arity)))
@@ -1,10 +1,12 @@
 use tract_core::internal::*;
 use tract_core::ops as tractops;
+use tract_core::ops::nn::{CumReduce, Reducer};
 
 use crate::model::ParsingContext;
 use crate::model::TfOpRegister;
 use crate::tfpb::tensorflow::NodeDef;
 
+mod arg_max_min;
 mod reduce;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
@@ -12,9 +14,15 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Add", |_, _| Ok(Box::new(tractops::math::add::bin())));
     reg.insert("AddN", add_n);
     reg.insert("AddV2", |_, _| Ok(Box::new(tractops::math::add::bin())));
+    reg.insert("ArgMax", arg_max_min::arg_max);
+    reg.insert("ArgMin", arg_max_min::arg_min);
+    reg.insert("BatchMatMul", batch_mat_mul);
+    reg.insert("BatchMatMulV2", batch_mat_mul);
     reg.insert("BiasAdd", |_, _| Ok(Box::new(tractops::math::add::bin())));
     reg.insert("Ceil", |_, _| Ok(Box::new(tractops::math::ceil())));
+    reg.insert("Cumsum", cumsum);
     reg.insert("Div", |_, _| Ok(Box::new(tractops::math::div::bin())));
+    reg.insert("Exp", |_, _| Ok(Box::new(tractops::math::exp())));
     reg.insert("FloorMod", |_, _| Ok(Box::new(tractops::math::rem::bin())));
     reg.insert("MatMul", mat_mul);
     reg.insert("Max", reduce::max);
@@ -31,6 +39,9 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Neg", |_, _| Ok(Box::new(tractops::math::neg())));
     reg.insert("RealDiv", |_, _| Ok(Box::new(tractops::math::div::bin())));
     reg.insert("Rsqrt", |_, _| Ok(Box::new(tractops::math::rsqrt())));
+    reg.insert("Sqrt", |_, _| Ok(Box::new(tractops::math::sqrt())));
+    reg.insert("Square", |_, _| Ok(Box::new(tractops::math::square())));
+    reg.insert("SquaredDifference", |_, _| Ok(Box::new(squared_difference::bin())));
     reg.insert("Sub", |_, _| Ok(Box::new(tractops::math::sub::bin())));
     reg.insert("Tanh", |_, _| Ok(Box::new(tractops::math::tanh())));
 }
@@ -39,6 +50,15 @@ pub fn add_n(_ctx: &ParsingContext, _pb: &NodeDef) -> TractResult<Box<dyn Infere
     Ok(Box::new(tractops::binary::Nary(Box::new(tractops::math::Add), false)))
 }
 
+bin_to_super_type!(squared_difference, SquaredDifference,
+     [f32, i8, i16, i32, i64, u8, u16, f16, f64] => |c, a, b| *c = (a.clone() - b) * (a.clone() - b));
+
+pub fn batch_mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let adj_x = pb.get_attr_opt_bool("adj_x")?.unwrap_or(false);
+    let adj_y = pb.get_attr_opt_bool("adj_y")?.unwrap_or(false);
+    Ok(Box::new(tract_core::ops::matmul::MatMul::default().with_a_trans(adj_x).with_b_trans(adj_y)))
+}
+
 pub fn mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let trans_a = pb.get_attr_bool("transpose_a")?;
     let trans_b = pb.get_attr_bool("transpose_b")?;
@@ -46,3 +66,89 @@ pub fn mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn Infer
         tract_core::ops::matmul::MatMul::default().with_a_trans(trans_a).with_b_trans(trans_b),
     ))
 }
+
+pub fn cumsum(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let exclusive = pb.get_attr_opt_bool("exclusive")?.unwrap_or(false);
+    let reverse = pb.get_attr_opt_bool("reverse")?.unwrap_or(false);
+    Ok(Box::new(Cumsum::new(exclusive, reverse)))
+}
+
+#[derive(Debug, Clone, new)]
+pub struct Cumsum {
+    exclusive: bool,
+    reverse: bool,
+}
+
+impl Cumsum {
+    fn core_op(&self, axis: &Tensor, rank: usize) -> TractResult<CumReduce> {
+        let axis = axis.cast_to_scalar::<i64>()?;
+        let axis = if axis < 0 { axis + rank as i64 } else { axis } as usize;
+        Ok(CumReduce::new(axis, Reducer::Sum, self.exclusive, self.reverse))
+    }
+}
+
+impl Op for Cumsum {
+    fn name(&self) -> Cow<str> {
+        "tf.Cumsum".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for Cumsum {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, axis) = args_2!(inputs);
+        self.core_op(&axis, input.rank())?.eval(tvec!(input))
+    }
+}
+
+impl InferenceRulesOp for Cumsum {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let rank = target.outlet_fact(input)?.shape.rank();
+        if let Some(axis) = target.outlet_fact(mapping[&node.inputs[1]])?.konst.clone() {
+            target.wire_node(&*node.name, self.core_op(&axis, rank)?, &[input])
+        } else {
+            bail!("Need axis to be const")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(exclusive: bool, reverse: bool) -> Arc<Tensor> {
+        let op = Cumsum::new(exclusive, reverse);
+        op.eval(tvec!(rctensor1(&[1i32, 2, 3]), rctensor0(0i32))).unwrap().remove(0)
+    }
+
+    #[test]
+    fn cumsum() {
+        assert_eq!(run(false, false), rctensor1(&[1i32, 3, 6]));
+        assert_eq!(run(true, false), rctensor1(&[0i32, 1, 3]));
+        assert_eq!(run(false, true), rctensor1(&[6i32, 5, 3]));
+        assert_eq!(run(true, true), rctensor1(&[5i32, 3, 0]));
+    }
+}
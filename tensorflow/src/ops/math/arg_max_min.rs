@@ -0,0 +1,89 @@
+use tract_core::internal::*;
+use tract_core::ops as tractops;
+
+use crate::model::ParsingContext;
+use crate::ops::resolve_axis_tensor;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn arg_max(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let output_type = pb.get_attr_opt_datum_type("output_type")?.unwrap_or(DatumType::I64);
+    Ok(Box::new(ArgMaxMin::new(true, output_type)))
+}
+
+pub fn arg_min(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let output_type = pb.get_attr_opt_datum_type("output_type")?.unwrap_or(DatumType::I64);
+    Ok(Box::new(ArgMaxMin::new(false, output_type)))
+}
+
+#[derive(Debug, Clone, new)]
+pub struct ArgMaxMin {
+    max: bool,
+    output_type: DatumType,
+}
+
+impl Op for ArgMaxMin {
+    fn name(&self) -> Cow<str> {
+        if self.max { "tf.ArgMax" } else { "tf.ArgMin" }.into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl StatelessOp for ArgMaxMin {
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, axis) = args_2!(inputs);
+        let axis = resolve_axis_tensor(&axis, input.rank())?;
+        let op = tractops::nn::ArgMaxMin::new(self.max, axis, false);
+        let mut result = op.eval(tvec!(input))?;
+        let result = result.remove(0).cast_to_dt(self.output_type)?.into_owned();
+        Ok(tvec!(result.into_arc_tensor()))
+    }
+}
+
+impl InferenceRulesOp for ArgMaxMin {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, self.output_type)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(inputs[0].rank.bex() - 1, &outputs[0].rank)?;
+        s.given_2(&inputs[0].shape, &inputs[1].value, move |s, shape, axis| {
+            let mut shape = shape.clone();
+            shape.remove(resolve_axis_tensor(&axis, shape.len())?);
+            s.equals(&outputs[0].shape, shape)
+        })
+    }
+
+    inference_op_as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        if let Some(ref axis) = target.outlet_fact(mapping[&node.inputs[1]])?.konst {
+            let rank = target.outlet_fact(mapping[&node.inputs[0]])?.shape.rank();
+            let axis = resolve_axis_tensor(axis, rank)?;
+            let op = tractops::nn::ArgMaxMin::new(self.max, axis, false);
+            if self.output_type == DatumType::I64 {
+                target.wire_node(&*node.name, op, &[mapping[&node.inputs[0]]])
+            } else {
+                let wire = target.wire_node(
+                    format!("{}-arg", node.name),
+                    op,
+                    &[mapping[&node.inputs[0]]],
+                )?;
+                target.wire_node(&*node.name, tractops::cast::Cast::new(self.output_type), &wire)
+            }
+        } else {
+            bail!("Need axis to be const")
+        }
+    }
+}
@@ -31,6 +31,21 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Placeholder", |_, _| Ok(Box::new(::tract_core::ops::source::Source::new())));
 }
 
+/// Resolve a TensorFlow axis, negative values counting from the end, against
+/// a rank.
+pub(crate) fn resolve_axis(axis: i64, rank: usize) -> TractResult<usize> {
+    let resolved = if axis < 0 { axis + rank as i64 } else { axis };
+    if resolved < 0 || resolved >= rank as i64 {
+        bail!("Axis {} is out of range for rank {}", axis, rank)
+    }
+    Ok(resolved as usize)
+}
+
+/// Resolve an axis given as a scalar tensor input.
+pub(crate) fn resolve_axis_tensor(axis: &Tensor, rank: usize) -> TractResult<usize> {
+    resolve_axis(*axis.cast_to::<i64>()?.to_scalar::<i64>()?, rank)
+}
+
 fn cast(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let dtype = node.get_attr_datum_type("DstT")?;
     Ok(Box::new(::tract_core::ops::cast::Cast::new(dtype)))
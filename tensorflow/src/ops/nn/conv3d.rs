@@ -0,0 +1,81 @@
+use tract_core::internal::*;
+use tract_core::ops::cnn::*;
+use tract_core::ops::nn::DataFormat;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn conv3d(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let data_format = super::data_format(pb)?;
+    let strides = data_format.shape(super::strides(pb)?);
+    if strides.hw_rank() != 3 {
+        bail!("Conv3D expects 5 strides, found {:?}", strides.shape)
+    }
+    let dilations =
+        data_format.shape(pb.get_attr_opt_list_int("dilations")?.unwrap_or_else(|| vec![1; 5]));
+    if dilations.hw_rank() != 3 || dilations.n() != Some(&1) || *dilations.c() != 1 {
+        bail!("Conv3D expects dilations of the form [1, d, h, w, 1], found {:?}", dilations.shape)
+    }
+    let mut op = Conv::default()
+        .hwio()
+        .padding(super::padding(pb)?)
+        .strides(strides.hw_dims().into())
+        .dilations(dilations.hw_dims().into());
+    if data_format == DataFormat::NHWC {
+        op = op.nhwc()
+    }
+    Ok(Box::new(op))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tfpb;
+    use tract_core::ndarray::*;
+
+    #[test]
+    fn conv3d_ndhwc_valid() {
+        let pb = tfpb::node()
+            .op("Conv3D")
+            .attr("data_format", "NDHWC")
+            .attr("padding", "VALID")
+            .attr("strides", vec![1i64, 1, 1, 2, 1]);
+        let conv = conv3d(&ParsingContext::default(), &pb).unwrap();
+        // NDHWC, 2x2x4 image, 1 channel
+        let data = Array::range(1f32, 17.0, 1.0).into_shape((1, 2, 2, 4, 1)).unwrap();
+        // DHWIO, 2x2x2 kernel summing everything, strided by 2 on W
+        let filter = Array::from_elem((2, 2, 2, 1, 1), 1f32);
+        let result = conv
+            .as_stateless()
+            .unwrap()
+            .eval(tvec![data.into_arc_tensor(), filter.into_arc_tensor()])
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            result,
+            arr1(&[60f32, 76.0]).into_shape((1, 1, 1, 2, 1)).unwrap().into_arc_tensor()
+        );
+    }
+
+    #[test]
+    fn conv3d_ndhwc_dilated() {
+        let pb = tfpb::node()
+            .op("Conv3D")
+            .attr("data_format", "NDHWC")
+            .attr("padding", "VALID")
+            .attr("strides", vec![1i64, 1, 1, 1, 1])
+            .attr("dilations", vec![1i64, 1, 1, 3, 1]);
+        let conv = conv3d(&ParsingContext::default(), &pb).unwrap();
+        // NDHWC, 1x1x4 image, 1 channel
+        let data = arr1(&[1f32, 2.0, 3.0, 4.0]).into_shape((1, 1, 1, 4, 1)).unwrap();
+        // DHWIO, 1x1x2 kernel, dilated by 3 on W: first and last pixel
+        let filter = arr1(&[1f32, 10.0]).into_shape((1, 1, 2, 1, 1)).unwrap();
+        let result = conv
+            .as_stateless()
+            .unwrap()
+            .eval(tvec![data.into_arc_tensor(), filter.into_arc_tensor()])
+            .unwrap()
+            .remove(0);
+        assert_eq!(result, arr1(&[41f32]).into_shape((1, 1, 1, 1, 1)).unwrap().into_arc_tensor());
+    }
+}
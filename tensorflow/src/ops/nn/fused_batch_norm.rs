@@ -5,12 +5,16 @@ use crate::tfpb::tensorflow::NodeDef;
 
 pub fn fused_batch_norm(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let epsilon = pb.get_attr_float::<f32>("epsilon")?;
-    Ok(Box::new(FusedBatchNorm::new(epsilon)))
+    // FusedBatchNormV3 adds a third reserve space to y, batch_mean,
+    // batch_variance, reserve_space_1 and reserve_space_2
+    let outputs = if pb.op == "FusedBatchNormV3" { 6 } else { 1 };
+    Ok(Box::new(FusedBatchNorm::new(epsilon, outputs)))
 }
 
 #[derive(Debug, Clone, new)]
 struct FusedBatchNorm {
     epsilon: f32,
+    outputs: usize,
 }
 
 impl FusedBatchNorm {
@@ -29,6 +33,12 @@ impl FusedBatchNorm {
         let beta = izip!(offset, mean, &alpha).map(|(o, m, s)| o - m * s).collect();
         Ok((alpha, beta))
     }
+
+    // in inference mode, batch statistics and reserve spaces echo the
+    // population statistics, the third reserve space is left empty
+    fn stats_outputs(&self, mean: Arc<Tensor>, variance: Arc<Tensor>) -> TVec<Arc<Tensor>> {
+        tvec!(mean.clone(), variance.clone(), mean, variance, rctensor1::<f32>(&[]))
+    }
 }
 
 impl Op for FusedBatchNorm {
@@ -48,16 +58,21 @@ impl StatelessOp for FusedBatchNorm {
     fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
         let (data, scale, offset, mean, variance) = args_5!(inputs);
         let mut data = data.into_tensor().into_array::<f32>()?;
-        let scale = scale.as_slice::<f32>()?;
-        let offset = offset.as_slice::<f32>()?;
-        let mean = mean.as_slice::<f32>()?;
-        let variance = variance.as_slice::<f32>()?;
-        let (alpha, beta) = self.coeffs(scale, offset, mean, variance)?;
+        let (alpha, beta) = self.coeffs(
+            scale.as_slice::<f32>()?,
+            offset.as_slice::<f32>()?,
+            mean.as_slice::<f32>()?,
+            variance.as_slice::<f32>()?,
+        )?;
         let alpha = tract_core::ndarray::arr1(&*alpha);
         let beta = tract_core::ndarray::arr1(&*beta);
         data *= &alpha;
         data += &beta;
-        Ok(tvec!(data.into_arc_tensor()))
+        let mut outputs = tvec!(data.into_arc_tensor());
+        if self.outputs > 1 {
+            outputs.extend(self.stats_outputs(mean, variance));
+        }
+        Ok(outputs)
     }
 }
 
@@ -70,6 +85,7 @@ impl InferenceRulesOp for FusedBatchNorm {
         outputs: &'p [TensorProxy],
     ) -> InferenceResult {
         check_input_arity(&inputs, 5)?;
+        check_output_arity(outputs, self.outputs)?;
         s.equals(&inputs[0].datum_type, f32::datum_type())?;
         s.equals(&inputs[1].datum_type, f32::datum_type())?;
         s.equals(&inputs[2].datum_type, f32::datum_type())?;
@@ -86,9 +102,22 @@ impl InferenceRulesOp for FusedBatchNorm {
         s.equals(&inputs[2].shape[0], &inputs[0].shape[3])?;
         s.equals(&inputs[3].shape[0], &inputs[0].shape[3])?;
         s.equals(&inputs[4].shape[0], &inputs[0].shape[3])?;
+        if self.outputs > 1 {
+            for (ix, input) in [3, 4, 3, 4].iter().enumerate() {
+                s.equals(&outputs[ix + 1].datum_type, f32::datum_type())?;
+                s.equals(&outputs[ix + 1].shape, &inputs[*input].shape)?;
+            }
+            s.equals(&outputs[5].datum_type, f32::datum_type())?;
+            s.equals(&outputs[5].rank, 1)?;
+            s.equals(&outputs[5].shape[0], 0.to_dim())?;
+        }
         Ok(())
     }
 
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.outputs)
+    }
+
     inference_op_as_op!();
 
     fn to_typed(
@@ -119,12 +148,47 @@ impl InferenceRulesOp for FusedBatchNorm {
             )?[0];
             let offset = target
                 .add_const(format!("{}-offset", node.name), tensor1(&*beta).into_arc_tensor())?;
-            return target.wire_node(
+            let mut wires = target.wire_node(
                 format!("{}-add", node.name),
                 tract_core::ops::math::add::bin(),
                 [offset, wire].as_ref(),
-            );
+            )?;
+            if self.outputs > 1 {
+                let (mean, variance) = (mapping[&node.inputs[3]], mapping[&node.inputs[4]]);
+                let reserve = target
+                    .add_const(format!("{}-reserve-space-3", node.name), rctensor1::<f32>(&[]))?;
+                wires.extend([mean, variance, mean, variance, reserve].iter().cloned());
+            }
+            return Ok(wires);
         };
         bail!("Batch norm parameters expected to be known")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tfpb;
+
+    #[test]
+    fn fused_batch_norm_v3_has_six_outputs() {
+        let pb = tfpb::node().op("FusedBatchNormV3").attr("epsilon", 0f32);
+        let op = fused_batch_norm(&ParsingContext::default(), &pb).unwrap();
+        assert_eq!(op.nboutputs().unwrap(), 6);
+        let op = FusedBatchNorm::new(0.0, 6);
+        let outputs = op
+            .eval(tvec!(
+                rctensor4(&[[[[1f32, 2.0]]]]),
+                rctensor1(&[2f32, 1.0]),
+                rctensor1(&[0f32, 1.0]),
+                rctensor1(&[1f32, 1.0]),
+                rctensor1(&[4f32, 1.0]),
+            ))
+            .unwrap();
+        assert_eq!(outputs.len(), 6);
+        assert_eq!(*outputs[0], tensor4(&[[[[0f32, 2.0]]]]));
+        assert_eq!(*outputs[1], tensor1(&[1f32, 1.0]));
+        assert_eq!(*outputs[4], tensor1(&[4f32, 1.0]));
+        assert_eq!(outputs[5].shape(), &[0]);
+    }
+}
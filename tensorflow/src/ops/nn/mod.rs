@@ -1,11 +1,12 @@
 use tract_core::internal::*;
 use tract_core::ops::cnn::PaddingSpec;
-use tract_core::ops::nn::{DataFormat, LayerSoftmax};
+use tract_core::ops::nn::{DataFormat, LayerLogSoftmax, LayerSoftmax};
 
-use crate::model::TfOpRegister;
+use crate::model::{ParsingContext, TfOpRegister};
 use crate::tfpb::tensorflow::NodeDef;
 
 pub mod conv2d;
+pub mod conv3d;
 pub mod dw_conv2d;
 pub mod fused_batch_norm;
 pub mod pools;
@@ -13,33 +14,50 @@ pub mod s2b;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("AvgPool", pools::avgpool);
+    reg.insert("AvgPool3D", pools::avgpool);
     reg.insert("Conv2D", conv2d::conv2d);
+    reg.insert("Conv3D", conv3d::conv3d);
     reg.insert("DepthwiseConv2dNative", dw_conv2d::depthwise_conv2d);
+    reg.insert("Elu", |_, _| Ok(Box::new(tract_core::ops::nn::elu(1.0))));
     reg.insert("FusedBatchNorm", fused_batch_norm::fused_batch_norm);
+    reg.insert("FusedBatchNormV3", fused_batch_norm::fused_batch_norm);
+    reg.insert("LeakyRelu", leaky_relu);
+    reg.insert("LogSoftmax", |_, _| Ok(Box::new(LayerLogSoftmax::new(-1))));
     reg.insert("MaxPool", pools::maxpool);
+    reg.insert("MaxPool3D", pools::maxpool);
     reg.insert("Relu", |_, _| Ok(Box::new(tract_core::ops::math::scalar_max((0.0).into()))));
     reg.insert("Relu6", |_, _| {
         Ok(Box::new(tract_core::ops::math::scalar_min_max((6.0).into(), (0.0).into())))
     });
     reg.insert("Sigmoid", |_, _| Ok(Box::new(tract_core::ops::nn::sigmoid())));
     reg.insert("Softmax", |_, _| Ok(Box::new(LayerSoftmax::new(1))));
+    reg.insert("Softplus", |_, _| Ok(Box::new(tract_core::ops::nn::softplus())));
     reg.insert("SpaceToBatchND", s2b::space_to_batch_nd);
     reg.insert("BatchToSpaceND", s2b::batch_to_space_nd);
 }
 
+pub fn leaky_relu(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let alpha = pb.get_attr_opt_float("alpha")?.unwrap_or(0.2);
+    Ok(Box::new(tract_core::ops::nn::leaky_relu(alpha)))
+}
+
 pub fn strides(pb: &NodeDef) -> TractResult<Vec<usize>> {
     let strides: Vec<usize> = pb.get_attr_list_int("strides")?;
-    if strides.len() != 4 || strides[0] != 1 && strides[3] != 1 {
-        Err(format!("strides must be of the form [1, h, v, 1], found {:?}", strides))?
+    if strides.len() != 4 && strides.len() != 5
+        || strides[0] != 1 && strides[strides.len() - 1] != 1
+    {
+        Err(format!(
+            "strides must be of the form [1, h, v, 1] or [1, d, h, v, 1], found {:?}",
+            strides
+        ))?
     };
     Ok(strides)
 }
 
 pub fn data_format(pb: &NodeDef) -> TractResult<DataFormat> {
-    let df = if pb.get_attr_opt_raw_str("data_format")?.unwrap_or(b"NHWC") == b"NHWC" {
-        DataFormat::NHWC
-    } else {
-        DataFormat::NCHW
+    let df = match pb.get_attr_opt_raw_str("data_format")?.unwrap_or(b"NHWC") {
+        b"NHWC" | b"NDHWC" => DataFormat::NHWC,
+        _ => DataFormat::NCHW,
     };
     Ok(df)
 }
@@ -50,6 +50,15 @@ impl OpState for VariableV2State {
             .ok_or_else(|| format!("Could not find state for variable {}", op.id))?;
         Ok(tvec!(tensor.clone().into()))
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        // variable values live in the session tensors
+        Ok(OpStateSnapshot::default())
+    }
+
+    fn restore(&mut self, _snapshot: &OpStateSnapshot) -> TractResult<()> {
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, new)]
@@ -161,6 +170,15 @@ impl OpState for AssignState {
         dispatch_datum!(assign(new.datum_type())(session, var_id, &new))?;
         Ok(tvec!(new))
     }
+
+    fn snapshot(&self) -> TractResult<OpStateSnapshot> {
+        // the assigned value lives in the session tensors
+        Ok(OpStateSnapshot::default())
+    }
+
+    fn restore(&mut self, _snapshot: &OpStateSnapshot) -> TractResult<()> {
+        Ok(())
+    }
 }
 
 impl StatefullOp for Assign {
@@ -0,0 +1,73 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn indices_depth_axis() -> BoxedStrategy<(Tensor, i32, i64)> {
+    (1usize..4, 1i32..5)
+        .prop_flat_map(|(r, depth)| (vec(1usize..4, r..r + 1), Just(depth), -1i64..(r as i64 + 1)))
+        .prop_flat_map(|(shape, depth, axis)| {
+            let size = shape.iter().product::<usize>();
+            (Just(shape), vec(0..depth, size..size + 1), Just(depth), Just(axis))
+        })
+        .prop_map(|(shape, indices, depth, axis)| {
+            (Array::from_shape_vec(shape, indices).unwrap().into(), depth, axis)
+        })
+        .boxed()
+}
+
+fn one_hot(indices: &Tensor, depth: i32, axis: i64) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_i32("indices"))
+        .node(const_i32("depth", &tensor0(depth)))
+        .node(const_f32("on", &tensor0(1.0f32)))
+        .node(const_f32("off", &tensor0(-1.0f32)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("OneHot")
+                .input("indices")
+                .input("depth")
+                .input("on")
+                .input("off")
+                .attr("T", DtFloat)
+                .attr("TI", DtInt32)
+                .attr("axis", axis),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("indices", indices.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_one_hot((ref indices, depth, axis) in indices_depth_axis()) {
+        one_hot(indices, depth, axis)?;
+    }
+}
+
+#[test]
+fn one_hot_0() {
+    one_hot(&tensor1(&[0i32, 2, 1]), 3, -1).unwrap()
+}
+
+#[test]
+fn one_hot_1() {
+    one_hot(&tensor1(&[0i32, 2, 1]), 3, 0).unwrap()
+}
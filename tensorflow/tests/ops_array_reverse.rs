@@ -0,0 +1,62 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn input_and_axes() -> BoxedStrategy<(Tensor, Vec<i32>)> {
+    (1usize..4)
+        .prop_flat_map(|r| (vec(1usize..5, r..r + 1), vec(any::<bool>(), r..r + 1)))
+        .prop_map(|(shape, flags)| {
+            let size = shape.iter().product::<usize>();
+            let input = Array::from_shape_vec(shape, (0..size).map(|i| i as f32).collect());
+            let axes = flags.iter().enumerate().filter(|f| *f.1).map(|f| f.0 as i32).collect();
+            (input.unwrap().into(), axes)
+        })
+        .boxed()
+}
+
+fn reverse(input: &Tensor, axes: &[i32]) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("axes", &tensor1(axes)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("ReverseV2")
+                .input("input")
+                .input("axes")
+                .attr("T", DtFloat)
+                .attr("Tidx", DtInt32),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_reverse((ref input, ref axes) in input_and_axes()) {
+        reverse(input, axes)?;
+    }
+}
+
+#[test]
+fn reverse_0() {
+    reverse(&tensor2(&[[0.0f32, 1.0, 2.0], [3.0, 4.0, 5.0]]), &[-1]).unwrap()
+}
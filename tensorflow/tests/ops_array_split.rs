@@ -0,0 +1,141 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn input_and_axis() -> BoxedStrategy<(Tensor, usize)> {
+    (1usize..4)
+        .prop_flat_map(|r| (vec(1usize..5, r..r + 1), 0..r))
+        .prop_map(|(shape, axis)| {
+            let size = shape.iter().product::<usize>();
+            let input = Array::from_shape_vec(shape, (0..size).map(|i| i as f32).collect());
+            (input.unwrap().into(), axis)
+        })
+        .boxed()
+}
+
+fn split(input: &Tensor, axis: usize, num_split: usize, output: usize) -> TestCaseResult {
+    prop_assume!(input.shape()[axis] % num_split == 0);
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("axis", &tensor0(axis as i32)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("Split")
+                .input("axis")
+                .input("input")
+                .attr("T", DtFloat)
+                .attr("num_split", num_split as i64),
+        )
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{}", output))
+                .attr("T", DtFloat),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "output")
+}
+
+fn split_v(input: &Tensor, axis: usize, splits: &[i32], output: usize) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("splits", &tensor1(splits)))
+        .node(const_i32("axis", &tensor0(axis as i32)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("SplitV")
+                .input("input")
+                .input("splits")
+                .input("axis")
+                .attr("T", DtFloat)
+                .attr("Tlen", DtInt32)
+                .attr("num_split", splits.len() as i64),
+        )
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{}", output))
+                .attr("T", DtFloat),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "output")
+}
+
+fn unpack(input: &Tensor, axis: usize, output: usize) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("Unpack")
+                .input("input")
+                .attr("T", DtFloat)
+                .attr("num", input.shape()[axis] as i64)
+                .attr("axis", axis as i64),
+        )
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{}", output))
+                .attr("T", DtFloat),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "output")
+}
+
+proptest! {
+    #[test]
+    fn proptest_split((ref input, axis) in input_and_axis(), num_split in 1usize..4) {
+        split(input, axis, num_split, num_split - 1)?;
+    }
+
+    #[test]
+    fn proptest_unpack((ref input, axis) in input_and_axis()) {
+        unpack(input, axis, input.shape()[axis] - 1)?;
+    }
+
+    #[test]
+    fn proptest_split_v((ref input, axis) in input_and_axis(), first in 0i32..5) {
+        let dim = input.shape()[axis] as i32;
+        prop_assume!(first <= dim);
+        split_v(input, axis, &[first, -1], 1)?;
+    }
+}
+
+#[test]
+fn split_0() {
+    split(&tensor1(&[0.0f32, 1.0, 2.0, 3.0]), 0, 2, 1).unwrap()
+}
+
+#[test]
+fn split_v_0() {
+    split_v(&tensor2(&[[0.0f32, 1.0, 2.0], [3.0, 4.0, 5.0]]), 1, &[1, 2], 1).unwrap()
+}
+
+#[test]
+fn unpack_0() {
+    unpack(&tensor2(&[[0.0f32, 1.0, 2.0], [3.0, 4.0, 5.0]]), 1, 2).unwrap()
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtBool, DtFloat};
+
+fn cond_and_values() -> BoxedStrategy<(Tensor, Tensor, Tensor)> {
+    (1usize..4)
+        .prop_flat_map(|r| (vec(1usize..4, r..r + 1), any::<bool>()))
+        .prop_flat_map(|(shape, rows)| {
+            let size = shape.iter().product::<usize>();
+            let cond_shape = if rows { vec![shape[0]] } else { shape.clone() };
+            let cond_size = cond_shape.iter().product::<usize>();
+            (
+                Just(shape),
+                Just(cond_shape),
+                vec(any::<bool>(), cond_size..cond_size + 1),
+                Just(size),
+            )
+        })
+        .prop_map(|(shape, cond_shape, cond, size)| {
+            let t = Array::from_shape_vec(shape.clone(), (0..size).map(|i| i as f32).collect());
+            let f = Array::from_shape_vec(shape, (0..size).map(|i| -(i as f32)).collect());
+            (
+                Array::from_shape_vec(cond_shape, cond).unwrap().into(),
+                t.unwrap().into(),
+                f.unwrap().into(),
+            )
+        })
+        .boxed()
+}
+
+fn select(op: &str, cond: &Tensor, t: &Tensor, f: &Tensor) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder("cond", DtBool, None))
+        .node(placeholder_f32("t"))
+        .node(placeholder_f32("f"))
+        .node(tfpb::node().name("op").op(op).input("cond").input("t").input("f").attr("T", DtFloat))
+        .write_to_bytes()?;
+    compare(&graph, vec![("cond", cond.clone()), ("t", t.clone()), ("f", f.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_select((ref cond, ref t, ref f) in cond_and_values()) {
+        select("Select", cond, t, f)?;
+    }
+
+    #[test]
+    fn proptest_select_v2((ref cond, ref t, ref f) in cond_and_values()) {
+        prop_assume!(cond.rank() == t.rank());
+        select("SelectV2", cond, t, f)?;
+    }
+}
+
+#[test]
+fn select_rows() {
+    let t = tensor2(&[[1.0f32, 2.0], [3.0, 4.0]]);
+    let f = tensor2(&[[-1.0f32, -2.0], [-3.0, -4.0]]);
+    select("Select", &tensor1(&[true, false]), &t, &f).unwrap()
+}
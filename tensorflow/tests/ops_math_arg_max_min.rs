@@ -0,0 +1,65 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{self, DtFloat, DtInt32, DtInt64};
+
+fn input_and_axis() -> BoxedStrategy<(Tensor, i32)> {
+    (1usize..4)
+        .prop_flat_map(|r| (vec(1usize..5, r..r + 1), -(r as i32)..(r as i32)))
+        .prop_flat_map(|(shape, axis)| {
+            let size = shape.iter().product::<usize>();
+            (Just(shape), vec(-10i32..10, size..size + 1), Just(axis))
+        })
+        .prop_map(|(shape, data, axis)| {
+            let data = data.into_iter().map(|x| x as f32).collect();
+            (Array::from_shape_vec(shape, data).unwrap().into(), axis)
+        })
+        .boxed()
+}
+
+fn arg(op: &str, input: &Tensor, axis: i32, output_type: DataType) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("axis", &tensor0(axis)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op(op)
+                .input("input")
+                .input("axis")
+                .attr("T", DtFloat)
+                .attr("Tidx", DtInt32)
+                .attr("output_type", output_type),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_arg_max((ref input, axis) in input_and_axis()) {
+        arg("ArgMax", input, axis, DtInt64)?;
+    }
+
+    #[test]
+    fn proptest_arg_min((ref input, axis) in input_and_axis()) {
+        arg("ArgMin", input, axis, DtInt32)?;
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn operands() -> BoxedStrategy<(Tensor, Tensor, bool, bool)> {
+    (1usize..3, 1usize..4, 1usize..4, 1usize..4, any::<bool>(), any::<bool>())
+        .prop_map(|(b, m, k, n, adj_x, adj_y)| {
+            let a_shape = if adj_x { (b, k, m) } else { (b, m, k) };
+            let b_shape = if adj_y { (b, n, k) } else { (b, k, n) };
+            let a = Array::from_shape_fn(a_shape, |(i, j, k)| (i * 100 + j * 10 + k) as f32);
+            let b = Array::from_shape_fn(b_shape, |(i, j, k)| (i + j * 3) as f32 - k as f32);
+            (a.into(), b.into(), adj_x, adj_y)
+        })
+        .boxed()
+}
+
+fn batch_mat_mul(op: &str, a: &Tensor, b: &Tensor, adj_x: bool, adj_y: bool) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("a"))
+        .node(placeholder_f32("b"))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op(op)
+                .input("a")
+                .input("b")
+                .attr("T", DtFloat)
+                .attr("adj_x", adj_x)
+                .attr("adj_y", adj_y),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("a", a.clone()), ("b", b.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_batch_mat_mul((ref a, ref b, adj_x, adj_y) in operands()) {
+        batch_mat_mul("BatchMatMul", a, b, adj_x, adj_y)?;
+    }
+
+    #[test]
+    fn proptest_batch_mat_mul_v2((ref a, ref b, adj_x, adj_y) in operands()) {
+        batch_mat_mul("BatchMatMulV2", a, b, adj_x, adj_y)?;
+    }
+}
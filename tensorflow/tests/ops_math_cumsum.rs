@@ -0,0 +1,63 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn input_and_axis() -> BoxedStrategy<(Tensor, i32)> {
+    (1usize..4)
+        .prop_flat_map(|r| (vec(1usize..5, r..r + 1), 0..(r as i32)))
+        .prop_map(|(shape, axis)| {
+            let size = shape.iter().product::<usize>();
+            let input = Array::from_shape_vec(shape, (0..size).map(|i| i as f32).collect());
+            (input.unwrap().into(), axis)
+        })
+        .boxed()
+}
+
+fn cumsum(input: &Tensor, axis: i32, exclusive: bool, reverse: bool) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("axis", &tensor0(axis)))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op("Cumsum")
+                .input("input")
+                .input("axis")
+                .attr("T", DtFloat)
+                .attr("Tidx", DtInt32)
+                .attr("exclusive", exclusive)
+                .attr("reverse", reverse),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_cumsum((ref input, axis) in input_and_axis(), exclusive in any::<bool>(), reverse in any::<bool>()) {
+        cumsum(input, axis, exclusive, reverse)?;
+    }
+}
+
+#[test]
+fn cumsum_exclusive_reverse() {
+    cumsum(&tensor1(&[1.0f32, 2.0, 3.0]), 0, true, true).unwrap()
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+use tract_core::ndarray::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn input() -> BoxedStrategy<Tensor> {
+    (1usize..4, 1usize..5)
+        .prop_flat_map(|(b, n)| (Just((b, n)), vec(-50i32..50, b * n..b * n + 1)))
+        .prop_map(|(shape, data)| {
+            let data = data.into_iter().map(|x| x as f32 / 10.0).collect();
+            Array::from_shape_vec(shape, data).unwrap().into()
+        })
+        .boxed()
+}
+
+fn unary(op: &str, input: &Tensor) -> TestCaseResult {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op(op).input("input").attr("T", DtFloat))
+        .write_to_bytes()?;
+    compare(&graph, vec![("input", input.clone())], "op")
+}
+
+proptest! {
+    #[test]
+    fn proptest_elu(ref i in input()) { unary("Elu", i)? }
+
+    #[test]
+    fn proptest_exp(ref i in input()) { unary("Exp", i)? }
+
+    #[test]
+    fn proptest_leaky_relu(ref i in input()) { unary("LeakyRelu", i)? }
+
+    #[test]
+    fn proptest_log_softmax(ref i in input()) { unary("LogSoftmax", i)? }
+
+    #[test]
+    fn proptest_softplus(ref i in input()) { unary("Softplus", i)? }
+
+    #[test]
+    fn proptest_sqrt(ref i in input()) { unary("Sqrt", &i.cast_to::<f32>()?.to_array_view::<f32>()?.mapv(f32::abs).into())? }
+
+    #[test]
+    fn proptest_square(ref i in input()) { unary("Square", i)? }
+
+    #[test]
+    fn proptest_zeros_like(ref i in input()) { unary("ZerosLike", i)? }
+
+    #[test]
+    fn proptest_squared_difference(ref a in input()) {
+        let b: Tensor = a.to_array_view::<f32>()?.mapv(|x| 1.0 - x).into();
+        let graph = tfpb::graph()
+            .node(placeholder_f32("a"))
+            .node(placeholder_f32("b"))
+            .node(tfpb::node().name("op").op("SquaredDifference").input("a").input("b").attr("T", DtFloat))
+            .write_to_bytes()?;
+        compare(&graph, vec![("a", a.clone()), ("b", b)], "op")?
+    }
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_core;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::prelude::*;
+use tract_core::ndarray::prelude::*;
+use tract_core::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn convolution_pb(strides: (usize, usize, usize), valid: bool, kernel: &Tensor) -> Result<Vec<u8>> {
+    let conv = tfpb::node()
+        .name("conv")
+        .op("Conv3D")
+        .input("data")
+        .input("kernel")
+        .attr("strides", vec![1, strides.0 as i64, strides.1 as i64, strides.2 as i64, 1])
+        .attr("padding", if valid { "VALID" } else { "SAME" })
+        .attr("T", DtFloat);
+
+    let graph =
+        tfpb::graph().node(placeholder_f32("data")).node(const_f32("kernel", kernel)).node(conv);
+
+    Ok(graph.write_to_bytes()?)
+}
+
+fn img_and_ker() -> BoxedStrategy<(Tensor, Tensor, (usize, usize, usize))> {
+    (1usize..3, 1usize..3, 1usize..3, 1usize..3, 1usize..3)
+        .prop_flat_map(|(ic, kd, kh, kw, kc)| (kd..6, kh..6, kw..6, Just((ic, kd, kh, kw, kc))))
+        .prop_flat_map(|(id, ih, iw, (ic, kd, kh, kw, kc))| {
+            let i_size = id * iw * ih * ic;
+            let k_size = kd * kw * kh * kc * ic;
+            (
+                Just((1, id, ih, iw, ic)),
+                Just((kd, kh, kw, ic, kc)),
+                ::proptest::collection::vec(-9i32..9, i_size..i_size + 1),
+                ::proptest::collection::vec(-9i32..9, k_size..k_size + 1),
+                (1..(kd + 1), 1..(kh + 1), 1..(kw + 1)),
+            )
+        })
+        .prop_map(|(img_shape, ker_shape, img, ker, strides)| {
+            (
+                Array::from(img.into_iter().map(|i| i as f32).collect::<Vec<_>>())
+                    .into_shape(img_shape)
+                    .unwrap()
+                    .into(),
+                Array::from(ker.into_iter().map(|i| i as f32).collect::<Vec<_>>())
+                    .into_shape(ker_shape)
+                    .unwrap()
+                    .into(),
+                strides,
+            )
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn conv3d_compare((ref i, ref k, strides) in img_and_ker(),
+                       valid in ::proptest::bool::ANY) {
+        let model = convolution_pb(strides, valid, &k).unwrap();
+        compare(&model, vec!(("data", i.clone())), "conv")?;
+    }
+}
+
+#[test]
+fn conv3d_eval_1() {
+    let i: Tensor = ArrayD::<f32>::from_elem(vec![1, 2, 2, 2, 1], 1.0).into();
+    let k: Tensor = ArrayD::<f32>::from_elem(vec![2, 1, 1, 1, 1], 1.0).into();
+    let model = convolution_pb((1, 1, 1), false, &k).unwrap();
+    compare(&model, vec![("data", i.into())], "conv").unwrap();
+}
@@ -65,6 +65,66 @@ fn pool(
     compare(&graph, vec![("data", i.clone().into())], "pool")
 }
 
+fn vol_and_pool() -> BoxedStrategy<(ArrayD<f32>, (usize, usize, usize), String, usize)> {
+    (1usize..4, 1usize..4, 1usize..4, 1usize..3, (1usize..3, 1usize..3, 1usize..3))
+        .prop_flat_map(|(id, ih, iw, ic, k)| {
+            let i_size = id * iw * ih * ic;
+            (
+                Just(vec![1, id, ih, iw, ic]),
+                Just(k),
+                ::proptest::collection::vec((-10..10).prop_map(|a| a as f32), i_size..i_size + 1),
+                prop_oneof!("VALID", "SAME"),
+                1usize..3,
+            )
+        })
+        .prop_map(|(shape, k, vol, padding, stride)| {
+            (Array::from(vol).into_shape(shape).unwrap(), k, padding, stride)
+        })
+        .boxed()
+}
+
+fn pool3d(
+    op: &str,
+    i: &ArrayD<f32>,
+    k: (usize, usize, usize),
+    padding: &str,
+    stride: usize,
+) -> TestCaseResult {
+    if padding == "VALID" {
+        prop_assume!(i.shape()[1] >= k.0);
+        prop_assume!(i.shape()[2] >= k.1);
+        prop_assume!(i.shape()[3] >= k.2);
+    }
+    let graph = tfpb::graph()
+        .node(placeholder_f32("data"))
+        .node(
+            tfpb::node()
+                .name("pool")
+                .op(op)
+                .input("data")
+                .attr("T", DtFloat)
+                .attr("strides", vec![1, stride as i64, stride as i64, stride as i64, 1])
+                .attr("ksize", vec![1, k.0 as i64, k.1 as i64, k.2 as i64, 1])
+                .attr("padding", padding),
+        )
+        .write_to_bytes()?;
+    compare(&graph, vec![("data", i.clone().into())], "pool")
+}
+
+proptest! {
+    #[test]
+    fn proptest_maxpool3d((ref i, k, ref padding, stride) in vol_and_pool()) {
+        pool3d("MaxPool3D", i, k, padding, stride)?;
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest_avgpool3d((ref i, k, ref padding, stride) in vol_and_pool()) {
+        pool3d("AvgPool3D", i, k, padding, stride)?;
+    }
+}
+
 proptest! {
     #[test]
     fn proptest_maxpool((ref i, k, ref padding, stride) in img_and_pool()) {